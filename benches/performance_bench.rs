@@ -134,8 +134,8 @@ fn chain_benchmarks(c: &mut Criterion) {
         }
         
         b.iter(|| {
-            let result = chain.validate().unwrap();
-            black_box(result);
+            chain.validate().unwrap();
+            black_box(&chain);
         })
     });
     
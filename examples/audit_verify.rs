@@ -0,0 +1,85 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Verify the audit log kept in NATS
+//!
+//! Opens the log written by `NatsObjectStore::with_audit` or
+//! `ContentService::with_audit`, checks every entry against the published
+//! head, and prints a summary of the last day. Exits with status 1 if the
+//! log has been tampered with.
+//!
+//! ```text
+//! cargo run --example audit_verify -- [nats-url] [trusted-key-base64]
+//! ```
+//!
+//! Pass the base64 Ed25519 key that signs the audit head to also detect
+//! entries dropped from the end of the log.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cim_ipld::object_store::{AuditAction, AuditLog, AuditQuery, NamedRefs, NatsObjectStore};
+use ed25519_dalek::VerifyingKey;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let url = args.next().unwrap_or_else(|| "nats://localhost:4222".to_string());
+    let trusted_key = args.next().map(|key| parse_key(&key)).transpose()?;
+
+    let client = match async_nats::connect(&url).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Cannot reach NATS at {url}: {e}");
+            std::process::exit(2);
+        }
+    };
+    let jetstream = async_nats::jetstream::new(client);
+
+    let backend = Arc::new(NatsObjectStore::new(jetstream.clone(), 1024).await?);
+    let mut refs = NamedRefs::nats(&jetstream).await?;
+    if let Some(key) = trusted_key {
+        refs = refs.with_trusted_key(key);
+    } else {
+        println!("No trusted key given: entries dropped from the end go undetected");
+    }
+
+    let log = match AuditLog::open(backend, Arc::new(refs)).await {
+        Ok(log) => log,
+        Err(e) => fail(&e),
+    };
+    let verification = match log.verify().await {
+        Ok(verification) => verification,
+        Err(e) => fail(&e),
+    };
+
+    match verification.head {
+        Some(head) => println!("Audit log intact: {} entries, head {head}", verification.entries),
+        None => println!("Audit log is empty"),
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let day_ago = now.saturating_sub(Duration::from_secs(24 * 60 * 60));
+    let last_day = AuditQuery::new().with_time_range(day_ago.as_millis() as u64, now.as_millis() as u64 + 1);
+    let recent = log.query(&last_day).await;
+    println!("Last 24 hours: {} entries", recent.len());
+    for action in [AuditAction::Store, AuditAction::Delete, AuditAction::SoftDelete, AuditAction::Expire] {
+        let count = recent.iter().filter(|entry| entry.content.action == action).count();
+        if count > 0 {
+            println!("  {action:?}: {count}");
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_key(key: &str) -> Result<VerifyingKey, Box<dyn std::error::Error>> {
+    let bytes: [u8; 32] = BASE64.decode(key)?
+        .try_into()
+        .map_err(|_| "trusted key must be 32 bytes")?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+fn fail(error: &cim_ipld::Error) -> ! {
+    eprintln!("Audit log verification failed: {error}");
+    std::process::exit(1);
+}
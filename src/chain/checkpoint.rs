@@ -0,0 +1,51 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Chain checkpoints
+//!
+//! A [`Checkpoint`] pins one entry of a chain by sequence number and CID,
+//! together with the accumulator peaks over every entry up to it and,
+//! optionally, the CID of an application state snapshot taken there.
+//!
+//! Once a checkpoint is trusted, validation can start from it instead of
+//! from genesis, and the entries before it can be pruned: the checkpoint
+//! entry stays in the chain, and its `previous_cid` still links to the
+//! pruned history.
+
+use serde::{Deserialize, Serialize};
+
+use super::cid_serde;
+use super::mmr::{bag_peaks, NodeHash};
+use crate::Cid;
+
+/// A trusted point in a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Sequence number of the checkpoint entry
+    pub sequence: u64,
+    /// CID of the checkpoint entry
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Accumulator peaks over every entry up to and including this one
+    pub peaks: Vec<NodeHash>,
+    /// CID of the application state after this entry, if one was recorded
+    #[serde(default, with = "cid_serde::option")]
+    pub snapshot: Option<Cid>,
+}
+
+impl Checkpoint {
+    /// Record the CID of a state snapshot, e.g. from `TypedContent::calculate_cid`
+    pub fn with_snapshot(mut self, snapshot: Cid) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Number of entries up to and including the checkpoint entry
+    pub fn entries(&self) -> u64 {
+        self.sequence + 1
+    }
+
+    /// Accumulator root over the entries up to the checkpoint
+    pub fn root(&self) -> NodeHash {
+        bag_peaks(&self.peaks).unwrap_or_default()
+    }
+}
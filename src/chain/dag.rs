@@ -0,0 +1,423 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Branching content chains
+//!
+//! A [`ContentDag`] is a chain whose entries may have several parents, so
+//! concurrent writers can append without coordinating and later merge. Each
+//! [`DagEntry`] is a DAG-CBOR block
+//! `{"content": <content>, "height": <n>, "parents": [<CID link>, ...]}`
+//! whose height is one more than its highest parent: a Merkle clock that
+//! orders every entry after its ancestors.
+//!
+//! Replicas holding the same entries agree on the heads and on the order
+//! given by [`ContentDag::topological_order`] (height, then CID), so a
+//! [`MergeStrategy`] applied to that order converges everywhere.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use serde::Serialize;
+use serde_cbor::Value;
+
+use super::{block_cid, decode_link, encode_link, to_dag_cbor};
+use crate::{Cid, Error, Result, TypedContent};
+
+/// An entry of a [`ContentDag`]
+#[derive(Debug, Clone)]
+pub struct DagEntry<T> {
+    /// The actual content
+    pub content: T,
+    /// Content identifier of this entry's block
+    pub cid: Cid,
+    /// CIDs of the parent entries, sorted and without duplicates
+    pub parents: Vec<Cid>,
+    /// Merkle clock: 0 for roots, otherwise one more than the highest parent
+    pub height: u64,
+}
+
+impl<T: TypedContent> DagEntry<T> {
+    /// Create an entry following `parents`
+    pub fn new(content: T, parents: &[&DagEntry<T>]) -> Result<Self> {
+        let height = parents.iter().map(|p| p.height + 1).max().unwrap_or(0);
+        let parents: Vec<Cid> = parents.iter().map(|p| p.cid).collect::<BTreeSet<_>>().into_iter().collect();
+
+        let block = encode_block(&content, &parents, height)?;
+        Ok(Self {
+            content,
+            cid: block_cid(&block)?,
+            parents,
+            height,
+        })
+    }
+
+    /// Encode this entry as its DAG-CBOR block
+    pub fn to_block(&self) -> Result<Vec<u8>> {
+        encode_block(&self.content, &self.parents, self.height)
+    }
+
+    /// Decode an entry from its DAG-CBOR block
+    ///
+    /// The CID is computed from `block`, which must be in canonical form.
+    pub fn from_block(block: &[u8]) -> Result<Self> {
+        let value: Value = serde_cbor::from_slice(block)
+            .map_err(|e| Error::CborError(e.to_string()))?;
+        let Value::Map(mut fields) = value else {
+            return Err(Error::InvalidContent("DAG entry is not a map".to_string()));
+        };
+        let mut field = |name: &str| {
+            fields.remove(&Value::Text(name.to_string()))
+                .ok_or_else(|| Error::InvalidContent(format!("DAG entry has no {name}")))
+        };
+
+        let content: T = serde_cbor::value::from_value(field("content")?)
+            .map_err(|e| Error::CborError(e.to_string()))?;
+        let height = match field("height")? {
+            Value::Integer(n) => u64::try_from(n)
+                .map_err(|_| Error::InvalidContent(format!("invalid DAG height {n}")))?,
+            other => return Err(Error::InvalidContent(format!("invalid DAG height {other:?}"))),
+        };
+        let parents = match field("parents")? {
+            Value::Array(links) => links.into_iter().map(decode_link).collect::<Result<Vec<_>>>()?,
+            other => return Err(Error::InvalidContent(format!("invalid DAG parents {other:?}"))),
+        };
+
+        let entry = Self {
+            content,
+            cid: block_cid(block)?,
+            parents,
+            height,
+        };
+        if entry.to_block()? != block || !entry.parents.windows(2).all(|w| w[0] < w[1]) {
+            return Err(Error::InvalidContent(format!("DAG entry {} is not canonical DAG-CBOR", entry.cid)));
+        }
+        Ok(entry)
+    }
+
+    /// Check that the stored CID matches the entry's content and links
+    pub fn verify_cid(&self) -> Result<()> {
+        let calculated = block_cid(&self.to_block()?)?;
+        if calculated != self.cid {
+            return Err(Error::InvalidCid(format!("CID mismatch: expected {}, calculated {}", self.cid, calculated)));
+        }
+        Ok(())
+    }
+}
+
+fn encode_block<T: Serialize>(content: &T, parents: &[Cid], height: u64) -> Result<Vec<u8>> {
+    let content = serde_cbor::value::to_value(content)
+        .map_err(|e| Error::CborError(e.to_string()))?;
+
+    let mut fields = BTreeMap::new();
+    fields.insert(Value::Text("content".to_string()), content);
+    fields.insert(Value::Text("height".to_string()), Value::Integer(height.into()));
+    fields.insert(Value::Text("parents".to_string()), Value::Array(parents.iter().map(encode_link).collect()));
+    to_dag_cbor(&Value::Map(fields))
+}
+
+/// How to combine the entries of a [`ContentDag`] into one value
+///
+/// `merge` receives every entry in [`ContentDag::topological_order`], so the
+/// result only depends on the set of entries, not on the order they arrived.
+pub trait MergeStrategy<T> {
+    /// Merged value
+    type Output;
+
+    /// Combine entries given in topological order
+    fn merge(&self, entries: &[&DagEntry<T>]) -> Self::Output;
+}
+
+/// Keep the content of the last entry in topological order
+///
+/// Among concurrent heads of equal height the one with the greatest CID wins.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LastWriterWins;
+
+impl<T: Clone> MergeStrategy<T> for LastWriterWins {
+    type Output = Option<T>;
+
+    fn merge(&self, entries: &[&DagEntry<T>]) -> Option<T> {
+        entries.last().map(|entry| entry.content.clone())
+    }
+}
+
+/// Fold every entry, in topological order, into an accumulator
+pub struct Fold<A, F> {
+    init: A,
+    step: F,
+}
+
+impl<A, F> Fold<A, F> {
+    /// Start from `init` and apply `step` to each entry's content
+    pub fn new(init: A, step: F) -> Self {
+        Self { init, step }
+    }
+}
+
+impl<T, A: Clone, F: Fn(A, &T) -> A> MergeStrategy<T> for Fold<A, F> {
+    type Output = A;
+
+    fn merge(&self, entries: &[&DagEntry<T>]) -> A {
+        entries.iter().fold(self.init.clone(), |acc, entry| (self.step)(acc, &entry.content))
+    }
+}
+
+/// A content chain that may fork and merge
+#[derive(Debug, Clone)]
+pub struct ContentDag<T: TypedContent> {
+    entries: HashMap<Cid, DagEntry<T>>,
+    children: HashMap<Cid, BTreeSet<Cid>>,
+    heads: BTreeSet<Cid>,
+}
+
+impl<T: TypedContent> ContentDag<T> {
+    /// Create an empty DAG
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            children: HashMap::new(),
+            heads: BTreeSet::new(),
+        }
+    }
+
+    /// Append content after every current head, merging any forks
+    pub fn append(&mut self, content: T) -> Result<&DagEntry<T>> {
+        let heads: Vec<Cid> = self.heads.iter().copied().collect();
+        self.append_to(content, &heads)
+    }
+
+    /// Append content after the given parents, which must be in the DAG
+    ///
+    /// Appending after an entry that is not a head forks the DAG.
+    pub fn append_to(&mut self, content: T, parents: &[Cid]) -> Result<&DagEntry<T>> {
+        let parents = parents.iter().map(|cid| self.require(cid)).collect::<Result<Vec<_>>>()?;
+        let entry = DagEntry::new(content, &parents)?;
+        self.insert(entry)
+    }
+
+    /// Add an existing entry whose parents are already in the DAG
+    ///
+    /// Inserting an entry that is already present is a no-op.
+    pub fn insert(&mut self, entry: DagEntry<T>) -> Result<&DagEntry<T>> {
+        let cid = entry.cid;
+        if !self.entries.contains_key(&cid) {
+            self.check(&entry)?;
+            for parent in &entry.parents {
+                self.heads.remove(parent);
+                self.children.entry(*parent).or_default().insert(cid);
+            }
+            self.heads.insert(cid);
+            self.entries.insert(cid, entry);
+        }
+        Ok(&self.entries[&cid])
+    }
+
+    /// Add every entry of `other` that is missing here
+    ///
+    /// Merging is commutative and idempotent: replicas that merge each
+    /// other's entries end up with the same entries and heads.
+    pub fn merge(&mut self, other: &ContentDag<T>) -> Result<()>
+    where
+        T: Clone,
+    {
+        for entry in other.topological_order() {
+            if !self.contains(&entry.cid) {
+                self.insert(entry.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn require(&self, cid: &Cid) -> Result<&DagEntry<T>> {
+        self.entries.get(cid).ok_or_else(|| Error::ChainValidationError {
+            expected: format!("parent {cid} in DAG"),
+            actual: "missing".to_string(),
+        })
+    }
+
+    /// Check an entry's CID, parents and height against the DAG
+    fn check(&self, entry: &DagEntry<T>) -> Result<()> {
+        entry.verify_cid()?;
+        let mut expected = 0;
+        for parent in &entry.parents {
+            expected = expected.max(self.require(parent)?.height + 1);
+        }
+        if entry.height != expected {
+            return Err(Error::SequenceValidationError {
+                expected,
+                actual: entry.height,
+            });
+        }
+        Ok(())
+    }
+
+    /// Entries without children, in CID order
+    pub fn heads(&self) -> Vec<&DagEntry<T>> {
+        self.heads.iter().map(|cid| &self.entries[cid]).collect()
+    }
+
+    /// Whether concurrent appends left more than one head
+    pub fn is_forked(&self) -> bool {
+        self.heads.len() > 1
+    }
+
+    /// CIDs of entries with more than one child, in CID order
+    pub fn forks(&self) -> Vec<Cid> {
+        let mut forks: Vec<Cid> = self.children.iter()
+            .filter(|(_, children)| children.len() > 1)
+            .map(|(cid, _)| *cid)
+            .collect();
+        forks.sort();
+        forks
+    }
+
+    /// Entry by CID
+    pub fn get(&self, cid: &Cid) -> Option<&DagEntry<T>> {
+        self.entries.get(cid)
+    }
+
+    /// Whether the DAG holds `cid`
+    pub fn contains(&self, cid: &Cid) -> bool {
+        self.entries.contains_key(cid)
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the DAG is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entry ordered by height, then CID
+    ///
+    /// Parents always come before their children, and the order is the same
+    /// on every replica holding the same entries.
+    pub fn topological_order(&self) -> Vec<&DagEntry<T>> {
+        let mut entries: Vec<&DagEntry<T>> = self.entries.values().collect();
+        entries.sort_by_key(|entry| (entry.height, entry.cid));
+        entries
+    }
+
+    /// Whether `ancestor` is reachable from `descendant` through parent links
+    ///
+    /// An entry is not its own ancestor. Two entries neither of which is an
+    /// ancestor of the other were appended concurrently.
+    pub fn is_ancestor(&self, ancestor: &Cid, descendant: &Cid) -> bool {
+        let Some(target) = self.entries.get(ancestor) else {
+            return false;
+        };
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<Cid> = self.entries.get(descendant)
+            .map(|entry| entry.parents.iter().copied().collect())
+            .unwrap_or_default();
+
+        while let Some(cid) = queue.pop_front() {
+            if cid == *ancestor {
+                return true;
+            }
+            let entry = &self.entries[&cid];
+            // Heights only decrease along parent links
+            if entry.height > target.height && seen.insert(cid) {
+                queue.extend(entry.parents.iter().copied());
+            }
+        }
+        false
+    }
+
+    /// Combine the entries with a merge strategy
+    pub fn resolve<S: MergeStrategy<T>>(&self, strategy: &S) -> S::Output {
+        strategy.merge(&self.topological_order())
+    }
+
+    /// Validate every entry's CID, parents and height
+    pub fn validate(&self) -> Result<()> {
+        for entry in self.entries.values() {
+            self.check(entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: TypedContent> Default for ContentDag<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContentType;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        delta: i64,
+    }
+
+    impl TypedContent for Counter {
+        const CODEC: u64 = 0x300100;
+        const CONTENT_TYPE: ContentType = ContentType::Custom(0x300100);
+    }
+
+    #[test]
+    fn test_fork_and_converge() {
+        let mut alice = ContentDag::new();
+        let root = alice.append(Counter { delta: 1 }).unwrap().cid;
+        let mut bob = alice.clone();
+
+        // Concurrent appends on two replicas
+        let a = alice.append(Counter { delta: 10 }).unwrap().cid;
+        let b = bob.append(Counter { delta: 100 }).unwrap().cid;
+        assert!(!alice.is_ancestor(&a, &b) && !alice.is_ancestor(&b, &a));
+
+        let mut merged_a = alice.clone();
+        merged_a.merge(&bob).unwrap();
+        let mut merged_b = bob.clone();
+        merged_b.merge(&alice).unwrap();
+
+        assert!(merged_a.is_forked());
+        assert_eq!(merged_a.forks(), vec![root]);
+        let heads = |dag: &ContentDag<Counter>| dag.heads().iter().map(|e| e.cid).collect::<Vec<_>>();
+        assert_eq!(heads(&merged_a), heads(&merged_b));
+
+        let sum = Fold::new(0, |acc: i64, c: &Counter| acc + c.delta);
+        assert_eq!(merged_a.resolve(&sum), 111);
+        assert_eq!(merged_a.resolve(&LastWriterWins), merged_b.resolve(&LastWriterWins));
+
+        // Appending after both heads merges the fork
+        let merge = merged_a.append(Counter { delta: 0 }).unwrap();
+        assert_eq!(merge.parents.len(), 2);
+        assert_eq!(merge.height, 2);
+        let merge = merge.cid;
+        assert!(!merged_a.is_forked());
+        assert!(merged_a.is_ancestor(&root, &merge));
+        assert_eq!(merged_a.resolve(&LastWriterWins), Some(Counter { delta: 0 }));
+        assert!(merged_a.validate().is_ok());
+    }
+
+    #[test]
+    fn test_block_round_trip_and_rejects_orphans() {
+        let mut dag = ContentDag::new();
+        let root = dag.append(Counter { delta: 1 }).unwrap().cid;
+        let left = dag.append_to(Counter { delta: 2 }, &[root]).unwrap().clone();
+        let right = dag.append_to(Counter { delta: 3 }, &[root]).unwrap().clone();
+        let merge = DagEntry::new(Counter { delta: 4 }, &[&right, &left]).unwrap();
+
+        let decoded = DagEntry::<Counter>::from_block(&merge.to_block().unwrap()).unwrap();
+        assert_eq!(decoded.cid, merge.cid);
+        assert_eq!(decoded.parents, merge.parents);
+
+        // Parents must arrive first
+        let mut replica = ContentDag::new();
+        assert!(replica.insert(decoded.clone()).is_err());
+
+        // A forged height is caught even with a matching CID
+        let mut forged = decoded.clone();
+        forged.height = 7;
+        forged.cid = block_cid(&forged.to_block().unwrap()).unwrap();
+        assert!(dag.insert(forged).is_err());
+        assert!(dag.insert(decoded).is_ok());
+    }
+}
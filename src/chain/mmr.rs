@@ -0,0 +1,482 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Merkle Mountain Range over chain entries
+//!
+//! [`MerkleMountainRange`] accumulates entry CIDs as they are appended,
+//! keeping one perfect binary tree per set bit of the entry count. Its root
+//! is the RFC 6962 Merkle tree hash of all entries, so it commits to every
+//! entry and their order, including the head.
+//!
+//! Proofs are logarithmic in the chain length:
+//! - an [`InclusionProof`] shows an entry is in the chain with a given root
+//! - a [`ConsistencyProof`] shows a longer chain extends a shorter one
+//!
+//! [`verify_inclusion`] and [`verify_consistency`] check them with nothing
+//! but the proof, the roots, and the entry CID.
+//!
+//! Hashes are BLAKE3 with domain separation: `H(0x00 || cid)` for leaves and
+//! `H(0x01 || left || right)` for inner nodes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Cid, Error, Result};
+
+/// Hash of an MMR node
+pub type NodeHash = [u8; 32];
+
+/// Hash of a leaf holding `cid`
+pub fn leaf_hash(cid: &Cid) -> NodeHash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[0x00]);
+    hasher.update(&cid.to_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+fn node_hash(left: &NodeHash, right: &NodeHash) -> NodeHash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+/// Largest power of two strictly below `n`, for `n > 1`
+fn split(n: u64) -> u64 {
+    1 << (63 - (n - 1).leading_zeros())
+}
+
+/// Accumulator of chain entry CIDs
+///
+/// After [`MerkleMountainRange::prune`] only the peaks over the pruned
+/// entries are kept: appending and the root still work, but proofs that
+/// need a pruned node fail.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleMountainRange {
+    /// `levels[h][i]` is the root of the perfect subtree over leaves
+    /// `(offsets[h] + i) * 2^h .. (offsets[h] + i + 1) * 2^h`
+    levels: Vec<Vec<NodeHash>>,
+    /// Index of the first node kept on each level
+    offsets: Vec<u64>,
+}
+
+impl MerkleMountainRange {
+    /// An empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continue an accumulator of `size` entries from its peaks, largest first
+    pub fn from_peaks(size: u64, peaks: &[NodeHash]) -> Result<Self> {
+        if peaks.len() != size.count_ones() as usize {
+            return Err(Error::InvalidContent(format!("{size} entries have {} peaks, not {}", size.count_ones(), peaks.len())));
+        }
+        let height = (u64::BITS - size.leading_zeros()) as usize;
+        let mut peaks = peaks.iter();
+        let mut mmr = Self::new();
+        for level in (0..height).rev() {
+            let count = size >> level;
+            if size & (1 << level) != 0 {
+                mmr.levels.push(vec![*peaks.next().unwrap()]);
+                mmr.offsets.push(count - 1);
+            } else {
+                mmr.levels.push(Vec::new());
+                mmr.offsets.push(count);
+            }
+        }
+        mmr.levels.reverse();
+        mmr.offsets.reverse();
+        Ok(mmr)
+    }
+
+    /// Append an entry CID
+    pub fn push(&mut self, cid: &Cid) {
+        let mut hash = leaf_hash(cid);
+        let mut level = 0;
+        loop {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+                self.offsets.push(0);
+            }
+            self.levels[level].push(hash);
+            let len = self.levels[level].len();
+            if !(self.offsets[level] + len as u64).is_multiple_of(2) {
+                break;
+            }
+            hash = node_hash(&self.levels[level][len - 2], &self.levels[level][len - 1]);
+            level += 1;
+        }
+    }
+
+    /// Drop every node only needed for proofs about the first `size` entries
+    pub fn prune(&mut self, size: u64) -> Result<()> {
+        if size > self.len() {
+            return Err(Error::InvalidContent(format!("accumulator has {} entries, not {size}", self.len())));
+        }
+        for (level, (nodes, offset)) in self.levels.iter_mut().zip(&mut self.offsets).enumerate() {
+            // Keep the peak over the pruned entries on this level, if any
+            let first = (size >> level) - ((size >> level) & 1);
+            if first > *offset {
+                nodes.drain(..(first - *offset) as usize);
+                *offset = first;
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| self.offsets[0] + leaves.len() as u64)
+    }
+
+    /// Check if nothing was appended
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Roots of the perfect subtrees, largest first
+    pub fn peaks(&self) -> Vec<NodeHash> {
+        let size = self.len();
+        (0..self.levels.len())
+            .rev()
+            .filter(|height| size & (1 << height) != 0)
+            .map(|height| *self.levels[height].last().unwrap())
+            .collect()
+    }
+
+    /// Root over all entries, or `None` when empty
+    pub fn root(&self) -> Option<NodeHash> {
+        bag_peaks(&self.peaks())
+    }
+
+    /// Root over the first `size` entries
+    pub fn root_at(&self, size: u64) -> Result<NodeHash> {
+        self.check_size(size)?;
+        self.subtree(0, size)
+    }
+
+    /// Proof that entry `index` is among the first `size` entries
+    pub fn inclusion_proof(&self, index: u64, size: u64) -> Result<InclusionProof> {
+        self.check_size(size)?;
+        if index >= size {
+            return Err(Error::InvalidContent(format!("entry {index} is not among the first {size}")));
+        }
+        let mut path = Vec::new();
+        self.inclusion_path(index, 0, size, &mut path)?;
+        Ok(InclusionProof {
+            leaf_index: index,
+            tree_size: size,
+            path,
+        })
+    }
+
+    /// Proof that the first `new_size` entries extend the first `old_size`
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Result<ConsistencyProof> {
+        self.check_size(new_size)?;
+        if old_size == 0 || old_size > new_size {
+            return Err(Error::InvalidContent(format!("no consistency proof from {old_size} to {new_size} entries")));
+        }
+        let mut path = Vec::new();
+        self.consistency_path(old_size, 0, new_size, true, &mut path)?;
+        Ok(ConsistencyProof {
+            old_size,
+            new_size,
+            path,
+        })
+    }
+
+    fn check_size(&self, size: u64) -> Result<()> {
+        if size == 0 || size > self.len() {
+            return Err(Error::InvalidContent(format!("accumulator has {} entries, not {size}", self.len())));
+        }
+        Ok(())
+    }
+
+    /// Merkle tree hash of leaves `start..end`
+    fn subtree(&self, start: u64, end: u64) -> Result<NodeHash> {
+        let n = end - start;
+        if n.is_power_of_two() && start.is_multiple_of(n) {
+            let height = n.trailing_zeros() as usize;
+            let index = start / n;
+            return index.checked_sub(self.offsets[height])
+                .and_then(|i| self.levels[height].get(i as usize))
+                .copied()
+                .ok_or_else(|| Error::InvalidContent(format!("entries {start}..{end} were pruned from the accumulator")));
+        }
+        let k = split(n);
+        Ok(node_hash(&self.subtree(start, start + k)?, &self.subtree(start + k, end)?))
+    }
+
+    fn inclusion_path(&self, index: u64, start: u64, end: u64, path: &mut Vec<NodeHash>) -> Result<()> {
+        let n = end - start;
+        if n <= 1 {
+            return Ok(());
+        }
+        let k = split(n);
+        if index < k {
+            self.inclusion_path(index, start, start + k, path)?;
+            path.push(self.subtree(start + k, end)?);
+        } else {
+            self.inclusion_path(index - k, start + k, end, path)?;
+            path.push(self.subtree(start, start + k)?);
+        }
+        Ok(())
+    }
+
+    fn consistency_path(&self, old_size: u64, start: u64, end: u64, complete: bool, path: &mut Vec<NodeHash>) -> Result<()> {
+        let n = end - start;
+        if old_size == n {
+            if !complete {
+                path.push(self.subtree(start, end)?);
+            }
+            return Ok(());
+        }
+        let k = split(n);
+        if old_size <= k {
+            self.consistency_path(old_size, start, start + k, complete, path)?;
+            path.push(self.subtree(start + k, end)?);
+        } else {
+            self.consistency_path(old_size - k, start + k, end, false, path)?;
+            path.push(self.subtree(start, start + k)?);
+        }
+        Ok(())
+    }
+}
+
+/// Root over an accumulator with the given peaks, largest first
+pub fn bag_peaks(peaks: &[NodeHash]) -> Option<NodeHash> {
+    let (last, rest) = peaks.split_last()?;
+    Some(rest.iter().rev().fold(*last, |acc, peak| node_hash(peak, &acc)))
+}
+
+/// Proof that an entry is part of a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// Position of the entry, which is its sequence number
+    pub leaf_index: u64,
+    /// Number of entries covered by the root
+    pub tree_size: u64,
+    /// Sibling hashes from the leaf up to the root
+    pub path: Vec<NodeHash>,
+}
+
+/// Proof that a chain extends an earlier version of itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    /// Number of entries of the earlier chain
+    pub old_size: u64,
+    /// Number of entries of the later chain
+    pub new_size: u64,
+    /// Subtree hashes linking both roots
+    pub path: Vec<NodeHash>,
+}
+
+fn to_hex(hash: &NodeHash) -> String {
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn proof_failed(what: &str, expected: &NodeHash, actual: &NodeHash) -> Error {
+    Error::ChainValidationError {
+        expected: format!("{what} {}", to_hex(expected)),
+        actual: to_hex(actual),
+    }
+}
+
+fn malformed(what: &str) -> Error {
+    Error::InvalidContent(format!("malformed {what} proof"))
+}
+
+/// Check that `cid` is entry `proof.leaf_index` of the chain with `root`
+pub fn verify_inclusion(cid: &Cid, proof: &InclusionProof, root: &NodeHash) -> Result<()> {
+    if proof.leaf_index >= proof.tree_size {
+        return Err(malformed("inclusion"));
+    }
+    let (mut index, mut last) = (proof.leaf_index, proof.tree_size - 1);
+    let mut hash = leaf_hash(cid);
+
+    for sibling in &proof.path {
+        if last == 0 {
+            return Err(malformed("inclusion"));
+        }
+        if index & 1 == 1 || index == last {
+            hash = node_hash(sibling, &hash);
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+
+    if last != 0 {
+        return Err(malformed("inclusion"));
+    }
+    if hash != *root {
+        return Err(proof_failed("inclusion root", root, &hash));
+    }
+    Ok(())
+}
+
+/// Check that the chain with `new_root` extends the chain with `old_root`
+pub fn verify_consistency(proof: &ConsistencyProof, old_root: &NodeHash, new_root: &NodeHash) -> Result<()> {
+    if proof.old_size == 0 || proof.old_size > proof.new_size {
+        return Err(malformed("consistency"));
+    }
+    if proof.old_size == proof.new_size {
+        if !proof.path.is_empty() {
+            return Err(malformed("consistency"));
+        }
+        if old_root != new_root {
+            return Err(proof_failed("root", old_root, new_root));
+        }
+        return Ok(());
+    }
+
+    let mut path = proof.path.iter();
+    let first = if proof.old_size.is_power_of_two() {
+        *old_root
+    } else {
+        *path.next().ok_or_else(|| malformed("consistency"))?
+    };
+    let (mut index, mut last) = (proof.old_size - 1, proof.new_size - 1);
+    while index & 1 == 1 {
+        index >>= 1;
+        last >>= 1;
+    }
+
+    let (mut old_hash, mut new_hash) = (first, first);
+    for node in path {
+        if last == 0 {
+            return Err(malformed("consistency"));
+        }
+        if index & 1 == 1 || index == last {
+            old_hash = node_hash(node, &old_hash);
+            new_hash = node_hash(node, &new_hash);
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            new_hash = node_hash(&new_hash, node);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+
+    if last != 0 {
+        return Err(malformed("consistency"));
+    }
+    if old_hash != *old_root {
+        return Err(proof_failed("old root", old_root, &old_hash));
+    }
+    if new_hash != *new_root {
+        return Err(proof_failed("new root", new_root, &new_hash));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cid(n: u64) -> Cid {
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(&n.to_le_bytes()).as_bytes()).unwrap();
+        Cid::new_v1(0x71, mh)
+    }
+
+    /// Reference RFC 6962 tree hash, computed from scratch
+    fn reference_root(cids: &[Cid]) -> NodeHash {
+        match cids.len() {
+            1 => leaf_hash(&cids[0]),
+            n => {
+                let k = split(n as u64) as usize;
+                node_hash(&reference_root(&cids[..k]), &reference_root(&cids[k..]))
+            }
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let cids: Vec<Cid> = (0..21).map(cid).collect();
+        let mut mmr = MerkleMountainRange::new();
+        for (i, cid) in cids.iter().enumerate() {
+            mmr.push(cid);
+            assert_eq!(mmr.root().unwrap(), reference_root(&cids[..=i]));
+        }
+        assert_eq!(mmr.peaks().len(), 3); // 21 = 16 + 4 + 1
+
+        for size in 1..=21 {
+            let root = mmr.root_at(size).unwrap();
+            for index in 0..size {
+                let proof = mmr.inclusion_proof(index, size).unwrap();
+                assert!(proof.path.len() <= 5);
+                verify_inclusion(&cids[index as usize], &proof, &root).unwrap();
+            }
+        }
+
+        let proof = mmr.inclusion_proof(3, 21).unwrap();
+        let root = mmr.root().unwrap();
+        assert!(verify_inclusion(&cids[4], &proof, &root).is_err());
+        let mut shifted = proof.clone();
+        shifted.leaf_index = 2;
+        assert!(verify_inclusion(&cids[3], &shifted, &root).is_err());
+        assert!(mmr.inclusion_proof(21, 21).is_err());
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let mut mmr = MerkleMountainRange::new();
+        for n in 0..13 {
+            mmr.push(&cid(n));
+        }
+
+        for new_size in 1..=13 {
+            let new_root = mmr.root_at(new_size).unwrap();
+            for old_size in 1..=new_size {
+                let proof = mmr.consistency_proof(old_size, new_size).unwrap();
+                verify_consistency(&proof, &mmr.root_at(old_size).unwrap(), &new_root).unwrap();
+            }
+        }
+
+        // A rewritten history does not extend the original
+        let mut forked = MerkleMountainRange::new();
+        for n in [0, 1, 2, 99] {
+            forked.push(&cid(n));
+        }
+        let proof = mmr.consistency_proof(4, 13).unwrap();
+        assert!(verify_consistency(&proof, &forked.root().unwrap(), &mmr.root().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_prune_and_restore_from_peaks() {
+        let mut mmr = MerkleMountainRange::new();
+        for n in 0..11 {
+            mmr.push(&cid(n));
+        }
+        let (peaks, root) = (mmr.peaks(), mmr.root().unwrap());
+        assert_eq!(root, mmr.root_at(11).unwrap());
+
+        let mut pruned = mmr.clone();
+        pruned.prune(11).unwrap();
+        let mut restored = MerkleMountainRange::from_peaks(11, &peaks).unwrap();
+        assert_eq!(restored.root(), Some(root));
+        assert!(MerkleMountainRange::from_peaks(11, &peaks[1..]).is_err());
+
+        for n in 11..20 {
+            mmr.push(&cid(n));
+            pruned.push(&cid(n));
+            restored.push(&cid(n));
+        }
+        assert_eq!(pruned.root(), mmr.root());
+        assert_eq!(restored.root(), mmr.root());
+
+        // Recent entries are still provable; pruned ones are not
+        let proof = restored.inclusion_proof(19, 20).unwrap();
+        verify_inclusion(&cid(19), &proof, &mmr.root().unwrap()).unwrap();
+        assert!(pruned.inclusion_proof(3, 20).is_err());
+        let proof = pruned.consistency_proof(11, 20).unwrap();
+        verify_consistency(&proof, &root, &mmr.root().unwrap()).unwrap();
+    }
+}
@@ -34,89 +34,141 @@
 //! // Verify chain properties
 //! assert_eq!(chained1.sequence, 0);
 //! assert_eq!(chained2.sequence, 1);
-//! assert_eq!(chained2.previous_cid, Some(chained1.cid.clone()));
+//! assert_eq!(chained2.previous_cid, Some(chained1.cid));
 //! # Ok(())
 //! # }
 //! ```
 
 use crate::{Cid, Error, Result, TypedContent};
+use crate::util::cid_serde;
+use ed25519_dalek::{SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use serde_cbor::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::{Bound, RangeBounds};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod checkpoint;
+mod dag;
+mod mmr;
+mod signing;
+
+pub use checkpoint::Checkpoint;
+pub use dag::{ContentDag, DagEntry, Fold, LastWriterWins, MergeStrategy};
+pub use mmr::{
+    bag_peaks, leaf_hash, verify_consistency, verify_inclusion, ConsistencyProof, InclusionProof, MerkleMountainRange,
+    NodeHash,
+};
+pub use signing::{EntrySignature, TrustPolicy};
+use signing::TrustState;
+
+/// Codec of chain entry blocks (DAG-CBOR)
+pub const CHAIN_ENTRY_CODEC: u64 = 0x71;
+
+/// CBOR tag of an IPLD CID link
+const CID_LINK_TAG: u64 = 42;
 
 /// A content item with chain linking
+///
+/// Each item is an IPLD block encoded as DAG-CBOR:
+/// `{"content": <content>, "previous": <CID link or null>, "sequence": <n>}`.
+/// The CID is the BLAKE3 hash of exactly those bytes, so any IPLD tool can
+/// decode an item and follow `previous` to walk the chain.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainedContent<T> {
     /// The actual content
     pub content: T,
 
-    /// Content identifier for this item
-    pub cid: String, // Store as string for easier serialization
+    /// Content identifier of this item's block
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
 
     /// CID of the previous item in the chain
-    pub previous_cid: Option<String>,
+    #[serde(with = "cid_serde::option")]
+    pub previous_cid: Option<Cid>,
 
     /// Sequence number in the chain
     pub sequence: u64,
 
     /// Timestamp when the item was chained
+    ///
+    /// Local metadata, not part of the block: items decoded with
+    /// [`ChainedContent::from_block`] carry the Unix epoch.
     pub timestamp: SystemTime,
+
+    /// Optional signature over the CID and sequence number
+    ///
+    /// Not part of the block, so signing does not change the CID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<EntrySignature>,
 }
 
 impl<T: TypedContent> ChainedContent<T> {
     /// Create a new chained content item
     pub fn new(content: T, previous: Option<&ChainedContent<T>>) -> Result<Self> {
         let sequence = previous.map(|p| p.sequence + 1).unwrap_or(0);
-        let previous_cid = previous.map(|p| p.cid.clone());
+        let previous_cid = previous.map(|p| p.cid);
         let timestamp = SystemTime::now();
 
-        // Create a temporary item to calculate CID
-        let mut chained = Self {
+        let block = encode_block(&content, previous_cid.as_ref(), sequence)?;
+        Ok(Self {
             content,
-            cid: String::new(), // Temporary
+            cid: block_cid(&block)?,
             previous_cid,
             sequence,
             timestamp,
-        };
-
-        // Calculate and set the actual CID
-        chained.cid = chained.calculate_cid()?;
+            signature: None,
+        })
+    }
 
-        Ok(chained)
+    /// Encode this item as its DAG-CBOR block
+    pub fn to_block(&self) -> Result<Vec<u8>> {
+        encode_block(&self.content, self.previous_cid.as_ref(), self.sequence)
     }
 
-    /// Calculate the CID for this chained content
-    fn calculate_cid(&self) -> Result<String> {
-        // Create a deterministic representation for hashing
-        let chain_data = ChainData {
-            content: &self.content,
-            previous_cid: &self.previous_cid,
-            sequence: self.sequence,
-            // Don't include timestamp in CID calculation for determinism
+    /// Decode an item from its DAG-CBOR block
+    ///
+    /// The CID is computed from `block`, which must be in canonical form.
+    pub fn from_block(block: &[u8]) -> Result<Self> {
+        let value: Value = serde_cbor::from_slice(block)
+            .map_err(|e| Error::CborError(e.to_string()))?;
+        let Value::Map(mut fields) = value else {
+            return Err(Error::InvalidContent("chain entry is not a map".to_string()));
+        };
+        let mut field = |name: &str| {
+            fields.remove(&Value::Text(name.to_string()))
+                .ok_or_else(|| Error::InvalidContent(format!("chain entry has no {name}")))
         };
 
-        // Serialize to calculate CID
-        let bytes = serde_json::to_vec(&chain_data)?;
-
-        // Create hash using BLAKE3
-        let hash = blake3::hash(&bytes);
-        let hash_bytes = hash.as_bytes();
-
-        // Create multihash manually with BLAKE3 code (0x1e)
-        let code = 0x1e; // BLAKE3-256
-        let size = hash_bytes.len() as u8;
-
-        // Build multihash: <varint code><varint size><hash>
-        let mut multihash_bytes = Vec::new();
-        multihash_bytes.push(code);
-        multihash_bytes.push(size);
-        multihash_bytes.extend_from_slice(hash_bytes);
+        let content: T = serde_cbor::value::from_value(field("content")?)
+            .map_err(|e| Error::CborError(e.to_string()))?;
+        let previous_cid = match field("previous")? {
+            Value::Null => None,
+            link => Some(decode_link(link)?),
+        };
+        let sequence = match field("sequence")? {
+            Value::Integer(n) => u64::try_from(n)
+                .map_err(|_| Error::InvalidContent(format!("invalid chain sequence {n}")))?,
+            other => return Err(Error::InvalidContent(format!("invalid chain sequence {other:?}"))),
+        };
 
-        // Create CID v1
-        let mh = multihash::Multihash::from_bytes(&multihash_bytes)
-            .map_err(|e| Error::MultihashError(e.to_string()))?;
-        let cid = Cid::new_v1(T::CODEC, mh);
+        let item = Self {
+            content,
+            cid: block_cid(block)?,
+            previous_cid,
+            sequence,
+            timestamp: UNIX_EPOCH,
+            signature: None,
+        };
+        if item.to_block()? != block {
+            return Err(Error::InvalidContent(format!("chain entry {} is not canonical DAG-CBOR", item.cid)));
+        }
+        Ok(item)
+    }
 
-        Ok(cid.to_string())
+    /// Calculate the CID for this chained content
+    fn calculate_cid(&self) -> Result<Cid> {
+        block_cid(&self.to_block()?)
     }
 
     /// Validate this item against a previous item
@@ -136,8 +188,8 @@ impl<T: TypedContent> ChainedContent<T> {
                 // Validate CID link
                 if prev.cid != *prev_cid {
                     return Err(Error::ChainValidationError {
-                        expected: prev.cid.clone(),
-                        actual: prev_cid.clone(),
+                        expected: prev.cid.to_string(),
+                        actual: prev_cid.to_string(),
                     });
                 }
                 // Validate sequence
@@ -151,13 +203,18 @@ impl<T: TypedContent> ChainedContent<T> {
             // Mismatch
             _ => {
                 return Err(Error::ChainValidationError {
-                    expected: previous.map(|p| p.cid.clone()).unwrap_or_default(),
-                    actual: self.previous_cid.clone().unwrap_or_default(),
+                    expected: previous.map(|p| p.cid.to_string()).unwrap_or_default(),
+                    actual: self.previous_cid.map(|c| c.to_string()).unwrap_or_default(),
                 });
             }
         }
 
         // Verify our own CID
+        self.verify_cid()
+    }
+
+    /// Check that the stored CID matches the item's content and links
+    pub fn verify_cid(&self) -> Result<()> {
         let calculated_cid = self.calculate_cid()?;
         if calculated_cid != self.cid {
             return Err(Error::InvalidCid(format!("CID mismatch: expected {}, calculated {}", self.cid, calculated_cid)));
@@ -172,44 +229,374 @@ impl<T: TypedContent> ChainedContent<T> {
     }
 }
 
-/// Content used for CID calculation (excludes mutable fields)
-#[derive(Serialize)]
-struct ChainData<'a, T: Serialize> {
-    content: &'a T,
-    previous_cid: &'a Option<String>,
-    sequence: u64,
+/// Encode the block of a chain item
+///
+/// Map keys are ordered by [`Value`]'s canonical ordering (shorter keys
+/// first, then bytewise), as DAG-CBOR requires. See [`to_dag_cbor`] for
+/// floats.
+fn encode_block<T: Serialize>(content: &T, previous: Option<&Cid>, sequence: u64) -> Result<Vec<u8>> {
+    let content = serde_cbor::value::to_value(content)
+        .map_err(|e| Error::CborError(e.to_string()))?;
+    let previous = match previous {
+        Some(cid) => encode_link(cid),
+        None => Value::Null,
+    };
+
+    let mut fields = BTreeMap::new();
+    fields.insert(Value::Text("content".to_string()), content);
+    fields.insert(Value::Text("previous".to_string()), previous);
+    fields.insert(Value::Text("sequence".to_string()), Value::Integer(sequence.into()));
+    to_dag_cbor(&Value::Map(fields))
+}
+
+/// Serialize a value as DAG-CBOR
+///
+/// `serde_cbor` writes each float in the shortest width that holds it
+/// exactly and accepts NaN and infinities; DAG-CBOR allows only finite
+/// floats, always in 64 bits. Everything else is encoded as `serde_cbor`
+/// does, with the shortest argument lengths and definite lengths.
+pub(crate) fn to_dag_cbor(value: &Value) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    write_dag_cbor(&mut out, value)?;
+    Ok(out)
+}
+
+fn write_dag_cbor(out: &mut Vec<u8>, value: &Value) -> Result<()> {
+    fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+        let major = major << 5;
+        match n {
+            0..=23 => out.push(major | n as u8),
+            24..=0xff => out.extend([major | 24, n as u8]),
+            0x100..=0xffff => {
+                out.push(major | 25);
+                out.extend((n as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                out.push(major | 26);
+                out.extend((n as u32).to_be_bytes());
+            }
+            _ => {
+                out.push(major | 27);
+                out.extend(n.to_be_bytes());
+            }
+        }
+    }
+
+    match value {
+        Value::Null => out.push(0xf6),
+        Value::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        Value::Integer(n) => {
+            let (major, arg) = if *n >= 0 { (0, *n) } else { (1, -1 - *n) };
+            let arg = u64::try_from(arg)
+                .map_err(|_| Error::CborError(format!("integer {n} does not fit in 64 bits")))?;
+            head(out, major, arg);
+        }
+        Value::Float(f) => {
+            if !f.is_finite() {
+                return Err(Error::CborError(format!("DAG-CBOR cannot encode {f}")));
+            }
+            out.push(0xfb);
+            out.extend(f.to_be_bytes());
+        }
+        Value::Bytes(bytes) => {
+            head(out, 2, bytes.len() as u64);
+            out.extend(bytes);
+        }
+        Value::Text(text) => {
+            head(out, 3, text.len() as u64);
+            out.extend(text.as_bytes());
+        }
+        Value::Array(items) => {
+            head(out, 4, items.len() as u64);
+            for item in items {
+                write_dag_cbor(out, item)?;
+            }
+        }
+        Value::Map(fields) => {
+            head(out, 5, fields.len() as u64);
+            for (key, value) in fields {
+                write_dag_cbor(out, key)?;
+                write_dag_cbor(out, value)?;
+            }
+        }
+        Value::Tag(tag, inner) => {
+            head(out, 6, *tag);
+            write_dag_cbor(out, inner)?;
+        }
+        other => return Err(Error::CborError(format!("unsupported CBOR value {other:?}"))),
+    }
+    Ok(())
+}
+
+/// A CID link: tag 42 over the CID bytes with a leading multibase identity prefix
+fn encode_link(cid: &Cid) -> Value {
+    let mut bytes = vec![0x00];
+    bytes.extend(cid.to_bytes());
+    Value::Tag(CID_LINK_TAG, Box::new(Value::Bytes(bytes)))
+}
+
+fn decode_link(value: Value) -> Result<Cid> {
+    match value {
+        Value::Tag(CID_LINK_TAG, inner) => match *inner {
+            Value::Bytes(bytes) if bytes.first() == Some(&0x00) => {
+                Cid::try_from(&bytes[1..]).map_err(|e| Error::InvalidCid(e.to_string()))
+            }
+            _ => Err(Error::InvalidCid("malformed CID link".to_string())),
+        },
+        other => Err(Error::InvalidCid(format!("expected a CID link, got {other:?}"))),
+    }
+}
+
+/// BLAKE3 CID of a DAG-CBOR block
+fn block_cid(block: &[u8]) -> Result<Cid> {
+    let hash = blake3::hash(block);
+    let mh = multihash::Multihash::wrap(0x1e, hash.as_bytes())
+        .map_err(|e| Error::MultihashError(e.to_string()))?;
+    Ok(Cid::new_v1(CHAIN_ENTRY_CODEC, mh))
 }
 
 /// A chain of content items with validation
+///
+/// Signed entries always have their signatures checked. With a
+/// [`TrustPolicy`], every entry must also be signed by a trusted key.
+///
+/// A chain pruned at, or loaded from, a [`Checkpoint`] starts at the
+/// checkpoint entry instead of genesis.
 #[derive(Debug, Clone)]
 pub struct ContentChain<T: TypedContent> {
     items: Vec<ChainedContent<T>>,
+    mmr: MerkleMountainRange,
+    trust: Option<TrustPolicy>,
+    trust_state: TrustState,
+    base: Option<Checkpoint>,
+    checkpoint_interval: Option<u64>,
+    checkpoints: Vec<Checkpoint>,
+    /// Sequence number of every entry by CID
+    by_cid: HashMap<Cid, u64>,
+    /// Entries ordered by timestamp, then sequence number
+    by_time: BTreeSet<(SystemTime, u64)>,
 }
 
 impl<T: TypedContent> ContentChain<T> {
     /// Create a new empty chain
     pub fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            items: Vec::new(),
+            mmr: MerkleMountainRange::new(),
+            trust: None,
+            trust_state: TrustState::default(),
+            base: None,
+            checkpoint_interval: None,
+            checkpoints: Vec::new(),
+            by_cid: HashMap::new(),
+            by_time: BTreeSet::new(),
+        }
+    }
+
+    /// Record a checkpoint every `interval` entries
+    ///
+    /// Checkpoints are taken as entries are added, at sequence numbers
+    /// `interval - 1`, `2 * interval - 1`, and so on.
+    pub fn with_checkpoint_interval(mut self, interval: u64) -> Self {
+        self.checkpoint_interval = Some(interval.max(1));
+        self
+    }
+
+    /// Require every entry to be signed by a key trusted by `policy`
+    ///
+    /// Entries already in the chain are not rejected here; call
+    /// [`ContentChain::validate`] to check them.
+    pub fn with_trust_policy(mut self, policy: TrustPolicy) -> Self {
+        let mut state = TrustState::default();
+        for item in &self.items {
+            if state.check(&policy, item).is_err() {
+                break;
+            }
+        }
+        self.trust = Some(policy);
+        self.trust_state = state;
+        self
+    }
+
+    /// Trust policy entries must satisfy, if any
+    pub fn trust_policy(&self) -> Option<&TrustPolicy> {
+        self.trust.as_ref()
     }
 
     /// Add content to the chain
     pub fn append(&mut self, content: T) -> Result<&ChainedContent<T>> {
-        let previous = self.items.last();
-        let chained = ChainedContent::new(content, previous)?;
+        let chained = ChainedContent::new(content, self.items.last())?;
+        self.push(chained)
+    }
 
-        // Validate the chain
-        chained.validate_chain(previous)?;
+    /// Add content to the chain, signed by `key`
+    pub fn append_signed(&mut self, content: T, key: &SigningKey) -> Result<&ChainedContent<T>> {
+        let chained = ChainedContent::new(content, self.items.last())?.sign(key);
+        self.push(chained)
+    }
 
-        self.items.push(chained);
+    /// Add content signed by `key`, handing the chain over to `next_key`
+    pub fn append_rotating(&mut self, content: T, key: &SigningKey, next_key: &VerifyingKey) -> Result<&ChainedContent<T>> {
+        let chained = ChainedContent::new(content, self.items.last())?.sign_with_rotation(key, next_key);
+        self.push(chained)
+    }
+
+    /// Add an already chained item, which must link to the current head
+    pub fn push(&mut self, item: ChainedContent<T>) -> Result<&ChainedContent<T>> {
+        item.validate_chain(self.items.last())?;
+        match &self.trust {
+            Some(policy) => {
+                let mut state = self.trust_state.clone();
+                state.check(policy, &item)?;
+                self.trust_state = state;
+            }
+            None if item.signature.is_some() => {
+                item.verify_signature()?;
+            }
+            None => {}
+        }
+        self.mmr.push(&item.cid);
+        self.index(item);
+        if let Some(interval) = self.checkpoint_interval {
+            if self.mmr.len().is_multiple_of(interval) {
+                let checkpoint = self.checkpoint().unwrap();
+                self.checkpoints.push(checkpoint);
+            }
+        }
         Ok(self.items.last().unwrap())
     }
 
+    /// Rebuild a chain from its items in sequence order, validating every link
+    pub fn from_items(items: impl IntoIterator<Item = ChainedContent<T>>) -> Result<Self> {
+        let mut chain = Self::new();
+        for item in items {
+            chain.push(item)?;
+        }
+        Ok(chain)
+    }
+
+    /// Continue a chain from a trusted checkpoint
+    ///
+    /// `items` start with the checkpoint entry, whose link to earlier
+    /// entries is taken on trust; every later entry is validated. Key
+    /// rotations made before the checkpoint are not replayed, so a trust
+    /// policy must trust the keys in use from the checkpoint on.
+    pub fn from_checkpoint(checkpoint: Checkpoint, items: impl IntoIterator<Item = ChainedContent<T>>) -> Result<Self> {
+        let mut items = items.into_iter();
+        let first = items.next().ok_or_else(|| Error::ChainValidationError {
+            expected: checkpoint.cid.to_string(),
+            actual: "no entries".to_string(),
+        })?;
+        check_checkpoint(&first, &checkpoint)?;
+        if first.signature.is_some() {
+            first.verify_signature()?;
+        }
+
+        let mut chain = Self::new();
+        chain.mmr = MerkleMountainRange::from_peaks(checkpoint.entries(), &checkpoint.peaks)?;
+        if chain.mmr.root() != Some(checkpoint.root()) {
+            return Err(Error::InvalidContent("checkpoint peaks do not form a root".to_string()));
+        }
+        chain.index(first);
+        chain.base = Some(checkpoint);
+        for item in items {
+            chain.push(item)?;
+        }
+        Ok(chain)
+    }
+
+    /// Checkpoint at the current head, or `None` when empty
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        let head = self.items.last()?;
+        Some(Checkpoint {
+            sequence: head.sequence,
+            cid: head.cid,
+            peaks: self.mmr.peaks(),
+            snapshot: None,
+        })
+    }
+
+    /// Checkpoints recorded at the configured interval, oldest first
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Checkpoint the chain starts from, if it does not start at genesis
+    pub fn base_checkpoint(&self) -> Option<&Checkpoint> {
+        self.base.as_ref()
+    }
+
+    /// Drop every entry before `checkpoint` and return how many were dropped
+    ///
+    /// The checkpoint entry becomes the first item; its `previous_cid` still
+    /// names the last pruned entry. The accumulator keeps its root, but
+    /// inclusion proofs for pruned entries are no longer available.
+    pub fn prune(&mut self, checkpoint: &Checkpoint) -> Result<usize> {
+        let index = self.index_of(checkpoint.sequence)
+            .ok_or(Error::SequenceValidationError {
+                expected: checkpoint.sequence,
+                actual: self.items.first().map_or(0, |item| item.sequence),
+            })?;
+        check_checkpoint(&self.items[index], checkpoint)?;
+
+        self.mmr.prune(checkpoint.entries())?;
+        for item in self.items.drain(..index) {
+            self.by_cid.remove(&item.cid);
+            self.by_time.remove(&(item.timestamp, item.sequence));
+        }
+        self.checkpoints.retain(|c| c.sequence >= checkpoint.sequence);
+        self.base = Some(checkpoint.clone());
+        Ok(index)
+    }
+
+    fn index_of(&self, sequence: u64) -> Option<usize> {
+        let first = self.items.first()?.sequence;
+        let index = usize::try_from(sequence.checked_sub(first)?).ok()?;
+        (index < self.items.len()).then_some(index)
+    }
+
     /// Validate the entire chain
+    ///
+    /// Checks every link, CID, and signature, and with a trust policy that
+    /// every entry is signed by a key trusted at its position in the chain.
     pub fn validate(&self) -> Result<()> {
+        match &self.base {
+            Some(checkpoint) => self.validate_from(checkpoint),
+            None => self.validate_items(&self.items, false),
+        }
+    }
+
+    /// Validate the entries from a trusted checkpoint to the head
+    ///
+    /// Entries before the checkpoint are not re-hashed, so the cost only
+    /// depends on how many entries were added since.
+    pub fn validate_from(&self, checkpoint: &Checkpoint) -> Result<()> {
+        let index = self.index_of(checkpoint.sequence)
+            .ok_or(Error::SequenceValidationError {
+                expected: checkpoint.sequence,
+                actual: self.items.first().map_or(0, |item| item.sequence),
+            })?;
+        check_checkpoint(&self.items[index], checkpoint)?;
+        self.validate_items(&self.items[index..], true)
+    }
+
+    /// Validate consecutive items, optionally taking the first item's link on trust
+    fn validate_items(&self, items: &[ChainedContent<T>], trust_first_link: bool) -> Result<()> {
         let mut previous: Option<&ChainedContent<T>> = None;
+        let mut state = TrustState::default();
 
-        for item in &self.items {
-            item.validate_chain(previous)?;
+        for item in items {
+            match previous {
+                None if trust_first_link => item.verify_cid()?,
+                _ => item.validate_chain(previous)?,
+            }
+            match &self.trust {
+                Some(policy) => state.check(policy, item)?,
+                None if item.signature.is_some() => {
+                    item.verify_signature()?;
+                }
+                None => {}
+            }
             previous = Some(item);
         }
 
@@ -236,20 +623,110 @@ impl<T: TypedContent> ContentChain<T> {
         &self.items
     }
 
+    /// Accumulator over the entry CIDs, kept in step with the chain
+    pub fn accumulator(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    /// Accumulator root over every entry, or `None` when empty
+    pub fn root(&self) -> Option<NodeHash> {
+        self.mmr.root()
+    }
+
+    /// Proof that the entry `cid` is part of the chain up to its current head
+    pub fn inclusion_proof(&self, cid: &Cid) -> Result<InclusionProof> {
+        let index = self.position(cid)?;
+        self.mmr.inclusion_proof(self.items[index].sequence, self.mmr.len())
+    }
+
+    /// Proof that the chain extends its first `old_len` entries
+    pub fn consistency_proof(&self, old_len: usize) -> Result<ConsistencyProof> {
+        self.mmr.consistency_proof(old_len as u64, self.mmr.len())
+    }
+
+    /// Add an item to the end of the chain and its indexes
+    fn index(&mut self, item: ChainedContent<T>) {
+        self.by_cid.insert(item.cid, item.sequence);
+        self.by_time.insert((item.timestamp, item.sequence));
+        self.items.push(item);
+    }
+
+    fn position(&self, cid: &Cid) -> Result<usize> {
+        self.by_cid
+            .get(cid)
+            .and_then(|sequence| self.index_of(*sequence))
+            .ok_or_else(|| Error::InvalidCid(format!("CID not found in chain: {cid}")))
+    }
+
+    /// Entry with the given CID
+    pub fn get_by_cid(&self, cid: &Cid) -> Option<&ChainedContent<T>> {
+        self.position(cid).ok().map(|index| &self.items[index])
+    }
+
+    /// Entry with the given sequence number
+    pub fn get_by_sequence(&self, sequence: u64) -> Option<&ChainedContent<T>> {
+        self.index_of(sequence).map(|index| &self.items[index])
+    }
+
+    /// Entries whose sequence numbers fall in `sequences`
+    ///
+    /// Sequence numbers outside the chain, e.g. pruned ones, are skipped.
+    pub fn range(&self, sequences: impl RangeBounds<u64>) -> &[ChainedContent<T>] {
+        let Some(first) = self.items.first().map(|item| item.sequence) else {
+            return &[];
+        };
+        let offset = |sequence: u64| sequence.saturating_sub(first).min(self.items.len() as u64) as usize;
+        let start = match sequences.start_bound() {
+            Bound::Included(s) => offset(*s),
+            Bound::Excluded(s) => offset(s.saturating_add(1)),
+            Bound::Unbounded => 0,
+        };
+        let end = match sequences.end_bound() {
+            Bound::Included(s) => offset(s.saturating_add(1)),
+            Bound::Excluded(s) => offset(*s),
+            Bound::Unbounded => self.items.len(),
+        };
+        &self.items[start..end.max(start)]
+    }
+
+    /// Entries timestamped in `[from, until)`, oldest first
+    pub fn between(&self, from: SystemTime, until: SystemTime) -> Vec<&ChainedContent<T>> {
+        if from >= until {
+            return Vec::new();
+        }
+        self.by_time
+            .range((from, 0)..(until, 0))
+            .filter_map(|(_, sequence)| self.get_by_sequence(*sequence))
+            .collect()
+    }
+
     /// Get items since a specific CID
-    pub fn items_since(&self, cid: &str) -> Result<Vec<&ChainedContent<T>>> {
+    pub fn items_since(&self, cid: &Cid) -> Result<Vec<&ChainedContent<T>>> {
         // Find the item with the given CID
-        let start_idx = self
-            .items
-            .iter()
-            .position(|e| e.cid == cid)
-            .ok_or_else(|| Error::InvalidCid(format!("CID not found in chain: {cid}")))?;
+        let start_idx = self.position(cid)?;
 
         // Return all items after that one
         Ok(self.items[start_idx + 1..].iter().collect())
     }
 }
 
+/// Check that `item` is the entry pinned by `checkpoint`
+fn check_checkpoint<T: TypedContent>(item: &ChainedContent<T>, checkpoint: &Checkpoint) -> Result<()> {
+    if item.sequence != checkpoint.sequence {
+        return Err(Error::SequenceValidationError {
+            expected: checkpoint.sequence,
+            actual: item.sequence,
+        });
+    }
+    if item.cid != checkpoint.cid {
+        return Err(Error::ChainValidationError {
+            expected: checkpoint.cid.to_string(),
+            actual: item.cid.to_string(),
+        });
+    }
+    item.verify_cid()
+}
+
 impl<T: TypedContent> Default for ContentChain<T> {
     fn default() -> Self {
         Self::new()
@@ -259,6 +736,11 @@ impl<T: TypedContent> Default for ContentChain<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn fake_cid(name: &str) -> Cid {
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(name.as_bytes()).as_bytes()).unwrap();
+        Cid::new_v1(0x71, mh)
+    }
     use crate::ContentType;
     use serde::{Deserialize, Serialize};
 
@@ -287,10 +769,10 @@ mod tests {
         // Then
         assert_eq!(chained.sequence, 0);
         assert!(chained.previous_cid.is_none());
-        assert!(!chained.cid.is_empty());
+        assert_eq!(chained.cid.codec(), CHAIN_ENTRY_CODEC);
 
         // Verify CID format
-        let cid = ChainedContent::<TestContent>::parse_cid(&chained.cid).unwrap();
+        let cid = ChainedContent::<TestContent>::parse_cid(&chained.cid.to_string()).unwrap();
         assert_eq!(cid.version(), cid::Version::V1);
     }
 
@@ -309,11 +791,11 @@ mod tests {
 
         // When
         let chained1 = chain.append(content1).unwrap();
-        let cid1 = chained1.cid.clone();
+        let cid1 = chained1.cid;
 
         let chained2 = chain.append(content2).unwrap();
         let sequence2 = chained2.sequence;
-        let prev_cid2 = chained2.previous_cid.clone();
+        let prev_cid2 = chained2.previous_cid;
 
         // Then
         assert_eq!(chain.len(), 2);
@@ -329,7 +811,7 @@ mod tests {
         assert_eq!(chain.len(), 0);
         assert!(chain.head().is_none());
         assert!(chain.validate().is_ok());
-        assert!(chain.items_since("invalid-cid").is_err());
+        assert!(chain.items_since(&fake_cid("invalid-cid")).is_err());
     }
 
     #[test]
@@ -382,7 +864,7 @@ mod tests {
         assert_eq!(items.len(), 4);
         
         // Test with non-existent CID
-        assert!(chain.items_since("non-existent-cid").is_err());
+        assert!(chain.items_since(&fake_cid("non-existent-cid")).is_err());
     }
 
     #[test]
@@ -399,7 +881,7 @@ mod tests {
         let mut item2 = ChainedContent::new(content, Some(&item1)).unwrap();
         
         // Tamper with the previous CID
-        item2.previous_cid = Some("wrong-cid".to_string());
+        item2.previous_cid = Some(fake_cid("wrong-cid"));
         
         // Validation should fail
         assert!(item2.validate_chain(Some(&item1)).is_err());
@@ -522,7 +1004,7 @@ mod tests {
                 data: format!("Data {i}"),
             };
             let chained = chain.append(content).unwrap();
-            cids.push(chained.cid.clone());
+            cids.push(chained.cid);
         }
 
         // When - get items since index 2
@@ -584,7 +1066,7 @@ mod tests {
         
         // Test case 1: Item has previous_cid but no previous item provided
         let mut chained_with_prev = ChainedContent::new(content1.clone(), None).unwrap();
-        chained_with_prev.previous_cid = Some("fake-cid".to_string());
+        chained_with_prev.previous_cid = Some(fake_cid("fake-cid"));
         chained_with_prev.sequence = 1;
         
         let result = chained_with_prev.validate_chain(None);
@@ -592,7 +1074,7 @@ mod tests {
         match result {
             Err(Error::ChainValidationError { expected, actual }) => {
                 assert_eq!(expected, "");
-                assert_eq!(actual, "fake-cid");
+                assert_eq!(actual, fake_cid("fake-cid").to_string());
             }
             _ => panic!("Expected ChainValidationError"),
         }
@@ -606,7 +1088,7 @@ mod tests {
         assert!(result.is_err());
         match result {
             Err(Error::ChainValidationError { expected, actual }) => {
-                assert_eq!(expected, first_item.cid);
+                assert_eq!(expected, first_item.cid.to_string());
                 assert_eq!(actual, "");
             }
             _ => panic!("Expected ChainValidationError"),
@@ -624,7 +1106,7 @@ mod tests {
         let mut chained = ChainedContent::new(content, None).unwrap();
         
         // Tamper with the CID
-        chained.cid = "bafyreigdyrzt5sfp7udm7hu76uh7y26fake".to_string();
+        chained.cid = fake_cid("tampered");
         
         // Validation should detect CID mismatch
         let result = chained.validate_chain(None);
@@ -640,24 +1122,70 @@ mod tests {
     }
 
     #[test]
-    fn test_chain_data_serialization() {
-        // Test the ChainData struct serialization
-        let content = TestContent {
-            id: "serialize-test".to_string(),
+    fn test_block_encoding() {
+        let first = ChainedContent::new(TestContent {
+            id: "block-test".to_string(),
             data: "data".to_string(),
-        };
-        
-        let chain_data = ChainData {
-            content: &content,
-            previous_cid: &Some("previous-cid".to_string()),
-            sequence: 42,
-        };
-        
-        // Serialize and verify it works
-        let serialized = serde_json::to_string(&chain_data).unwrap();
-        assert!(serialized.contains("serialize-test"));
-        assert!(serialized.contains("previous-cid"));
-        assert!(serialized.contains("42"));
+        }, None).unwrap();
+        let second = ChainedContent::new(first.content.clone(), Some(&first)).unwrap();
+
+        // The CID is the hash of the block, and the block decodes to the same item
+        let block = second.to_block().unwrap();
+        assert_eq!(block_cid(&block).unwrap(), second.cid);
+        let decoded = ChainedContent::<TestContent>::from_block(&block).unwrap();
+        assert_eq!(decoded.content, second.content);
+        assert_eq!(decoded.previous_cid, Some(first.cid));
+        assert_eq!(decoded.sequence, 1);
+        decoded.validate_chain(Some(&first)).unwrap();
+
+        // `previous` is a tag 42 CID link that generic DAG-CBOR decoders follow
+        let value: Value = serde_cbor::from_slice(&block).unwrap();
+        let Value::Map(fields) = value else { panic!("block is not a map") };
+        let previous = fields.get(&Value::Text("previous".to_string())).unwrap();
+        assert!(matches!(previous, Value::Tag(42, _)));
+        assert_eq!(decode_link(previous.clone()).unwrap(), first.cid);
+
+        // Non-canonical encodings are rejected
+        let reordered = serde_cbor::to_vec(&serde_json::json!({
+            "sequence": 0, "previous": null, "content": {"id": "x", "data": "y"}
+        })).unwrap();
+        assert!(ChainedContent::<TestContent>::from_block(&reordered).is_err());
+    }
+
+    #[test]
+    fn test_floats_are_encoded_as_dag_cbor() {
+        #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+        struct Reading {
+            value: f64,
+        }
+
+        impl TypedContent for Reading {
+            const CODEC: u64 = 0x300000;
+            const CONTENT_TYPE: ContentType = ContentType::Event;
+        }
+
+        // 0.5 fits in a half float, but DAG-CBOR always uses 64 bits
+        let entry = ChainedContent::new(Reading { value: 0.5 }, None).unwrap();
+        let block = entry.to_block().unwrap();
+        let mut float = vec![0xfb];
+        float.extend(0.5f64.to_be_bytes());
+        assert!(block.windows(float.len()).any(|w| w == float.as_slice()));
+        let decoded = ChainedContent::<Reading>::from_block(&block).unwrap();
+        assert_eq!(decoded.content, entry.content);
+        assert_eq!(decoded.cid, entry.cid);
+
+        // The shortened encoding serde_cbor would write is not canonical
+        let shortened = serde_cbor::to_vec(&serde_cbor::value::to_value(BTreeMap::from([
+            ("content", serde_cbor::value::to_value(&entry.content).unwrap()),
+            ("previous", Value::Null),
+            ("sequence", Value::Integer(0)),
+        ])).unwrap()).unwrap();
+        assert_ne!(shortened, block);
+        assert!(ChainedContent::<Reading>::from_block(&shortened).is_err());
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(ChainedContent::new(Reading { value }, None), Err(Error::CborError(_))));
+        }
     }
 
     #[test]
@@ -672,7 +1200,7 @@ mod tests {
         let chained = ChainedContent::new(content, None).unwrap();
         
         // The multihash creation with valid BLAKE3 should succeed
-        assert!(!chained.cid.is_empty());
+        assert_eq!(chained.cid.codec(), CHAIN_ENTRY_CODEC);
         
         // Test with invalid multihash bytes
         let invalid_bytes = vec![0xff, 0xff]; // Invalid varint
@@ -715,15 +1243,15 @@ mod tests {
         
         // Create second item with corrupted previous_cid
         let mut second = ChainedContent::new(content2, Some(&first)).unwrap();
-        second.previous_cid = Some("corrupted-cid".to_string());
+        second.previous_cid = Some(fake_cid("corrupted-cid"));
         
         // Validation should fail
         let result = second.validate_chain(Some(&first));
         assert!(result.is_err());
         match result {
             Err(Error::ChainValidationError { expected, actual }) => {
-                assert_eq!(expected, first.cid);
-                assert_eq!(actual, "corrupted-cid");
+                assert_eq!(expected, first.cid.to_string());
+                assert_eq!(actual, fake_cid("corrupted-cid").to_string());
             }
             _ => panic!("Expected ChainValidationError"),
         }
@@ -771,7 +1299,7 @@ mod tests {
         assert!(chain.validate().is_ok());
         
         // Test items_since with any CID on empty chain
-        let result = chain.items_since("any-cid");
+        let result = chain.items_since(&fake_cid("any-cid"));
         assert!(result.is_err());
         match result {
             Err(Error::InvalidCid(msg)) => {
@@ -797,7 +1325,7 @@ mod tests {
         // Manually create and add a corrupted second item
         let mut corrupted = ChainedContent::new(content1, None).unwrap();
         corrupted.sequence = 1;
-        corrupted.previous_cid = Some("wrong-cid".to_string());
+        corrupted.previous_cid = Some(fake_cid("wrong-cid"));
         
         // Force add the corrupted item
         chain.items.push(corrupted);
@@ -807,6 +1335,117 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_chain_proofs() {
+        let mut chain = ContentChain::new();
+        for i in 0..6 {
+            chain.append(TestContent {
+                id: format!("item-{}", i),
+                data: format!("data-{}", i),
+            }).unwrap();
+        }
+        let old_root = chain.root().unwrap();
+
+        for i in 6..11 {
+            chain.append(TestContent {
+                id: format!("item-{}", i),
+                data: format!("data-{}", i),
+            }).unwrap();
+        }
+        let root = chain.root().unwrap();
+
+        let entry = &chain.items()[4];
+        let proof = chain.inclusion_proof(&entry.cid).unwrap();
+        assert_eq!(proof.leaf_index, entry.sequence);
+        assert!(verify_inclusion(&entry.cid, &proof, &root).is_ok());
+        assert!(verify_inclusion(&entry.cid, &proof, &old_root).is_err());
+
+        let head = chain.head().unwrap();
+        assert!(verify_inclusion(&head.cid, &chain.inclusion_proof(&head.cid).unwrap(), &root).is_ok());
+
+        let consistency = chain.consistency_proof(6).unwrap();
+        assert!(verify_consistency(&consistency, &old_root, &root).is_ok());
+        assert!(chain.inclusion_proof(&fake_cid("missing")).is_err());
+    }
+
+    #[test]
+    fn test_checkpoints_and_pruning() {
+        let content = |i: u64| TestContent {
+            id: format!("item-{}", i),
+            data: format!("data-{}", i),
+        };
+        let mut chain = ContentChain::new().with_checkpoint_interval(4);
+        for i in 0..10 {
+            chain.append(content(i)).unwrap();
+        }
+        let sequences: Vec<u64> = chain.checkpoints().iter().map(|c| c.sequence).collect();
+        assert_eq!(sequences, vec![3, 7]);
+
+        let checkpoint = chain.checkpoints()[1].clone().with_snapshot(fake_cid("state-at-7"));
+        assert!(chain.validate_from(&checkpoint).is_ok());
+        let root = chain.root().unwrap();
+
+        // Continue from the checkpoint with only the entries after it
+        let tail: Vec<_> = chain.items()[7..].to_vec();
+        let mut resumed = ContentChain::from_checkpoint(checkpoint.clone(), tail.clone()).unwrap();
+        assert_eq!(resumed.root(), Some(root));
+        assert!(resumed.validate().is_ok());
+        resumed.append(content(10)).unwrap();
+        chain.append(content(10)).unwrap();
+        assert_eq!(resumed.root(), chain.root());
+
+        // Pruning keeps the checkpoint entry and its link to the pruned history
+        assert_eq!(chain.prune(&checkpoint).unwrap(), 7);
+        assert_eq!(chain.items()[0].sequence, 7);
+        assert_eq!(chain.items()[0].previous_cid, Some(tail[0].previous_cid.unwrap()));
+        assert!(chain.validate().is_ok());
+        assert_eq!(chain.root(), resumed.root());
+        assert!(chain.inclusion_proof(&chain.head().unwrap().cid).is_ok());
+
+        // A checkpoint must pin the entry it names
+        let mut forged = checkpoint.clone();
+        forged.cid = fake_cid("elsewhere");
+        assert!(ContentChain::from_checkpoint(forged, tail).is_err());
+    }
+
+    #[test]
+    fn test_indexed_lookups() {
+        use std::time::Duration;
+
+        let mut chain = ContentChain::new();
+        let start = SystemTime::now();
+        for i in 0..8u64 {
+            let mut item = ChainedContent::new(TestContent {
+                id: format!("item-{}", i),
+                data: format!("data-{}", i),
+            }, chain.head()).unwrap();
+            item.timestamp = start + Duration::from_secs(i * 10);
+            chain.push(item).unwrap();
+        }
+
+        let third = chain.items()[3].cid;
+        assert_eq!(chain.get_by_cid(&third).unwrap().sequence, 3);
+        assert!(chain.get_by_cid(&fake_cid("missing")).is_none());
+        assert_eq!(chain.get_by_sequence(5).unwrap().content.id, "item-5");
+        assert!(chain.get_by_sequence(8).is_none());
+
+        let ids = |items: &[ChainedContent<TestContent>]| items.iter().map(|i| i.sequence).collect::<Vec<_>>();
+        assert_eq!(ids(chain.range(2..5)), vec![2, 3, 4]);
+        assert_eq!(ids(chain.range(6..)), vec![6, 7]);
+        assert_eq!(ids(chain.range(..=1)), vec![0, 1]);
+        assert!(chain.range(20..30).is_empty());
+
+        let found = chain.between(start + Duration::from_secs(15), start + Duration::from_secs(40));
+        assert_eq!(found.iter().map(|i| i.sequence).collect::<Vec<_>>(), vec![2, 3]);
+
+        // Indexes follow pruning
+        let checkpoint = ContentChain::from_items(chain.items()[..5].to_vec()).unwrap().checkpoint().unwrap();
+        chain.prune(&checkpoint).unwrap();
+        assert!(chain.get_by_cid(&third).is_none());
+        assert_eq!(ids(chain.range(2..6)), vec![4, 5]);
+        assert_eq!(chain.between(start, start + Duration::from_secs(100)).len(), 4);
+    }
+
     #[test]
     fn test_json_serialization_error_handling() {
         use std::f64;
@@ -834,9 +1473,7 @@ mod tests {
         
         // JSON doesn't support Infinity, so this might fail
         // But our code should handle it without panicking
-        match result {
-            Ok(_) => {} // Implementation handles it
-            Err(_) => {} // Or returns an error
-        }
+        // Either the implementation handles it or returns an error
+        let _ = result;
     }
 }
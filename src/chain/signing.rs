@@ -0,0 +1,211 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Ed25519 signatures on chain entries
+//!
+//! A signature binds an entry's CID and sequence number to the key that
+//! signed it, and optionally names the key that signs the following entries.
+//! Signatures are not part of the entry block, so signing an entry does not
+//! change its CID.
+//!
+//! A [`TrustPolicy`] lists the keys allowed to extend a chain. A trusted key
+//! rotates by signing an entry with a `next_key`: from the following entry
+//! on, the old key is retired and the new key is trusted in its place.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use super::ChainedContent;
+use crate::{Error, Result, TypedContent};
+
+/// Ed25519 signature over a chain entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntrySignature {
+    /// Base64 public key of the signer
+    pub public_key: String,
+    /// Base64 signature
+    pub signature: String,
+    /// Base64 public key that takes over from the signer after this entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+}
+
+impl<T: TypedContent> ChainedContent<T> {
+    fn signing_payload(&self, public_key: &str, next_key: Option<&str>) -> Vec<u8> {
+        format!(
+            "cim-ipld/chain-entry/v1\n{}\n{}\n{public_key}\n{}",
+            self.cid,
+            self.sequence,
+            next_key.unwrap_or_default()
+        )
+        .into_bytes()
+    }
+
+    fn signed(mut self, key: &SigningKey, next_key: Option<&VerifyingKey>) -> Self {
+        let public_key = BASE64.encode(key.verifying_key().as_bytes());
+        let next_key = next_key.map(|next| BASE64.encode(next.as_bytes()));
+        let signature = key.sign(&self.signing_payload(&public_key, next_key.as_deref()));
+        self.signature = Some(EntrySignature {
+            public_key,
+            signature: BASE64.encode(signature.to_bytes()),
+            next_key,
+        });
+        self
+    }
+
+    /// Sign the entry's CID and sequence number
+    pub fn sign(self, key: &SigningKey) -> Self {
+        self.signed(key, None)
+    }
+
+    /// Sign the entry and hand the chain over to `next_key`
+    pub fn sign_with_rotation(self, key: &SigningKey, next_key: &VerifyingKey) -> Self {
+        self.signed(key, Some(next_key))
+    }
+
+    /// Check the signature and return the key that made it
+    pub fn verify_signature(&self) -> Result<VerifyingKey> {
+        let invalid = |reason: &str| Error::InvalidSignature(format!("chain entry {}: {reason}", self.cid));
+        let signature = self.signature.as_ref().ok_or_else(|| invalid("entry is not signed"))?;
+
+        let key = decode_key(&signature.public_key).ok_or_else(|| invalid("malformed public key"))?;
+        if let Some(next_key) = &signature.next_key {
+            decode_key(next_key).ok_or_else(|| invalid("malformed next key"))?;
+        }
+        let bytes: [u8; 64] = BASE64.decode(&signature.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("malformed signature"))?;
+
+        let payload = self.signing_payload(&signature.public_key, signature.next_key.as_deref());
+        key.verify(&payload, &Signature::from_bytes(&bytes))
+            .map_err(|_| invalid("signature does not match"))?;
+        Ok(key)
+    }
+
+    /// Key the signer handed the chain over to, if any
+    pub fn next_key(&self) -> Option<VerifyingKey> {
+        self.signature.as_ref()?.next_key.as_deref().and_then(decode_key)
+    }
+}
+
+fn decode_key(encoded: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = BASE64.decode(encoded).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// Keys allowed to sign entries of a chain
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustPolicy {
+    trusted_keys: Vec<VerifyingKey>,
+}
+
+impl TrustPolicy {
+    /// A policy trusting no key yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust entries signed by `key`
+    pub fn with_key(mut self, key: VerifyingKey) -> Self {
+        if !self.trusted_keys.contains(&key) {
+            self.trusted_keys.push(key);
+        }
+        self
+    }
+
+    /// Whether `key` is trusted by the policy itself, ignoring rotations
+    pub fn trusts(&self, key: &VerifyingKey) -> bool {
+        self.trusted_keys.contains(key)
+    }
+
+    /// Keys trusted by the policy
+    pub fn keys(&self) -> &[VerifyingKey] {
+        &self.trusted_keys
+    }
+}
+
+/// Rotations seen while walking a chain under a [`TrustPolicy`]
+#[derive(Debug, Clone, Default)]
+pub(super) struct TrustState {
+    delegated: Vec<VerifyingKey>,
+    retired: Vec<VerifyingKey>,
+}
+
+impl TrustState {
+    /// Check the next entry of the chain and record any rotation it makes
+    pub(super) fn check<T: TypedContent>(&mut self, policy: &TrustPolicy, entry: &ChainedContent<T>) -> Result<()> {
+        let signer = entry.verify_signature()?;
+        let untrusted = |reason: &str| Error::InvalidSignature(format!("chain entry {}: {reason}", entry.cid));
+        if self.retired.contains(&signer) {
+            return Err(untrusted("signed by a rotated-out key"));
+        }
+        if !policy.trusts(&signer) && !self.delegated.contains(&signer) {
+            return Err(untrusted("signed by an untrusted key"));
+        }
+
+        if let Some(next) = entry.next_key() {
+            if next != signer {
+                self.retired.push(signer);
+                self.retired.retain(|key| *key != next);
+                self.delegated.push(next);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::ContentChain;
+    use crate::util::TestEntry;
+    use rand::rngs::OsRng;
+
+
+    #[test]
+    fn test_trust_policy_rejects_unsigned_and_untrusted() {
+        let (alice, mallory) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+        let policy = TrustPolicy::new().with_key(alice.verifying_key());
+
+        let mut chain = ContentChain::new().with_trust_policy(policy.clone());
+        let first = chain.append_signed(TestEntry { n: 0 }, &alice).unwrap();
+        assert_eq!(first.verify_signature().unwrap(), alice.verifying_key());
+        assert!(chain.append(TestEntry { n: 1 }).is_err());
+        assert!(chain.append_signed(TestEntry { n: 1 }, &mallory).is_err());
+        assert_eq!(chain.len(), 1);
+
+        // The signature commits to the signer key
+        let mut forged = ChainedContent::new(TestEntry { n: 1 }, chain.head()).unwrap().sign(&alice);
+        forged.signature.as_mut().unwrap().public_key = BASE64.encode(mallory.verifying_key().as_bytes());
+        assert!(forged.verify_signature().is_err());
+
+        // An unsigned chain passes without a policy but not with one
+        let mut unsigned = ContentChain::new();
+        unsigned.append(TestEntry { n: 0 }).unwrap();
+        assert!(unsigned.validate().is_ok());
+        let err = unsigned.with_trust_policy(policy).validate().unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::InvalidSignature);
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let (old, new) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+        let policy = TrustPolicy::new().with_key(old.verifying_key());
+
+        let mut chain = ContentChain::new().with_trust_policy(policy.clone());
+        chain.append_signed(TestEntry { n: 0 }, &old).unwrap();
+        chain.append_rotating(TestEntry { n: 1 }, &old, &new.verifying_key()).unwrap();
+        chain.append_signed(TestEntry { n: 2 }, &new).unwrap();
+        assert!(chain.validate().is_ok());
+
+        // The rotated-out key can no longer extend the chain
+        assert!(chain.append_signed(TestEntry { n: 3 }, &old).is_err());
+
+        // Rebuilding from entries applies the same rotation
+        let rebuilt = ContentChain::from_items(chain.items().to_vec()).unwrap().with_trust_policy(policy);
+        assert!(rebuilt.validate().is_ok());
+        let mut rebuilt = rebuilt;
+        assert!(rebuilt.append_signed(TestEntry { n: 3 }, &new).is_ok());
+    }
+}
@@ -1510,6 +1510,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_codec_constants() {
         // Verify all codec constants are in the correct range
         assert!(codec::PDF >= 0x600000 && codec::PDF <= 0x60FFFF);
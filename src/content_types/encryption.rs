@@ -15,6 +15,10 @@ use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use blake3::Hasher;
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use crate::error::ErrorCode;
+use crate::object_store::{AuditAction, AuditEvent, AuditLog};
+use cid::Cid;
+use std::sync::Arc;
 
 /// Error types for encryption operations
 #[derive(Debug, thiserror::Error)]
@@ -35,6 +39,19 @@ pub enum EncryptionError {
     KeyDerivationFailed,
 }
 
+impl EncryptionError {
+    /// Machine-readable classification of this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::EncryptionFailed(_) => ErrorCode::Encryption,
+            Self::DecryptionFailed(_) => ErrorCode::Decryption,
+            Self::InvalidKeySize { .. } | Self::InvalidNonce | Self::KeyDerivationFailed => {
+                ErrorCode::InvalidKey
+            }
+        }
+    }
+}
+
 pub type EncryptionResult<T> = std::result::Result<T, EncryptionError>;
 
 /// Encryption algorithms supported
@@ -312,6 +329,7 @@ impl ContentEncryption {
 pub struct KeyRotation {
     old_encryption: ContentEncryption,
     new_encryption: ContentEncryption,
+    audit: Option<(Arc<AuditLog>, String)>,
 }
 
 impl KeyRotation {
@@ -324,9 +342,17 @@ impl KeyRotation {
         Ok(Self {
             old_encryption: ContentEncryption::new(old_key, algorithm)?,
             new_encryption: ContentEncryption::new(new_key, algorithm)?,
+            audit: None,
         })
     }
 
+    /// Record content rotated with [`KeyRotation::rotate_content`] in an
+    /// audit log under `actor`
+    pub fn with_audit(mut self, audit: Arc<AuditLog>, actor: impl Into<String>) -> Self {
+        self.audit = Some((audit, actor.into()));
+        self
+    }
+
     /// Rotate encryption on data
     pub fn rotate(&self, encrypted: &EncryptedData) -> EncryptionResult<EncryptedData> {
         // Decrypt with old key
@@ -335,6 +361,19 @@ impl KeyRotation {
         // Re-encrypt with new key
         self.new_encryption.encrypt(&plaintext, encrypted.aad.as_deref())
     }
+
+    /// Rotate encryption on the content stored under `cid`
+    ///
+    /// With an audit log, a re-encrypt event naming both key hashes is
+    /// recorded before the rotated data is returned.
+    pub async fn rotate_content(&self, cid: &Cid, encrypted: &EncryptedData) -> crate::Result<EncryptedData> {
+        let rotated = self.rotate(encrypted)?;
+        if let Some((audit, actor)) = &self.audit {
+            let detail = format!("key {} -> {}", encrypted.key_hash, rotated.key_hash);
+            audit.record(AuditEvent::new(AuditAction::ReEncrypt, *cid, actor.as_str()).with_detail(detail)).await?;
+        }
+        Ok(rotated)
+    }
 }
 
 #[cfg(test)]
@@ -389,6 +428,26 @@ mod tests {
         assert_eq!(plaintext, &decrypted[..]);
     }
 
+    #[tokio::test]
+    async fn test_rotation_is_audited() {
+        let old_key = ContentEncryption::generate_key(EncryptionAlgorithm::Aes256Gcm);
+        let new_key = ContentEncryption::generate_key(EncryptionAlgorithm::Aes256Gcm);
+        let old_encryption = ContentEncryption::new(old_key.clone(), EncryptionAlgorithm::Aes256Gcm).unwrap();
+        let audit = Arc::new(AuditLog::new());
+        let rotation = KeyRotation::new(old_key, new_key, EncryptionAlgorithm::Aes256Gcm)
+            .unwrap()
+            .with_audit(audit.clone(), "key-service");
+
+        let cid = crate::util::test_cid(b"secret");
+        let encrypted = old_encryption.encrypt(b"Sensitive data", None).unwrap();
+        let rotated = rotation.rotate_content(&cid, &encrypted).await.unwrap();
+
+        let events = audit.query(&crate::object_store::AuditQuery::new().with_action(AuditAction::ReEncrypt)).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].content.cid, cid);
+        assert!(events[0].content.detail.as_ref().unwrap().ends_with(&rotated.key_hash));
+    }
+
     #[test]
     fn test_key_derivation() {
         let password = "strong password";
@@ -585,6 +644,7 @@ mod tests {
         let rotation = KeyRotation {
             old_encryption,
             new_encryption,
+            audit: None,
         };
         
         let plaintext = b"Data to rotate";
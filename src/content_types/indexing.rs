@@ -11,14 +11,68 @@ use crate::{
         ContentType, codec,
         persistence::IndexPersistence,
     },
+    metrics::{self, Gauge, Histogram, LATENCY_BUCKETS},
     Result,
 };
 use cid::Cid;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::RwLock;
 
+/// Content index metric handles, registered once
+///
+/// Size gauges count the items of every index in the process and are
+/// moved by each change instead of being recomputed.
+struct IndexMetrics {
+    search: Histogram,
+    documents: Gauge,
+    images: Gauge,
+    audio: Gauge,
+    video: Gauge,
+    unique_words: Gauge,
+    unique_tags: Gauge,
+}
+
+fn index_metrics() -> &'static IndexMetrics {
+    static METRICS: OnceLock<IndexMetrics> = OnceLock::new();
+    METRICS.get_or_init(|| {
+        let registry = metrics::global();
+        let entries = |kind| {
+            registry.gauge("cim_ipld_index_entries", "Indexed items by kind", &[("kind", kind)])
+        };
+        IndexMetrics {
+            search: registry.histogram(
+                "cim_ipld_index_search_duration_seconds",
+                "Content index search latency",
+                &[],
+                LATENCY_BUCKETS,
+            ),
+            documents: entries("documents"),
+            images: entries("images"),
+            audio: entries("audio"),
+            video: entries("video"),
+            unique_words: registry.gauge(
+                "cim_ipld_index_unique_words",
+                "Distinct words in the text index",
+                &[],
+            ),
+            unique_tags: registry.gauge(
+                "cim_ipld_index_unique_tags",
+                "Distinct tags in the tag index",
+                &[],
+            ),
+        }
+    })
+}
+
+/// Move a size gauge by the change from `before` to `after`
+fn record_size_change(gauge: &Gauge, before: usize, after: usize) {
+    if before != after {
+        gauge.add(after as f64 - before as f64);
+    }
+}
+
 // Serde support for CID
 mod cid_serde {
     use cid::Cid;
@@ -170,11 +224,13 @@ impl ContentIndex {
     pub async fn load_from_persistence(&self) -> Result<()> {
         if let Some(ref persistence) = self.persistence {
             // Load text index
-            if let Some((word_to_cids, cid_to_text)) = persistence.load_text_index().await
-                .map_err(|e| crate::Error::StorageError(e.to_string()))? {
+            if let Some((word_to_cids, cid_to_text)) = persistence.load_text_index().await? {
                 let mut text_index = self.text_index.write().await;
+                let words = text_index.word_to_cids.len();
                 text_index.word_to_cids = word_to_cids;
                 text_index.cid_to_text = cid_to_text;
+                let after = text_index.word_to_cids.len();
+                record_size_change(&index_metrics().unique_words, words, after);
             }
 
             // Load other indices similarly...
@@ -188,18 +244,15 @@ impl ContentIndex {
         if let Some(ref persistence) = self.persistence {
             // Persist text index
             let text_index = self.text_index.read().await;
-            persistence.save_text_index(&text_index.word_to_cids, &text_index.cid_to_text).await
-                .map_err(|e| crate::Error::StorageError(e.to_string()))?;
+            persistence.save_text_index(&text_index.word_to_cids, &text_index.cid_to_text).await?;
 
             // Persist tag index
             let tag_index = self.tag_index.read().await;
-            persistence.save_tag_index(&tag_index.tag_to_cids, &tag_index.cid_to_tags).await
-                .map_err(|e| crate::Error::StorageError(e.to_string()))?;
+            persistence.save_tag_index(&tag_index.tag_to_cids, &tag_index.cid_to_tags).await?;
 
             // Persist type index
             let type_index = self.type_index.read().await;
-            persistence.save_type_index(&type_index.type_to_cids).await
-                .map_err(|e| crate::Error::StorageError(e.to_string()))?;
+            persistence.save_type_index(&type_index.type_to_cids).await?;
 
             // Persist metadata cache
             let cache = self.metadata_cache.read().await;
@@ -208,13 +261,11 @@ impl ContentIndex {
                 &cache.images,
                 &cache.audio,
                 &cache.video,
-            ).await
-                .map_err(|e| crate::Error::StorageError(e.to_string()))?;
+            ).await?;
 
             // Persist stats
             let stats = self.stats().await;
-            persistence.save_stats(&stats).await
-                .map_err(|e| crate::Error::StorageError(e.to_string()))?;
+            persistence.save_stats(&stats).await?;
         }
         Ok(())
     }
@@ -256,7 +307,9 @@ impl ContentIndex {
         // Cache metadata
         {
             let mut cache = self.metadata_cache.write().await;
-            cache.documents.insert(cid, metadata.clone());
+            if cache.documents.insert(cid, metadata.clone()).is_none() {
+                index_metrics().documents.add(1.0);
+            }
         }
 
         // Persist if enabled
@@ -292,7 +345,9 @@ impl ContentIndex {
         // Cache metadata
         {
             let mut cache = self.metadata_cache.write().await;
-            cache.images.insert(cid, metadata.clone());
+            if cache.images.insert(cid, metadata.clone()).is_none() {
+                index_metrics().images.add(1.0);
+            }
         }
 
         // Persist if enabled
@@ -303,8 +358,63 @@ impl ContentIndex {
         Ok(())
     }
 
+    /// Remove content from every index; returns whether it was indexed
+    pub async fn remove(&self, cid: &Cid) -> Result<bool> {
+        let sizes = index_metrics();
+        let mut removed = false;
+        {
+            let mut text_index = self.text_index.write().await;
+            let words = text_index.word_to_cids.len();
+            removed |= text_index.cid_to_text.remove(cid).is_some();
+            text_index.word_to_cids.retain(|_, cids| {
+                removed |= cids.remove(cid);
+                !cids.is_empty()
+            });
+            let after = text_index.word_to_cids.len();
+            record_size_change(&sizes.unique_words, words, after);
+        }
+        {
+            let mut tag_index = self.tag_index.write().await;
+            let tags = tag_index.tag_to_cids.len();
+            removed |= tag_index.cid_to_tags.remove(cid).is_some();
+            tag_index.tag_to_cids.retain(|_, cids| {
+                removed |= cids.remove(cid);
+                !cids.is_empty()
+            });
+            record_size_change(&sizes.unique_tags, tags, tag_index.tag_to_cids.len());
+        }
+        {
+            let mut type_index = self.type_index.write().await;
+            type_index.type_to_cids.retain(|_, cids| {
+                removed |= cids.remove(cid);
+                !cids.is_empty()
+            });
+        }
+        {
+            let mut cache = self.metadata_cache.write().await;
+            let kinds = [
+                (cache.documents.remove(cid).is_some(), &sizes.documents),
+                (cache.images.remove(cid).is_some(), &sizes.images),
+                (cache.audio.remove(cid).is_some(), &sizes.audio),
+                (cache.video.remove(cid).is_some(), &sizes.video),
+            ];
+            for (was_cached, gauge) in kinds {
+                if was_cached {
+                    gauge.add(-1.0);
+                    removed = true;
+                }
+            }
+        }
+
+        if removed && self.persistence.is_some() {
+            self.persist().await?;
+        }
+        Ok(removed)
+    }
+
     /// Search the index
     pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let _timer = index_metrics().search.start_timer();
         let mut results = HashMap::new();
         let mut scores: HashMap<Cid, f32> = HashMap::new();
 
@@ -377,8 +487,11 @@ impl ContentIndex {
             }
         }
 
-        // Sort by score
-        final_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        // Sort by score, breaking ties by CID so pagination is stable
+        final_results.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score).unwrap()
+                .then_with(|| a.cid.to_bytes().cmp(&b.cid.to_bytes()))
+        });
 
         // Apply pagination
         let offset = query.offset.unwrap_or(0);
@@ -426,6 +539,7 @@ pub struct IndexStats {
 
 fn index_text(index: &mut TextIndex, cid: Cid, text: &str) {
     let words = tokenize(text);
+    let known = index.word_to_cids.len();
     
     for word in words {
         index.word_to_cids
@@ -435,9 +549,11 @@ fn index_text(index: &mut TextIndex, cid: Cid, text: &str) {
     }
     
     index.cid_to_text.insert(cid, text.to_string());
+    record_size_change(&index_metrics().unique_words, known, index.word_to_cids.len());
 }
 
 fn index_tags(index: &mut TagIndex, cid: Cid, tags: &[String]) {
+    let known = index.tag_to_cids.len();
     for tag in tags {
         index.tag_to_cids
             .entry(tag.to_lowercase())
@@ -446,6 +562,7 @@ fn index_tags(index: &mut TagIndex, cid: Cid, tags: &[String]) {
     }
     
     index.cid_to_tags.insert(cid, tags.to_vec());
+    record_size_change(&index_metrics().unique_tags, known, index.tag_to_cids.len());
 }
 
 fn search_text(index: &TextIndex, query: &str) -> Vec<(Cid, f32)> {
@@ -624,6 +741,30 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_remove() {
+        let index = ContentIndex::new();
+
+        let cid = Cid::default();
+        let metadata = DocumentMetadata {
+            title: Some("Temporary Upload".to_string()),
+            tags: vec!["temp".to_string()],
+            ..Default::default()
+        };
+        index.index_document(cid, &metadata, Some("scratch content")).await.unwrap();
+
+        assert!(index.remove(&cid).await.unwrap());
+        assert!(!index.remove(&cid).await.unwrap());
+
+        let query = SearchQuery {
+            text: Some("scratch".to_string()),
+            tags: vec!["temp".to_string()],
+            ..Default::default()
+        };
+        assert!(index.search(&query).await.unwrap().is_empty());
+        assert_eq!(index.stats().await.total_documents, 0);
+    }
+
     #[tokio::test]
     async fn test_empty_search_query() {
         let index = ContentIndex::new();
@@ -1012,7 +1153,7 @@ mod tests {
         }
         
         let json = r#"{"cid": "invalid-cid"}"#;
-        let result: std::result::Result<TestStruct, _> = serde_json::from_str(json);
+        let result = serde_json::from_str::<TestStruct>(json).map(|s| s.cid);
         assert!(result.is_err());
     }
 
@@ -1062,7 +1203,16 @@ mod tests {
             }
         }
         
-        // Test would need IndexPersistence trait to be public or refactored
-        // This demonstrates the pattern for testing persistence errors
+        let persistence = FailingPersistence {
+            fail_save: AtomicBool::new(true),
+            fail_load: AtomicBool::new(false),
+        };
+        assert!(persistence.save_text_index(&HashMap::new(), &HashMap::new()).await.is_err());
+        assert!(persistence.load_text_index().await.unwrap().is_none());
+
+        persistence.fail_save.store(false, Ordering::Relaxed);
+        persistence.fail_load.store(true, Ordering::Relaxed);
+        assert!(persistence.save_text_index(&HashMap::new(), &HashMap::new()).await.is_ok());
+        assert!(persistence.load_text_index().await.is_err());
     }
 } 
\ No newline at end of file
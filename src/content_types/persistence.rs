@@ -9,13 +9,19 @@ use crate::{
     content_types::indexing::IndexStats,
     content_types::{ContentType, DocumentMetadata, ImageMetadata, AudioMetadata, VideoMetadata},
 };
+use crate::error::ErrorCode;
+use crate::object_store::{Resilience, Retryable};
 use async_nats::jetstream::{self, kv::{Store as KvStore, Config as KvConfig}};
+use async_nats::jetstream::context::CreateKeyValueErrorKind;
+use async_nats::jetstream::kv::{EntryErrorKind, PutErrorKind};
+use bytes::Bytes;
 use cid::Cid;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use super::encryption::{ContentEncryption, EncryptionAlgorithm, EncryptedData};
+use super::encryption::{ContentEncryption, EncryptionAlgorithm, EncryptedData, EncryptionError};
 
 /// Error types for persistence operations
 #[derive(Debug, thiserror::Error)]
@@ -24,19 +30,95 @@ pub enum PersistenceError {
     Nats(#[from] async_nats::Error),
 
     #[error("Serialization error: {0}")]
-    Serialization(String),
-
-    #[error("Encryption error: {0}")]
-    Encryption(String),
-
-    #[error("Decryption error: {0}")]
-    Decryption(String),
+    Serialization(#[source] Box<dyn std::error::Error + Send + Sync>),
 
     #[error("Key not found: {0}")]
     NotFound(String),
 
     #[error("Invalid encryption key")]
     InvalidKey,
+
+    #[error("No encryption key configured")]
+    NoKey,
+
+    #[error("NATS unreachable: {context}")]
+    Unreachable {
+        context: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    #[error("Operation timed out: {context}")]
+    Timeout {
+        context: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    #[error("Circuit open: {0}")]
+    CircuitOpen(String),
+
+    #[error("Cipher error: {0}")]
+    Crypto(#[from] EncryptionError),
+}
+
+impl PersistenceError {
+    /// The NATS server could not be reached while doing `context`
+    pub fn unreachable(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Unreachable { context: context.into(), source: Some(source.into()) }
+    }
+
+    /// The NATS server did not answer in time while doing `context`
+    pub fn timeout(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Timeout { context: context.into(), source: Some(source.into()) }
+    }
+
+    /// Machine-readable classification of this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Nats(_) => ErrorCode::Storage,
+            Self::Serialization(_) => ErrorCode::Serialization,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::InvalidKey | Self::NoKey => ErrorCode::InvalidKey,
+            Self::Unreachable { .. } => ErrorCode::Unavailable,
+            Self::Timeout { .. } => ErrorCode::Timeout,
+            Self::CircuitOpen(_) => ErrorCode::CircuitOpen,
+            Self::Crypto(e) => e.code(),
+        }
+    }
+
+    /// Whether the operation may succeed if tried again later
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+
+    /// Whether the requested key or store does not exist
+    pub fn is_not_found(&self) -> bool {
+        self.code() == ErrorCode::NotFound
+    }
+}
+
+impl Retryable for PersistenceError {
+    fn is_transient(&self) -> bool {
+        matches!(self, Self::Unreachable { .. } | Self::Timeout { .. })
+    }
+
+    fn circuit_open(scope: &str) -> Self {
+        Self::CircuitOpen(format!("too many failures reaching KV bucket {scope}"))
+    }
+
+    fn deadline_exceeded(operation: &str, deadline: Duration) -> Self {
+        Self::Timeout {
+            context: format!("{operation} did not complete within {deadline:?}"),
+            source: None,
+        }
+    }
 }
 
 pub type PersistenceResult<T> = std::result::Result<T, PersistenceError>;
@@ -93,6 +175,8 @@ pub struct IndexPersistence {
     /// Whether to enable NATS native encryption
     #[allow(dead_code)]
     native_encryption: bool,
+    /// Retry policy and circuit breakers for KV calls
+    resilience: Arc<Resilience>,
 }
 
 impl IndexPersistence {
@@ -104,8 +188,7 @@ impl IndexPersistence {
     ) -> PersistenceResult<Self> {
         // Create encryption service if key provided
         let encryption = if let Some(key) = encryption_key {
-            Some(ContentEncryption::new(key, EncryptionAlgorithm::ChaCha20Poly1305)
-                .map_err(|e| PersistenceError::Encryption(e.to_string()))?)
+            Some(ContentEncryption::new(key, EncryptionAlgorithm::ChaCha20Poly1305)?)
         } else {
             None
         };
@@ -118,6 +201,7 @@ impl IndexPersistence {
             metadata_store: Arc::new(RwLock::new(None)),
             encryption,
             native_encryption,
+            resilience: Arc::new(Resilience::default()),
         };
 
         // Initialize KV stores
@@ -126,6 +210,62 @@ impl IndexPersistence {
         Ok(persistence)
     }
 
+    /// Replace the retry policy and circuit breakers used for KV calls
+    ///
+    /// Stores are opened by `new` with the default policy.
+    pub fn with_resilience(mut self, resilience: Resilience) -> Self {
+        self.resilience = Arc::new(resilience);
+        self
+    }
+
+    /// Create or open a KV bucket
+    async fn open_store(&self, config: KvConfig) -> PersistenceResult<KvStore> {
+        let bucket = config.bucket.clone();
+        self.resilience
+            .run(&bucket, "open_kv", || async {
+                self.jetstream.create_key_value(config.clone()).await
+                    .map_err(|e| match e.kind() {
+                        CreateKeyValueErrorKind::TimedOut => {
+                            PersistenceError::timeout(format!("opening {bucket}"), e)
+                        }
+                        CreateKeyValueErrorKind::BucketCreate => {
+                            PersistenceError::unreachable(format!("opening {bucket}"), e)
+                        }
+                        _ => PersistenceError::Nats(e.into()),
+                    })
+            })
+            .await
+    }
+
+    /// Put a value, retrying transient failures
+    async fn put_value(&self, store: &KvStore, key: &str, data: Vec<u8>) -> PersistenceResult<()> {
+        let value = Bytes::from(data);
+        self.resilience
+            .run(&store.name, "kv_put", || async {
+                store.put(key, value.clone()).await
+                    .map(|_| ())
+                    .map_err(|e| match e.kind() {
+                        PutErrorKind::InvalidKey => PersistenceError::Nats(e.into()),
+                        _ => PersistenceError::unreachable(format!("put {key}"), e),
+                    })
+            })
+            .await
+    }
+
+    /// Get a value, retrying transient failures
+    async fn get_value(&self, store: &KvStore, key: &str) -> PersistenceResult<Option<Bytes>> {
+        self.resilience
+            .run(&store.name, "kv_get", || async {
+                store.get(key).await
+                    .map_err(|e| match e.kind() {
+                        EntryErrorKind::TimedOut => PersistenceError::timeout(format!("get {key}"), e),
+                        EntryErrorKind::InvalidKey => PersistenceError::Nats(e.into()),
+                        _ => PersistenceError::unreachable(format!("get {key}"), e),
+                    })
+            })
+            .await
+    }
+
     /// Initialize KV stores
     async fn initialize_stores(&self) -> PersistenceResult<()> {
         // Text index store
@@ -135,8 +275,7 @@ impl IndexPersistence {
             history: 5,
             ..Default::default()
         };
-        let text_store = self.jetstream.create_key_value(text_config).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        let text_store = self.open_store(text_config).await?;
         *self.text_store.write().await = Some(text_store);
 
         // Tag index store
@@ -146,8 +285,7 @@ impl IndexPersistence {
             history: 5,
             ..Default::default()
         };
-        let tag_store = self.jetstream.create_key_value(tag_config).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        let tag_store = self.open_store(tag_config).await?;
         *self.tag_store.write().await = Some(tag_store);
 
         // Type index store
@@ -157,8 +295,7 @@ impl IndexPersistence {
             history: 5,
             ..Default::default()
         };
-        let type_store = self.jetstream.create_key_value(type_config).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        let type_store = self.open_store(type_config).await?;
         *self.type_store.write().await = Some(type_store);
 
         // Metadata cache store
@@ -168,8 +305,7 @@ impl IndexPersistence {
             history: 5,
             ..Default::default()
         };
-        let metadata_store = self.jetstream.create_key_value(metadata_config).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        let metadata_store = self.open_store(metadata_config).await?;
         *self.metadata_store.write().await = Some(metadata_store);
 
         Ok(())
@@ -178,12 +314,11 @@ impl IndexPersistence {
     /// Encrypt data if encryption is enabled
     fn encrypt_data(&self, data: &[u8]) -> PersistenceResult<Vec<u8>> {
         if let Some(ref encryption) = self.encryption {
-            let encrypted = encryption.encrypt(data, None)
-                .map_err(|e| PersistenceError::Encryption(e.to_string()))?;
+            let encrypted = encryption.encrypt(data, None)?;
             
             // Serialize the encrypted data structure
             serde_json::to_vec(&encrypted)
-                .map_err(|e| PersistenceError::Encryption(e.to_string()))
+                .map_err(|e| PersistenceError::Serialization(e.into()))
         } else {
             Ok(data.to_vec())
         }
@@ -194,10 +329,9 @@ impl IndexPersistence {
         if let Some(ref encryption) = self.encryption {
             // Deserialize the encrypted data structure
             let encrypted: EncryptedData = serde_json::from_slice(data)
-                .map_err(|e| PersistenceError::Decryption(e.to_string()))?;
+                .map_err(|e| PersistenceError::Serialization(e.into()))?;
             
-            encryption.decrypt(&encrypted)
-                .map_err(|e| PersistenceError::Decryption(e.to_string()))
+            Ok(encryption.decrypt(&encrypted)?)
         } else {
             Ok(data.to_vec())
         }
@@ -225,14 +359,13 @@ impl IndexPersistence {
 
         // Serialize
         let data = serde_json::to_vec(&persisted)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         // Encrypt if enabled
         let data = self.encrypt_data(&data)?;
 
         // Store in NATS KV
-        store.put("text_index", data.into()).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        self.put_value(store, "text_index", data).await?;
 
         Ok(())
     }
@@ -243,10 +376,8 @@ impl IndexPersistence {
         let store = store_guard.as_ref().ok_or(PersistenceError::NotFound("text store".to_string()))?;
 
         // Get from KV store
-        let entry = match store.get("text_index").await {
-            Ok(Some(entry)) => entry,
-            Ok(None) => return Ok(None),
-            Err(e) => return Err(PersistenceError::Nats(e.to_string().into())),
+        let Some(entry) = self.get_value(store, "text_index").await? else {
+            return Ok(None);
         };
 
         // Decrypt if needed
@@ -254,7 +385,7 @@ impl IndexPersistence {
 
         // Deserialize
         let persisted: PersistedTextIndex = serde_json::from_slice(&data)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         // Convert back to runtime format
         let word_to_cids = persisted.word_to_cids.into_iter()
@@ -263,19 +394,17 @@ impl IndexPersistence {
                     .map(|s| Cid::try_from(s.as_str()))
                     .collect();
                 cids.map(|cids| (k, cids))
-                    .map_err(|_| PersistenceError::Serialization("Invalid CID".to_string()))
+                    .map_err(|e| PersistenceError::Serialization(e.into()))
             })
-            .collect::<PersistenceResult<HashMap<_, _>>>()
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .collect::<PersistenceResult<HashMap<_, _>>>()?;
 
         let cid_to_text = persisted.cid_to_text.into_iter()
             .map(|(k, v)| {
                 Cid::try_from(k.as_str())
                     .map(|cid| (cid, v))
-                    .map_err(|_| PersistenceError::Serialization("Invalid CID".to_string()))
+                    .map_err(|e| PersistenceError::Serialization(e.into()))
             })
-            .collect::<PersistenceResult<HashMap<_, _>>>()
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .collect::<PersistenceResult<HashMap<_, _>>>()?;
 
         Ok(Some((word_to_cids, cid_to_text)))
     }
@@ -299,12 +428,11 @@ impl IndexPersistence {
         };
 
         let data = serde_json::to_vec(&persisted)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         let data = self.encrypt_data(&data)?;
 
-        store.put("tag_index", data.into()).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        self.put_value(store, "tag_index", data).await?;
 
         Ok(())
     }
@@ -328,12 +456,11 @@ impl IndexPersistence {
         };
 
         let data = serde_json::to_vec(&persisted)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         let data = self.encrypt_data(&data)?;
 
-        store.put("type_index", data.into()).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        self.put_value(store, "type_index", data).await?;
 
         Ok(())
     }
@@ -365,12 +492,11 @@ impl IndexPersistence {
         };
 
         let data = serde_json::to_vec(&persisted)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         let data = self.encrypt_data(&data)?;
 
-        store.put("metadata_cache", data.into()).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        self.put_value(store, "metadata_cache", data).await?;
 
         Ok(())
     }
@@ -382,8 +508,7 @@ impl IndexPersistence {
         metadata: &[u8],
     ) -> PersistenceResult<EncryptedCidWrapper> {
         if let Some(ref encryption) = self.encryption {
-            let encrypted = encryption.encrypt(metadata, Some(cid.to_string().as_bytes()))
-                .map_err(|e| PersistenceError::Encryption(e.to_string()))?;
+            let encrypted = encryption.encrypt(metadata, Some(cid.to_string().as_bytes()))?;
 
             Ok(EncryptedCidWrapper {
                 cid: cid.to_string(),
@@ -392,7 +517,7 @@ impl IndexPersistence {
                 key_hash: encrypted.key_hash,
             })
         } else {
-            Err(PersistenceError::Encryption("No encryption key configured".to_string()))
+            Err(PersistenceError::NoKey)
         }
     }
 
@@ -408,10 +533,9 @@ impl IndexPersistence {
                 key_hash: wrapper.key_hash.clone(),
             };
 
-            encryption.decrypt(&encrypted_data)
-                .map_err(|e| PersistenceError::Decryption(e.to_string()))
+            Ok(encryption.decrypt(&encrypted_data)?)
         } else {
-            Err(PersistenceError::Decryption("No encryption key configured".to_string()))
+            Err(PersistenceError::NoKey)
         }
     }
 
@@ -421,12 +545,11 @@ impl IndexPersistence {
         let store = store_guard.as_ref().ok_or(PersistenceError::NotFound("metadata store".to_string()))?;
 
         let data = serde_json::to_vec(stats)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         let data = self.encrypt_data(&data)?;
 
-        store.put("index_stats", data.into()).await
-            .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        self.put_value(store, "index_stats", data).await?;
 
         Ok(())
     }
@@ -436,16 +559,14 @@ impl IndexPersistence {
         let store_guard = self.metadata_store.read().await;
         let store = store_guard.as_ref().ok_or(PersistenceError::NotFound("metadata store".to_string()))?;
 
-        let entry = match store.get("index_stats").await {
-            Ok(Some(entry)) => entry,
-            Ok(None) => return Ok(None),
-            Err(e) => return Err(PersistenceError::Nats(e.to_string().into())),
+        let Some(entry) = self.get_value(store, "index_stats").await? else {
+            return Ok(None);
         };
 
         let data = self.decrypt_data(&entry)?;
 
         let stats = serde_json::from_slice(&data)
-            .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+            .map_err(|e| PersistenceError::Serialization(e.into()))?;
 
         Ok(Some(stats))
     }
@@ -461,10 +582,10 @@ impl IndexPersistence {
         let backup_key = format!("backup_{}_{}_{}", backup_name, timestamp, "text");
 
         // Backup text index
-        if let Ok(Some(entry)) = self.text_store.read().await.as_ref().unwrap().get("text_index").await {
-            self.text_store.read().await.as_ref().unwrap()
-                .put(&backup_key, entry).await
-                .map_err(|e| PersistenceError::Nats(e.to_string().into()))?;
+        let store_guard = self.text_store.read().await;
+        let store = store_guard.as_ref().ok_or(PersistenceError::NotFound("text store".to_string()))?;
+        if let Some(entry) = self.get_value(store, "text_index").await? {
+            self.put_value(store, &backup_key, entry.to_vec()).await?;
         }
 
         // Similar for other indices...
@@ -507,6 +628,7 @@ mod tests {
     use super::*;
 
     #[tokio::test]
+    #[ignore] // Requires NATS server running
     async fn test_encryption_wrapper() {
         // Test creating and decrypting wrapper
         let key = vec![0u8; 32]; // 32-byte key
@@ -14,11 +14,18 @@ use crate::{
         DocumentMetadata, ImageMetadata,
         content_type_name, codec,
     },
-    object_store::{NatsObjectStore, PullOptions},
+    object_store::{
+        Action, AuditAction, AuditEvent, AuditLog, Authorizer, Batch, BatchCommit, ContentBucket, Credential, Expiry, ExpiryRecord, NatsObjectStore, ObjectStoreError,
+        PullOptions, Retention, RetentionGuard, StorageBackend, Tombstone, Tombstones,
+        DEFAULT_PURGE_WINDOW,
+    },
     TypedContent, ContentType, Cid, Result, Error,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
 use serde::{Deserialize, Serialize};
 
 // Serde support for CID
@@ -52,6 +59,14 @@ pub struct ContentService {
     config: ContentServiceConfig,
     /// Content lifecycle hooks
     hooks: Arc<RwLock<LifecycleHooks>>,
+    /// Tombstones of soft-deleted content
+    tombstones: Arc<Tombstones>,
+    /// Per-object expiry times
+    expiry: Arc<Expiry>,
+    /// Token every operation is checked against, if access is restricted
+    capability: Option<(Arc<Authorizer>, Arc<Credential>)>,
+    /// Audit log and the actor operations are recorded under
+    audit: Option<(Arc<AuditLog>, String)>,
 }
 
 /// Configuration for the content service
@@ -67,6 +82,8 @@ pub struct ContentServiceConfig {
     pub allowed_types: Vec<ContentType>,
     /// Enable content deduplication
     pub enable_deduplication: bool,
+    /// Time soft-deleted content can be restored before it is purged
+    pub purge_window: Duration,
 }
 
 impl Default for ContentServiceConfig {
@@ -77,6 +94,7 @@ impl Default for ContentServiceConfig {
             max_content_size: 100 * 1024 * 1024, // 100MB
             allowed_types: Vec::new(), // All types allowed
             enable_deduplication: true,
+            purge_window: DEFAULT_PURGE_WINDOW,
         }
     }
 }
@@ -93,7 +111,6 @@ struct LifecycleHooks {
     /// Called before content is stored
     pre_store: Vec<PreStoreHook>,
     /// Called after content is stored
-    #[allow(dead_code)]
     post_store: Vec<PostStoreHook>,
     /// Called before content is retrieved
     #[allow(dead_code)]
@@ -136,14 +153,64 @@ impl ContentService {
         storage: Arc<NatsObjectStore>,
         config: ContentServiceConfig,
     ) -> Self {
+        let tombstones = Tombstones::new(storage.clone()).with_purge_window(config.purge_window);
+        let expiry = Expiry::new(storage.clone());
         Self {
             storage,
             index: Arc::new(ContentIndex::new()),
             config,
             hooks: Arc::new(RwLock::new(LifecycleHooks::default())),
+            tombstones: Arc::new(tombstones),
+            expiry: Arc::new(expiry),
+            capability: None,
+            audit: None,
+        }
+    }
+
+    /// Record stores, deletes, restores, expiries, and transforms, and
+    /// reads if the log asks for them, in an audit log under `actor`
+    ///
+    /// Soft deletes are recorded under the actor passed to
+    /// [`Self::soft_delete`].
+    pub fn with_audit(mut self, audit: Arc<AuditLog>, actor: impl Into<String>) -> Self {
+        self.audit = Some((audit, actor.into()));
+        self
+    }
+
+    /// Append an event for `cid` to the audit log, if there is one
+    async fn audit(&self, action: AuditAction, cid: &Cid, build: impl FnOnce(AuditEvent) -> AuditEvent) -> Result<()> {
+        let Some((audit, actor)) = &self.audit else {
+            return Ok(());
+        };
+        let event = build(AuditEvent::new(action, *cid, actor.as_str()));
+        match action {
+            AuditAction::Read => audit.record_read(event).await,
+            _ => audit.record(event).await.map(|_| ()),
         }
     }
 
+    /// Restrict this service to what the credential's token grants
+    ///
+    /// Clone the service and call this per caller; every store, retrieve,
+    /// search, list, and delete is then signed with the credential and
+    /// checked against its token.
+    pub fn with_capability(mut self, authorizer: Arc<Authorizer>, credential: Credential) -> Self {
+        self.capability = Some((authorizer, Arc::new(credential)));
+        self
+    }
+
+    fn authorize(&self, action: Action, bucket: &str, cid: Option<&Cid>) -> Result<()> {
+        match &self.capability {
+            Some((authorizer, credential)) => Ok(authorizer.authorize(&credential.invoke(action, bucket, cid))?),
+            None => Ok(()),
+        }
+    }
+
+    fn authorize_type(&self, action: Action, content_type: ContentType, cid: Option<&Cid>) -> Result<()> {
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        self.authorize(action, bucket.as_str(), cid)
+    }
+
     /// Store a document
     pub async fn store_document(
         &self,
@@ -162,39 +229,19 @@ impl ContentService {
         let content_type = match format {
             "pdf" => {
                 let pdf = PdfDocument::new(data, metadata)?;
-                self.store_typed_content(pdf).await?
+                self.store_typed_content(pdf, None).await?
             }
             "markdown" | "md" => {
                 let content = String::from_utf8(data)
                     .map_err(|_| Error::InvalidContent("Invalid UTF-8".to_string()))?;
                 let md = MarkdownDocument::new(content, metadata)?;
-                
-                // Index if enabled
-                if self.config.auto_index {
-                    self.index.index_document(
-                        md.calculate_cid()?,
-                        &md.metadata,
-                        Some(&md.content),
-                    ).await?;
-                }
-                
-                self.store_typed_content(md).await?
+                self.store_typed_content(md, None).await?
             }
             "text" | "txt" => {
                 let content = String::from_utf8(data)
                     .map_err(|_| Error::InvalidContent("Invalid UTF-8".to_string()))?;
                 let txt = TextDocument::new(content, metadata)?;
-                
-                // Index if enabled
-                if self.config.auto_index {
-                    self.index.index_document(
-                        txt.calculate_cid()?,
-                        &txt.metadata,
-                        Some(&txt.content),
-                    ).await?;
-                }
-                
-                self.store_typed_content(txt).await?
+                self.store_typed_content(txt, None).await?
             }
             _ => {
                 return Err(Error::InvalidContent(format!(
@@ -223,31 +270,11 @@ impl ContentService {
         let content_type = match format {
             "jpeg" | "jpg" => {
                 let jpeg = JpegImage::new(data, metadata)?;
-                
-                // Index if enabled
-                if self.config.auto_index {
-                    self.index.index_image(
-                        jpeg.calculate_cid()?,
-                        &jpeg.metadata,
-                        ContentType::Custom(codec::JPEG),
-                    ).await?;
-                }
-                
-                self.store_typed_content(jpeg).await?
+                self.store_typed_content(jpeg, None).await?
             }
             "png" => {
                 let png = PngImage::new(data, metadata)?;
-                
-                // Index if enabled
-                if self.config.auto_index {
-                    self.index.index_image(
-                        png.calculate_cid()?,
-                        &png.metadata,
-                        ContentType::Custom(codec::PNG),
-                    ).await?;
-                }
-                
-                self.store_typed_content(png).await?
+                self.store_typed_content(png, None).await?
             }
             _ => {
                 return Err(Error::InvalidContent(format!(
@@ -259,37 +286,43 @@ impl ContentService {
         Ok(content_type)
     }
 
-    /// Store typed content
+    /// Store typed content, expiring it after `ttl` if given
+    ///
+    /// Every store, single or batched, passes [`Self::check_store`] before
+    /// anything is written and [`Self::finish_store`] afterwards.
     async fn store_typed_content<T: TypedContent>(
         &self,
         content: T,
+        ttl: Option<Duration>,
     ) -> Result<StoreResult> {
         let content_type = T::CONTENT_TYPE;
-        
-        // Check allowed types
-        if !self.config.allowed_types.is_empty() 
-            && !self.config.allowed_types.contains(&content_type) {
-            return Err(Error::InvalidContent(format!("Content type {} not allowed", content_type_name(content_type))));
-        }
 
         // Calculate CID for deduplication check
         let cid = content.calculate_cid()?;
+        let data = content.to_bytes()?;
+        self.check_store(content_type, &cid, &data).await?;
         
-        // Check if already exists (deduplication)
-        let deduplicated = if self.config.enable_deduplication {
-            match self.storage.exists(&cid, content_type.codec()).await {
-                Ok(true) => true,
-                Ok(false) => false,
-                Err(_) => false, // Assume doesn't exist on error
+        // Check if already exists (deduplication); this decides whether a
+        // TTL is kept, so an unreachable store fails the call
+        let exists = self.storage.exists(&cid, content_type.codec()).await?;
+        let deduplicated = self.config.enable_deduplication && exists;
+
+        // Storing content for good drops its TTL; the TTL is recorded
+        // before a write so the content never exists without it
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        match ttl {
+            Some(ttl) => {
+                self.expiry.store_ttl(bucket.as_str(), &cid, ttl, exists).await?;
             }
-        } else {
-            false
-        };
+            None if exists => {
+                self.expiry.clear(&cid).await?;
+            }
+            None => {}
+        }
 
         // Store if not deduplicated
         let size = if !deduplicated {
-            let stored_cid = self.storage.put(&content).await
-                .map_err(|e| Error::InvalidContent(format!("Storage error: {e}")))?;
+            let stored_cid = self.storage.put(&content).await?;
             assert_eq!(cid, stored_cid); // Verify CID consistency
             
             // Get actual size from storage
@@ -305,6 +338,8 @@ impl ContentService {
             }
         };
 
+        self.finish_store(&cid, content_type, &data, deduplicated, None).await?;
+
         Ok(StoreResult {
             cid,
             content_type,
@@ -317,8 +352,85 @@ impl ContentService {
         })
     }
 
+    /// Reject content before it is written
+    ///
+    /// Checks the allowed types and the capability token, then runs the
+    /// pre-store hooks.
+    async fn check_store(&self, content_type: ContentType, cid: &Cid, data: &[u8]) -> Result<()> {
+        if !self.config.allowed_types.is_empty()
+            && !self.config.allowed_types.iter().any(|t| t.codec() == content_type.codec()) {
+            return Err(Error::InvalidContent(format!("Content type {} not allowed", content_type_name(content_type))));
+        }
+        self.authorize_type(Action::Write, content_type, Some(cid))?;
+
+        let hooks = self.hooks.read().await;
+        for hook in &hooks.pre_store {
+            hook(data, &content_type)?;
+        }
+        Ok(())
+    }
+
+    /// Index, audit, and run post-store hooks for content just stored
+    async fn finish_store(
+        &self,
+        cid: &Cid,
+        content_type: ContentType,
+        data: &[u8],
+        deduplicated: bool,
+        batch: Option<&Cid>,
+    ) -> Result<()> {
+        // Storing soft-deleted content again makes it visible
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        self.tombstones.clear(bucket.as_str(), cid).await?;
+        self.index_content(cid, content_type, data).await?;
+
+        self.audit(AuditAction::Store, cid, |event| {
+            let event = event.with_bucket(bucket.as_str());
+            match (batch, deduplicated) {
+                (Some(manifest), true) => event.with_detail(format!("batch {manifest}, deduplicated")),
+                (Some(manifest), false) => event.with_detail(format!("batch {manifest}")),
+                (None, true) => event.with_detail("deduplicated"),
+                (None, false) => event,
+            }
+        }).await?;
+
+        let hooks = self.hooks.read().await;
+        for hook in &hooks.post_store {
+            hook(cid, &content_type);
+        }
+        Ok(())
+    }
+
+    /// Add searchable content to the index, if auto-indexing is enabled
+    async fn index_content(&self, cid: &Cid, content_type: ContentType, data: &[u8]) -> Result<()> {
+        if !self.config.auto_index {
+            return Ok(());
+        }
+        match content_type.codec() {
+            codec::MARKDOWN => {
+                let md = <MarkdownDocument as TypedContent>::from_bytes(data)?;
+                self.index.index_document(*cid, &md.metadata, Some(&md.content)).await
+            }
+            codec::TEXT => {
+                let txt = <TextDocument as TypedContent>::from_bytes(data)?;
+                self.index.index_document(*cid, &txt.metadata, Some(&txt.content)).await
+            }
+            codec::JPEG => {
+                let jpeg = <JpegImage as TypedContent>::from_bytes(data)?;
+                self.index.index_image(*cid, &jpeg.metadata, content_type).await
+            }
+            codec::PNG => {
+                let png = <PngImage as TypedContent>::from_bytes(data)?;
+                self.index.index_image(*cid, &png.metadata, content_type).await
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Retrieve content by CID
     pub async fn retrieve<T: TypedContent>(&self, cid: &Cid) -> Result<RetrieveResult<T>> {
+        self.authorize_type(Action::Read, T::CONTENT_TYPE, Some(cid))?;
+
         // Call pre-retrieve hooks
         {
             let hooks = self.hooks.read().await;
@@ -327,9 +439,15 @@ impl ContentService {
             }
         }
 
+        // Soft-deleted content is hidden until restored
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        if self.tombstones.is_deleted(bucket.as_str(), cid).await? {
+            return Err(ObjectStoreError::NotFound(cid.to_string()).into());
+        }
+
         // Retrieve from storage
-        let content: T = self.storage.get(cid).await
-            .map_err(|e| Error::InvalidContent(format!("Storage error: {e}")))?;
+        let content: T = self.storage.get(cid).await?;
+        self.audit(AuditAction::Read, cid, |event| event).await?;
 
         // Call post-retrieve hooks
         {
@@ -347,9 +465,146 @@ impl ContentService {
         })
     }
 
-    /// Search for content
+    /// Search for content, leaving out soft-deleted content and content
+    /// the capability token does not allow reading
     pub async fn search(&self, query: SearchQuery) -> Result<Vec<SearchResult>> {
-        self.index.search(&query).await
+        let mut results = self.index.search(&query).await?;
+        if !results.is_empty() {
+            let deleted = self.tombstones.deleted().await?;
+            results.retain(|result| {
+                let bucket = ContentBucket::for_content_type(result.content_type.codec());
+                !deleted.contains(bucket.as_str(), &result.cid)
+            });
+        }
+        if self.capability.is_some() {
+            results.retain(|result| {
+                self.authorize_type(Action::Read, result.content_type, Some(&result.cid)).is_ok()
+            });
+        }
+        Ok(results)
+    }
+
+    /// Hide content behind a tombstone until it is restored or purged
+    pub async fn soft_delete(&self, cid: &Cid, content_type: ContentType, reason: &str, actor: &str) -> Result<Tombstone> {
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        self.authorize(Action::Delete, bucket.as_str(), Some(cid))?;
+        let tombstone = self.tombstones.delete(bucket.as_str(), cid, reason, actor).await?;
+        if let Some((audit, _)) = &self.audit {
+            audit.record(
+                AuditEvent::new(AuditAction::SoftDelete, *cid, actor)
+                    .with_bucket(bucket.as_str())
+                    .with_detail(reason),
+            ).await?;
+        }
+        Ok(tombstone)
+    }
+
+    /// Undo a soft delete within the purge window
+    pub async fn restore(&self, cid: &Cid, content_type: ContentType) -> Result<Tombstone> {
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        self.authorize(Action::Delete, bucket.as_str(), Some(cid))?;
+        let tombstone = self.tombstones.restore(bucket.as_str(), cid).await?;
+        self.audit(AuditAction::Restore, cid, |event| event.with_bucket(bucket.as_str())).await?;
+        Ok(tombstone)
+    }
+
+    /// Tombstone registry used for soft deletes
+    pub fn tombstones(&self) -> &Arc<Tombstones> {
+        &self.tombstones
+    }
+
+    /// Refuse to soft delete, purge, or expire content under retention
+    ///
+    /// Purges and expiries delete through a [`RetentionGuard`] over the
+    /// store; use [`NatsObjectStore::with_retention`] to guard every other
+    /// delete as well.
+    pub fn with_retention(mut self, retention: Arc<Retention>) -> Self {
+        let guarded: Arc<dyn StorageBackend> = Arc::new(RetentionGuard::new(self.storage.clone(), retention.clone()));
+        let tombstones = Tombstones::new(guarded.clone())
+            .with_purge_window(self.config.purge_window)
+            .with_retention(retention);
+        self.tombstones = Arc::new(tombstones);
+        self.expiry = Arc::new(Expiry::new(guarded));
+        self
+    }
+
+    /// Store content that is deleted once `ttl` has passed
+    ///
+    /// Content that is already stored keeps its expiry as described in
+    /// [`Expiry::store_ttl`].
+    pub async fn store_with_ttl<T: TypedContent>(&self, content: T, ttl: Duration) -> Result<StoreResult> {
+        self.authorize_type(Action::Delete, T::CONTENT_TYPE, None)?;
+        let size = content.to_bytes()?.len();
+        if size > self.config.max_content_size {
+            return Err(Error::InvalidContent(format!(
+                "Content size {} exceeds maximum {}",
+                size, self.config.max_content_size
+            )));
+        }
+        self.store_typed_content(content, Some(ttl)).await
+    }
+
+    /// Expire stored content after `ttl`, replacing any earlier TTL
+    pub async fn set_ttl(&self, cid: &Cid, content_type: ContentType, ttl: Duration) -> Result<ExpiryRecord> {
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        self.authorize(Action::Delete, bucket.as_str(), Some(cid))?;
+        if !self.storage.exists(cid, content_type.codec()).await? {
+            return Err(ObjectStoreError::NotFound(cid.to_string()).into());
+        }
+        Ok(self.expiry.set_ttl(bucket.as_str(), cid, ttl).await?)
+    }
+
+    /// Expiry registry used for per-object TTLs
+    pub fn expiry(&self) -> &Arc<Expiry> {
+        &self.expiry
+    }
+
+    /// Push content's expiry back by `by`
+    pub async fn extend_ttl(&self, cid: &Cid, by: Duration) -> Result<ExpiryRecord> {
+        self.authorize_ttl(cid).await?;
+        Ok(self.expiry.extend(cid, by).await?)
+    }
+
+    /// Keep content for good; returns whether it had a TTL
+    pub async fn clear_ttl(&self, cid: &Cid) -> Result<bool> {
+        self.authorize_ttl(cid).await?;
+        Ok(self.expiry.clear(cid).await?)
+    }
+
+    async fn authorize_ttl(&self, cid: &Cid) -> Result<()> {
+        if self.capability.is_none() {
+            return Ok(());
+        }
+        match self.expiry.get(cid).await? {
+            Some(record) => self.authorize(Action::Write, &record.bucket, Some(cid)),
+            None => Ok(()),
+        }
+    }
+
+    /// Delete content whose TTL has passed and remove it from the index
+    pub async fn expire_due(&self) -> Result<Vec<ExpiryRecord>> {
+        let expired = self.expiry.expire_due().await?;
+        for record in &expired {
+            self.index.remove(&record.cid).await?;
+            self.audit(AuditAction::Expire, &record.cid, |event| event.with_bucket(record.bucket.as_str())).await?;
+        }
+        Ok(expired)
+    }
+
+    /// Run expiry passes in the background at the given interval
+    pub fn spawn_expirer(&self, interval: Duration) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match service.expire_due().await {
+                    Ok(expired) if !expired.is_empty() => debug!("Expired {} objects", expired.len()),
+                    Ok(_) => {}
+                    Err(e) => warn!("Expiry pass failed: {}", e),
+                }
+            }
+        })
     }
 
     /// Get content statistics
@@ -373,12 +628,18 @@ impl ContentService {
         content_type: ContentType,
         options: PullOptions,
     ) -> Result<Vec<Cid>> {
+        self.authorize_type(Action::List, content_type, None)?;
+
         // Get objects from storage
-        let objects = self.storage.list_by_content_type(content_type.codec(), None).await
-            .map_err(|e| Error::InvalidContent(format!("Storage error: {e}")))?;
+        let objects = self.storage.list_by_content_type(content_type.codec(), None).await?;
 
-        // Extract CIDs
-        let mut cids: Vec<Cid> = objects.into_iter().map(|info| info.cid).collect();
+        // Extract CIDs, leaving out soft-deleted content
+        let bucket = ContentBucket::for_content_type(content_type.codec());
+        let deleted = self.tombstones.deleted().await?;
+        let mut cids: Vec<Cid> = objects.into_iter()
+            .map(|info| info.cid)
+            .filter(|cid| !deleted.contains(bucket.as_str(), cid))
+            .collect();
 
         // Apply filtering and sorting based on options
         if let Some(limit) = options.limit {
@@ -394,6 +655,17 @@ impl ContentService {
         cid: &Cid,
         target: TransformTarget,
         options: TransformOptions,
+    ) -> Result<TransformationResult> {
+        let result = self.transform_content(cid, target, options).await?;
+        self.audit(AuditAction::Transform, cid, |event| event.with_detail(format!("{target:?}"))).await?;
+        Ok(result)
+    }
+
+    async fn transform_content(
+        &self,
+        cid: &Cid,
+        target: TransformTarget,
+        options: TransformOptions,
     ) -> Result<TransformationResult> {
         use crate::content_types::transformers::{document, image, TransformMetadata};
         use std::time::SystemTime;
@@ -568,7 +840,7 @@ impl ContentService {
             .map(|(idx, item)| {
                 let service = self.clone();
                 async move {
-                    match service.store_typed_content(item).await {
+                    match service.store_typed_content(item, None).await {
                         Ok(result) => Ok((idx, result)),
                         Err(e) => Err((idx, e)),
                     }
@@ -592,6 +864,46 @@ impl ContentService {
         
         batch_result
     }
+
+    /// Store items all together or not at all
+    ///
+    /// Items go through the same checks, hooks, indexing, and auditing as
+    /// single stores. Returns the batch manifest; if any item is rejected or
+    /// fails to store, nothing is indexed and no manifest is written.
+    pub async fn batch_store_atomic<T: TypedContent>(&self, items: &[T]) -> Result<BatchCommit> {
+        let mut batch = Batch::new();
+        for item in items {
+            batch.add(item)?;
+        }
+        self.commit_batch(batch).await
+    }
+
+    /// Commit a batch of possibly mixed content types
+    ///
+    /// Every object is checked against the allowed types, capability, size
+    /// limit, and pre-store hooks before anything is written.
+    pub async fn commit_batch(&self, batch: Batch) -> Result<BatchCommit> {
+        for object in batch.objects() {
+            self.check_store(object.content_type, &object.cid, &object.data).await?;
+        }
+        if let Some(size) = batch.sizes().find(|size| *size > self.config.max_content_size) {
+            return Err(Error::InvalidContent(format!(
+                "Content size {} exceeds maximum {}",
+                size, self.config.max_content_size
+            )));
+        }
+
+        let commit = batch.commit(self.storage.as_ref()).await?;
+        for object in batch.objects() {
+            let deduplicated = commit.deduplicated.contains(&object.cid);
+            if deduplicated {
+                self.expiry.clear(&object.cid).await?;
+            }
+            self.finish_store(&object.cid, object.content_type, &object.data, deduplicated, Some(&commit.manifest_cid))
+                .await?;
+        }
+        Ok(commit)
+    }
 }
 
 // Make ContentService cloneable
@@ -602,6 +914,10 @@ impl Clone for ContentService {
             index: Arc::clone(&self.index),
             config: self.config.clone(),
             hooks: Arc::clone(&self.hooks),
+            tombstones: Arc::clone(&self.tombstones),
+            expiry: Arc::clone(&self.expiry),
+            capability: self.capability.clone(),
+            audit: self.audit.clone(),
         }
     }
 }
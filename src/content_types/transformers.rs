@@ -10,9 +10,55 @@ use crate::{
         MarkdownDocument,
         VideoMetadata,
     },
+    metrics::{self, Histogram, HistogramTimer, LATENCY_BUCKETS},
     Error, Result,
 };
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Transformations whose durations are recorded
+const TRANSFORMS: &[&str] = &[
+    "document.markdown_to_html",
+    "document.to_plain_text",
+    "image.convert_format",
+    "image.resize",
+    "image.generate_thumbnail",
+    "audio.convert_format",
+    "audio.extract_metadata",
+    "video.convert_format",
+    "video.extract_metadata",
+    "video.extract_thumbnail",
+    "batch",
+    "validation.validate_document",
+];
+
+/// Duration histograms per transformation, registered once
+fn transform_metrics() -> &'static HashMap<&'static str, Histogram> {
+    static METRICS: OnceLock<HashMap<&'static str, Histogram>> = OnceLock::new();
+    METRICS.get_or_init(|| {
+        let registry = metrics::global();
+        TRANSFORMS
+            .iter()
+            .map(|transform| {
+                let histogram = registry.histogram(
+                    "cim_ipld_transform_duration_seconds",
+                    "Content transformation duration",
+                    &[("transform", transform)],
+                    LATENCY_BUCKETS,
+                );
+                (*transform, histogram)
+            })
+            .collect()
+    })
+}
+
+/// Start timing a transformation; the duration is recorded when the timer drops
+fn transform_timer(transform: &'static str) -> HistogramTimer {
+    transform_metrics()
+        .get(transform)
+        .expect("transform is listed in TRANSFORMS")
+        .start_timer()
+}
 
 // Define AudioFormat and AudioMetadata locally if they're not available
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -128,6 +174,7 @@ pub mod document {
     
     /// Convert Markdown to HTML using pulldown-cmark
     pub fn markdown_to_html(markdown: &MarkdownDocument) -> Result<String> {
+        let _timer = transform_timer("document.markdown_to_html");
         let mut html_output = String::new();
         html_output.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
         html_output.push_str("<meta charset=\"UTF-8\">\n");
@@ -157,6 +204,7 @@ pub mod document {
     
     /// Convert any document to plain text
     pub fn to_plain_text(content: &str) -> Result<String> {
+        let _timer = transform_timer("document.to_plain_text");
         // Use regex to strip HTML tags
         let tag_regex = Regex::new(r"<[^>]+>").map_err(|e| 
             Error::InvalidContent(format!("Regex error: {e}"))
@@ -218,6 +266,7 @@ pub mod image {
         to_format: &str,
         quality: Option<u8>,
     ) -> Result<Vec<u8>> {
+        let _timer = transform_timer("image.convert_format");
         if from_format == to_format {
             return Ok(data.to_vec());
         }
@@ -236,6 +285,7 @@ pub mod image {
         width: u32,
         height: u32,
     ) -> Result<Vec<u8>> {
+        let _timer = transform_timer("image.resize");
         let img = load_image(data, format)?;
         
         // Calculate new dimensions maintaining aspect ratio
@@ -257,6 +307,7 @@ pub mod image {
         format: &str,
         max_size: u32,
     ) -> Result<Vec<u8>> {
+        let _timer = transform_timer("image.generate_thumbnail");
         let img = load_image(data, format)?;
         
         // Generate thumbnail
@@ -323,6 +374,7 @@ pub mod audio {
         to_format: &str,
         _bitrate: Option<u32>,
     ) -> Result<Vec<u8>> {
+        let _timer = transform_timer("audio.convert_format");
         if from_format == to_format {
             return Ok(data.to_vec());
         }
@@ -336,6 +388,7 @@ pub mod audio {
     
     /// Extract audio metadata using symphonia
     pub fn extract_metadata(data: &[u8], format: &str) -> Result<AudioMetadata> {
+        let _timer = transform_timer("audio.extract_metadata");
         // Clone data to avoid lifetime issues with symphonia
         let data_vec = data.to_vec();
         let cursor = Cursor::new(data_vec);
@@ -437,6 +490,7 @@ pub mod video {
         to_format: &str,
         _options: VideoConversionOptions,
     ) -> Result<Vec<u8>> {
+        let _timer = transform_timer("video.convert_format");
         if from_format == to_format {
             return Ok(data.to_vec());
         }
@@ -461,6 +515,7 @@ pub mod video {
     /// Extract basic video metadata
     /// Note: Full metadata extraction would require a library like ffmpeg or gstreamer
     pub fn extract_metadata(data: &[u8], format: &str) -> Result<VideoMetadata> {
+        let _timer = transform_timer("video.extract_metadata");
         let mut metadata = VideoMetadata {
             video_codec: None,
             audio_codec: None,
@@ -525,6 +580,7 @@ pub mod video {
         _format: &str,
         _timestamp_ms: u64,
     ) -> Result<Vec<u8>> {
+        let _timer = transform_timer("video.extract_thumbnail");
         // Thumbnail extraction requires video decoding capabilities
         // This could be implemented using:
         // 1. ffmpeg-sys crate for direct ffmpeg bindings
@@ -594,6 +650,7 @@ impl BatchTransformer {
         F: Fn(T) -> Result<TransformationResult> + Send + Sync + Clone + 'static,
     {
         use futures::stream::{self, StreamExt};
+        let _timer = transform_timer("batch");
         
         let results = stream::iter(items)
             .map(|item| {
@@ -614,6 +671,7 @@ pub mod validation {
     
     /// Validate document content
     pub fn validate_document(data: &[u8], format: &str) -> Result<ValidationReport> {
+        let _timer = transform_timer("validation.validate_document");
         let mut report = ValidationReport::new(format);
         
         match format {
@@ -799,30 +857,7 @@ mod tests {
             _ => panic!("Expected InvalidContent error"),
         }
         
-        // Test converting to unsupported format (will fail at encode)
-        // First create a valid minimal PNG
-        let valid_png = [
-            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
-            0x00, 0x00, 0x00, 0x0D, // IHDR chunk length
-            0x49, 0x48, 0x44, 0x52, // IHDR
-            0x00, 0x00, 0x00, 0x01, // width: 1
-            0x00, 0x00, 0x00, 0x01, // height: 1
-            0x08, 0x02, // bit depth: 8, color type: RGB
-            0x00, 0x00, 0x00, // compression, filter, interlace
-            0x90, 0x77, 0x53, 0xDE, // CRC
-            0x00, 0x00, 0x00, 0x0C, // IDAT chunk length
-            0x49, 0x44, 0x41, 0x54, // IDAT
-            0x08, 0x99, 0x01, 0x01, 0x00, 0x00, 0xFE, 0xFF, // compressed data
-            0x00, 0x00, 0x00, 0x02, // Adler32
-            0x00, 0x01, // CRC start
-            0x00, 0x01, // CRC end
-            0x00, 0x00, 0x00, 0x00, // IEND chunk length
-            0x49, 0x45, 0x4E, 0x44, // IEND
-            0xAE, 0x42, 0x60, 0x82, // CRC
-        ];
-        
-        // This test would fail because we're creating an invalid PNG above
-        // For now, just test that unsupported format returns error
+        // Test that an unsupported source format returns an error
         let simple_data = b"some data";
         let result = convert_format(simple_data, "unknown", "jpeg", None);
         assert!(result.is_err());
@@ -2,8 +2,91 @@
 
 //! Error types for CIM-IPLD
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::content_types::encryption::EncryptionError;
+use crate::content_types::persistence::PersistenceError;
+use crate::object_store::ObjectStoreError;
+
+/// Stable, machine-readable error classification
+///
+/// Codes are shared by every error type in the crate, so callers can branch
+/// on the cause without matching each enum or parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Serialization,
+    InvalidCid,
+    ChainValidation,
+    SequenceValidation,
+    InvalidCodec,
+    CodecNotFound,
+    ContentTypeMismatch,
+    Multihash,
+    InvalidContent,
+    NotFound,
+    Integrity,
+    Compression,
+    Storage,
+    Unavailable,
+    Timeout,
+    CircuitOpen,
+    Conflict,
+    InvalidSignature,
+    Retained,
+    QuotaExceeded,
+    Unauthorized,
+    HistoryTruncated,
+    Encryption,
+    Decryption,
+    InvalidKey,
+}
+
+impl ErrorCode {
+    /// The code as a stable string, e.g. `"not_found"`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Serialization => "serialization",
+            Self::InvalidCid => "invalid_cid",
+            Self::ChainValidation => "chain_validation",
+            Self::SequenceValidation => "sequence_validation",
+            Self::InvalidCodec => "invalid_codec",
+            Self::CodecNotFound => "codec_not_found",
+            Self::ContentTypeMismatch => "content_type_mismatch",
+            Self::Multihash => "multihash",
+            Self::InvalidContent => "invalid_content",
+            Self::NotFound => "not_found",
+            Self::Integrity => "integrity",
+            Self::Compression => "compression",
+            Self::Storage => "storage",
+            Self::Unavailable => "unavailable",
+            Self::Timeout => "timeout",
+            Self::CircuitOpen => "circuit_open",
+            Self::Conflict => "conflict",
+            Self::InvalidSignature => "invalid_signature",
+            Self::Retained => "retained",
+            Self::QuotaExceeded => "quota_exceeded",
+            Self::Unauthorized => "unauthorized",
+            Self::HistoryTruncated => "history_truncated",
+            Self::Encryption => "encryption",
+            Self::Decryption => "decryption",
+            Self::InvalidKey => "invalid_key",
+        }
+    }
+
+    /// Whether the same operation may succeed if tried again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Unavailable | Self::Timeout | Self::CircuitOpen)
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Failed to serialize content: {0}")]
@@ -35,9 +118,53 @@ pub enum Error {
 
     #[error("Invalid content: {0}")]
     InvalidContent(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
     
     #[error("Storage error: {0}")]
     StorageError(String),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] ObjectStoreError),
+
+    #[error("Persistence error: {0}")]
+    Persistence(#[from] PersistenceError),
+
+    #[error("Encryption error: {0}")]
+    Encryption(#[from] EncryptionError),
+}
+
+impl Error {
+    /// Machine-readable classification of this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::SerializationError(_) | Self::CborError(_) => ErrorCode::Serialization,
+            Self::InvalidCid(_) => ErrorCode::InvalidCid,
+            Self::ChainValidationError { .. } => ErrorCode::ChainValidation,
+            Self::SequenceValidationError { .. } => ErrorCode::SequenceValidation,
+            Self::InvalidCodecRange(_) => ErrorCode::InvalidCodec,
+            Self::CodecNotFound(_) => ErrorCode::CodecNotFound,
+            Self::ContentTypeMismatch { .. } => ErrorCode::ContentTypeMismatch,
+            Self::MultihashError(_) => ErrorCode::Multihash,
+            Self::InvalidContent(_) => ErrorCode::InvalidContent,
+            Self::InvalidSignature(_) => ErrorCode::InvalidSignature,
+            Self::StorageError(_) => ErrorCode::Storage,
+            Self::ObjectStore(e) => e.code(),
+            Self::Persistence(e) => e.code(),
+            Self::Encryption(e) => e.code(),
+        }
+    }
+
+    /// Whether the operation may succeed if tried again later
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+
+    /// Whether the requested content or key does not exist
+    pub fn is_not_found(&self) -> bool {
+        self.code() == ErrorCode::NotFound
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -148,6 +275,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_literal_unwrap)]
     fn test_error_result_type() {
         // Test that Result type alias works correctly
         let success: Result<i32> = Ok(42);
@@ -164,6 +292,49 @@ mod tests {
         assert_send_sync::<Error>();
     }
 
+    #[test]
+    fn test_error_codes() {
+        let err = Error::InvalidCid("x".to_string());
+        assert_eq!(err.code(), ErrorCode::InvalidCid);
+        assert_eq!(err.code().as_str(), "invalid_cid");
+        assert!(!err.is_retryable());
+
+        let err = Error::from(ObjectStoreError::NotFound("cid".to_string()));
+        assert!(err.is_not_found());
+        assert_eq!(err.code().to_string(), "not_found");
+
+        let err = Error::from(ObjectStoreError::timeout("get", "no reply"));
+        assert!(err.is_retryable());
+        assert!(!err.is_not_found());
+
+        let err = Error::from(PersistenceError::from(EncryptionError::InvalidNonce));
+        assert_eq!(err.code(), ErrorCode::InvalidKey);
+    }
+
+    #[test]
+    fn test_wrapped_errors_keep_source() {
+        let err = Error::from(PersistenceError::from(EncryptionError::KeyDerivationFailed));
+        let persistence = err.source().expect("persistence error");
+        assert!(persistence.source().is_some_and(|e| e.is::<EncryptionError>()));
+
+        match err {
+            Error::Persistence(PersistenceError::Crypto(EncryptionError::KeyDerivationFailed)) => {}
+            other => panic!("unexpected error {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_object_store_errors_keep_source() {
+        let json_err = serde_json::from_str::<String>("invalid").unwrap_err();
+        let err = ObjectStoreError::deserialization(json_err);
+        assert!(err.source().is_some_and(|e| e.is::<serde_json::Error>()));
+
+        // Copies handed to coalesced readers share the same source
+        let ObjectStoreError::Deserialization(source) = &err else { unreachable!() };
+        let copy = ObjectStoreError::Deserialization(source.clone());
+        assert!(copy.source().is_some_and(|e| e.is::<serde_json::Error>()));
+    }
+
     #[test]
     fn test_error_source_chain() {
         // Test that errors can be chained properly
@@ -110,9 +110,11 @@ pub mod chain;
 pub mod codec;
 pub mod content_types;
 pub mod error;
+pub mod metrics;
 pub mod traits;
 pub mod types;
 pub mod object_store;
+mod util;
 
 // Re-exports for convenience
 pub use cid::Cid;
@@ -126,7 +128,7 @@ pub use codec::ipld_codecs::{
     AlchemistJsonCodec, WorkflowGraphJsonCodec, ContextGraphJsonCodec,
     CodecOperations, types as codec_types,
 };
-pub use error::{Error, Result};
+pub use error::{Error, ErrorCode, Result};
 pub use traits::TypedContent;
 pub use types::ContentType;
 
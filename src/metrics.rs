@@ -0,0 +1,392 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Metrics for storage, caching, indexing and transformations
+//!
+//! The crate records counters, gauges and histograms into a
+//! [`MetricsRegistry`]. Instrumented components use the process-wide
+//! [`global()`] registry; applications expose it by serving the output of
+//! [`MetricsRegistry::render`] from their own HTTP endpoint, in Prometheus
+//! text format.
+//!
+//! Looking a metric up takes the registry lock, so hot paths register their
+//! handles once and keep them; handles are cheap to clone and share.
+//!
+//! ```
+//! let requests = cim_ipld::metrics::global().counter(
+//!     "example_requests_total",
+//!     "Requests handled",
+//!     &[("route", "/")],
+//! );
+//! requests.inc();
+//! assert!(cim_ipld::metrics::global().render().contains("example_requests_total{route=\"/\"}"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use tracing::warn;
+
+/// Default histogram buckets for operation latencies, in seconds
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Histogram buckets for ratios between 0 and 1
+pub const RATIO_BUCKETS: &[f64] = &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
+
+/// A monotonically increasing counter
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    /// Increment by one
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    /// Increment by `n`
+    pub fn inc_by(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Current value
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A value that can go up and down
+#[derive(Debug, Clone, Default)]
+pub struct Gauge(Arc<AtomicU64>);
+
+impl Gauge {
+    /// Set the value
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Add to the value; use a negative delta to subtract
+    pub fn add(&self, delta: f64) {
+        let _ = self.0.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + delta).to_bits())
+        });
+    }
+
+    /// Current value
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug)]
+struct HistogramInner {
+    bounds: Vec<f64>,
+    counts: Vec<AtomicU64>,
+    sum: AtomicU64,
+    count: AtomicU64,
+}
+
+/// A distribution of observed values over fixed buckets
+#[derive(Debug, Clone)]
+pub struct Histogram(Arc<HistogramInner>);
+
+impl Histogram {
+    fn new(bounds: &[f64]) -> Self {
+        Self(Arc::new(HistogramInner {
+            bounds: bounds.to_vec(),
+            counts: bounds.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+            count: AtomicU64::new(0),
+        }))
+    }
+
+    /// Record an observation
+    pub fn observe(&self, value: f64) {
+        let inner = &self.0;
+        if let Some(index) = inner.bounds.iter().position(|bound| value <= *bound) {
+            inner.counts[index].fetch_add(1, Ordering::Relaxed);
+        }
+        let _ = inner.sum.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + value).to_bits())
+        });
+        inner.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Start a timer that records elapsed seconds when dropped
+    pub fn start_timer(&self) -> HistogramTimer {
+        HistogramTimer {
+            histogram: self.clone(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Number of observations
+    pub fn count(&self) -> u64 {
+        self.0.count.load(Ordering::Relaxed)
+    }
+
+    /// Sum of all observations
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.0.sum.load(Ordering::Relaxed))
+    }
+}
+
+/// Records the time since it was created into a histogram when dropped
+#[derive(Debug)]
+pub struct HistogramTimer {
+    histogram: Histogram,
+    start: Instant,
+}
+
+impl Drop for HistogramTimer {
+    fn drop(&mut self) {
+        self.histogram.observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(Histogram),
+}
+
+impl Metric {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Counter(_) => "counter",
+            Self::Gauge(_) => "gauge",
+            Self::Histogram(_) => "histogram",
+        }
+    }
+}
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug)]
+struct Family {
+    help: String,
+    type_name: &'static str,
+    series: BTreeMap<Labels, Metric>,
+}
+
+/// A collection of named metrics that renders as Prometheus text
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    families: Arc<Mutex<BTreeMap<String, Family>>>,
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get or register a counter
+    pub fn counter(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Counter {
+        match self.register(name, help, labels, || Metric::Counter(Counter::default())) {
+            Some(Metric::Counter(counter)) => counter,
+            _ => Counter::default(),
+        }
+    }
+
+    /// Get or register a gauge
+    pub fn gauge(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Gauge {
+        match self.register(name, help, labels, || Metric::Gauge(Gauge::default())) {
+            Some(Metric::Gauge(gauge)) => gauge,
+            _ => Gauge::default(),
+        }
+    }
+
+    /// Get or register a histogram with the given bucket upper bounds
+    pub fn histogram(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        buckets: &[f64],
+    ) -> Histogram {
+        match self.register(name, help, labels, || Metric::Histogram(Histogram::new(buckets))) {
+            Some(Metric::Histogram(histogram)) => histogram,
+            _ => Histogram::new(buckets),
+        }
+    }
+
+    /// Look up a series, creating it if needed
+    ///
+    /// Returns `None` if the name is already used by a different metric type;
+    /// callers then hand out a detached metric that is never rendered.
+    fn register(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        create: impl FnOnce() -> Metric,
+    ) -> Option<Metric> {
+        let metric = create();
+        let mut families = self.families.lock().unwrap();
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            type_name: metric.type_name(),
+            series: BTreeMap::new(),
+        });
+
+        if family.type_name != metric.type_name() {
+            warn!(
+                "Metric {} is already registered as a {}, not a {}",
+                name,
+                family.type_name,
+                metric.type_name()
+            );
+            return None;
+        }
+
+        let labels: Labels = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Some(family.series.entry(labels).or_insert(metric).clone())
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut out = String::new();
+
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {} {}", name, escape_help(&family.help));
+            let _ = writeln!(out, "# TYPE {} {}", name, family.type_name);
+
+            for (labels, metric) in &family.series {
+                match metric {
+                    Metric::Counter(counter) => {
+                        let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), counter.get());
+                    }
+                    Metric::Gauge(gauge) => {
+                        let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), format_value(gauge.get()));
+                    }
+                    Metric::Histogram(histogram) => {
+                        let inner = &histogram.0;
+                        let mut cumulative = 0;
+                        for (bound, count) in inner.bounds.iter().zip(&inner.counts) {
+                            cumulative += count.load(Ordering::Relaxed);
+                            let le = format_value(*bound);
+                            let _ = writeln!(out, "{}_bucket{} {}", name, format_labels(labels, Some(&le)), cumulative);
+                        }
+                        let total = histogram.count();
+                        let _ = writeln!(out, "{}_bucket{} {}", name, format_labels(labels, Some("+Inf")), total);
+                        let _ = writeln!(out, "{}_sum{} {}", name, format_labels(labels, None), format_value(histogram.sum()));
+                        let _ = writeln!(out, "{}_count{} {}", name, format_labels(labels, None), total);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// The process-wide registry used by instrumented components
+pub fn global() -> &'static MetricsRegistry {
+    static GLOBAL: OnceLock<MetricsRegistry> = OnceLock::new();
+    GLOBAL.get_or_init(MetricsRegistry::new)
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{le}\""));
+    }
+
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn escape_help(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_and_gauge_rendering() {
+        let registry = MetricsRegistry::new();
+        let puts = registry.counter("ops_total", "Operations", &[("op", "put")]);
+        registry.counter("ops_total", "Operations", &[("op", "get")]).inc_by(3);
+        puts.inc();
+        puts.inc();
+
+        // Registering again returns the same series
+        registry.counter("ops_total", "Operations", &[("op", "put")]).inc();
+
+        let size = registry.gauge("cache_bytes", "Cache size", &[]);
+        size.set(10.0);
+        size.add(-2.5);
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE ops_total counter"));
+        assert!(text.contains("ops_total{op=\"put\"} 3"));
+        assert!(text.contains("ops_total{op=\"get\"} 3"));
+        assert!(text.contains("# TYPE cache_bytes gauge"));
+        assert!(text.contains("cache_bytes 7.5"));
+    }
+
+    #[test]
+    fn test_histogram_rendering() {
+        let registry = MetricsRegistry::new();
+        let latency = registry.histogram("latency_seconds", "Latency", &[("op", "get")], &[0.1, 1.0]);
+        latency.observe(0.05);
+        latency.observe(0.5);
+        latency.observe(5.0);
+
+        let text = registry.render();
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"0.1\"} 1"));
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"1\"} 2"));
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"+Inf\"} 3"));
+        assert!(text.contains("latency_seconds_sum{op=\"get\"} 5.55"));
+        assert!(text.contains("latency_seconds_count{op=\"get\"} 3"));
+
+        {
+            let _timer = latency.start_timer();
+        }
+        assert_eq!(latency.count(), 4);
+    }
+
+    #[test]
+    fn test_type_conflicts_and_escaping() {
+        let registry = MetricsRegistry::new();
+        registry.counter("conflict", "A counter", &[]).inc();
+        registry.gauge("conflict", "A gauge", &[]).set(1.0);
+        registry.counter("labels", "Escaping", &[("path", "a\"b\\c")]).inc();
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE conflict counter"));
+        assert!(!text.contains("gauge"));
+        assert!(text.contains(r#"labels{path="a\"b\\c"} 1"#));
+    }
+}
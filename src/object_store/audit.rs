@@ -0,0 +1,520 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Tamper-evident audit log of storage operations
+//!
+//! Every audited operation appends an [`AuditEvent`] to a [`ContentChain`],
+//! so each entry's CID commits to the whole history before it: editing,
+//! dropping, or reordering an entry breaks the chain, which
+//! [`AuditLog::verify`] reports.
+//!
+//! Entries can be kept in a [`StorageBackend`], one DAG-CBOR block per entry
+//! keyed by the entry CID, with the latest entry published in
+//! [`NamedRefs`] under [`AUDIT_HEAD_REF`]. The head only moves by
+//! compare-and-swap, so several processes can share one log, and with signed
+//! refs dropping entries from the end is detected as well: the published head
+//! would be missing. [`AuditLog::open`] reloads and validates the entries.
+//! The `audit_verify` example opens the log kept in NATS and verifies it.
+//!
+//! Entries are also held in memory, timestamped with their event time, so
+//! time-range queries use [`ContentChain::between`] instead of a scan.
+//! [`NatsObjectStore::with_audit`](super::NatsObjectStore::with_audit) and
+//! `ContentService::with_audit` record their operations automatically.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use super::{ListOptions, NamedRefs, ObjectStoreError, StorageBackend};
+use crate::chain::{ChainedContent, ContentChain};
+use crate::{ContentType, Error, Result, TypedContent};
+use crate::util::cid_serde;
+
+/// Bucket holding audit entries
+pub const AUDIT_BUCKET: &str = "cim-audit";
+
+/// Ref naming the latest entry of a stored audit log
+pub const AUDIT_HEAD_REF: &str = "audit/head";
+
+/// Codec of [`AuditEvent`]
+pub const AUDIT_EVENT_CODEC: u64 = 0x300108;
+
+/// Times [`AuditLog::record`] retries when other writers move the head
+const MAX_RECORD_ATTEMPTS: usize = 16;
+
+/// Latest event time a query can name, the end of the year 9999
+const MAX_EVENT_MILLIS: u64 = 253_402_300_799_999;
+
+/// An event time in milliseconds since the Unix epoch
+fn event_time(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis.min(MAX_EVENT_MILLIS))
+}
+
+/// Chain an event after `previous`, timestamped with the event time
+fn chain_event(
+    event: AuditEvent,
+    previous: Option<&ChainedContent<AuditEvent>>,
+) -> Result<ChainedContent<AuditEvent>> {
+    let mut entry = ChainedContent::new(event, previous)?;
+    entry.timestamp = event_time(entry.content.at);
+    Ok(entry)
+}
+
+/// Kind of audited operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Content was written
+    Store,
+    /// Content was fetched
+    Read,
+    /// Content was removed from storage
+    Delete,
+    /// Content was marked deleted but kept for restoring
+    SoftDelete,
+    /// A soft delete was undone
+    Restore,
+    /// Content was removed when its TTL ran out
+    Expire,
+    /// Content was converted to another format
+    Transform,
+    /// Encrypted content was re-encrypted under a new key
+    ReEncrypt,
+}
+
+/// One audited operation on a CID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// What was done
+    pub action: AuditAction,
+    /// Content it was done to
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Bucket holding the content, if known
+    pub bucket: Option<String>,
+    /// Who did it
+    pub actor: String,
+    /// When, in milliseconds since the Unix epoch
+    pub at: u64,
+    /// Free-form detail such as a transform target
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    /// An event happening now
+    pub fn new(action: AuditAction, cid: Cid, actor: impl Into<String>) -> Self {
+        Self {
+            action,
+            cid,
+            bucket: None,
+            actor: actor.into(),
+            at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            detail: None,
+        }
+    }
+
+    /// Record the bucket holding the content
+    pub fn with_bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.bucket = Some(bucket.into());
+        self
+    }
+
+    /// Attach free-form detail
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl TypedContent for AuditEvent {
+    const CODEC: u64 = AUDIT_EVENT_CODEC;
+    const CONTENT_TYPE: ContentType = ContentType::Custom(AUDIT_EVENT_CODEC);
+}
+
+/// Filter for [`AuditLog::query`]; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Only events on this CID
+    pub cid: Option<Cid>,
+    /// Only events by this actor
+    pub actor: Option<String>,
+    /// Only events of this kind
+    pub action: Option<AuditAction>,
+    /// Only events at or after this time, in milliseconds since the Unix epoch
+    pub from: Option<u64>,
+    /// Only events before this time, in milliseconds since the Unix epoch
+    pub until: Option<u64>,
+}
+
+impl AuditQuery {
+    /// Match every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only events on `cid`
+    pub fn with_cid(mut self, cid: Cid) -> Self {
+        self.cid = Some(cid);
+        self
+    }
+
+    /// Only events by `actor`
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Only events of kind `action`
+    pub fn with_action(mut self, action: AuditAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Only events in `[from, until)`, in milliseconds since the Unix epoch
+    pub fn with_time_range(mut self, from: u64, until: u64) -> Self {
+        self.from = Some(from);
+        self.until = Some(until);
+        self
+    }
+
+    /// Whether `event` passes the filter
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.cid.is_none_or(|cid| event.cid == cid)
+            && self.actor.as_ref().is_none_or(|actor| &event.actor == actor)
+            && self.action.is_none_or(|action| event.action == action)
+            && self.from.is_none_or(|from| event.at >= from)
+            && self.until.is_none_or(|until| event.at < until)
+    }
+}
+
+/// Result of a successful [`AuditLog::verify`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditVerification {
+    /// Number of entries checked
+    pub entries: usize,
+    /// CID of the latest entry
+    pub head: Option<Cid>,
+}
+
+/// Where a stored log keeps its entries and its head
+struct AuditStore {
+    backend: Arc<dyn StorageBackend>,
+    refs: Arc<NamedRefs>,
+}
+
+/// Append-only, hash-linked log of audit events
+pub struct AuditLog {
+    chain: Mutex<ContentChain<AuditEvent>>,
+    store: Option<AuditStore>,
+    record_reads: bool,
+}
+
+impl AuditLog {
+    /// A log kept in memory only
+    pub fn new() -> Self {
+        Self {
+            chain: Mutex::new(ContentChain::new()),
+            store: None,
+            record_reads: false,
+        }
+    }
+
+    /// A log with entries in `backend` and its head in `refs`, continuing
+    /// the entries already stored there
+    ///
+    /// Fails if the stored entries do not form a valid chain ending at the
+    /// published head, or if entries are stored but no head is published.
+    /// Give `refs` a signer and trust its key, so that the head itself
+    /// cannot be forged or rolled back.
+    pub async fn open(backend: Arc<dyn StorageBackend>, refs: Arc<NamedRefs>) -> Result<Self> {
+        let store = AuditStore { backend, refs };
+        let mut chain = ContentChain::new();
+        store.catch_up(&mut chain).await?;
+        if chain.is_empty() {
+            let stored = store.backend
+                .list_blocks_page(AUDIT_BUCKET, &ListOptions::default().with_limit(1))
+                .await?;
+            if !stored.objects.is_empty() {
+                return Err(Error::ChainValidationError {
+                    expected: format!("a head published as {AUDIT_HEAD_REF}"),
+                    actual: "stored entries without one".to_string(),
+                });
+            }
+        }
+        debug!("Opened audit log with {} entries", chain.len());
+        Ok(Self {
+            chain: Mutex::new(chain),
+            store: Some(store),
+            record_reads: false,
+        })
+    }
+
+    /// Also record reads, not only mutations
+    pub fn with_reads(mut self, record_reads: bool) -> Self {
+        self.record_reads = record_reads;
+        self
+    }
+
+    /// Whether reads are recorded
+    pub fn records_reads(&self) -> bool {
+        self.record_reads
+    }
+
+    /// Append an event and return its entry
+    ///
+    /// With a backend, the entry is stored before it becomes the head, so a
+    /// failed write leaves the log unchanged. If another writer moved the
+    /// head, its entries are loaded and the event is appended after them.
+    pub async fn record(&self, event: AuditEvent) -> Result<ChainedContent<AuditEvent>> {
+        let mut chain = self.chain.lock().await;
+        let Some(store) = &self.store else {
+            let entry = chain_event(event, chain.head())?;
+            return Ok(chain.push(entry)?.clone());
+        };
+
+        for _ in 0..MAX_RECORD_ATTEMPTS {
+            let entry = chain_event(event.clone(), chain.head())?;
+            let expected = chain.head().map(|head| head.cid);
+            store.backend.put_block(AUDIT_BUCKET, &entry.cid, entry.to_block()?).await?;
+            match store.refs.compare_and_swap(AUDIT_HEAD_REF, expected, entry.cid).await {
+                Ok(_) => return Ok(chain.push(entry)?.clone()),
+                Err(ObjectStoreError::Conflict(reason)) => {
+                    debug!("Audit head moved, catching up: {}", reason);
+                    store.catch_up(&mut chain).await?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(ObjectStoreError::Conflict(format!(
+            "{AUDIT_HEAD_REF} kept changing after {MAX_RECORD_ATTEMPTS} attempts"
+        ))
+        .into())
+    }
+
+    /// Record a read if reads are being recorded
+    pub async fn record_read(&self, event: AuditEvent) -> Result<()> {
+        if self.record_reads {
+            self.record(event).await?;
+        }
+        Ok(())
+    }
+
+    /// Load entries other writers appended to a stored log
+    pub async fn refresh(&self) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.catch_up(&mut *self.chain.lock().await).await?;
+        }
+        Ok(())
+    }
+
+    /// Entries matching `query`, oldest first by event time
+    ///
+    /// A time range only visits the entries inside it. Entries of other
+    /// writers are included as of the last [`AuditLog::record`] or
+    /// [`AuditLog::refresh`].
+    pub async fn query(&self, query: &AuditQuery) -> Vec<ChainedContent<AuditEvent>> {
+        let chain = self.chain.lock().await;
+        let candidates: Vec<_> = if query.from.is_some() || query.until.is_some() {
+            let from = event_time(query.from.unwrap_or(0));
+            let until = event_time(query.until.unwrap_or(MAX_EVENT_MILLIS));
+            chain.between(from, until)
+        } else {
+            chain.items().iter().collect()
+        };
+        candidates
+            .into_iter()
+            .filter(|entry| query.matches(&entry.content))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of entries
+    pub async fn len(&self) -> usize {
+        self.chain.lock().await.len()
+    }
+
+    /// Whether nothing has been recorded
+    pub async fn is_empty(&self) -> bool {
+        self.chain.lock().await.is_empty()
+    }
+
+    /// Check that the log has not been tampered with
+    ///
+    /// Validates every link and CID of the chain. With a backend, entries of
+    /// other writers are loaded first, then the stored entries are reloaded
+    /// from the published head and must match the log entry for entry.
+    pub async fn verify(&self) -> Result<AuditVerification> {
+        let mut chain = self.chain.lock().await;
+        if let Some(store) = &self.store {
+            store.catch_up(&mut chain).await?;
+        }
+        chain.validate()?;
+
+        if let Some(store) = &self.store {
+            let stored = store.load().await?;
+            if stored.len() != chain.len() {
+                return Err(Error::ChainValidationError {
+                    expected: format!("{} entries", chain.len()),
+                    actual: format!("{} stored entries", stored.len()),
+                });
+            }
+            for (expected, actual) in chain.items().iter().zip(stored.items()) {
+                if expected.cid != actual.cid {
+                    return Err(Error::ChainValidationError {
+                        expected: expected.cid.to_string(),
+                        actual: actual.cid.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(AuditVerification {
+            entries: chain.len(),
+            head: chain.head().map(|entry| entry.cid),
+        })
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditStore {
+    /// CID of the published head, if any
+    async fn head(&self) -> Result<Option<Cid>> {
+        Ok(self.refs.get(AUDIT_HEAD_REF).await?.map(|head| head.cid()))
+    }
+
+    /// Read one stored entry, checking that it hashes to `cid`
+    async fn entry(&self, cid: &Cid) -> Result<ChainedContent<AuditEvent>> {
+        let data = self.backend.get_block(AUDIT_BUCKET, cid).await?;
+        let entry = ChainedContent::<AuditEvent>::from_block(&data)?;
+        if entry.cid != *cid {
+            return Err(Error::InvalidCid(format!(
+                "audit entry stored under {cid} hashes to {}",
+                entry.cid
+            )));
+        }
+        Ok(entry)
+    }
+
+    /// Entries from the published head back to, but not including, `known`
+    ///
+    /// Fails if `known` is not part of the published history.
+    async fn entries_after(&self, known: Option<Cid>) -> Result<Vec<ChainedContent<AuditEvent>>> {
+        let mut entries = Vec::new();
+        let mut next = self.head().await?;
+        while next != known {
+            let Some(cid) = next else {
+                return Err(Error::ChainValidationError {
+                    expected: format!("{known:?} in the history of {AUDIT_HEAD_REF}"),
+                    actual: "a history without it".to_string(),
+                });
+            };
+            let mut entry = self.entry(&cid).await?;
+            entry.timestamp = event_time(entry.content.at);
+            next = entry.previous_cid;
+            entries.push(entry);
+        }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Append the entries published after the head of `chain`
+    async fn catch_up(&self, chain: &mut ContentChain<AuditEvent>) -> Result<()> {
+        for entry in self.entries_after(chain.head().map(|head| head.cid)).await? {
+            chain.push(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Read the whole stored chain back from the published head
+    async fn load(&self) -> Result<ContentChain<AuditEvent>> {
+        ContentChain::from_items(self.entries_after(None).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_cid;
+    use crate::object_store::MemoryBackend;
+
+    #[tokio::test]
+    async fn test_record_and_query() {
+        let log = AuditLog::new().with_reads(false);
+        let (report, photo) = (test_cid(b"report"), test_cid(b"photo"));
+
+        log.record(AuditEvent::new(AuditAction::Store, report, "alice").with_bucket("cim-documents")).await.unwrap();
+        log.record(AuditEvent::new(AuditAction::Store, photo, "bob")).await.unwrap();
+        log.record(AuditEvent::new(AuditAction::Delete, report, "bob")).await.unwrap();
+        log.record_read(AuditEvent::new(AuditAction::Read, report, "carol")).await.unwrap();
+
+        assert_eq!(log.len().await, 3);
+        assert_eq!(log.query(&AuditQuery::new().with_cid(report)).await.len(), 2);
+        let by_bob = log.query(&AuditQuery::new().with_actor("bob").with_action(AuditAction::Delete)).await;
+        assert_eq!(by_bob.len(), 1);
+        assert_eq!(by_bob[0].content.cid, report);
+        assert!(log.query(&AuditQuery::new().with_time_range(0, 1)).await.is_empty());
+        let now = AuditEvent::new(AuditAction::Read, report, "carol").at;
+        assert_eq!(log.query(&AuditQuery::new().with_time_range(now - 60_000, now + 1)).await.len(), 3);
+
+        let verification = log.verify().await.unwrap();
+        assert_eq!(verification.entries, 3);
+    }
+
+    #[tokio::test]
+    async fn test_persisted_log_detects_tampering() {
+        let backend = Arc::new(MemoryBackend::new());
+        let refs = Arc::new(NamedRefs::in_memory());
+        let log = AuditLog::open(backend.clone(), refs.clone()).await.unwrap();
+        let report = test_cid(b"report");
+        let first = log.record(AuditEvent::new(AuditAction::Store, report, "alice")).await.unwrap();
+        log.record(AuditEvent::new(AuditAction::SoftDelete, report, "alice")).await.unwrap();
+
+        let reopened = AuditLog::open(backend.clone(), refs.clone()).await.unwrap();
+        assert_eq!(reopened.verify().await.unwrap(), log.verify().await.unwrap());
+        let since = AuditQuery { from: Some(first.content.at), ..AuditQuery::new() };
+        assert_eq!(reopened.query(&since).await.len(), 2);
+
+        // Rewrite history: the first entry now names someone else
+        let mut forged = first.clone();
+        forged.content.actor = "mallory".to_string();
+        backend.put_block(AUDIT_BUCKET, &first.cid, forged.to_block().unwrap()).await.unwrap();
+
+        assert!(log.verify().await.is_err());
+        assert!(AuditLog::open(backend.clone(), refs).await.is_err());
+
+        // Entries without a published head are refused too
+        assert!(AuditLog::open(backend, Arc::new(NamedRefs::in_memory())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writers_share_the_head_and_truncation_is_detected() {
+        let backend = Arc::new(MemoryBackend::new());
+        let key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let refs = Arc::new(NamedRefs::in_memory().with_signer(key.clone()).with_trusted_key(key.verifying_key()));
+        let (alice, bob) = (
+            AuditLog::open(backend.clone(), refs.clone()).await.unwrap(),
+            AuditLog::open(backend.clone(), refs.clone()).await.unwrap(),
+        );
+        let report = test_cid(b"report");
+
+        alice.record(AuditEvent::new(AuditAction::Store, report, "alice")).await.unwrap();
+        let last = bob.record(AuditEvent::new(AuditAction::ReEncrypt, report, "bob")).await.unwrap();
+        assert_eq!(last.sequence, 1);
+        alice.refresh().await.unwrap();
+        assert_eq!(alice.query(&AuditQuery::new()).await.len(), 2);
+        assert_eq!(alice.verify().await.unwrap(), bob.verify().await.unwrap());
+
+        // Dropping the latest entry leaves the published head dangling
+        backend.delete_block(AUDIT_BUCKET, &last.cid).await.unwrap();
+        assert!(alice.verify().await.is_err());
+        assert!(AuditLog::open(backend, refs).await.is_err());
+    }
+}
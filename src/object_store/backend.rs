@@ -0,0 +1,488 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Pluggable storage backends for content-addressed blocks
+//!
+//! A [`StorageBackend`] stores already-serialized blocks keyed by bucket name
+//! and CID. Backends know nothing about [`TypedContent`](crate::TypedContent);
+//! higher-level services such as [`TieredStore`](super::TieredStore) compose
+//! several backends and handle CID verification on top of them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use cid::Cid;
+use tokio::sync::RwLock;
+
+use super::listing::{paginate, ListOptions, ListPage, PageCollector};
+use super::watch::{ContentEventKind, ContentEventStream, EventLog, WatchFrom};
+use super::{ObjectInfo, ObjectStoreError, Result};
+
+/// Byte-level storage for content-addressed blocks
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Short backend name used in logs and reports
+    fn name(&self) -> &str;
+
+    /// Store a block under the given bucket
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()>;
+
+    /// Retrieve a block from the given bucket
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>>;
+
+    /// Check whether a block exists in the given bucket
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool>;
+
+    /// Delete a block from the given bucket
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()>;
+
+    /// List all blocks in the given bucket
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>>;
+
+    /// List one page of blocks matching the given filters, in CID order
+    ///
+    /// The default implementation filters the full listing; backends should
+    /// override it to avoid materializing the whole bucket.
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        Ok(paginate(self.list_blocks(bucket).await?, options))
+    }
+
+    /// Names of the buckets holding blocks
+    ///
+    /// Backends that cannot enumerate their buckets return an error.
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        Err(ObjectStoreError::Storage(format!(
+            "The {} backend does not support listing buckets",
+            self.name()
+        )))
+    }
+
+    /// Watch the given bucket for blocks being added or deleted
+    ///
+    /// Backends without change notifications return an error.
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        let _ = from;
+        Err(ObjectStoreError::Storage(format!(
+            "The {} backend does not support watching bucket {}",
+            self.name(),
+            bucket
+        )))
+    }
+}
+
+/// A block held by the in-memory backend
+#[derive(Debug, Clone)]
+struct MemoryBlock {
+    data: Vec<u8>,
+    created_at: SystemTime,
+}
+
+/// In-memory storage backend
+///
+/// Useful as a hot tier and as a stand-in for NATS in tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    buckets: Arc<RwLock<HashMap<String, BTreeMap<String, MemoryBlock>>>>,
+    events: EventLog,
+}
+
+impl MemoryBackend {
+    /// Create a new empty in-memory backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total number of bytes held across all buckets
+    pub async fn total_size(&self) -> usize {
+        let buckets = self.buckets.read().await;
+        buckets
+            .values()
+            .flat_map(|blocks| blocks.values())
+            .map(|block| block.data.len())
+            .sum()
+    }
+}
+
+#[async_trait]
+impl StorageBackend for MemoryBackend {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        let mut buckets = self.buckets.write().await;
+        let blocks = buckets.entry(bucket.to_string()).or_default();
+        if let Some(block) = blocks.get_mut(&cid.to_string()) {
+            block.data = data;
+            return Ok(());
+        }
+
+        let size = data.len();
+        blocks.insert(cid.to_string(), MemoryBlock {
+            data,
+            created_at: SystemTime::now(),
+        });
+        self.events.record(ContentEventKind::Added, bucket, cid, size);
+        Ok(())
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        let buckets = self.buckets.read().await;
+        buckets
+            .get(bucket)
+            .and_then(|blocks| blocks.get(&cid.to_string()))
+            .map(|block| block.data.clone())
+            .ok_or_else(|| ObjectStoreError::NotFound(cid.to_string()))
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        let buckets = self.buckets.read().await;
+        Ok(buckets
+            .get(bucket)
+            .is_some_and(|blocks| blocks.contains_key(&cid.to_string())))
+    }
+
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        let mut buckets = self.buckets.write().await;
+        buckets
+            .get_mut(bucket)
+            .and_then(|blocks| blocks.remove(&cid.to_string()))
+            .ok_or_else(|| ObjectStoreError::NotFound(cid.to_string()))?;
+        self.events.record(ContentEventKind::Deleted, bucket, cid, 0);
+        Ok(())
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        let buckets = self.buckets.read().await;
+        let Some(blocks) = buckets.get(bucket) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(blocks
+            .iter()
+            .filter_map(|(key, block)| {
+                Cid::try_from(key.as_str()).ok().map(|cid| ObjectInfo {
+                    cid,
+                    size: block.data.len(),
+                    created_at: block.created_at,
+                    compressed: false,
+                })
+            })
+            .collect())
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        let buckets = self.buckets.read().await;
+        let mut collector = PageCollector::new(options);
+        let Some(blocks) = buckets.get(bucket) else {
+            return Ok(collector.finish());
+        };
+
+        // Blocks are keyed by CID string, so the page can stop early
+        for (key, block) in blocks.range::<str, _>(options.key_range()) {
+            let Ok(cid) = Cid::try_from(key.as_str()) else {
+                continue;
+            };
+            collector.offer(ObjectInfo {
+                cid,
+                size: block.data.len(),
+                created_at: block.created_at,
+                compressed: false,
+            });
+            if collector.is_full() {
+                break;
+            }
+        }
+
+        Ok(collector.finish())
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        let buckets = self.buckets.read().await;
+        let mut names: Vec<String> = buckets.iter()
+            .filter(|(_, blocks)| !blocks.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        self.events.watch(bucket, from)
+    }
+}
+
+/// Local filesystem storage backend
+///
+/// Blocks are stored as `<root>/<bucket>/<cid>`. Writes go through a
+/// temporary file and a rename so a crash never leaves a partial block.
+#[derive(Debug, Clone)]
+pub struct LocalDiskBackend {
+    root: PathBuf,
+}
+
+impl LocalDiskBackend {
+    /// Create a backend rooted at the given directory, creating it if needed
+    pub async fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        tokio::fs::create_dir_all(&root)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        Ok(Self { root })
+    }
+
+    /// Root directory of this backend
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn block_path(&self, bucket: &str, cid: &Cid) -> PathBuf {
+        self.root.join(bucket).join(cid.to_string())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalDiskBackend {
+    fn name(&self) -> &str {
+        "local-disk"
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        let dir = self.root.join(bucket);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+
+        let path = self.block_path(bucket, cid);
+        let tmp_path = dir.join(format!(".{cid}.tmp"));
+        tokio::fs::write(&tmp_path, &data)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        match tokio::fs::read(self.block_path(bucket, cid)).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(ObjectStoreError::NotFound(cid.to_string()))
+            }
+            Err(e) => Err(ObjectStoreError::Storage(e.to_string())),
+        }
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        tokio::fs::try_exists(self.block_path(bucket, cid))
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))
+    }
+
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        match tokio::fs::remove_file(self.block_path(bucket, cid)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(ObjectStoreError::NotFound(cid.to_string()))
+            }
+            Err(e) => Err(ObjectStoreError::Storage(e.to_string())),
+        }
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        Ok(self.list_blocks_page(bucket, &ListOptions::default()).await?.objects)
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        let mut collector = PageCollector::new(options);
+        let mut entries = match tokio::fs::read_dir(self.root.join(bucket)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(collector.finish()),
+            Err(e) => return Err(ObjectStoreError::Storage(e.to_string())),
+        };
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?
+        {
+            let name = entry.file_name();
+            let Some(cid) = name.to_str().and_then(|n| Cid::try_from(n).ok()) else {
+                // Skips temporary files and anything else that is not a block
+                continue;
+            };
+            if !collector.wants(&cid) {
+                continue;
+            }
+            let metadata = entry
+                .metadata()
+                .await
+                .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+
+            collector.offer(ObjectInfo {
+                cid,
+                size: metadata.len() as usize,
+                created_at: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                compressed: false,
+            });
+        }
+
+        Ok(collector.finish())
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        let mut entries = tokio::fs::read_dir(&self.root)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        let mut names = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?
+        {
+            let is_dir = entry
+                .file_type()
+                .await
+                .map_err(|e| ObjectStoreError::Storage(e.to_string()))?
+                .is_dir();
+            if let Some(name) = entry.file_name().to_str().filter(|_| is_dir) {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_store::ListFilter;
+
+    fn test_cid(data: &[u8]) -> Cid {
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+        Cid::new_v1(0x55, mh)
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_roundtrip() {
+        let backend = MemoryBackend::new();
+        let cid = test_cid(b"hello");
+
+        backend.put_block("cim-test", &cid, b"hello".to_vec()).await.unwrap();
+
+        assert!(backend.has_block("cim-test", &cid).await.unwrap());
+        assert!(!backend.has_block("cim-other", &cid).await.unwrap());
+        assert_eq!(backend.get_block("cim-test", &cid).await.unwrap(), b"hello");
+        assert_eq!(backend.list_blocks("cim-test").await.unwrap().len(), 1);
+        assert_eq!(backend.total_size().await, 5);
+
+        backend.delete_block("cim-test", &cid).await.unwrap();
+        assert!(matches!(
+            backend.get_block("cim-test", &cid).await,
+            Err(ObjectStoreError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_local_disk_backend_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = LocalDiskBackend::new(dir.path()).await.unwrap();
+        let cid = test_cid(b"on disk");
+
+        backend.put_block("cim-test", &cid, b"on disk".to_vec()).await.unwrap();
+
+        assert!(backend.has_block("cim-test", &cid).await.unwrap());
+        assert_eq!(backend.get_block("cim-test", &cid).await.unwrap(), b"on disk");
+
+        let listed = backend.list_blocks("cim-test").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].cid, cid);
+        assert_eq!(listed[0].size, 7);
+        assert_eq!(backend.list_buckets().await.unwrap(), vec!["cim-test".to_string()]);
+
+        backend.delete_block("cim-test", &cid).await.unwrap();
+        assert!(!backend.has_block("cim-test", &cid).await.unwrap());
+        assert!(backend.list_blocks("cim-missing").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_blocks_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let backends: Vec<Box<dyn StorageBackend>> = vec![
+            Box::new(MemoryBackend::new()),
+            Box::new(LocalDiskBackend::new(dir.path()).await.unwrap()),
+        ];
+
+        for backend in backends {
+            for i in 0..5u8 {
+                let data = vec![i; usize::from(i) + 1];
+                backend.put_block("cim-test", &test_cid(&data), data).await.unwrap();
+            }
+
+            let options = ListOptions {
+                filter: ListFilter {
+                    min_size: Some(2),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .with_limit(3);
+
+            let first = backend.list_blocks_page("cim-test", &options).await.unwrap();
+            assert_eq!(first.objects.len(), 3, "{}", backend.name());
+            let cursor = first.next_cursor.clone().unwrap();
+
+            let second = backend
+                .list_blocks_page("cim-test", &options.clone().after(cursor))
+                .await
+                .unwrap();
+            assert_eq!(second.objects.len(), 1, "{}", backend.name());
+            assert!(second.next_cursor.is_none());
+            assert!(first.objects.iter().chain(&second.objects).all(|o| o.size >= 2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_watch() {
+        use futures::StreamExt;
+
+        let backend = MemoryBackend::new();
+        let first = test_cid(b"first");
+        let second = test_cid(b"second");
+
+        backend.put_block("cim-test", &first, b"first".to_vec()).await.unwrap();
+        let mut live = backend.watch_blocks("cim-test", WatchFrom::New).await.unwrap();
+
+        backend.put_block("cim-test", &second, b"second".to_vec()).await.unwrap();
+        backend.put_block("cim-test", &second, b"second".to_vec()).await.unwrap();
+        backend.delete_block("cim-test", &first).await.unwrap();
+
+        let added = live.next().await.unwrap().unwrap();
+        assert_eq!(added.kind, ContentEventKind::Added);
+        assert_eq!(added.cid, second);
+        assert_eq!(added.size, 6);
+        assert_eq!(added.codec, 0x55);
+
+        // Re-storing an existing block is not a change
+        let deleted = live.next().await.unwrap().unwrap();
+        assert_eq!(deleted.kind, ContentEventKind::Deleted);
+        assert_eq!(deleted.cid, first);
+
+        // A restarted consumer resumes after the last sequence it processed
+        let mut resumed = backend
+            .watch_blocks("cim-test", WatchFrom::Sequence(added.sequence + 1))
+            .await
+            .unwrap();
+        assert_eq!(resumed.next().await.unwrap().unwrap(), deleted);
+    }
+
+    #[tokio::test]
+    async fn test_local_disk_backend_watch_unsupported() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = LocalDiskBackend::new(dir.path()).await.unwrap();
+        assert!(backend.watch_blocks("cim-test", WatchFrom::New).await.is_err());
+    }
+}
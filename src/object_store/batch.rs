@@ -0,0 +1,364 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! All-or-nothing batches of objects
+//!
+//! A [`Batch`] stages serialized objects and commits them together. Objects
+//! are content addressed, so staging writes each one to its bucket where it
+//! stays unreferenced; only once every write has succeeded is a
+//! [`BatchManifest`] listing all of them written. The manifest CID is the
+//! commit point: readers that resolve a batch through its manifest see every
+//! object or none.
+//!
+//! A failed batch writes no manifest and deletes the objects it wrote
+//! itself. Objects that already existed when they were staged are left
+//! alone, since other content may rely on them.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cid::Cid;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::{ContentBucket, ObjectStoreError, Result, StorageBackend};
+use crate::{ContentType, TypedContent};
+use crate::util::cid_serde;
+
+/// Bucket holding batch manifests
+pub const MANIFEST_BUCKET: &str = "cim-batches";
+
+/// Codec of [`BatchManifest`]
+pub const BATCH_MANIFEST_CODEC: u64 = 0x300107;
+
+/// Number of objects staged concurrently
+const STAGE_CONCURRENCY: usize = 16;
+
+/// One object referenced by a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Bucket the object was written to
+    pub bucket: String,
+    /// Object CID
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Serialized size in bytes
+    pub size: usize,
+}
+
+/// Commit marker listing every object of a batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchManifest {
+    /// Objects in the order they were added
+    pub entries: Vec<ManifestEntry>,
+    /// Commit time in seconds since the Unix epoch
+    pub committed_at: u64,
+}
+
+impl TypedContent for BatchManifest {
+    const CODEC: u64 = BATCH_MANIFEST_CODEC;
+    const CONTENT_TYPE: ContentType = ContentType::Custom(BATCH_MANIFEST_CODEC);
+}
+
+/// Result of a committed batch
+#[derive(Debug, Clone)]
+pub struct BatchCommit {
+    /// CID of the manifest; the batch exists once this is stored
+    pub manifest_cid: Cid,
+    /// The stored manifest
+    pub manifest: BatchManifest,
+    /// Objects written by this batch, excluding ones that already existed
+    pub written: usize,
+    /// Objects that already existed and were not written again
+    pub deduplicated: Vec<Cid>,
+}
+
+/// A serialized object waiting to be committed
+#[derive(Debug, Clone)]
+pub(crate) struct StagedObject {
+    pub(crate) bucket: String,
+    pub(crate) cid: Cid,
+    pub(crate) content_type: ContentType,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Objects to be stored together or not at all
+#[derive(Debug, Clone, Default)]
+pub struct Batch {
+    objects: Vec<StagedObject>,
+}
+
+impl Batch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add content to the batch, returning its CID
+    ///
+    /// Content can be of any type, e.g. a graph together with its nodes and
+    /// edges. Adding the same content twice stores it once.
+    pub fn add<T: TypedContent>(&mut self, content: &T) -> Result<Cid> {
+        let cid = content.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec()).as_str();
+
+        if !self.objects.iter().any(|o| o.cid == cid && o.bucket == bucket) {
+            let data = content.to_bytes()
+                .map_err(ObjectStoreError::serialization)?;
+            self.objects.push(StagedObject {
+                bucket: bucket.to_string(),
+                cid,
+                content_type: T::CONTENT_TYPE,
+                data,
+            });
+        }
+        Ok(cid)
+    }
+
+    /// Number of distinct objects in the batch
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Whether the batch has no objects
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// CIDs of the objects in the order they were added
+    pub fn cids(&self) -> Vec<Cid> {
+        self.objects.iter().map(|o| o.cid).collect()
+    }
+
+    /// Codecs of the content types in the batch
+    pub fn content_types(&self) -> impl Iterator<Item = u64> + '_ {
+        self.objects.iter().map(|o| o.content_type.codec())
+    }
+
+    /// Serialized sizes of the objects in the batch
+    pub fn sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.objects.iter().map(|o| o.data.len())
+    }
+
+    pub(crate) fn objects(&self) -> &[StagedObject] {
+        &self.objects
+    }
+
+    /// Stage every object, then publish the manifest
+    ///
+    /// On failure, objects written by this batch are removed and the first
+    /// error is returned; no manifest is stored.
+    pub async fn commit(&self, backend: &dyn StorageBackend) -> Result<BatchCommit> {
+        let results: Vec<_> = stream::iter(self.objects.iter().map(|object| stage(backend, object)))
+            .buffered(STAGE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut written = Vec::new();
+        let mut deduplicated = Vec::new();
+        let mut failure = None;
+        for (object, result) in self.objects.iter().zip(results) {
+            match result {
+                Ok(true) => written.push(object),
+                Ok(false) => deduplicated.push(object.cid),
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+
+        if let Some(error) = failure {
+            rollback(backend, &written).await;
+            return Err(error);
+        }
+
+        let manifest = BatchManifest {
+            entries: self.objects
+                .iter()
+                .map(|o| ManifestEntry {
+                    bucket: o.bucket.clone(),
+                    cid: o.cid,
+                    size: o.data.len(),
+                })
+                .collect(),
+            committed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        let manifest_cid = manifest.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+        let data = manifest.to_bytes()
+            .map_err(ObjectStoreError::serialization)?;
+
+        if let Err(e) = backend.put_block(MANIFEST_BUCKET, &manifest_cid, data).await {
+            rollback(backend, &written).await;
+            return Err(e);
+        }
+
+        debug!("Committed batch {} with {} objects", manifest_cid, manifest.entries.len());
+        Ok(BatchCommit {
+            manifest_cid,
+            manifest,
+            written: written.len(),
+            deduplicated,
+        })
+    }
+}
+
+/// Write an object unless it already exists; returns whether it was written
+async fn stage(backend: &dyn StorageBackend, object: &StagedObject) -> Result<bool> {
+    if backend.has_block(&object.bucket, &object.cid).await? {
+        return Ok(false);
+    }
+    backend.put_block(&object.bucket, &object.cid, object.data.clone()).await?;
+    Ok(true)
+}
+
+/// Remove objects written by a failed batch
+///
+/// Failures are only logged, so the error that failed the batch is the one
+/// returned.
+async fn rollback(backend: &dyn StorageBackend, written: &[&StagedObject]) {
+    for object in written {
+        if let Err(e) = backend.delete_block(&object.bucket, &object.cid).await {
+            warn!("Failed to roll back {} in {}: {}", object.cid, object.bucket, e);
+        }
+    }
+}
+
+/// Load and verify a batch manifest
+pub async fn load_manifest(backend: &dyn StorageBackend, manifest_cid: &Cid) -> Result<BatchManifest> {
+    let data = backend.get_block(MANIFEST_BUCKET, manifest_cid).await?;
+    let manifest = BatchManifest::from_bytes(&data)
+        .map_err(ObjectStoreError::deserialization)?;
+    let computed = manifest.calculate_cid()
+        .map_err(ObjectStoreError::serialization)?;
+    if computed != *manifest_cid {
+        return Err(ObjectStoreError::CidMismatch {
+            expected: manifest_cid.to_string(),
+            actual: computed.to_string(),
+        });
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_store::{ListOptions, ListPage, MemoryBackend, ObjectInfo};
+    use async_trait::async_trait;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Node(String);
+
+    impl TypedContent for Node {
+        const CODEC: u64 = 0x300101;
+        const CONTENT_TYPE: ContentType = ContentType::Custom(0x300101);
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Graph(Vec<String>);
+
+    impl TypedContent for Graph {
+        const CODEC: u64 = 0x300100;
+        const CONTENT_TYPE: ContentType = ContentType::Custom(0x300100);
+    }
+
+    /// Memory backend that rejects writes of one CID
+    struct FailingBackend {
+        inner: MemoryBackend,
+        reject: Cid,
+    }
+
+    #[async_trait]
+    impl StorageBackend for FailingBackend {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+            if *cid == self.reject {
+                return Err(ObjectStoreError::Storage("rejected".to_string()));
+            }
+            self.inner.put_block(bucket, cid, data).await
+        }
+
+        async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+            self.inner.get_block(bucket, cid).await
+        }
+
+        async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+            self.inner.has_block(bucket, cid).await
+        }
+
+        async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+            self.inner.delete_block(bucket, cid).await
+        }
+
+        async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+            self.inner.list_blocks(bucket).await
+        }
+
+        async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+            self.inner.list_blocks_page(bucket, options).await
+        }
+    }
+
+    fn graph_batch() -> (Batch, Vec<Cid>) {
+        let mut batch = Batch::new();
+        let nodes: Vec<_> = (0..3).map(|i| Node(format!("node-{i}"))).collect();
+        let mut cids: Vec<_> = nodes.iter().map(|n| batch.add(n).unwrap()).collect();
+        cids.push(batch.add(&Graph(nodes.iter().map(|n| n.0.clone()).collect())).unwrap());
+        (batch, cids)
+    }
+
+    #[tokio::test]
+    async fn test_commit_writes_objects_and_manifest() {
+        let backend = MemoryBackend::new();
+        let (batch, cids) = graph_batch();
+        assert_eq!(batch.len(), 4);
+
+        let commit = batch.commit(&backend).await.unwrap();
+        assert_eq!(commit.written, 4);
+
+        let manifest = load_manifest(&backend, &commit.manifest_cid).await.unwrap();
+        assert_eq!(manifest, commit.manifest);
+        let listed: Vec<_> = manifest.entries.iter().map(|e| e.cid).collect();
+        assert_eq!(listed, cids);
+        for entry in &manifest.entries {
+            assert!(backend.has_block(&entry.bucket, &entry.cid).await.unwrap());
+        }
+        assert_eq!(manifest.entries[3].bucket, "cim-graphs");
+    }
+
+    #[tokio::test]
+    async fn test_failed_commit_rolls_back() {
+        let (batch, cids) = graph_batch();
+        let backend = FailingBackend {
+            inner: MemoryBackend::new(),
+            reject: cids[2],
+        };
+
+        let existing = Node("node-0".to_string());
+        backend.inner.put_block("cim-nodes", &cids[0], existing.to_bytes().unwrap()).await.unwrap();
+
+        let result = batch.commit(&backend).await;
+        assert!(matches!(result, Err(ObjectStoreError::Storage(_))));
+        assert!(backend.list_blocks(MANIFEST_BUCKET).await.unwrap().is_empty());
+
+        // Blocks the batch wrote are removed; one that already existed is kept
+        assert!(backend.has_block("cim-nodes", &cids[0]).await.unwrap());
+        assert!(!backend.has_block("cim-nodes", &cids[1]).await.unwrap());
+        assert!(!backend.has_block("cim-graphs", &cids[3]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_content_is_staged_once() {
+        let mut batch = Batch::new();
+        let a = batch.add(&Node("same".to_string())).unwrap();
+        let b = batch.add(&Node("same".to_string())).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(batch.len(), 1);
+    }
+}
@@ -0,0 +1,738 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Capability tokens for content access
+//!
+//! A [`CapabilityToken`] grants its audience a set of [`Action`]s over a
+//! [`Resource`] pattern: a bucket name or prefix, a content domain, or a
+//! single CID. Tokens are signed with Ed25519 and carry their delegation
+//! chain as proofs, in the style of UCAN: the holder of a token may issue a
+//! narrower one to another key, and the verifier walks the chain back to a
+//! trusted root key. Verification needs nothing but the token and the root
+//! keys, so it works offline.
+//!
+//! Holding a token is not enough to use it. Each request is an
+//! [`Invocation`]: the action and target, signed with the audience's key, so
+//! a token copied off the wire is useless without that key. A [`Credential`]
+//! pairs a token with the key and signs invocations for it.
+//!
+//! [`Authorizer`] verifies tokens and checks invocations against them;
+//! [`CapabilityGuard`] enforces a credential on every call to a
+//! [`StorageBackend`].
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cid::Cid;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ContentDomain, ContentEventStream, ListOptions, ListPage, ObjectInfo, ObjectStoreError,
+    PartitionStrategy, Result, StorageBackend, WatchFrom,
+};
+use crate::util::{cid_serde, unix_secs};
+
+/// Longest delegation chain accepted by [`Authorizer::verify`]
+pub const MAX_DELEGATION_DEPTH: usize = 8;
+
+/// How long an [`Invocation`] is accepted after it is signed
+pub const DEFAULT_INVOCATION_WINDOW: Duration = Duration::from_secs(300);
+
+/// How far in the future an invocation may be dated, to allow for clock skew
+const MAX_CLOCK_SKEW_SECS: u64 = 30;
+
+/// Operation on stored content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Fetch content or check that it exists
+    Read,
+    /// Store content
+    Write,
+    /// Delete, soft delete, or restore content
+    Delete,
+    /// Enumerate or watch a bucket
+    List,
+}
+
+impl Action {
+    /// Every action
+    pub const ALL: [Action; 4] = [Action::Read, Action::Write, Action::Delete, Action::List];
+}
+
+/// Content a capability applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    /// All content
+    Any,
+    /// A bucket by name, or every bucket starting with a prefix when the
+    /// pattern ends in `*`
+    Bucket(String),
+    /// Every bucket of a content domain
+    Domain(ContentDomain),
+    /// One object, in whichever bucket holds it
+    Cid(#[serde(with = "cid_serde")] Cid),
+}
+
+impl Resource {
+    /// Whether a request for `cid` in `bucket` falls under this resource
+    ///
+    /// `domain` is the content domain `bucket` belongs to, if any.
+    pub fn matches(&self, bucket: &str, domain: Option<ContentDomain>, cid: Option<&Cid>) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Bucket(pattern) => match pattern.strip_suffix('*') {
+                Some(prefix) => bucket.starts_with(prefix),
+                None => pattern == bucket,
+            },
+            Self::Domain(d) => domain == Some(*d),
+            Self::Cid(c) => cid == Some(c),
+        }
+    }
+
+    /// Whether everything `other` covers is also covered by this resource
+    pub fn contains(&self, other: &Resource) -> bool {
+        match (self, other) {
+            (Self::Any, _) => true,
+            (Self::Bucket(pattern), Self::Bucket(bucket)) => match pattern.strip_suffix('*') {
+                Some(prefix) => bucket.starts_with(prefix),
+                None => pattern == bucket,
+            },
+            (Self::Domain(a), Self::Domain(b)) => a == b,
+            (Self::Cid(a), Self::Cid(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Actions granted over a resource
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capability {
+    /// Content the actions apply to
+    pub resource: Resource,
+    /// Granted actions
+    pub actions: Vec<Action>,
+}
+
+impl Capability {
+    /// Grant `actions` over `resource`
+    pub fn new(resource: Resource, actions: impl IntoIterator<Item = Action>) -> Self {
+        Self {
+            resource,
+            actions: actions.into_iter().collect(),
+        }
+    }
+
+    /// Whether this capability grants `other` in full
+    pub fn contains(&self, other: &Capability) -> bool {
+        self.resource.contains(&other.resource)
+            && other.actions.iter().all(|action| self.actions.contains(action))
+    }
+
+    /// Whether this capability allows `action` on `cid` in `bucket`
+    pub fn allows(&self, action: Action, bucket: &str, domain: Option<ContentDomain>, cid: Option<&Cid>) -> bool {
+        self.actions.contains(&action) && self.resource.matches(bucket, domain, cid)
+    }
+}
+
+/// Signed grant of capabilities from an issuer key to an audience key
+///
+/// Keys are base64 Ed25519 public keys. A root token has no proofs and must
+/// be issued by a trusted key; a delegated token embeds the token it was
+/// derived from, whose audience must be the delegated token's issuer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityToken {
+    /// Key that signed the token
+    pub issuer: String,
+    /// Key the capabilities are granted to
+    pub audience: String,
+    /// Granted capabilities
+    pub capabilities: Vec<Capability>,
+    /// Start of validity in seconds since the Unix epoch
+    pub not_before: u64,
+    /// End of validity in seconds since the Unix epoch
+    pub expires_at: u64,
+    /// Random value making otherwise identical tokens distinct
+    pub nonce: String,
+    /// Tokens this one was delegated from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proofs: Vec<CapabilityToken>,
+    /// Base64 Ed25519 signature over every other field
+    pub signature: String,
+}
+
+/// Token fields covered by the signature
+#[derive(Serialize)]
+struct SigningPayload<'a> {
+    version: &'static str,
+    issuer: &'a str,
+    audience: &'a str,
+    capabilities: &'a [Capability],
+    not_before: u64,
+    expires_at: u64,
+    nonce: &'a str,
+    proofs: Vec<&'a str>,
+}
+
+/// Base64 encoding of a public key, as used in token issuer and audience
+pub fn key_id(key: &VerifyingKey) -> String {
+    BASE64.encode(key.as_bytes())
+}
+
+fn unauthorized(reason: impl Into<String>) -> ObjectStoreError {
+    ObjectStoreError::Unauthorized(reason.into())
+}
+
+/// Check a base64 Ed25519 `signature` by the base64 `key` over `payload`
+fn verify_signed(what: &str, key: &str, signature: &str, payload: &[u8]) -> Result<()> {
+    let invalid = |reason: &str| ObjectStoreError::InvalidSignature(format!("{what}: {reason}"));
+
+    let public_key: [u8; 32] = BASE64.decode(key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("malformed key"))?;
+    let key = VerifyingKey::from_bytes(&public_key).map_err(|_| invalid("malformed key"))?;
+    let bytes: [u8; 64] = BASE64.decode(signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("malformed signature"))?;
+
+    key.verify(payload, &Signature::from_bytes(&bytes))
+        .map_err(|_| invalid("signature does not match"))
+}
+
+fn random_nonce() -> String {
+    let mut nonce = [0u8; 12];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
+    BASE64.encode(nonce)
+}
+
+impl CapabilityToken {
+    /// Issue a root token granting `capabilities` to `audience` for `ttl`
+    pub fn issue(
+        key: &SigningKey,
+        audience: &VerifyingKey,
+        capabilities: Vec<Capability>,
+        ttl: Duration,
+    ) -> Self {
+        let now = SystemTime::now();
+        Self::signed(key, audience, capabilities, unix_secs(now), unix_secs(now + ttl), Vec::new())
+    }
+
+    /// Pass on part of this token's capabilities to another key
+    ///
+    /// `key` must be this token's audience. Capabilities not covered by this
+    /// token are refused, and the new token expires no later than this one.
+    pub fn delegate(
+        &self,
+        key: &SigningKey,
+        audience: &VerifyingKey,
+        capabilities: Vec<Capability>,
+        ttl: Duration,
+    ) -> Result<Self> {
+        if key_id(&key.verifying_key()) != self.audience {
+            return Err(unauthorized("only the audience of a token can delegate it"));
+        }
+        if let Some(extra) = capabilities.iter().find(|c| !self.grants(c)) {
+            return Err(unauthorized(format!("cannot delegate {extra:?}: not granted")));
+        }
+
+        let now = SystemTime::now();
+        let expires_at = unix_secs(now + ttl).min(self.expires_at);
+        Ok(Self::signed(key, audience, capabilities, unix_secs(now), expires_at, vec![self.clone()]))
+    }
+
+    fn signed(
+        key: &SigningKey,
+        audience: &VerifyingKey,
+        capabilities: Vec<Capability>,
+        not_before: u64,
+        expires_at: u64,
+        proofs: Vec<CapabilityToken>,
+    ) -> Self {
+        let mut token = Self {
+            issuer: key_id(&key.verifying_key()),
+            audience: key_id(audience),
+            capabilities,
+            not_before,
+            expires_at,
+            nonce: random_nonce(),
+            proofs,
+            signature: String::new(),
+        };
+        token.signature = BASE64.encode(key.sign(&token.signing_payload()).to_bytes());
+        token
+    }
+
+    fn signing_payload(&self) -> Vec<u8> {
+        let payload = SigningPayload {
+            version: "cim-ipld/capability/v1",
+            issuer: &self.issuer,
+            audience: &self.audience,
+            capabilities: &self.capabilities,
+            not_before: self.not_before,
+            expires_at: self.expires_at,
+            nonce: &self.nonce,
+            // A proof's signature commits to all of its fields
+            proofs: self.proofs.iter().map(|p| p.signature.as_str()).collect(),
+        };
+        serde_json::to_vec(&payload).expect("signing payload serializes")
+    }
+
+    /// Whether one of this token's capabilities covers `capability`
+    pub fn grants(&self, capability: &Capability) -> bool {
+        self.capabilities.iter().any(|c| c.contains(capability))
+    }
+
+    /// Whether this token allows `action` on `cid` in `bucket`
+    ///
+    /// Only looks at the token's own capabilities; call
+    /// [`Authorizer::verify`] to check that it is genuine.
+    pub fn allows(&self, action: Action, bucket: &str, domain: Option<ContentDomain>, cid: Option<&Cid>) -> bool {
+        self.capabilities.iter().any(|c| c.allows(action, bucket, domain, cid))
+    }
+
+    /// Check the token's own signature
+    pub fn verify_signature(&self) -> Result<()> {
+        verify_signed("capability token", &self.issuer, &self.signature, &self.signing_payload())
+    }
+
+    /// Serialize the token for transport
+    pub fn encode(&self) -> String {
+        BASE64.encode(serde_json::to_vec(self).expect("token serializes"))
+    }
+
+    /// Parse a token produced by [`CapabilityToken::encode`]
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let bytes = BASE64.decode(encoded)
+            .map_err(ObjectStoreError::deserialization)?;
+        serde_json::from_slice(&bytes).map_err(ObjectStoreError::deserialization)
+    }
+}
+
+/// Request made under a capability token, signed by the token's audience
+///
+/// Names one action on one target, so an intercepted invocation cannot be
+/// turned into another request. [`Authorizer::authorize`] accepts it once,
+/// within its window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invocation {
+    /// Token the request is made under
+    pub token: CapabilityToken,
+    /// Requested action
+    pub action: Action,
+    /// Bucket the request targets
+    pub bucket: String,
+    /// Object the request targets, if it targets one
+    #[serde(default, with = "cid_serde::option")]
+    pub cid: Option<Cid>,
+    /// Signing time in seconds since the Unix epoch
+    pub issued_at: u64,
+    /// Random value making otherwise identical invocations distinct
+    pub nonce: String,
+    /// Base64 Ed25519 signature by the token's audience over every other field
+    pub signature: String,
+}
+
+/// Invocation fields covered by the signature
+#[derive(Serialize)]
+struct InvocationPayload<'a> {
+    version: &'static str,
+    token: &'a str,
+    action: Action,
+    bucket: &'a str,
+    cid: Option<String>,
+    issued_at: u64,
+    nonce: &'a str,
+}
+
+impl Invocation {
+    fn signing_payload(&self) -> Vec<u8> {
+        let payload = InvocationPayload {
+            version: "cim-ipld/invocation/v1",
+            // The token's signature commits to all of its fields
+            token: &self.token.signature,
+            action: self.action,
+            bucket: &self.bucket,
+            cid: self.cid.map(|cid| cid.to_string()),
+            issued_at: self.issued_at,
+            nonce: &self.nonce,
+        };
+        serde_json::to_vec(&payload).expect("invocation payload serializes")
+    }
+
+    /// Check that the token's audience signed the invocation
+    pub fn verify_signature(&self) -> Result<()> {
+        verify_signed("invocation", &self.token.audience, &self.signature, &self.signing_payload())
+    }
+
+    /// Serialize the invocation for transport
+    pub fn encode(&self) -> String {
+        BASE64.encode(serde_json::to_vec(self).expect("invocation serializes"))
+    }
+
+    /// Parse an invocation produced by [`Invocation::encode`]
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let bytes = BASE64.decode(encoded)
+            .map_err(ObjectStoreError::deserialization)?;
+        serde_json::from_slice(&bytes).map_err(ObjectStoreError::deserialization)
+    }
+}
+
+/// Capability token held together with its audience's signing key
+#[derive(Clone)]
+pub struct Credential {
+    token: CapabilityToken,
+    key: SigningKey,
+}
+
+impl std::fmt::Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credential")
+            .field("token", &self.token)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Credential {
+    /// Hold `token` with `key`, which must be the token's audience
+    pub fn new(token: CapabilityToken, key: SigningKey) -> Result<Self> {
+        if key_id(&key.verifying_key()) != token.audience {
+            return Err(unauthorized("key is not the audience of the token"));
+        }
+        Ok(Self { token, key })
+    }
+
+    /// The token held
+    pub fn token(&self) -> &CapabilityToken {
+        &self.token
+    }
+
+    /// Sign a request for `action` on `cid` in `bucket`
+    pub fn invoke(&self, action: Action, bucket: &str, cid: Option<&Cid>) -> Invocation {
+        let mut invocation = Invocation {
+            token: self.token.clone(),
+            action,
+            bucket: bucket.to_string(),
+            cid: cid.copied(),
+            issued_at: unix_secs(SystemTime::now()),
+            nonce: random_nonce(),
+            signature: String::new(),
+        };
+        invocation.signature = BASE64.encode(self.key.sign(&invocation.signing_payload()).to_bytes());
+        invocation
+    }
+}
+
+/// Verifies capability tokens and checks invocations against them
+///
+/// Clones share the record of invocations already accepted.
+#[derive(Debug, Clone)]
+pub struct Authorizer {
+    roots: Vec<String>,
+    strategy: PartitionStrategy,
+    window: Duration,
+    /// Accepted invocations by signing time and signature, until they leave the window
+    seen: Arc<Mutex<BTreeSet<(u64, String)>>>,
+}
+
+impl Authorizer {
+    /// Trust tokens whose delegation chain starts at one of `roots`
+    pub fn new(roots: impl IntoIterator<Item = VerifyingKey>) -> Self {
+        Self {
+            roots: roots.into_iter().map(|key| key_id(&key)).collect(),
+            strategy: PartitionStrategy::default(),
+            window: DEFAULT_INVOCATION_WINDOW,
+            seen: Arc::new(Mutex::new(BTreeSet::new())),
+        }
+    }
+
+    /// Accept invocations for `window` after they are signed
+    pub fn with_invocation_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Resolve [`Resource::Domain`] capabilities with a custom bucket mapping
+    pub fn with_partition_strategy(mut self, strategy: PartitionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Check a token's signatures, validity period and delegation chain
+    pub fn verify(&self, token: &CapabilityToken) -> Result<()> {
+        self.verify_at(token, SystemTime::now())
+    }
+
+    /// Check a token as of `now`
+    pub fn verify_at(&self, token: &CapabilityToken, now: SystemTime) -> Result<()> {
+        self.verify_chain(token, unix_secs(now), 0)
+    }
+
+    fn verify_chain(&self, token: &CapabilityToken, now: u64, depth: usize) -> Result<()> {
+        if depth >= MAX_DELEGATION_DEPTH {
+            return Err(unauthorized("delegation chain too long"));
+        }
+        token.verify_signature()?;
+        if now < token.not_before {
+            return Err(unauthorized("token is not yet valid"));
+        }
+        if now >= token.expires_at {
+            return Err(unauthorized("token has expired"));
+        }
+
+        if token.proofs.is_empty() {
+            if !self.roots.contains(&token.issuer) {
+                return Err(unauthorized(format!("issuer {} is not trusted", token.issuer)));
+            }
+            return Ok(());
+        }
+
+        for proof in &token.proofs {
+            self.verify_chain(proof, now, depth + 1)?;
+            if proof.audience != token.issuer {
+                return Err(unauthorized("proof was not granted to the token issuer"));
+            }
+            if token.expires_at > proof.expires_at {
+                return Err(unauthorized("token outlives its proof"));
+            }
+        }
+        for capability in &token.capabilities {
+            if !token.proofs.iter().any(|proof| proof.grants(capability)) {
+                return Err(unauthorized(format!("{capability:?} is not granted by any proof")));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check an invocation: its token, its audience signature, its age, and
+    /// that the token allows what it requests
+    ///
+    /// Each invocation is accepted only once.
+    pub fn authorize(&self, invocation: &Invocation) -> Result<()> {
+        self.authorize_at(invocation, SystemTime::now())
+    }
+
+    fn authorize_at(&self, invocation: &Invocation, now: SystemTime) -> Result<()> {
+        let Invocation { token, action, bucket, cid, issued_at, .. } = invocation;
+        let (action, cid, now) = (*action, cid.as_ref(), unix_secs(now));
+
+        self.verify_chain(token, now, 0)?;
+        invocation.verify_signature()?;
+        if *issued_at > now + MAX_CLOCK_SKEW_SECS {
+            return Err(unauthorized("invocation is dated in the future"));
+        }
+        let oldest = now.saturating_sub(self.window.as_secs());
+        if *issued_at < oldest {
+            return Err(unauthorized("invocation is too old"));
+        }
+
+        let domain = self.strategy.domain_for_bucket(bucket);
+        if !token.allows(action, bucket, domain, cid) {
+            return Err(unauthorized(match cid {
+                Some(cid) => format!("{action:?} on {cid} in {bucket} not granted"),
+                None => format!("{action:?} on {bucket} not granted"),
+            }));
+        }
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        *seen = seen.split_off(&(oldest, String::new()));
+        if !seen.insert((*issued_at, invocation.signature.clone())) {
+            return Err(unauthorized("invocation was already used"));
+        }
+        Ok(())
+    }
+}
+
+/// Storage backend wrapper that enforces a capability token
+///
+/// Every call is signed as an invocation and checked against the token, so
+/// access ends when the token expires. Listing and watching need
+/// [`Action::List`] on the bucket.
+pub struct CapabilityGuard {
+    inner: Arc<dyn StorageBackend>,
+    authorizer: Arc<Authorizer>,
+    credential: Credential,
+}
+
+impl CapabilityGuard {
+    /// Allow only what the credential's token grants
+    pub fn new(inner: Arc<dyn StorageBackend>, authorizer: Arc<Authorizer>, credential: Credential) -> Self {
+        Self { inner, authorizer, credential }
+    }
+
+    /// The token being enforced
+    pub fn token(&self) -> &CapabilityToken {
+        self.credential.token()
+    }
+
+    fn authorize(&self, action: Action, bucket: &str, cid: Option<&Cid>) -> Result<()> {
+        self.authorizer.authorize(&self.credential.invoke(action, bucket, cid))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for CapabilityGuard {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        self.authorize(Action::Write, bucket, Some(cid))?;
+        self.inner.put_block(bucket, cid, data).await
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        self.authorize(Action::Read, bucket, Some(cid))?;
+        self.inner.get_block(bucket, cid).await
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        self.authorize(Action::Read, bucket, Some(cid))?;
+        self.inner.has_block(bucket, cid).await
+    }
+
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        self.authorize(Action::Delete, bucket, Some(cid))?;
+        self.inner.delete_block(bucket, cid).await
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        self.authorize(Action::List, bucket, None)?;
+        self.inner.list_blocks(bucket).await
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        self.authorize(Action::List, bucket, None)?;
+        self.inner.list_blocks_page(bucket, options).await
+    }
+
+    /// Only buckets the token may list are returned
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        let mut names = self.inner.list_buckets().await?;
+        names.retain(|bucket| self.authorize(Action::List, bucket, None).is_ok());
+        Ok(names)
+    }
+
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        self.authorize(Action::List, bucket, None)?;
+        self.inner.watch_blocks(bucket, from).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_cid;
+    use crate::object_store::MemoryBackend;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn key() -> SigningKey {
+        SigningKey::generate(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn test_delegation_is_attenuated() {
+        let (root, alice, bob) = (key(), key(), key());
+        let authorizer = Authorizer::new([root.verifying_key()]);
+        let docs = Capability::new(Resource::Bucket("cim-documents".into()), [Action::Read, Action::Write]);
+
+        let token = CapabilityToken::issue(&root, &alice.verifying_key(), vec![docs], HOUR);
+        authorizer.verify(&token).unwrap();
+
+        let read_only = Capability::new(Resource::Bucket("cim-documents".into()), [Action::Read]);
+        let delegated = token.delegate(&alice, &bob.verifying_key(), vec![read_only], HOUR).unwrap();
+        let bob_credential = Credential::new(delegated.clone(), bob.clone()).unwrap();
+        authorizer.authorize(&bob_credential.invoke(Action::Read, "cim-documents", None)).unwrap();
+        let err = authorizer.authorize(&bob_credential.invoke(Action::Write, "cim-documents", None)).unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::Unauthorized);
+
+        // Only the audience may delegate, and only what it holds
+        let any = Capability::new(Resource::Any, [Action::Read]);
+        assert!(token.delegate(&bob, &bob.verifying_key(), vec![any.clone()], HOUR).is_err());
+        assert!(token.delegate(&alice, &bob.verifying_key(), vec![any.clone()], HOUR).is_err());
+
+        // A hand-built escalation fails verification
+        let forged = CapabilityToken::signed(&alice, &bob.verifying_key(), vec![any], 0, u64::MAX, vec![token]);
+        assert!(authorizer.verify(&forged).is_err());
+
+        // Tokens survive transport
+        let decoded = CapabilityToken::decode(&delegated.encode()).unwrap();
+        authorizer.verify(&decoded).unwrap();
+    }
+
+    #[test]
+    fn test_expired_untrusted_and_tampered_tokens() {
+        let (root, alice) = (key(), key());
+        let authorizer = Authorizer::new([root.verifying_key()]);
+        let all = Capability::new(Resource::Any, Action::ALL);
+
+        let token = CapabilityToken::issue(&root, &alice.verifying_key(), vec![all.clone()], HOUR);
+        assert!(authorizer.verify_at(&token, SystemTime::now() + 2 * HOUR).is_err());
+
+        let self_issued = CapabilityToken::issue(&alice, &alice.verifying_key(), vec![all], HOUR);
+        assert!(authorizer.verify(&self_issued).is_err());
+
+        let mut tampered = token.clone();
+        tampered.expires_at += 3600;
+        let err = authorizer.verify(&tampered).unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::InvalidSignature);
+    }
+
+    #[tokio::test]
+    async fn test_guard_enforces_token() {
+        let (root, alice) = (key(), key());
+        let authorizer = Arc::new(Authorizer::new([root.verifying_key()]));
+        let backend = Arc::new(MemoryBackend::new());
+        let (mine, other) = (test_cid(b"mine"), test_cid(b"other"));
+        backend.put_block("cim-documents", &other, b"other".to_vec()).await.unwrap();
+
+        let token = CapabilityToken::issue(&root, &alice.verifying_key(), vec![
+            Capability::new(Resource::Bucket("cim-doc*".into()), [Action::Read, Action::Write]),
+            Capability::new(Resource::Cid(mine), [Action::Delete]),
+        ], HOUR);
+        let guard = CapabilityGuard::new(backend.clone(), authorizer, Credential::new(token, alice).unwrap());
+
+        guard.put_block("cim-documents", &mine, b"mine".to_vec()).await.unwrap();
+        assert_eq!(guard.get_block("cim-documents", &other).await.unwrap(), b"other");
+        assert!(matches!(
+            guard.delete_block("cim-documents", &other).await,
+            Err(ObjectStoreError::Unauthorized(_))
+        ));
+        assert!(guard.list_blocks("cim-documents").await.is_err());
+        assert!(guard.get_block("cim-events", &other).await.is_err());
+
+        guard.delete_block("cim-documents", &mine).await.unwrap();
+        assert!(backend.has_block("cim-documents", &other).await.unwrap());
+    }
+
+    #[test]
+    fn test_invocation_binds_the_audience() {
+        let (root, alice, mallory) = (key(), key(), key());
+        let authorizer = Authorizer::new([root.verifying_key()]);
+        let docs = Capability::new(Resource::Bucket("cim-documents".into()), [Action::Read]);
+        let token = CapabilityToken::issue(&root, &alice.verifying_key(), vec![docs], HOUR);
+
+        // A stolen token cannot be held or invoked without the audience key
+        assert!(Credential::new(token.clone(), mallory.clone()).is_err());
+        let mut stolen = Credential { token: token.clone(), key: mallory }.invoke(Action::Read, "cim-documents", None);
+        assert_eq!(authorizer.authorize(&stolen).unwrap_err().code(), crate::ErrorCode::InvalidSignature);
+
+        // An invocation is good for one request, once, within its window
+        let credential = Credential::new(token, alice).unwrap();
+        let invocation = Invocation::decode(&credential.invoke(Action::Read, "cim-documents", None).encode()).unwrap();
+        stolen = invocation.clone();
+        stolen.bucket = "cim-events".into();
+        assert!(authorizer.authorize(&stolen).is_err());
+        authorizer.authorize(&invocation).unwrap();
+        assert!(authorizer.authorize(&invocation).is_err());
+
+        let late = credential.invoke(Action::Read, "cim-documents", None);
+        assert!(authorizer.authorize_at(&late, SystemTime::now() + DEFAULT_INVOCATION_WINDOW * 2).is_err());
+    }
+}
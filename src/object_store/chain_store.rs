@@ -0,0 +1,771 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Persistent content chains
+//!
+//! [`ChainStore`] keeps every [`ChainedContent`] entry as its own DAG-CBOR
+//! block, keyed by the entry CID, and publishes the chain head under a name in
+//! [`NamedRefs`]. A chain is rebuilt from its head by following
+//! `previous_cid` links back to the first entry; every entry's CID, link,
+//! and sequence number is checked on the way. Long chains can be read a
+//! page at a time with [`ChainStore::pages`], newest entries first.
+//!
+//! Entry signatures are not part of the blocks; they are kept next to them in
+//! [`CHAIN_SIGNATURE_BUCKET`], keyed by the entry CID, and reattached on load.
+//!
+//! Each entry also gets an index record in [`CHAIN_INDEX_BUCKET`] with its
+//! sequence number, its timestamp, and links to the entries 1, 2, 4, ...
+//! positions back. [`ChainStore::get_by_sequence`], [`ChainStore::range`],
+//! and [`ChainStore::between`] use them to find entries of a stored chain in
+//! a logarithmic number of reads, without loading the chain.
+//!
+//! Several writers can extend the same chain: [`ChainStore::append_if_head`]
+//! only appends if the head is still the one the writer last saw, and
+//! [`ChainStore::subscribe`] streams entries as other writers add them,
+//! in-process or, with NATS-backed refs, across processes.
+
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cid::Cid;
+use ed25519_dalek::SigningKey;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::{NamedRef, NamedRefs, ObjectStoreError, RefEventStream, StorageBackend};
+use crate::chain::{ChainedContent, Checkpoint, ContentChain, EntrySignature};
+use crate::{Error, Result, TypedContent};
+
+/// Bucket holding chain entries
+pub const CHAIN_BUCKET: &str = "cim-chains";
+
+/// Bucket holding signatures of chain entries
+pub const CHAIN_SIGNATURE_BUCKET: &str = "cim-chain-signatures";
+
+/// Bucket holding lookup indexes of chain entries
+pub const CHAIN_INDEX_BUCKET: &str = "cim-chain-index";
+
+/// Lookup data kept next to a stored chain entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EntryIndex {
+    sequence: u64,
+    /// Milliseconds since the Unix epoch, never below the previous entry's
+    timestamp: u64,
+    /// CIDs of the entries `2^k` positions back, for `k = 0, 1, ...`
+    jumps: Vec<String>,
+}
+
+impl EntryIndex {
+    fn jump(&self, k: usize) -> Result<Option<Cid>> {
+        self.jumps.get(k)
+            .map(|cid| cid.parse().map_err(|e: cid::Error| Error::InvalidCid(e.to_string())))
+            .transpose()
+    }
+}
+
+/// Error for an index record that disagrees with the entry it leads to
+fn corrupt_index(cid: &Cid, claimed: u64, actual: u64) -> Error {
+    Error::ChainValidationError {
+        expected: format!("entry {claimed} at {cid}, as indexed"),
+        actual: format!("entry {actual}"),
+    }
+}
+
+/// Entries per page when not specified
+pub const DEFAULT_PAGE_SIZE: usize = 256;
+
+/// Stream of entries appended to a chain, oldest first
+pub type ChainEntryStream<T> = BoxStream<'static, Result<ChainedContent<T>>>;
+
+/// Content chains stored block by block with named heads
+#[derive(Clone)]
+pub struct ChainStore {
+    backend: Arc<dyn StorageBackend>,
+    refs: Arc<NamedRefs>,
+    bucket: String,
+    signer: Option<SigningKey>,
+}
+
+impl ChainStore {
+    /// Store entries in `backend` and heads in `refs`
+    pub fn new(backend: Arc<dyn StorageBackend>, refs: Arc<NamedRefs>) -> Self {
+        Self {
+            backend,
+            refs,
+            bucket: CHAIN_BUCKET.to_string(),
+            signer: None,
+        }
+    }
+
+    /// Store entries in a bucket other than [`CHAIN_BUCKET`]
+    pub fn with_bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.bucket = bucket.into();
+        self
+    }
+
+    /// Sign every entry created by [`ChainStore::append`]
+    pub fn with_signer(mut self, key: SigningKey) -> Self {
+        self.signer = Some(key);
+        self
+    }
+
+    /// Refs holding chain heads
+    pub fn refs(&self) -> &Arc<NamedRefs> {
+        &self.refs
+    }
+
+    /// Store one entry and its signature, if any, and return its CID
+    pub async fn put_entry<T: TypedContent>(&self, entry: &ChainedContent<T>) -> Result<Cid> {
+        entry.verify_cid()?;
+        if !self.backend.has_block(&self.bucket, &entry.cid).await? {
+            self.backend.put_block(&self.bucket, &entry.cid, entry.to_block()?).await?;
+        }
+        if let Some(signature) = &entry.signature {
+            entry.verify_signature()?;
+            self.backend.put_block(CHAIN_SIGNATURE_BUCKET, &entry.cid, serde_json::to_vec(signature)?).await?;
+        }
+        self.put_index(entry).await?;
+        Ok(entry.cid)
+    }
+
+    /// Write the index record of an entry whose predecessor is indexed
+    async fn put_index<T: TypedContent>(&self, entry: &ChainedContent<T>) -> Result<()> {
+        if self.backend.has_block(CHAIN_INDEX_BUCKET, &entry.cid).await? {
+            return Ok(());
+        }
+        let mut index = EntryIndex {
+            sequence: entry.sequence,
+            timestamp: entry.timestamp.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            jumps: Vec::new(),
+        };
+
+        if let Some(previous) = entry.previous_cid {
+            // Entries stored before indexing existed are left unindexed
+            let Some(mut target) = self.get_index(&previous).await? else {
+                return Ok(());
+            };
+            index.timestamp = index.timestamp.max(target.timestamp);
+            let mut cid = previous;
+            loop {
+                index.jumps.push(cid.to_string());
+                // Twice as far back: the jump of the same length from the target
+                let Some(next) = target.jump(index.jumps.len() - 1)? else {
+                    break;
+                };
+                let Some(next_index) = self.get_index(&next).await? else {
+                    break;
+                };
+                cid = next;
+                target = next_index;
+            }
+        }
+
+        self.backend.put_block(CHAIN_INDEX_BUCKET, &entry.cid, serde_json::to_vec(&index)?).await?;
+        Ok(())
+    }
+
+    async fn get_index(&self, cid: &Cid) -> Result<Option<EntryIndex>> {
+        match self.backend.get_block(CHAIN_INDEX_BUCKET, cid).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Load one entry, checking that its block hashes to `cid`
+    ///
+    /// A stored signature is reattached, but only checked when the entry is
+    /// added to a [`ContentChain`].
+    pub async fn get_entry<T: TypedContent>(&self, cid: &Cid) -> Result<ChainedContent<T>> {
+        let mut entry = self.read_block::<T>(cid).await?;
+        entry.signature = self.get_signature(cid).await?;
+        if let Some(index) = self.get_index(cid).await? {
+            entry.timestamp = UNIX_EPOCH + Duration::from_millis(index.timestamp);
+        }
+        Ok(entry)
+    }
+
+    /// Read one entry's block, checking that it hashes to `cid`
+    async fn read_block<T: TypedContent>(&self, cid: &Cid) -> Result<ChainedContent<T>> {
+        let block = self.backend.get_block(&self.bucket, cid).await?;
+        let entry = ChainedContent::<T>::from_block(&block)?;
+        if entry.cid != *cid {
+            return Err(Error::InvalidCid(format!(
+                "chain entry stored under {cid} hashes to {}",
+                entry.cid
+            )));
+        }
+        Ok(entry)
+    }
+
+    /// CID of entry `sequence` of the chain ending at `head`
+    ///
+    /// Follows index jumps, or `previous_cid` links for unindexed entries.
+    /// Index records are not content-addressed, so every entry on the way is
+    /// read and must have the sequence number its index and the jump to it
+    /// claim.
+    async fn find<T: TypedContent>(&self, head: &Cid, sequence: u64) -> Result<Cid> {
+        let mut cid = *head;
+        let mut expected = None;
+        loop {
+            let entry = self.read_block::<T>(&cid).await?;
+            if let Some(expected) = expected.filter(|expected| *expected != entry.sequence) {
+                return Err(corrupt_index(&cid, expected, entry.sequence));
+            }
+            if entry.sequence == sequence {
+                return Ok(cid);
+            }
+
+            let index = self.get_index(&cid).await?;
+            if let Some(index) = index.as_ref().filter(|index| index.sequence != entry.sequence) {
+                return Err(corrupt_index(&cid, index.sequence, entry.sequence));
+            }
+            let jump = match index {
+                Some(index) if entry.sequence > sequence && !index.jumps.is_empty() => {
+                    let distance = entry.sequence - sequence;
+                    let k = ((u64::BITS - 1 - distance.leading_zeros()) as usize).min(index.jumps.len() - 1);
+                    index.jump(k)?.map(|next| (next, entry.sequence - (1 << k)))
+                }
+                _ => entry.previous_cid.map(|next| (next, entry.sequence.saturating_sub(1))),
+            };
+            match jump {
+                Some((next, next_sequence)) if entry.sequence > sequence => {
+                    cid = next;
+                    expected = Some(next_sequence);
+                }
+                _ => {
+                    return Err(Error::SequenceValidationError {
+                        expected: sequence,
+                        actual: entry.sequence,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Entry `sequence` of the chain ending at `head`
+    pub async fn get_by_sequence<T: TypedContent>(&self, head: &Cid, sequence: u64) -> Result<ChainedContent<T>> {
+        let cid = self.find::<T>(head, sequence).await?;
+        let entry = self.get_entry::<T>(&cid).await?;
+        if entry.sequence != sequence {
+            return Err(Error::SequenceValidationError {
+                expected: sequence,
+                actual: entry.sequence,
+            });
+        }
+        Ok(entry)
+    }
+
+    /// Entries of the chain ending at `head` whose sequence numbers fall in `sequences`
+    pub async fn range<T: TypedContent>(
+        &self,
+        head: &Cid,
+        sequences: impl RangeBounds<u64>,
+    ) -> Result<Vec<ChainedContent<T>>> {
+        let start = match sequences.start_bound() {
+            Bound::Included(s) => *s,
+            Bound::Excluded(s) => s.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match sequences.end_bound() {
+            Bound::Included(s) => Some(s.saturating_add(1)),
+            Bound::Excluded(s) => Some(*s),
+            Bound::Unbounded => None,
+        };
+
+        let last = self.get_entry::<T>(head).await?.sequence;
+        let end = end.map_or(last + 1, |end| end.min(last + 1));
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        let mut next = Some(self.find::<T>(head, end - 1).await?);
+        while let Some(cid) = next.take() {
+            let entry: ChainedContent<T> = self.get_entry(&cid).await?;
+            if entry.sequence > start {
+                next = entry.previous_cid;
+            }
+            entries.push(entry);
+        }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Entries of the chain ending at `head` timestamped in `[from, until)`
+    ///
+    /// Stored timestamps have millisecond precision and never decrease along
+    /// a chain, so the bounds are found by binary search over sequence numbers.
+    /// Timestamps are only kept in the index, so this fails with
+    /// [`ObjectStoreError::NotFound`] if an entry the search looks at is not
+    /// indexed.
+    pub async fn between<T: TypedContent>(
+        &self,
+        head: &Cid,
+        from: SystemTime,
+        until: SystemTime,
+    ) -> Result<Vec<ChainedContent<T>>> {
+        let len = self.get_entry::<T>(head).await?.sequence + 1;
+        let start = self.first_at_or_after::<T>(head, len, from).await?;
+        let end = self.first_at_or_after::<T>(head, len, until).await?;
+        self.range(head, start..end).await
+    }
+
+    /// Smallest sequence number whose stored timestamp is at least `time`
+    async fn first_at_or_after<T: TypedContent>(&self, head: &Cid, len: u64, time: SystemTime) -> Result<u64> {
+        let (mut low, mut high) = (0, len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let cid = self.find::<T>(head, middle).await?;
+            let Some(index) = self.get_index(&cid).await? else {
+                return Err(ObjectStoreError::NotFound(format!("index of chain entry {cid}")).into());
+            };
+            if UNIX_EPOCH + Duration::from_millis(index.timestamp) < time {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        Ok(low)
+    }
+
+    async fn get_signature(&self, cid: &Cid) -> Result<Option<EntrySignature>> {
+        match self.backend.get_block(CHAIN_SIGNATURE_BUCKET, cid).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store every entry of a chain and publish its head under `name`
+    ///
+    /// The head only moves forward: if `name` already points at an entry
+    /// that is not part of `chain`, the save fails with a conflict.
+    pub async fn save<T: TypedContent>(&self, name: &str, chain: &ContentChain<T>) -> Result<NamedRef> {
+        chain.validate()?;
+        for entry in chain.items() {
+            self.put_entry(entry).await?;
+        }
+        let head = self.refs.advance_chain_head(name, chain).await?;
+        debug!("Saved chain {} with {} entries at {}", name, chain.len(), head.cid());
+        Ok(head)
+    }
+
+    /// Append content to the chain published under `name`
+    ///
+    /// Creates the chain if `name` does not exist yet. Fails with a conflict
+    /// if another writer moved the head in the meantime.
+    pub async fn append<T: TypedContent>(&self, name: &str, content: T) -> Result<ChainedContent<T>> {
+        let current = self.refs.get(name).await?.map(|head| head.cid());
+        self.append_if_head(name, current, content).await
+    }
+
+    /// Append content only if the chain's head is still `expected`
+    ///
+    /// `None` means the chain must not exist yet. Fails with
+    /// [`ObjectStoreError::Conflict`] if the head has moved; the caller can
+    /// read the new head, reconcile, and try again.
+    pub async fn append_if_head<T: TypedContent>(
+        &self,
+        name: &str,
+        expected: Option<Cid>,
+        content: T,
+    ) -> Result<ChainedContent<T>> {
+        let current = self.refs.get(name).await?.map(|head| head.cid());
+        if current != expected {
+            return Err(ObjectStoreError::Conflict(format!(
+                "head of {name} is {}, expected {}",
+                describe(current),
+                describe(expected)
+            ))
+            .into());
+        }
+        let head = match &expected {
+            Some(cid) => Some(self.get_entry::<T>(cid).await?),
+            None => None,
+        };
+
+        let mut entry = ChainedContent::new(content, head.as_ref())?;
+        if let Some(key) = &self.signer {
+            entry = entry.sign(key);
+        }
+        let cid = self.put_entry(&entry).await?;
+        self.refs.compare_and_swap(name, expected, cid).await?;
+        Ok(entry)
+    }
+
+    /// Stream entries appended to the chain published under `name`
+    ///
+    /// Only entries added after the call are delivered, in chain order, no
+    /// matter whether they were appended one at a time or saved together.
+    /// Head changes are observed through the refs watch, so writers in other
+    /// processes are seen when the refs are stored in NATS.
+    pub async fn subscribe<T: TypedContent + 'static>(&self, name: &str) -> Result<ChainEntryStream<T>> {
+        // Watch before reading the head so no change falls in between
+        let events = self.refs.watch(name).await?;
+        let head = match self.refs.get(name).await? {
+            Some(head) => Some((head.cid(), self.get_entry::<T>(&head.cid()).await?.sequence)),
+            None => None,
+        };
+
+        let subscription = Subscription {
+            store: self.clone(),
+            name: name.to_string(),
+            events,
+            head,
+            pending: VecDeque::new(),
+        };
+        Ok(stream::unfold(subscription, |mut subscription| async move {
+            let item = subscription.next().await?;
+            Some((item, subscription))
+        })
+        .boxed())
+    }
+
+    /// Latest entry of the chain published under `name`
+    pub async fn head<T: TypedContent>(&self, name: &str) -> Result<Option<ChainedContent<T>>> {
+        match self.refs.get(name).await? {
+            Some(head) => Ok(Some(self.get_entry(&head.cid()).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Load the whole chain published under `name`
+    pub async fn load<T: TypedContent>(&self, name: &str) -> Result<ContentChain<T>> {
+        let head = self.refs.resolve(name).await?;
+        self.load_from(&head).await
+    }
+
+    /// Load the whole chain ending at `head`, validating every entry
+    pub async fn load_from<T: TypedContent>(&self, head: &Cid) -> Result<ContentChain<T>> {
+        let mut pages = self.pages::<T>(*head, DEFAULT_PAGE_SIZE);
+        let mut entries = Vec::new();
+        while let Some(page) = pages.next_page().await? {
+            entries.push(page);
+        }
+        ContentChain::from_items(entries.into_iter().rev().flatten())
+    }
+
+    /// Load the chain ending at `head` back to a trusted checkpoint
+    ///
+    /// Only the checkpoint entry and the entries after it are read, so the
+    /// cost does not grow with the history before the checkpoint.
+    pub async fn load_since<T: TypedContent>(&self, head: &Cid, checkpoint: &Checkpoint) -> Result<ContentChain<T>> {
+        let mut entries = Vec::new();
+        let mut next = Some(*head);
+        while let Some(cid) = next.take() {
+            let entry: ChainedContent<T> = self.get_entry(&cid).await?;
+            if entry.sequence > checkpoint.sequence {
+                next = entry.previous_cid;
+            }
+            entries.push(entry);
+        }
+        entries.reverse();
+        ContentChain::from_checkpoint(checkpoint.clone(), entries)
+    }
+
+    /// Read the chain ending at `head` one page at a time, newest page first
+    pub fn pages<T: TypedContent>(&self, head: Cid, page_size: usize) -> ChainPages<'_, T> {
+        ChainPages {
+            store: self,
+            next: Some(head),
+            newer_sequence: None,
+            page_size: page_size.max(1),
+            _entries: std::marker::PhantomData,
+        }
+    }
+}
+
+fn describe(cid: Option<Cid>) -> String {
+    cid.map_or_else(|| "nothing".to_string(), |cid| cid.to_string())
+}
+
+/// State of a [`ChainStore::subscribe`] stream
+struct Subscription<T> {
+    store: ChainStore,
+    name: String,
+    events: RefEventStream,
+    /// Last head delivered, with its sequence number
+    head: Option<(Cid, u64)>,
+    pending: VecDeque<ChainedContent<T>>,
+}
+
+impl<T: TypedContent> Subscription<T> {
+    async fn next(&mut self) -> Option<Result<ChainedContent<T>>> {
+        loop {
+            if let Some(entry) = self.pending.pop_front() {
+                return Some(Ok(entry));
+            }
+            let event = match self.events.next().await? {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e.into())),
+            };
+            // The watch matches by prefix; deletions carry no head
+            let Some(value) = event.value.filter(|_| event.name == self.name) else {
+                continue;
+            };
+            if let Err(e) = self.catch_up(value.cid).await {
+                return Some(Err(e));
+            }
+        }
+    }
+
+    /// Queue the entries between the last delivered head and `new_head`
+    async fn catch_up(&mut self, new_head: Cid) -> Result<()> {
+        let mut entries = Vec::new();
+        let mut next = Some(new_head);
+        while let Some(cid) = next.take() {
+            if self.head.is_some_and(|(head, _)| head == cid) {
+                break;
+            }
+            let entry: ChainedContent<T> = self.store.get_entry(&cid).await?;
+            if let Some((head, sequence)) = self.head {
+                if entry.sequence <= sequence {
+                    if entries.is_empty() {
+                        // An older head than the one already delivered
+                        return Ok(());
+                    }
+                    return Err(Error::ChainValidationError {
+                        expected: format!("descendant of {head}"),
+                        actual: new_head.to_string(),
+                    });
+                }
+            }
+            next = entry.previous_cid;
+            entries.push(entry);
+        }
+
+        if let Some(last) = entries.first() {
+            self.head = Some((last.cid, last.sequence));
+        }
+        self.pending.extend(entries.into_iter().rev());
+        Ok(())
+    }
+}
+
+/// Pages of a stored chain, read lazily from the head backwards
+///
+/// Each page holds up to `page_size` entries in chain order; the first page
+/// ends at the head and the last page starts at the first entry. Links
+/// between entries are validated as they are read, including across pages.
+pub struct ChainPages<'a, T: TypedContent> {
+    store: &'a ChainStore,
+    next: Option<Cid>,
+    newer_sequence: Option<u64>,
+    page_size: usize,
+    _entries: std::marker::PhantomData<T>,
+}
+
+impl<T: TypedContent> ChainPages<'_, T> {
+    /// Read the next, older page; `None` once the first entry was returned
+    pub async fn next_page(&mut self) -> Result<Option<Vec<ChainedContent<T>>>> {
+        let mut page = Vec::new();
+
+        while page.len() < self.page_size {
+            let Some(cid) = self.next.take() else {
+                break;
+            };
+            // `get_entry` checks the entry hashes to the CID the newer entry links to
+            let entry: ChainedContent<T> = self.store.get_entry(&cid).await?;
+            if let Some(newer) = self.newer_sequence {
+                if entry.sequence + 1 != newer {
+                    return Err(Error::SequenceValidationError {
+                        expected: newer.saturating_sub(1),
+                        actual: entry.sequence,
+                    });
+                }
+            }
+
+            self.next = match &entry.previous_cid {
+                Some(previous) => Some(*previous),
+                None => {
+                    entry.validate_chain(None)?;
+                    None
+                }
+            };
+            self.newer_sequence = Some(entry.sequence);
+            page.push(entry);
+        }
+
+        if page.is_empty() {
+            return Ok(None);
+        }
+        page.reverse();
+        Ok(Some(page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_store::MemoryBackend;
+    use crate::util::TestEntry;
+
+
+    fn store() -> (Arc<MemoryBackend>, ChainStore) {
+        let backend = Arc::new(MemoryBackend::new());
+        let store = ChainStore::new(backend.clone(), Arc::new(NamedRefs::in_memory()));
+        (backend, store)
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let (_, store) = store();
+        let mut chain = ContentChain::new();
+        for n in 0..5 {
+            chain.append(TestEntry { n }).unwrap();
+        }
+
+        let head = store.save("chains.orders", &chain).await.unwrap();
+        assert_eq!(head.cid(), chain.head().unwrap().cid);
+
+        let loaded: ContentChain<TestEntry> = store.load("chains.orders").await.unwrap();
+        assert_eq!(loaded.len(), 5);
+        assert_eq!(loaded.items()[3].content, TestEntry { n: 3 });
+
+        let appended = store.append("chains.orders", TestEntry { n: 5 }).await.unwrap();
+        assert_eq!(appended.sequence, 5);
+        assert_eq!(store.head::<TestEntry>("chains.orders").await.unwrap().unwrap().cid, appended.cid);
+
+        // Saving the stale in-memory chain would move the head backwards
+        let mut fork = loaded.clone();
+        fork.append(TestEntry { n: 99 }).unwrap();
+        assert!(store.save("chains.orders", &fork).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pages_and_tamper_detection() {
+        let (backend, store) = store();
+        for n in 0..10 {
+            store.append("log", TestEntry { n }).await.unwrap();
+        }
+        let head = store.refs().resolve("log").await.unwrap();
+
+        let mut pages = store.pages::<TestEntry>(head, 4);
+        let mut sizes = Vec::new();
+        let mut first = None;
+        while let Some(page) = pages.next_page().await.unwrap() {
+            sizes.push(page.len());
+            first = Some(page[0].content.n);
+        }
+        assert_eq!(sizes, vec![4, 4, 2]);
+        assert_eq!(first, Some(0));
+
+        // Rewrite an old entry in place
+        let chain: ContentChain<TestEntry> = store.load_from(&head).await.unwrap();
+        let mut forged = chain.items()[2].clone();
+        forged.content.n = 42;
+        backend.put_block(CHAIN_BUCKET, &forged.cid, forged.to_block().unwrap()).await.unwrap();
+
+        assert!(store.load_from::<TestEntry>(&head).await.is_err());
+
+        // Loading from a later checkpoint never reads the forged entry
+        let checkpoint = ContentChain::from_items(chain.items()[..=5].to_vec()).unwrap().checkpoint().unwrap();
+        let tail: ContentChain<TestEntry> = store.load_since(&head, &checkpoint).await.unwrap();
+        assert_eq!(tail.len(), 5);
+        assert_eq!(tail.root(), chain.root());
+    }
+
+    #[tokio::test]
+    async fn test_indexed_lookups() {
+        let (_, store) = store();
+        // Stored timestamps have millisecond precision
+        let start = UNIX_EPOCH + Duration::from_millis(1_750_000_000_000);
+        let mut chain = ContentChain::new();
+        for n in 0..37 {
+            let mut entry = ChainedContent::new(TestEntry { n }, chain.head()).unwrap();
+            entry.timestamp = start + Duration::from_secs(u64::from(n));
+            chain.push(entry).unwrap();
+        }
+        store.save("indexed", &chain).await.unwrap();
+        let head = store.refs().resolve("indexed").await.unwrap();
+
+        for sequence in [0, 1, 16, 23, 36] {
+            let entry: ChainedContent<TestEntry> = store.get_by_sequence(&head, sequence).await.unwrap();
+            assert_eq!(entry.cid, chain.items()[sequence as usize].cid);
+        }
+        assert!(store.get_by_sequence::<TestEntry>(&head, 37).await.is_err());
+
+        let range: Vec<u32> = store.range::<TestEntry>(&head, 10..14).await.unwrap()
+            .iter().map(|e| e.content.n).collect();
+        assert_eq!(range, vec![10, 11, 12, 13]);
+        assert_eq!(store.range::<TestEntry>(&head, 30..).await.unwrap().len(), 7);
+
+        let found = store.between::<TestEntry>(&head, start + Duration::from_secs(5), start + Duration::from_secs(9))
+            .await.unwrap();
+        assert_eq!(found.iter().map(|e| e.content.n).collect::<Vec<_>>(), vec![5, 6, 7, 8]);
+
+        // Timestamps survive a round trip, so in-memory lookups agree
+        let loaded: ContentChain<TestEntry> = store.load("indexed").await.unwrap();
+        assert_eq!(loaded.between(start + Duration::from_secs(5), start + Duration::from_secs(9)).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_index_is_checked_against_entries() {
+        let (backend, store) = store();
+        let mut chain = ContentChain::new();
+        for n in 0..20 {
+            chain.append(TestEntry { n }).unwrap();
+        }
+        store.save("checked", &chain).await.unwrap();
+        let head = chain.head().unwrap().cid;
+        let cid_of = |sequence: usize| chain.items()[sequence].cid;
+        let original = backend.get_block(CHAIN_INDEX_BUCKET, &head).await.unwrap();
+
+        // The jump 16 back from the head leads to the wrong entry
+        let mut index: EntryIndex = serde_json::from_slice(&original).unwrap();
+        index.jumps[4] = cid_of(4).to_string();
+        backend.put_block(CHAIN_INDEX_BUCKET, &head, serde_json::to_vec(&index).unwrap()).await.unwrap();
+        assert!(matches!(
+            store.get_by_sequence::<TestEntry>(&head, 3).await,
+            Err(Error::ChainValidationError { .. })
+        ));
+
+        // The index claims another sequence number than its entry
+        let mut index: EntryIndex = serde_json::from_slice(&original).unwrap();
+        index.sequence = 18;
+        backend.put_block(CHAIN_INDEX_BUCKET, &head, serde_json::to_vec(&index).unwrap()).await.unwrap();
+        assert!(store.get_by_sequence::<TestEntry>(&head, 3).await.is_err());
+
+        // Without an index, lookups follow links but timestamps are unknown
+        backend.delete_block(CHAIN_INDEX_BUCKET, &head).await.unwrap();
+        assert_eq!(store.get_by_sequence::<TestEntry>(&head, 3).await.unwrap().cid, cid_of(3));
+        assert!(matches!(
+            store.between::<TestEntry>(&head, UNIX_EPOCH, SystemTime::now()).await,
+            Err(Error::ObjectStore(ObjectStoreError::NotFound(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_append_if_head_and_subscribe() {
+        let (backend, store) = store();
+        // A second writer sharing the same storage and refs
+        let other = ChainStore::new(backend, store.refs().clone());
+
+        let mut updates = store.subscribe::<TestEntry>("shared").await.unwrap();
+        let first = store.append_if_head("shared", None, TestEntry { n: 0 }).await.unwrap();
+        other.append_if_head("shared", Some(first.cid), TestEntry { n: 1 }).await.unwrap();
+
+        // The first writer's view of the head is stale now
+        let err = store.append_if_head("shared", Some(first.cid), TestEntry { n: 2 }).await.unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::Conflict);
+        let head = store.refs().resolve("shared").await.unwrap();
+        store.append_if_head("shared", Some(head), TestEntry { n: 2 }).await.unwrap();
+
+        // Saving several entries at once delivers each of them
+        let mut chain: ContentChain<TestEntry> = store.load("shared").await.unwrap();
+        chain.append(TestEntry { n: 3 }).unwrap();
+        chain.append(TestEntry { n: 4 }).unwrap();
+        other.save("shared", &chain).await.unwrap();
+
+        let mut seen = Vec::new();
+        while seen.len() < 5 {
+            let entry = tokio::time::timeout(std::time::Duration::from_secs(5), updates.next())
+                .await.unwrap().unwrap().unwrap();
+            seen.push((entry.sequence, entry.content.n));
+        }
+        assert_eq!(seen, vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+    }
+}
@@ -0,0 +1,799 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Chain replication between nodes
+//!
+//! Two nodes holding the same named chain in their [`ChainStore`]s sync with
+//! a small request/response protocol:
+//!
+//! 1. the puller asks for the remote head ([`SyncRequest::Head`])
+//! 2. it compares CIDs at a few sequence numbers ([`SyncRequest::Locate`]),
+//!    probing exponentially further back, then bisecting, to find the last
+//!    common entry
+//! 3. if its own head is that entry, it fetches the missing entries in
+//!    batches ([`SyncRequest::Entries`]), checks every CID, link, sequence
+//!    number, and signature, and moves its head with a compare-and-swap
+//!
+//! If the chains forked, nothing is written and [`SyncOutcome::Diverged`]
+//! names the common ancestor. Entries travel as their DAG-CBOR blocks, so
+//! nodes need not know the content type.
+//!
+//! A server only answers for entries of its published chains: the head named
+//! in a request must be the chain's current head or one of its ancestors.
+//!
+//! The protocol runs over any [`SyncTransport`]: [`LocalTransport`] calls a
+//! [`ChainSyncServer`] in-process, [`NatsTransport`] and [`serve_nats`] use
+//! NATS request/reply.
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cid::Cid;
+use async_nats::RequestErrorKind;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::{ChainStore, ObjectStoreError};
+use crate::chain::{ChainedContent, EntrySignature, CHAIN_ENTRY_CODEC};
+use crate::{ContentType, Error, ErrorCode, Result, TypedContent};
+use crate::util::cid_serde;
+
+/// Entries per [`SyncRequest::Entries`] batch when not specified
+pub const DEFAULT_SYNC_BATCH: usize = 256;
+
+/// Largest batch a [`ChainSyncServer`] returns
+pub const MAX_SYNC_BATCH: usize = 4096;
+
+/// Most sequence numbers a [`ChainSyncServer`] locates per request
+pub const MAX_LOCATE_SEQUENCES: usize = 128;
+
+/// Entry content of any type, so entries can be moved without decoding it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+struct OpaqueContent(serde_cbor::Value);
+
+impl TypedContent for OpaqueContent {
+    const CODEC: u64 = CHAIN_ENTRY_CODEC;
+    const CONTENT_TYPE: ContentType = ContentType::Custom(CHAIN_ENTRY_CODEC);
+}
+
+/// Head of a chain on one node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    /// CID of the latest entry
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Its sequence number
+    pub sequence: u64,
+}
+
+/// An entry's CID at a sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocatedEntry {
+    /// Sequence number
+    pub sequence: u64,
+    /// CID of the entry there
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+}
+
+/// A chain entry on the wire
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireEntry {
+    /// Base64 DAG-CBOR block
+    pub block: String,
+    /// Signature kept next to the block, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<EntrySignature>,
+    /// Indexed timestamp in milliseconds since the Unix epoch, if known
+    ///
+    /// Blocks do not carry the time an entry was appended, so it travels
+    /// alongside them for [`ChainStore::between`] to work on replicas.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+}
+
+/// Request from a pulling node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncRequest {
+    /// Current head of a chain
+    Head { chain: String },
+    /// CIDs at the given sequence numbers of the chain ending at `head`,
+    /// which must be part of `chain`
+    Locate {
+        chain: String,
+        #[serde(with = "cid_serde")]
+        head: Cid,
+        sequences: Vec<u64>,
+    },
+    /// Up to `limit` entries from sequence `from` of the chain ending at
+    /// `head`, which must be part of `chain`
+    Entries {
+        chain: String,
+        #[serde(with = "cid_serde")]
+        head: Cid,
+        from: u64,
+        limit: usize,
+    },
+}
+
+/// Answer from a serving node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncResponse {
+    /// Head of the requested chain, `None` if the node does not have it
+    Head { head: Option<ChainHead> },
+    /// CIDs at the requested sequence numbers that exist
+    Located { entries: Vec<LocatedEntry> },
+    /// Consecutive entries, oldest first
+    Entries { entries: Vec<WireEntry> },
+    /// The request failed, classified by `code`
+    Error { code: ErrorCode, message: String },
+}
+
+/// Carries sync requests to a remote node
+#[async_trait]
+pub trait SyncTransport: Send + Sync {
+    /// Send a request and wait for its response
+    async fn request(&self, request: SyncRequest) -> Result<SyncResponse>;
+}
+
+/// Serves sync requests from a [`ChainStore`]
+pub struct ChainSyncServer {
+    store: ChainStore,
+}
+
+impl ChainSyncServer {
+    /// Serve the chains of `store`
+    pub fn new(store: ChainStore) -> Self {
+        Self { store }
+    }
+
+    /// Answer one request; failures become [`SyncResponse::Error`]
+    pub async fn handle(&self, request: SyncRequest) -> SyncResponse {
+        match self.respond(request).await {
+            Ok(response) => response,
+            Err(e) => SyncResponse::Error {
+                code: e.code(),
+                message: e.to_string(),
+            },
+        }
+    }
+
+    async fn respond(&self, request: SyncRequest) -> Result<SyncResponse> {
+        match request {
+            SyncRequest::Head { chain } => {
+                let head = match self.store.head::<OpaqueContent>(&chain).await? {
+                    Some(entry) => Some(ChainHead {
+                        cid: entry.cid,
+                        sequence: entry.sequence,
+                    }),
+                    None => None,
+                };
+                Ok(SyncResponse::Head { head })
+            }
+            SyncRequest::Locate { chain, head, sequences } => {
+                if sequences.len() > MAX_LOCATE_SEQUENCES {
+                    return Err(Error::InvalidContent(format!(
+                        "{} sequence numbers requested, at most {MAX_LOCATE_SEQUENCES} are located",
+                        sequences.len()
+                    )));
+                }
+                let last = self.published_sequence(&chain, &head).await?;
+                let mut entries = Vec::new();
+                for sequence in sequences.into_iter().filter(|s| *s <= last) {
+                    let entry = self.store.get_by_sequence::<OpaqueContent>(&head, sequence).await?;
+                    entries.push(LocatedEntry { sequence, cid: entry.cid });
+                }
+                Ok(SyncResponse::Located { entries })
+            }
+            SyncRequest::Entries { chain, head, from, limit } => {
+                self.published_sequence(&chain, &head).await?;
+                let limit = limit.clamp(1, MAX_SYNC_BATCH) as u64;
+                let entries = self.store.range::<OpaqueContent>(&head, from..from.saturating_add(limit)).await?
+                    .iter()
+                    .map(|entry| {
+                        Ok(WireEntry {
+                            block: BASE64.encode(entry.to_block()?),
+                            signature: entry.signature.clone(),
+                            timestamp: entry.timestamp.duration_since(UNIX_EPOCH).ok()
+                                .filter(|since| !since.is_zero())
+                                .map(|since| since.as_millis() as u64),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(SyncResponse::Entries { entries })
+            }
+        }
+    }
+
+    /// Sequence number of `head`, if it is the published head of `chain` or
+    /// one of its ancestors
+    ///
+    /// Keeps requests from reading entries that were never published, such
+    /// as those of chains the server is still fetching.
+    async fn published_sequence(&self, chain: &str, head: &Cid) -> Result<u64> {
+        let unpublished = || ObjectStoreError::NotFound(format!("{head} is not part of chain {chain}"));
+        let published = self.store.head::<OpaqueContent>(chain).await?.ok_or_else(unpublished)?;
+        if published.cid == *head {
+            return Ok(published.sequence);
+        }
+        let sequence = self.store.get_entry::<OpaqueContent>(head).await
+            .map_err(|_| unpublished())?
+            .sequence;
+        if sequence > published.sequence
+            || self.store.get_by_sequence::<OpaqueContent>(&published.cid, sequence).await?.cid != *head
+        {
+            return Err(unpublished().into());
+        }
+        Ok(sequence)
+    }
+}
+
+/// Transport calling a [`ChainSyncServer`] in the same process
+pub struct LocalTransport {
+    server: Arc<ChainSyncServer>,
+}
+
+impl LocalTransport {
+    /// Send requests straight to `server`
+    pub fn new(server: Arc<ChainSyncServer>) -> Self {
+        Self { server }
+    }
+}
+
+#[async_trait]
+impl SyncTransport for LocalTransport {
+    async fn request(&self, request: SyncRequest) -> Result<SyncResponse> {
+        Ok(self.server.handle(request).await)
+    }
+}
+
+/// Transport sending JSON requests over NATS request/reply
+pub struct NatsTransport {
+    client: async_nats::Client,
+    subject: String,
+}
+
+impl NatsTransport {
+    /// Send requests to the node serving `subject`
+    pub fn new(client: async_nats::Client, subject: impl Into<String>) -> Self {
+        Self {
+            client,
+            subject: subject.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl SyncTransport for NatsTransport {
+    async fn request(&self, request: SyncRequest) -> Result<SyncResponse> {
+        let payload = serde_json::to_vec(&request)?;
+        let reply = self.client.request(self.subject.clone(), payload.into()).await
+            .map_err(|e| request_error(&self.subject, e))?;
+        Ok(serde_json::from_slice(&reply.payload)?)
+    }
+}
+
+/// Classify a failed request: no reply in time, or no node serving `subject`
+fn request_error(subject: &str, error: async_nats::RequestError) -> ObjectStoreError {
+    match error.kind() {
+        RequestErrorKind::TimedOut => ObjectStoreError::timeout(format!("sync request on {subject}"), error),
+        RequestErrorKind::NoResponders | RequestErrorKind::Other => {
+            ObjectStoreError::unreachable(format!("sync request on {subject}"), error)
+        }
+    }
+}
+
+/// Answer sync requests arriving on `subject` until the subscription ends
+pub async fn serve_nats(client: async_nats::Client, subject: impl Into<String>, server: Arc<ChainSyncServer>) -> Result<()> {
+    let subject = subject.into();
+    let mut requests = client.subscribe(subject.clone()).await
+        .map_err(|e| ObjectStoreError::unreachable(format!("subscribing to {subject}"), e))?;
+    debug!("Serving chain sync on {}", subject);
+
+    while let Some(message) = requests.next().await {
+        let Some(reply) = message.reply else {
+            continue;
+        };
+        let response = match serde_json::from_slice::<SyncRequest>(&message.payload) {
+            Ok(request) => server.handle(request).await,
+            Err(e) => SyncResponse::Error {
+                code: ErrorCode::InvalidContent,
+                message: format!("invalid sync request: {e}"),
+            },
+        };
+        if let Err(e) = client.publish(reply, serde_json::to_vec(&response)?.into()).await {
+            warn!("Failed to answer sync request on {}: {}", subject, e);
+        }
+    }
+    Ok(())
+}
+
+/// Result of [`ChainSync::pull`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// Both nodes have the same head, or neither has the chain
+    UpToDate,
+    /// The local chain already contains the remote one
+    Ahead { local: Cid, remote: Option<Cid> },
+    /// Missing entries were fetched and the local head moved to the remote's
+    FastForwarded { from: Option<Cid>, to: Cid, entries: usize },
+    /// The chains forked after `common_ancestor`; nothing was changed
+    Diverged {
+        common_ancestor: Option<LocatedEntry>,
+        local: Cid,
+        remote: Cid,
+    },
+}
+
+/// Pulls chains from remote nodes into a [`ChainStore`]
+pub struct ChainSync {
+    store: ChainStore,
+    batch_size: usize,
+}
+
+impl ChainSync {
+    /// Sync into `store`
+    pub fn new(store: ChainStore) -> Self {
+        Self {
+            store,
+            batch_size: DEFAULT_SYNC_BATCH,
+        }
+    }
+
+    /// Fetch up to `batch_size` entries per request
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_SYNC_BATCH);
+        self
+    }
+
+    /// Bring the local chain `name` up to date with the remote node
+    pub async fn pull(&self, name: &str, remote: &dyn SyncTransport) -> Result<SyncOutcome> {
+        let remote_head = match remote.request(SyncRequest::Head { chain: name.to_string() }).await? {
+            SyncResponse::Head { head } => head,
+            other => return Err(unexpected(other)),
+        };
+        let local_head = match self.store.head::<OpaqueContent>(name).await? {
+            Some(entry) => Some(ChainHead {
+                cid: entry.cid,
+                sequence: entry.sequence,
+            }),
+            None => None,
+        };
+
+        let (local, remote_head) = match (local_head, remote_head) {
+            (None, None) => return Ok(SyncOutcome::UpToDate),
+            (Some(local), None) => {
+                return Ok(SyncOutcome::Ahead {
+                    local: local.cid,
+                    remote: None,
+                })
+            }
+            (None, Some(remote_head)) => return self.fast_forward(name, None, remote_head, remote).await,
+            (Some(local), Some(remote_head)) => (local, remote_head),
+        };
+        if local.cid == remote_head.cid {
+            return Ok(SyncOutcome::UpToDate);
+        }
+
+        let common_ancestor = self.common_ancestor(name, local, remote_head, remote).await?;
+        match common_ancestor {
+            Some(ancestor) if ancestor.cid == local.cid => {
+                self.fast_forward(name, Some(local), remote_head, remote).await
+            }
+            Some(ancestor) if ancestor.cid == remote_head.cid => Ok(SyncOutcome::Ahead {
+                local: local.cid,
+                remote: Some(remote_head.cid),
+            }),
+            common_ancestor => {
+                debug!("Chain {} diverged after {:?}", name, common_ancestor);
+                Ok(SyncOutcome::Diverged {
+                    common_ancestor,
+                    local: local.cid,
+                    remote: remote_head.cid,
+                })
+            }
+        }
+    }
+
+    /// Last entry both chains share, if any
+    async fn common_ancestor(
+        &self,
+        name: &str,
+        local: ChainHead,
+        remote_head: ChainHead,
+        remote: &dyn SyncTransport,
+    ) -> Result<Option<LocatedEntry>> {
+        // Probe 0, 1, 2, 4, ... entries below the shorter head
+        let top = local.sequence.min(remote_head.sequence);
+        let mut probes = vec![top];
+        let mut step = 1;
+        while step <= top {
+            probes.push(top - step);
+            step *= 2;
+        }
+        if probes.last() != Some(&0) {
+            probes.push(0);
+        }
+
+        let (mut matched, mut mismatched) = (None, None);
+        for located in self.locate(name, remote_head.cid, probes, remote).await? {
+            if self.local_cid(local.cid, located.sequence).await? == located.cid {
+                matched = Some(located);
+                break;
+            }
+            mismatched = Some(located.sequence);
+        }
+
+        // Bisect between the highest match and the lowest mismatch above it
+        while let Some(high) = mismatched {
+            let low = matched.map_or(0, |m| m.sequence + 1);
+            if low >= high {
+                break;
+            }
+            let middle = low + (high - low) / 2;
+            let located = self.locate(name, remote_head.cid, vec![middle], remote).await?;
+            let located = located.first().copied().ok_or_else(|| missing(middle))?;
+            if self.local_cid(local.cid, middle).await? == located.cid {
+                matched = Some(located);
+            } else {
+                mismatched = Some(middle);
+            }
+        }
+        Ok(matched)
+    }
+
+    async fn locate(&self, name: &str, head: Cid, sequences: Vec<u64>, remote: &dyn SyncTransport) -> Result<Vec<LocatedEntry>> {
+        let request = SyncRequest::Locate {
+            chain: name.to_string(),
+            head,
+            sequences,
+        };
+        match remote.request(request).await? {
+            SyncResponse::Located { entries } => Ok(entries),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn local_cid(&self, head: Cid, sequence: u64) -> Result<Cid> {
+        Ok(self.store.get_by_sequence::<OpaqueContent>(&head, sequence).await?.cid)
+    }
+
+    /// Fetch and verify the entries after `local`, then move the head to `remote_head`
+    async fn fast_forward(
+        &self,
+        name: &str,
+        local: Option<ChainHead>,
+        remote_head: ChainHead,
+        remote: &dyn SyncTransport,
+    ) -> Result<SyncOutcome> {
+        let mut previous = local.map(|head| head.cid);
+        let mut next = local.map_or(0, |head| head.sequence + 1);
+        let mut fetched = 0;
+
+        while next <= remote_head.sequence {
+            let request = SyncRequest::Entries {
+                chain: name.to_string(),
+                head: remote_head.cid,
+                from: next,
+                limit: self.batch_size,
+            };
+            let entries = match remote.request(request).await? {
+                SyncResponse::Entries { entries } if !entries.is_empty() => entries,
+                SyncResponse::Entries { .. } => return Err(missing(next)),
+                other => return Err(unexpected(other)),
+            };
+
+            for wire in entries {
+                let entry = decode_entry(wire)?;
+                if entry.sequence != next {
+                    return Err(Error::SequenceValidationError {
+                        expected: next,
+                        actual: entry.sequence,
+                    });
+                }
+                if entry.previous_cid != previous {
+                    return Err(Error::ChainValidationError {
+                        expected: previous.map(|cid| cid.to_string()).unwrap_or_default(),
+                        actual: entry.previous_cid.map(|cid| cid.to_string()).unwrap_or_default(),
+                    });
+                }
+                self.store.put_entry(&entry).await?;
+                previous = Some(entry.cid);
+                next += 1;
+                fetched += 1;
+                if next > remote_head.sequence {
+                    break;
+                }
+            }
+        }
+
+        if previous != Some(remote_head.cid) {
+            return Err(Error::ChainValidationError {
+                expected: remote_head.cid.to_string(),
+                actual: previous.map(|cid| cid.to_string()).unwrap_or_default(),
+            });
+        }
+        self.store.refs().compare_and_swap(name, local.map(|head| head.cid), remote_head.cid).await?;
+        debug!("Fast-forwarded chain {} by {} entries to {}", name, fetched, remote_head.cid);
+        Ok(SyncOutcome::FastForwarded {
+            from: local.map(|head| head.cid),
+            to: remote_head.cid,
+            entries: fetched,
+        })
+    }
+}
+
+/// Decode a wire entry, computing its CID from the block and checking its signature
+fn decode_entry(wire: WireEntry) -> Result<ChainedContent<OpaqueContent>> {
+    let block = BASE64.decode(&wire.block)
+        .map_err(|e| Error::InvalidContent(format!("invalid entry encoding: {e}")))?;
+    let mut entry = ChainedContent::<OpaqueContent>::from_block(&block)?;
+    entry.signature = wire.signature;
+    if let Some(millis) = wire.timestamp {
+        entry.timestamp = UNIX_EPOCH + Duration::from_millis(millis);
+    }
+    if entry.signature.is_some() {
+        entry.verify_signature()?;
+    }
+    Ok(entry)
+}
+
+fn unexpected(response: SyncResponse) -> Error {
+    match response {
+        SyncResponse::Error { code, message } => ObjectStoreError::Remote { code, message }.into(),
+        other => Error::InvalidContent(format!("unexpected sync response: {other:?}")),
+    }
+}
+
+fn missing(sequence: u64) -> Error {
+    ObjectStoreError::NotFound(format!("remote chain has no entry {sequence}")).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::ContentChain;
+    use crate::object_store::{MemoryBackend, NamedRefs};
+    use crate::util::TestEntry;
+
+
+    fn node() -> ChainStore {
+        ChainStore::new(Arc::new(MemoryBackend::new()), Arc::new(NamedRefs::in_memory()))
+    }
+
+    fn serve(store: &ChainStore) -> LocalTransport {
+        LocalTransport::new(Arc::new(ChainSyncServer::new(store.clone())))
+    }
+
+    #[tokio::test]
+    async fn test_pull_fast_forwards_in_batches() {
+        let (a, b) = (node(), node());
+        for n in 0..10 {
+            a.append("orders", TestEntry { n }).await.unwrap();
+        }
+        let sync = ChainSync::new(b.clone()).with_batch_size(3);
+
+        let outcome = sync.pull("orders", &serve(&a)).await.unwrap();
+        let head = a.refs().resolve("orders").await.unwrap();
+        assert_eq!(outcome, SyncOutcome::FastForwarded { from: None, to: head, entries: 10 });
+
+        for n in 10..25 {
+            a.append("orders", TestEntry { n }).await.unwrap();
+        }
+        let outcome = sync.pull("orders", &serve(&a)).await.unwrap();
+        assert!(matches!(outcome, SyncOutcome::FastForwarded { entries: 15, .. }));
+
+        let synced: ContentChain<TestEntry> = b.load("orders").await.unwrap();
+        assert_eq!(synced.len(), 25);
+        assert_eq!(synced.root(), a.load::<TestEntry>("orders").await.unwrap().root());
+        assert_eq!(sync.pull("orders", &serve(&a)).await.unwrap(), SyncOutcome::UpToDate);
+
+        // The other direction finds nothing to fetch
+        b.append("orders", TestEntry { n: 25 }).await.unwrap();
+        let outcome = sync.pull("orders", &serve(&a)).await.unwrap();
+        assert!(matches!(outcome, SyncOutcome::Ahead { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_pull_detects_divergence() {
+        let (a, b) = (node(), node());
+        for n in 0..12 {
+            a.append("orders", TestEntry { n }).await.unwrap();
+        }
+        let sync = ChainSync::new(b.clone());
+        sync.pull("orders", &serve(&a)).await.unwrap();
+        let fork_point = b.refs().resolve("orders").await.unwrap();
+
+        a.append("orders", TestEntry { n: 100 }).await.unwrap();
+        for n in 200..205 {
+            b.append("orders", TestEntry { n }).await.unwrap();
+        }
+        let local = b.refs().resolve("orders").await.unwrap();
+
+        match sync.pull("orders", &serve(&a)).await.unwrap() {
+            SyncOutcome::Diverged { common_ancestor, local: head, .. } => {
+                assert_eq!(common_ancestor, Some(LocatedEntry { sequence: 11, cid: fork_point }));
+                assert_eq!(head, local);
+            }
+            other => panic!("expected divergence, got {other:?}"),
+        }
+        // Nothing was written
+        assert_eq!(b.refs().resolve("orders").await.unwrap(), local);
+
+        // Messages survive the wire format
+        let request = SyncRequest::Entries { chain: "orders".into(), head: local, from: 3, limit: 10 };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(serde_json::from_str::<SyncRequest>(&json).unwrap(), request);
+    }
+
+    /// Transport that rewrites the responses of a server
+    struct Tampering<F> {
+        inner: LocalTransport,
+        tamper: F,
+    }
+
+    #[async_trait]
+    impl<F: Fn(SyncResponse) -> SyncResponse + Send + Sync> SyncTransport for Tampering<F> {
+        async fn request(&self, request: SyncRequest) -> Result<SyncResponse> {
+            Ok((self.tamper)(self.inner.request(request).await?))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_only_answers_for_published_chains() {
+        let a = node();
+        let first = a.append("orders", TestEntry { n: 0 }).await.unwrap();
+        let second = a.append("orders", TestEntry { n: 1 }).await.unwrap();
+        // Stored but never published
+        let orphan = ChainedContent::new(TestEntry { n: 99 }, Some(&second)).unwrap();
+        a.put_entry(&orphan).await.unwrap();
+        let server = ChainSyncServer::new(a.clone());
+
+        let locate = |head, sequences| SyncRequest::Locate { chain: "orders".into(), head, sequences };
+        assert!(matches!(server.handle(locate(first.cid, vec![0])).await, SyncResponse::Located { .. }));
+        assert!(matches!(
+            server.handle(locate(orphan.cid, vec![0])).await,
+            SyncResponse::Error { code: ErrorCode::NotFound, .. }
+        ));
+        let entries = SyncRequest::Entries { chain: "invoices".into(), head: second.cid, from: 0, limit: 10 };
+        assert!(matches!(server.handle(entries).await, SyncResponse::Error { code: ErrorCode::NotFound, .. }));
+
+        // Locate requests are capped like entry batches
+        let many = locate(second.cid, vec![0; MAX_LOCATE_SEQUENCES + 1]);
+        let response = server.handle(many).await;
+        assert!(matches!(response, SyncResponse::Error { code: ErrorCode::InvalidContent, .. }));
+
+        // The code survives the wire format
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""code":"invalid_content""#));
+        assert_eq!(serde_json::from_str::<SyncResponse>(&json).unwrap(), response);
+    }
+
+    #[tokio::test]
+    async fn test_pull_single_entry_chains() {
+        let (a, b, c) = (node(), node(), node());
+        let genesis = a.append("orders", TestEntry { n: 0 }).await.unwrap();
+        let sync = ChainSync::new(b.clone());
+
+        let outcome = sync.pull("orders", &serve(&a)).await.unwrap();
+        assert_eq!(outcome, SyncOutcome::FastForwarded { from: None, to: genesis.cid, entries: 1 });
+        assert_eq!(sync.pull("orders", &serve(&a)).await.unwrap(), SyncOutcome::UpToDate);
+
+        // A different first entry shares nothing
+        c.append("orders", TestEntry { n: 1 }).await.unwrap();
+        match ChainSync::new(c).pull("orders", &serve(&a)).await.unwrap() {
+            SyncOutcome::Diverged { common_ancestor, remote, .. } => {
+                assert_eq!(common_ancestor, None);
+                assert_eq!(remote, genesis.cid);
+            }
+            other => panic!("expected divergence, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pull_rejects_remote_errors_and_malformed_blocks() {
+        let (a, b) = (node(), node());
+        for n in 0..4 {
+            a.append("orders", TestEntry { n }).await.unwrap();
+        }
+        let sync = ChainSync::new(b.clone());
+
+        let failing = Tampering {
+            inner: serve(&a),
+            tamper: |response| match response {
+                SyncResponse::Entries { .. } => SyncResponse::Error {
+                    code: ErrorCode::Unavailable,
+                    message: "disk on fire".into(),
+                },
+                other => other,
+            },
+        };
+        let err = sync.pull("orders", &failing).await.unwrap_err();
+        assert!(err.to_string().contains("disk on fire"));
+        assert_eq!(err.code(), ErrorCode::Unavailable);
+        assert!(err.is_retryable());
+
+        let missing = Tampering {
+            inner: serve(&a),
+            tamper: |response| match response {
+                SyncResponse::Entries { .. } => SyncResponse::Error {
+                    code: ErrorCode::NotFound,
+                    message: "gone".into(),
+                },
+                other => other,
+            },
+        };
+        let err = sync.pull("orders", &missing).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::NotFound);
+        assert!(!err.is_retryable());
+
+        let garbled = Tampering {
+            inner: serve(&a),
+            tamper: |response| match response {
+                SyncResponse::Entries { mut entries } => {
+                    let mut block = BASE64.decode(&entries[1].block).unwrap();
+                    *block.last_mut().unwrap() ^= 1;
+                    entries[1].block = BASE64.encode(block);
+                    SyncResponse::Entries { entries }
+                }
+                other => other,
+            },
+        };
+        assert!(sync.pull("orders", &garbled).await.is_err());
+
+        let undecodable = Tampering {
+            inner: serve(&a),
+            tamper: |response| match response {
+                SyncResponse::Entries { mut entries } => {
+                    entries[0].block = "not base64!".into();
+                    SyncResponse::Entries { entries }
+                }
+                other => other,
+            },
+        };
+        assert!(sync.pull("orders", &undecodable).await.is_err());
+
+        // Nothing was published locally
+        assert!(b.refs().get("orders").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pull_keeps_entry_timestamps() {
+        let (a, b) = (node(), node());
+        // Stored timestamps have millisecond precision
+        let start = UNIX_EPOCH + Duration::from_millis(1_750_000_000_000);
+        let mut chain = ContentChain::new();
+        for n in 0..8 {
+            let mut entry = ChainedContent::new(TestEntry { n }, chain.head()).unwrap();
+            entry.timestamp = start + Duration::from_secs(u64::from(n));
+            chain.push(entry).unwrap();
+        }
+        a.save("orders", &chain).await.unwrap();
+
+        ChainSync::new(b.clone()).with_batch_size(3).pull("orders", &serve(&a)).await.unwrap();
+        let head = b.refs().resolve("orders").await.unwrap();
+        let found = b.between::<TestEntry>(&head, start + Duration::from_secs(2), start + Duration::from_secs(5))
+            .await.unwrap();
+        assert_eq!(found.iter().map(|e| e.content.n).collect::<Vec<_>>(), vec![2, 3, 4]);
+        let entry: ChainedContent<TestEntry> = b.get_entry(&head).await.unwrap();
+        assert_eq!(entry.timestamp, start + Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_transport_errors_are_classified() {
+        let timed_out = request_error("sync.orders", RequestErrorKind::TimedOut.into());
+        assert!(matches!(timed_out, ObjectStoreError::Timeout { .. }));
+        assert!(timed_out.is_retryable());
+
+        let no_responders = request_error("sync.orders", RequestErrorKind::NoResponders.into());
+        assert!(matches!(no_responders, ObjectStoreError::Unreachable { .. }));
+        assert!(no_responders.is_retryable());
+        assert!(std::error::Error::source(&no_responders)
+            .is_some_and(|e| e.is::<async_nats::RequestError>()));
+    }
+}
@@ -2,15 +2,40 @@
 
 //! Content storage service with deduplication and caching
 
-use super::{NatsObjectStore, ObjectStoreError, Result, ContentBucket, ObjectInfo};
+use super::{NatsObjectStore, ObjectStoreError, Result, ContentBucket, ObjectInfo, Retention, RetentionGuard, StorageBackend};
+use super::batch::{self, Batch, BatchCommit, BatchManifest};
+use super::disk_cache::{DiskCache, TierStats};
+use super::expiry::{Expiry, ExpiryRecord};
+use super::tombstone::{PurgeRecord, Tombstone, Tombstones, DEFAULT_PURGE_WINDOW};
 use cid::Cid;
+use crate::metrics::{self, Counter, Gauge};
 use crate::TypedContent;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::stream::{self, StreamExt};
 use lru::LruCache;
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Default time a confirmed miss is remembered
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(30);
+
+/// Default number of batch items processed concurrently
+const DEFAULT_BATCH_CONCURRENCY: usize = 16;
+
+/// How stores interact with the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WritePolicy {
+    /// Cache content as it is stored
+    #[default]
+    WriteThrough,
+    /// Only cache content once it is read back
+    WriteAround,
+}
 
 /// Cache entry with metadata
 #[derive(Clone)]
@@ -21,13 +46,33 @@ struct CacheEntry {
     size: usize,
 }
 
+/// Outcome of a backend read shared by all coalesced callers
+type FetchResult = std::result::Result<Arc<Vec<u8>>, Arc<ObjectStoreError>>;
+type SharedFetch = Shared<BoxFuture<'static, FetchResult>>;
+type InflightMap = HashMap<(String, Cid), SharedFetch>;
+type NegativeCache = LruCache<(String, Cid), Instant>;
+
 /// Content storage service with caching
 pub struct ContentStorageService {
-    object_store: Arc<NatsObjectStore>,
+    backend: Arc<dyn StorageBackend>,
     cache: Arc<RwLock<LruCache<Cid, CacheEntry>>>,
     cache_ttl: Duration,
     max_cache_size: usize,
     current_cache_size: Arc<RwLock<usize>>,
+    negative_cache: Arc<RwLock<NegativeCache>>,
+    negative_ttl: Duration,
+    /// Bumped by every write, so a miss racing a write is not remembered
+    write_generation: AtomicU64,
+    write_policy: WritePolicy,
+    batch_concurrency: usize,
+    inflight: Arc<Mutex<InflightMap>>,
+    disk_cache: Option<Arc<DiskCache>>,
+    tombstones: Arc<Tombstones>,
+    purge_window: Duration,
+    retention: Option<Arc<Retention>>,
+    expiry: Arc<Expiry>,
+    memory_hits: AtomicU64,
+    memory_misses: AtomicU64,
 }
 
 impl ContentStorageService {
@@ -38,110 +83,510 @@ impl ContentStorageService {
         cache_ttl: Duration,
         max_cache_size: usize,
     ) -> Self {
-        let cache = LruCache::new(NonZeroUsize::new(cache_capacity).unwrap());
+        Self::with_backend(object_store, cache_capacity, cache_ttl, max_cache_size)
+    }
+
+    /// Create a content storage service over any storage backend
+    pub fn with_backend(
+        backend: Arc<dyn StorageBackend>,
+        cache_capacity: usize,
+        cache_ttl: Duration,
+        max_cache_size: usize,
+    ) -> Self {
+        let capacity = NonZeroUsize::new(cache_capacity).unwrap();
 
         Self {
-            object_store,
-            cache: Arc::new(RwLock::new(cache)),
+            tombstones: Arc::new(Tombstones::new(backend.clone())),
+            purge_window: DEFAULT_PURGE_WINDOW,
+            retention: None,
+            expiry: Arc::new(Expiry::new(backend.clone())),
+            backend,
+            cache: Arc::new(RwLock::new(LruCache::new(capacity))),
             cache_ttl,
             max_cache_size,
             current_cache_size: Arc::new(RwLock::new(0)),
+            negative_cache: Arc::new(RwLock::new(LruCache::new(capacity))),
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+            write_generation: AtomicU64::new(0),
+            write_policy: WritePolicy::default(),
+            batch_concurrency: DEFAULT_BATCH_CONCURRENCY,
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+            disk_cache: None,
+            memory_hits: AtomicU64::new(0),
+            memory_misses: AtomicU64::new(0),
         }
     }
 
+    /// Add a persistent disk cache tier below the memory cache
+    pub fn with_disk_cache(mut self, disk_cache: Arc<DiskCache>) -> Self {
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+
+    /// Set how long confirmed misses are remembered; zero disables negative caching
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Set how long soft-deleted content can be restored before it is purged
+    pub fn with_purge_window(mut self, window: Duration) -> Self {
+        self.purge_window = window;
+        self.tombstones = self.build_tombstones();
+        self
+    }
+
+    /// Refuse to delete, soft delete, or purge content under retention
+    ///
+    /// Wraps the backend in a [`RetentionGuard`], so every delete and
+    /// overwrite made through this service is checked.
+    pub fn with_retention(mut self, retention: Arc<Retention>) -> Self {
+        self.backend = Arc::new(RetentionGuard::new(self.backend.clone(), retention.clone()));
+        self.expiry = Arc::new(Expiry::new(self.backend.clone()));
+        self.retention = Some(retention);
+        self.tombstones = self.build_tombstones();
+        self
+    }
+
+    fn build_tombstones(&self) -> Arc<Tombstones> {
+        let tombstones = Tombstones::new(self.backend.clone()).with_purge_window(self.purge_window);
+        Arc::new(match &self.retention {
+            Some(retention) => tombstones.with_retention(retention.clone()),
+            None => tombstones,
+        })
+    }
+
+    /// Set whether stores populate the cache
+    pub fn with_write_policy(mut self, policy: WritePolicy) -> Self {
+        self.write_policy = policy;
+        self
+    }
+
+    /// Set how many batch items are processed concurrently
+    pub fn with_batch_concurrency(mut self, concurrency: usize) -> Self {
+        self.batch_concurrency = concurrency.max(1);
+        self
+    }
+
     /// Store content with deduplication
     pub async fn store<T: TypedContent>(&self, content: &T) -> Result<Cid> {
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+
         // Calculate CID for deduplication
         let cid = content.calculate_cid()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+            .map_err(ObjectStoreError::serialization)?;
 
-        // Check if already exists
-        if self.object_store.exists(&cid, T::CONTENT_TYPE.codec()).await? {
+        // Check if already exists; storing it again keeps it for good
+        if self.backend.has_block(bucket.as_str(), &cid).await? {
             debug!("Content already exists: {}", cid);
+            self.expiry.clear(&cid).await?;
+            self.mark_stored(bucket.as_str(), &cid).await?;
             return Ok(cid);
         }
 
-        // Store in object store
-        self.object_store.put(content).await?;
-
-        // Cache the content
+        // Store in the backend
         let data = content.to_bytes()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
-        self.cache_content(cid, data, T::CONTENT_TYPE.codec()).await;
+            .map_err(ObjectStoreError::serialization)?;
+        self.backend.put_block(bucket.as_str(), &cid, data.clone()).await?;
+        self.mark_stored(bucket.as_str(), &cid).await?;
+
+        if self.write_policy == WritePolicy::WriteThrough {
+            self.cache_on_disk(&cid, T::CONTENT_TYPE.codec(), &data).await;
+            self.cache_content(cid, data, T::CONTENT_TYPE.codec()).await;
+        }
 
         info!("Stored content: {} (type: {})", cid, T::CONTENT_TYPE.codec());
         Ok(cid)
     }
 
+    /// Store content that is deleted once `ttl` has passed
+    ///
+    /// See [`Expiry::store_ttl`] for content that is already stored.
+    pub async fn store_with_ttl<T: TypedContent>(&self, content: &T, ttl: Duration) -> Result<Cid> {
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        let cid = content.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+
+        let stored = self.backend.has_block(bucket.as_str(), &cid).await?;
+        if self.expiry.store_ttl(bucket.as_str(), &cid, ttl, stored).await?.is_none() {
+            debug!("Content already stored without a TTL: {}", cid);
+            self.mark_stored(bucket.as_str(), &cid).await?;
+            return Ok(cid);
+        }
+
+        // The TTL is recorded first so the content never exists without it
+        let data = content.to_bytes()
+            .map_err(ObjectStoreError::serialization)?;
+        self.backend.put_block(bucket.as_str(), &cid, data.clone()).await?;
+        self.mark_stored(bucket.as_str(), &cid).await?;
+        if self.write_policy == WritePolicy::WriteThrough {
+            self.cache_on_disk(&cid, T::CONTENT_TYPE.codec(), &data).await;
+            self.cache_content(cid, data, T::CONTENT_TYPE.codec()).await;
+        }
+
+        info!("Stored content: {} (type: {}, ttl: {:?})", cid, T::CONTENT_TYPE.codec(), ttl);
+        Ok(cid)
+    }
+
     /// Retrieve content with caching
+    ///
+    /// Concurrent reads of the same CID share a single backend request, and
+    /// confirmed misses are remembered for the negative cache TTL.
     pub async fn get<T: TypedContent>(&self, cid: &Cid) -> Result<T> {
         // Check cache first
         if let Some(entry) = self.get_from_cache(cid).await {
             if entry.content_type == T::CONTENT_TYPE.codec() {
                 debug!("Cache hit for: {}", cid);
+                self.memory_hits.fetch_add(1, Ordering::Relaxed);
+                record_cache_lookup(CacheTier::Memory, true);
                 return T::from_bytes(&entry.data)
-                    .map_err(|e| ObjectStoreError::Deserialization(e.to_string()));
+                    .map_err(ObjectStoreError::deserialization);
             }
         }
+        self.memory_misses.fetch_add(1, Ordering::Relaxed);
+        record_cache_lookup(CacheTier::Memory, false);
+
+        if let Some((content, data)) = self.get_from_disk::<T>(cid).await {
+            debug!("Disk cache hit for: {}", cid);
+            self.cache_content(*cid, data, T::CONTENT_TYPE.codec()).await;
+            return Ok(content);
+        }
+
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        if self.is_known_missing(bucket.as_str(), cid).await {
+            debug!("Negative cache hit for: {}", cid);
+            return Err(ObjectStoreError::NotFound(cid.to_string()));
+        }
+        let generation = self.write_generation.load(Ordering::Acquire);
+        if self.tombstones.is_deleted(bucket.as_str(), cid).await? {
+            debug!("Content is soft deleted: {}", cid);
+            self.remember_missing(bucket.as_str(), cid, generation).await;
+            return Err(ObjectStoreError::NotFound(cid.to_string()));
+        }
+
+        // Fetch from the backend
+        let data = match self.fetch_block(bucket.as_str(), cid).await {
+            Ok(data) => data,
+            Err(ObjectStoreError::NotFound(key)) => {
+                self.remember_missing(bucket.as_str(), cid, generation).await;
+                return Err(ObjectStoreError::NotFound(key));
+            }
+            Err(e) => return Err(e),
+        };
 
-        // Fetch from object store
-        let content = self.object_store.get::<T>(cid).await?;
+        let content = Self::verify::<T>(cid, &data)?;
 
         // Cache for future use
-        let data = content.to_bytes()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
-        self.cache_content(*cid, data, T::CONTENT_TYPE.codec()).await;
+        self.cache_on_disk(cid, T::CONTENT_TYPE.codec(), &data).await;
+        self.cache_content(*cid, data.to_vec(), T::CONTENT_TYPE.codec()).await;
 
         Ok(content)
     }
 
+    /// Deserialize content and check that it hashes to the expected CID
+    fn verify<T: TypedContent>(cid: &Cid, data: &[u8]) -> Result<T> {
+        let content = T::from_bytes(data)
+            .map_err(ObjectStoreError::deserialization)?;
+        let computed_cid = content.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+        if computed_cid != *cid {
+            return Err(ObjectStoreError::CidMismatch {
+                expected: cid.to_string(),
+                actual: computed_cid.to_string(),
+            });
+        }
+        Ok(content)
+    }
+
+    /// Load and verify content from the disk cache
+    ///
+    /// Entries that do not verify are dropped so the next read refetches them.
+    async fn get_from_disk<T: TypedContent>(&self, cid: &Cid) -> Option<(T, Vec<u8>)> {
+        let disk_cache = self.disk_cache.as_ref()?;
+        let (data, content_type) = match disk_cache.get(cid).await {
+            Ok(found) => found?,
+            Err(e) => {
+                warn!("Disk cache read failed for {}: {}", cid, e);
+                return None;
+            }
+        };
+        if content_type != T::CONTENT_TYPE.codec() {
+            return None;
+        }
+
+        match Self::verify::<T>(cid, &data) {
+            Ok(content) => Some((content, data)),
+            Err(e) => {
+                warn!("Discarding disk cache entry for {}: {}", cid, e);
+                let _ = disk_cache.remove(cid).await;
+                None
+            }
+        }
+    }
+
+    /// Write content to the disk cache, if one is configured
+    async fn cache_on_disk(&self, cid: &Cid, content_type: u64, data: &[u8]) {
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Err(e) = disk_cache.put(cid, content_type, data).await {
+                warn!("Disk cache write failed for {}: {}", cid, e);
+            }
+        }
+    }
+
     /// Check if content exists
     pub async fn exists(&self, cid: &Cid, content_type: u64) -> Result<bool> {
         // Check cache first
         if self.get_from_cache(cid).await.is_some() {
             return Ok(true);
         }
+        let bucket = ContentBucket::for_content_type(content_type);
+        if self.is_known_missing(bucket.as_str(), cid).await {
+            return Ok(false);
+        }
 
-        // Check object store
-        self.object_store.exists(cid, content_type).await
+        // Check the backend
+        if !self.backend.has_block(bucket.as_str(), cid).await? {
+            return Ok(false);
+        }
+        Ok(!self.tombstones.is_deleted(bucket.as_str(), cid).await?)
     }
 
-    /// Delete content
+    /// Delete content permanently
+    ///
+    /// Prefer [`Self::soft_delete`] when the deletion may need to be undone.
     pub async fn delete(&self, cid: &Cid, content_type: u64) -> Result<()> {
-        // Remove from cache
-        self.remove_from_cache(cid).await;
+        let bucket = ContentBucket::for_content_type(content_type);
+        self.evict(cid).await;
+
+        // Delete from the backend
+        let generation = self.write_generation.load(Ordering::Acquire);
+        self.backend.delete_block(bucket.as_str(), cid).await?;
+        self.remember_missing(bucket.as_str(), cid, generation).await;
+        Ok(())
+    }
+
+    /// Hide content behind a tombstone until it is restored or purged
+    ///
+    /// The content disappears from [`Self::get`], [`Self::exists`], and
+    /// [`Self::list`] but keeps its bytes until the purge window ends.
+    pub async fn soft_delete(&self, cid: &Cid, content_type: u64, reason: &str, actor: &str) -> Result<Tombstone> {
+        let bucket = ContentBucket::for_content_type(content_type);
+        let generation = self.write_generation.load(Ordering::Acquire);
+        let tombstone = self.tombstones.delete(bucket.as_str(), cid, reason, actor).await?;
+        self.evict(cid).await;
+        self.remember_missing(bucket.as_str(), cid, generation).await;
+        Ok(tombstone)
+    }
+
+    /// Undo a soft delete within the purge window
+    pub async fn restore(&self, cid: &Cid, content_type: u64) -> Result<Tombstone> {
+        let bucket = ContentBucket::for_content_type(content_type);
+        let tombstone = self.tombstones.restore(bucket.as_str(), cid).await?;
+        self.forget_missing(bucket.as_str(), cid).await;
+        Ok(tombstone)
+    }
+
+    /// Purge soft-deleted content whose restore window has ended
+    pub async fn purge_expired(&self) -> Result<Vec<PurgeRecord>> {
+        self.tombstones.purge_expired().await
+    }
+
+    /// Tombstone registry used for soft deletes
+    pub fn tombstones(&self) -> &Arc<Tombstones> {
+        &self.tombstones
+    }
+
+    /// Expire stored content after `ttl`, replacing any earlier TTL
+    pub async fn set_ttl(&self, cid: &Cid, content_type: u64, ttl: Duration) -> Result<ExpiryRecord> {
+        let bucket = ContentBucket::for_content_type(content_type);
+        if !self.backend.has_block(bucket.as_str(), cid).await? {
+            return Err(ObjectStoreError::NotFound(cid.to_string()));
+        }
+        self.expiry.set_ttl(bucket.as_str(), cid, ttl).await
+    }
+
+    /// Push content's expiry back by `by`
+    pub async fn extend_ttl(&self, cid: &Cid, by: Duration) -> Result<ExpiryRecord> {
+        self.expiry.extend(cid, by).await
+    }
 
-        // Delete from object store
-        self.object_store.delete(cid, content_type).await
+    /// Keep content for good; returns whether it had a TTL
+    pub async fn clear_ttl(&self, cid: &Cid) -> Result<bool> {
+        self.expiry.clear(cid).await
     }
 
-    /// List content in a bucket
+    /// Delete content whose TTL has passed, dropping it from the caches
+    pub async fn expire_due(&self) -> Result<Vec<ExpiryRecord>> {
+        let generation = self.write_generation.load(Ordering::Acquire);
+        let expired = self.expiry.expire_due().await?;
+        for record in &expired {
+            self.evict(&record.cid).await;
+            self.remember_missing(&record.bucket, &record.cid, generation).await;
+        }
+        Ok(expired)
+    }
+
+    /// Expiry registry used for per-object TTLs
+    pub fn expiry(&self) -> &Arc<Expiry> {
+        &self.expiry
+    }
+
+    /// Make just-stored content visible again
+    ///
+    /// Storing soft-deleted content clears its tombstone, so the content is
+    /// neither hidden nor purged later.
+    async fn mark_stored(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        self.tombstones.clear(bucket, cid).await?;
+        self.forget_missing(bucket, cid).await;
+        Ok(())
+    }
+
+    /// Drop content from the memory and disk caches
+    async fn evict(&self, cid: &Cid) {
+        self.remove_from_cache(cid).await;
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Err(e) = disk_cache.remove(cid).await {
+                warn!("Disk cache removal failed for {}: {}", cid, e);
+            }
+        }
+    }
+
+    /// List content in a bucket, leaving out soft-deleted content
     pub async fn list(&self, bucket: ContentBucket) -> Result<Vec<ObjectInfo>> {
-        self.object_store.list(bucket).await
+        let deleted = self.tombstones.deleted().await?;
+        let mut objects = self.backend.list_blocks(bucket.as_str()).await?;
+        objects.retain(|info| !deleted.contains(bucket.as_str(), &info.cid));
+        Ok(objects)
     }
 
-    /// Store multiple contents in batch
+    /// Store multiple contents in batch, failing on the first error
+    ///
+    /// Items stored before the failure are kept; use [`Self::store_atomic`]
+    /// when the batch must be all or nothing.
     pub async fn store_batch<T: TypedContent>(&self, contents: &[T]) -> Result<Vec<Cid>> {
-        let mut cids = Vec::with_capacity(contents.len());
+        self.try_store_batch(contents).await.into_iter().collect()
+    }
 
+    /// Get multiple contents in batch, failing on the first error
+    pub async fn get_batch<T: TypedContent>(&self, cids: &[Cid]) -> Result<Vec<T>> {
+        self.try_get_batch(cids).await.into_iter().collect()
+    }
+
+    /// Store contents of one type all together or not at all
+    pub async fn store_atomic<T: TypedContent>(&self, contents: &[T]) -> Result<BatchCommit> {
+        let mut batch = Batch::new();
         for content in contents {
-            let cid = self.store(content).await?;
-            cids.push(cid);
+            batch.add(content)?;
+        }
+        self.commit_batch(batch).await
+    }
+
+    /// Commit a batch, then update the caches for its objects
+    ///
+    /// Nothing is cached unless the whole batch commits.
+    pub async fn commit_batch(&self, batch: Batch) -> Result<BatchCommit> {
+        let commit = batch.commit(self.backend.as_ref()).await?;
+
+        for object in batch.objects() {
+            self.mark_stored(&object.bucket, &object.cid).await?;
+            if self.write_policy == WritePolicy::WriteThrough {
+                self.cache_on_disk(&object.cid, object.content_type.codec(), &object.data).await;
+                self.cache_content(object.cid, object.data.clone(), object.content_type.codec()).await;
+            }
         }
 
-        Ok(cids)
+        info!("Committed batch {} ({} objects)", commit.manifest_cid, batch.len());
+        Ok(commit)
     }
 
-    /// Get multiple contents in batch
-    pub async fn get_batch<T: TypedContent>(&self, cids: &[Cid]) -> Result<Vec<T>> {
-        let mut contents = Vec::with_capacity(cids.len());
+    /// Load the manifest of a committed batch
+    pub async fn get_manifest(&self, manifest_cid: &Cid) -> Result<BatchManifest> {
+        batch::load_manifest(self.backend.as_ref(), manifest_cid).await
+    }
+
+    /// Store multiple contents concurrently, returning a result per item in input order
+    pub async fn try_store_batch<T: TypedContent>(&self, contents: &[T]) -> Vec<Result<Cid>> {
+        stream::iter(contents.iter().map(|content| self.store(content)))
+            .buffered(self.batch_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Get multiple contents concurrently, returning a result per item in input order
+    pub async fn try_get_batch<T: TypedContent>(&self, cids: &[Cid]) -> Vec<Result<T>> {
+        stream::iter(cids.iter().map(|cid| self.get::<T>(cid)))
+            .buffered(self.batch_concurrency)
+            .collect()
+            .await
+    }
 
-        for cid in cids {
-            let content = self.get::<T>(cid).await?;
-            contents.push(content);
+    /// Read a block, sharing the request with concurrent readers of the same block
+    async fn fetch_block(&self, bucket: &str, cid: &Cid) -> Result<Arc<Vec<u8>>> {
+        let key = (bucket.to_string(), *cid);
+        let fetch = {
+            let mut inflight = self.inflight.lock().unwrap();
+            inflight
+                .entry(key.clone())
+                .or_insert_with(|| {
+                    let backend = self.backend.clone();
+                    let registry = self.inflight.clone();
+                    let key = key.clone();
+                    async move {
+                        let result = backend.get_block(&key.0, &key.1).await
+                            .map(Arc::new)
+                            .map_err(Arc::new);
+                        registry.lock().unwrap().remove(&key);
+                        result
+                    }
+                    .boxed()
+                    .shared()
+                })
+                .clone()
+        };
+
+        fetch.await.map_err(|e| {
+            Arc::try_unwrap(e).unwrap_or_else(|shared| duplicate_error(&shared))
+        })
+    }
+
+    /// Whether a block is a recently confirmed miss
+    async fn is_known_missing(&self, bucket: &str, cid: &Cid) -> bool {
+        if self.negative_ttl.is_zero() {
+            return false;
+        }
+
+        let key = (bucket.to_string(), *cid);
+        let mut negative = self.negative_cache.write().await;
+        match negative.get(&key) {
+            Some(missed_at) if missed_at.elapsed() < self.negative_ttl => true,
+            Some(_) => {
+                negative.pop(&key);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Remember a confirmed miss, unless a write happened since `generation`
+    /// was read
+    ///
+    /// The check runs under the negative cache lock, and writers bump the
+    /// generation before taking it in [`Self::forget_missing`], so a miss
+    /// observed before a concurrent write can never outlive that write.
+    async fn remember_missing(&self, bucket: &str, cid: &Cid, generation: u64) {
+        if self.negative_ttl.is_zero() {
+            return;
+        }
+        let mut negative = self.negative_cache.write().await;
+        if self.write_generation.load(Ordering::Acquire) == generation {
+            negative.put((bucket.to_string(), *cid), Instant::now());
         }
+    }
 
-        Ok(contents)
+    /// Drop a remembered miss after the block was written
+    async fn forget_missing(&self, bucket: &str, cid: &Cid) {
+        self.write_generation.fetch_add(1, Ordering::AcqRel);
+        self.negative_cache.write().await.pop(&(bucket.to_string(), *cid));
     }
 
     /// Cache content
@@ -154,6 +599,7 @@ impl ContentStorageService {
         while *current_size + size > self.max_cache_size && !cache.is_empty() {
             if let Some((_, evicted)) = cache.pop_lru() {
                 *current_size -= evicted.size;
+                record_cache_eviction(CacheTier::Memory);
             }
         }
 
@@ -165,10 +611,15 @@ impl ContentStorageService {
             size,
         };
 
-        if let Some(old_entry) = cache.put(cid, entry) {
+        // Replaces an existing entry or evicts the LRU entry at capacity
+        if let Some((old_cid, old_entry)) = cache.push(cid, entry) {
             *current_size -= old_entry.size;
+            if old_cid != cid {
+                record_cache_eviction(CacheTier::Memory);
+            }
         }
         *current_size += size;
+        record_cache_occupancy(CacheTier::Memory, cache.len(), *current_size);
     }
 
     /// Get from cache
@@ -197,15 +648,24 @@ impl ContentStorageService {
         if let Some(entry) = cache.pop(cid) {
             let mut current_size = self.current_cache_size.write().await;
             *current_size -= entry.size;
+            record_cache_occupancy(CacheTier::Memory, cache.len(), *current_size);
         }
     }
 
-    /// Clear cache
+    /// Clear cache, including remembered misses and the disk tier
     pub async fn clear_cache(&self) {
         let mut cache = self.cache.write().await;
         cache.clear();
         let mut current_size = self.current_cache_size.write().await;
         *current_size = 0;
+        record_cache_occupancy(CacheTier::Memory, 0, 0);
+        self.negative_cache.write().await.clear();
+
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Err(e) = disk_cache.clear().await {
+                warn!("Failed to clear disk cache: {}", e);
+            }
+        }
     }
 
     /// Get cache statistics
@@ -213,15 +673,129 @@ impl ContentStorageService {
         let cache = self.cache.read().await;
         let current_size = self.current_cache_size.read().await;
 
+        let disk = match &self.disk_cache {
+            Some(disk_cache) => Some(disk_cache.stats().await),
+            None => None,
+        };
+
         CacheStats {
             entries: cache.len(),
             size: *current_size,
             capacity: cache.cap().get(),
             max_size: self.max_cache_size,
+            memory: TierStats {
+                hits: self.memory_hits.load(Ordering::Relaxed),
+                misses: self.memory_misses.load(Ordering::Relaxed),
+                entries: cache.len(),
+                size: *current_size,
+            },
+            disk,
+        }
+    }
+}
+
+/// A cache tier reported in metrics
+#[derive(Debug, Clone, Copy)]
+pub(super) enum CacheTier {
+    Memory,
+    Disk,
+}
+
+/// Metric handles for one cache tier, registered once
+struct CacheMetrics {
+    hits: Counter,
+    misses: Counter,
+    evictions: Counter,
+    entries: Gauge,
+    bytes: Gauge,
+}
+
+impl CacheMetrics {
+    fn register(tier: &str) -> Self {
+        let registry = metrics::global();
+        let requests = |result| {
+            registry.counter(
+                "cim_ipld_cache_requests_total",
+                "Cache lookups by tier and result",
+                &[("tier", tier), ("result", result)],
+            )
+        };
+        Self {
+            hits: requests("hit"),
+            misses: requests("miss"),
+            evictions: registry.counter("cim_ipld_cache_evictions_total", "Cache evictions by tier", &[("tier", tier)]),
+            entries: registry.gauge("cim_ipld_cache_entries", "Cached entries by tier", &[("tier", tier)]),
+            bytes: registry.gauge("cim_ipld_cache_size_bytes", "Cached bytes by tier", &[("tier", tier)]),
         }
     }
 }
 
+impl CacheTier {
+    fn metrics(self) -> &'static CacheMetrics {
+        static MEMORY: OnceLock<CacheMetrics> = OnceLock::new();
+        static DISK: OnceLock<CacheMetrics> = OnceLock::new();
+        match self {
+            Self::Memory => MEMORY.get_or_init(|| CacheMetrics::register("memory")),
+            Self::Disk => DISK.get_or_init(|| CacheMetrics::register("disk")),
+        }
+    }
+}
+
+/// Record a cache lookup for a tier
+pub(super) fn record_cache_lookup(tier: CacheTier, hit: bool) {
+    let metrics = tier.metrics();
+    if hit { &metrics.hits } else { &metrics.misses }.inc();
+}
+
+/// Record an entry evicted to make room in a tier
+pub(super) fn record_cache_eviction(tier: CacheTier) {
+    tier.metrics().evictions.inc();
+}
+
+/// Publish the current number of entries and bytes in a tier
+pub(super) fn record_cache_occupancy(tier: CacheTier, entries: usize, bytes: usize) {
+    let metrics = tier.metrics();
+    metrics.entries.set(entries as f64);
+    metrics.bytes.set(bytes as f64);
+}
+
+/// Copy an error shared between coalesced readers
+fn duplicate_error(error: &ObjectStoreError) -> ObjectStoreError {
+    match error {
+        ObjectStoreError::Nats(source) => ObjectStoreError::Nats(source.clone()),
+        ObjectStoreError::Serialization(source) => ObjectStoreError::Serialization(source.clone()),
+        ObjectStoreError::Deserialization(source) => ObjectStoreError::Deserialization(source.clone()),
+        ObjectStoreError::Compression(source) => ObjectStoreError::Compression(source.clone()),
+        ObjectStoreError::NotFound(key) => ObjectStoreError::NotFound(key.clone()),
+        ObjectStoreError::BucketNotFound(name) => ObjectStoreError::BucketNotFound(name.clone()),
+        ObjectStoreError::BucketCreation(msg) => ObjectStoreError::BucketCreation(msg.clone()),
+        ObjectStoreError::Storage(msg) => ObjectStoreError::Storage(msg.clone()),
+        ObjectStoreError::CidMismatch { expected, actual } => ObjectStoreError::CidMismatch {
+            expected: expected.clone(),
+            actual: actual.clone(),
+        },
+        ObjectStoreError::Unreachable { context, source } => ObjectStoreError::Unreachable {
+            context: context.clone(),
+            source: source.clone(),
+        },
+        ObjectStoreError::Timeout { context, source } => ObjectStoreError::Timeout {
+            context: context.clone(),
+            source: source.clone(),
+        },
+        ObjectStoreError::CircuitOpen(msg) => ObjectStoreError::CircuitOpen(msg.clone()),
+        ObjectStoreError::Conflict(msg) => ObjectStoreError::Conflict(msg.clone()),
+        ObjectStoreError::InvalidSignature(msg) => ObjectStoreError::InvalidSignature(msg.clone()),
+        ObjectStoreError::Retained(msg) => ObjectStoreError::Retained(msg.clone()),
+        ObjectStoreError::QuotaExceeded(msg) => ObjectStoreError::QuotaExceeded(msg.clone()),
+        ObjectStoreError::Unauthorized(msg) => ObjectStoreError::Unauthorized(msg.clone()),
+        ObjectStoreError::HistoryTruncated(msg) => ObjectStoreError::HistoryTruncated(msg.clone()),
+        ObjectStoreError::Remote { code, message } => ObjectStoreError::Remote {
+            code: *code,
+            message: message.clone(),
+        },
+    }
+}
+
 /// Cache statistics
 #[derive(Debug, Clone)]
 pub struct CacheStats {
@@ -229,6 +803,10 @@ pub struct CacheStats {
     pub size: usize,
     pub capacity: usize,
     pub max_size: usize,
+    /// Hits and misses of the memory cache
+    pub memory: TierStats,
+    /// Hits and misses of the disk cache, if configured
+    pub disk: Option<TierStats>,
 }
 
 #[cfg(test)]
@@ -248,6 +826,8 @@ mod tests {
             size: 1024,
             capacity: 100,
             max_size: 10240,
+            memory: TierStats::default(),
+            disk: None,
         };
 
         assert_eq!(stats.entries, 10);
@@ -327,8 +907,6 @@ mod tests {
     struct MockObjectStore {
         fail_put: AtomicBool,
         fail_get: AtomicBool,
-        fail_exists: AtomicBool,
-        fail_delete: AtomicBool,
         storage: Arc<RwLock<std::collections::HashMap<String, Vec<u8>>>>,
     }
 
@@ -337,8 +915,6 @@ mod tests {
             Self {
                 fail_put: AtomicBool::new(false),
                 fail_get: AtomicBool::new(false),
-                fail_exists: AtomicBool::new(false),
-                fail_delete: AtomicBool::new(false),
                 storage: Arc::new(RwLock::new(std::collections::HashMap::new())),
             }
         }
@@ -348,9 +924,9 @@ mod tests {
                 return Err(ObjectStoreError::Storage("Put operation failed".to_string()));
             }
             let cid = content.calculate_cid()
-                .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+                .map_err(ObjectStoreError::serialization)?;
             let data = content.to_bytes()
-                .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+                .map_err(ObjectStoreError::serialization)?;
             let mut storage = self.storage.write().await;
             storage.insert(cid.to_string(), data);
             Ok(())
@@ -364,62 +940,12 @@ mod tests {
             let data = storage.get(&cid.to_string())
                 .ok_or_else(|| ObjectStoreError::NotFound(cid.to_string()))?;
             T::from_bytes(data)
-                .map_err(|e| ObjectStoreError::Deserialization(e.to_string()))
-        }
-
-        async fn exists(&self, cid: &Cid, _content_type: u64) -> Result<bool> {
-            if self.fail_exists.load(Ordering::Relaxed) {
-                return Err(ObjectStoreError::Storage("Exists check failed".to_string()));
-            }
-            let storage = self.storage.read().await;
-            Ok(storage.contains_key(&cid.to_string()))
-        }
-
-        async fn delete(&self, cid: &Cid, _content_type: u64) -> Result<()> {
-            if self.fail_delete.load(Ordering::Relaxed) {
-                return Err(ObjectStoreError::Storage("Delete operation failed".to_string()));
-            }
-            let mut storage = self.storage.write().await;
-            storage.remove(&cid.to_string());
-            Ok(())
-        }
-
-        async fn list(&self, _bucket: ContentBucket) -> Result<Vec<ObjectInfo>> {
-            Ok(vec![])
+                .map_err(ObjectStoreError::deserialization)
         }
     }
 
     #[tokio::test]
     async fn test_store_serialization_error() {
-        let _mock_store = Arc::new(MockObjectStore::new());
-        
-        // Create a wrapper that matches NatsObjectStore interface
-        struct StoreWrapper {
-            mock: Arc<MockObjectStore>,
-        }
-        
-        impl StoreWrapper {
-            async fn put<T: TypedContent>(&self, content: &T) -> Result<()> {
-                self.mock.put(content).await
-            }
-            
-            async fn get<T: TypedContent>(&self, cid: &Cid) -> Result<T> {
-                self.mock.get(cid).await
-            }
-            
-            async fn exists(&self, cid: &Cid, content_type: u64) -> Result<bool> {
-                self.mock.exists(cid, content_type).await
-            }
-            
-            async fn delete(&self, cid: &Cid, content_type: u64) -> Result<()> {
-                self.mock.delete(cid, content_type).await
-            }
-            
-            async fn list(&self, bucket: ContentBucket) -> Result<Vec<ObjectInfo>> {
-                self.mock.list(bucket).await
-            }
-        }
-
         // Test CID calculation error
         let content = FailingContent {
             fail_serialize: AtomicBool::new(false),
@@ -430,12 +956,12 @@ mod tests {
         // Would need to refactor ContentStorageService to accept a trait
         // For now, test the error handling patterns directly
         let result = content.calculate_cid()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()));
+            .map_err(ObjectStoreError::serialization);
         
         assert!(result.is_err());
         match result {
-            Err(ObjectStoreError::Serialization(msg)) => {
-                assert!(msg.contains("Invalid CID"));
+            Err(ObjectStoreError::Serialization(source)) => {
+                assert!(source.to_string().contains("Invalid CID"));
             }
             _ => panic!("Expected serialization error"),
         }
@@ -446,12 +972,12 @@ mod tests {
         // Test deserialization error path
         let bytes = b"fail_deserialize";
         let result = FailingContent::from_bytes(bytes)
-            .map_err(|e| ObjectStoreError::Deserialization(e.to_string()));
+            .map_err(ObjectStoreError::deserialization);
         
         assert!(result.is_err());
         match result {
-            Err(ObjectStoreError::Deserialization(msg)) => {
-                assert!(msg.contains("Invalid content"));
+            Err(ObjectStoreError::Deserialization(source)) => {
+                assert!(source.to_string().contains("Invalid content"));
             }
             _ => panic!("Expected deserialization error"),
         }
@@ -499,12 +1025,12 @@ mod tests {
 
         // Test to_bytes error handling
         let result = content.to_bytes()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()));
+            .map_err(ObjectStoreError::serialization);
         
         assert!(result.is_err());
         match result {
-            Err(ObjectStoreError::Serialization(msg)) => {
-                assert!(msg.contains("CBOR serialization error"));
+            Err(ObjectStoreError::Serialization(source)) => {
+                assert!(source.to_string().contains("CBOR serialization error"));
             }
             _ => panic!("Expected serialization error"),
         }
@@ -574,4 +1100,397 @@ mod tests {
         // Should have evicted the first entry
         assert!(*size_guard + new_size <= max_cache_size);
     }
+
+    /// Memory backend that counts reads and can hold them at a gate
+    struct CountingBackend {
+        inner: crate::object_store::MemoryBackend,
+        reads: std::sync::atomic::AtomicUsize,
+        gate: Gate,
+    }
+
+    /// Holds the next read after it reached the backend until released
+    #[derive(Default)]
+    struct Gate {
+        closed: AtomicBool,
+        entered: tokio::sync::Notify,
+        release: tokio::sync::Notify,
+    }
+
+    impl Gate {
+        fn close(&self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+
+        async fn pass(&self) {
+            if self.closed.swap(false, Ordering::SeqCst) {
+                self.entered.notify_one();
+                self.release.notified().await;
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl StorageBackend for CountingBackend {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+            self.inner.put_block(bucket, cid, data).await
+        }
+
+        async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            let result = self.inner.get_block(bucket, cid).await;
+            self.gate.pass().await;
+            result
+        }
+
+        async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+            self.inner.has_block(bucket, cid).await
+        }
+
+        async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+            self.inner.delete_block(bucket, cid).await
+        }
+
+        async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+            self.inner.list_blocks(bucket).await
+        }
+    }
+
+    fn counting_service() -> (Arc<CountingBackend>, ContentStorageService) {
+        let backend = Arc::new(CountingBackend {
+            inner: crate::object_store::MemoryBackend::new(),
+            reads: std::sync::atomic::AtomicUsize::new(0),
+            gate: Gate::default(),
+        });
+        let service = ContentStorageService::with_backend(
+            backend.clone(),
+            100,
+            Duration::from_secs(60),
+            1024 * 1024,
+        );
+        (backend, service)
+    }
+
+    fn text(data: &str) -> FailingContent {
+        FailingContent {
+            fail_serialize: AtomicBool::new(false),
+            fail_deserialize: AtomicBool::new(false),
+            data: data.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_gets_are_coalesced() {
+        let (backend, service) = counting_service();
+        let service = service.with_write_policy(WritePolicy::WriteAround);
+        let cid = service.store(&text("shared")).await.unwrap();
+
+        // The first read is held in the backend while the others join it
+        backend.gate.close();
+        let (results, _) = tokio::join!(
+            futures::future::join_all((0..10).map(|_| service.get::<FailingContent>(&cid))),
+            async {
+                backend.gate.entered.notified().await;
+                backend.gate.release.notify_one();
+            },
+        );
+
+        assert!(results.iter().all(|r| r.as_ref().is_ok_and(|c| c.data == "shared")));
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 1);
+        assert!(service.inflight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shared_errors_keep_their_source() {
+        use std::error::Error as _;
+
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        let errors = [
+            ObjectStoreError::Nats(io.into()),
+            ObjectStoreError::unreachable("get k", std::io::Error::other("no route")),
+        ];
+        for error in &errors {
+            let copy = duplicate_error(error);
+            assert_eq!(copy.code(), error.code());
+            assert!(copy.source().is_some_and(|e| e.is::<std::io::Error>()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache() {
+        let (backend, service) = counting_service();
+        let missing = text("missing");
+        let cid = missing.calculate_cid().unwrap();
+
+        for _ in 0..3 {
+            let result = service.get::<FailingContent>(&cid).await;
+            assert!(matches!(result, Err(ObjectStoreError::NotFound(_))));
+        }
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 1);
+        assert!(!service.exists(&cid, FailingContent::CODEC).await.unwrap());
+
+        // Storing the content clears the remembered miss
+        service.store(&missing).await.unwrap();
+        assert_eq!(service.get::<FailingContent>(&cid).await.unwrap().data, "missing");
+    }
+
+    #[tokio::test]
+    async fn test_miss_racing_a_store_is_not_remembered() {
+        let (backend, service) = counting_service();
+        let content = text("racing");
+        let cid = content.calculate_cid().unwrap();
+
+        // The read misses, then the store completes before the miss is reported
+        backend.gate.close();
+        let (read, stored) = tokio::join!(service.get::<FailingContent>(&cid), async {
+            backend.gate.entered.notified().await;
+            let stored = service.store(&content).await;
+            backend.gate.release.notify_one();
+            stored
+        });
+        assert!(matches!(read, Err(ObjectStoreError::NotFound(_))));
+        stored.unwrap();
+
+        service.clear_cache().await;
+        assert!(service.exists(&cid, FailingContent::CODEC).await.unwrap());
+        assert_eq!(service.get::<FailingContent>(&cid).await.unwrap().data, "racing");
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_expires() {
+        let (backend, service) = counting_service();
+        let service = service.with_negative_ttl(Duration::from_millis(10));
+        let cid = text("later").calculate_cid().unwrap();
+
+        assert!(service.get::<FailingContent>(&cid).await.is_err());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(service.get::<FailingContent>(&cid).await.is_err());
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_policies() {
+        let (_, service) = counting_service();
+        service.store(&text("through")).await.unwrap();
+        assert_eq!(service.cache_stats().await.entries, 1);
+
+        let (backend, service) = counting_service();
+        let service = service.with_write_policy(WritePolicy::WriteAround);
+        let cid = service.store(&text("around")).await.unwrap();
+        assert_eq!(service.cache_stats().await.entries, 0);
+
+        // Reads populate the cache
+        service.get::<FailingContent>(&cid).await.unwrap();
+        service.get::<FailingContent>(&cid).await.unwrap();
+        assert_eq!(service.cache_stats().await.entries, 1);
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_results_per_item() {
+        let (_, service) = counting_service();
+        let service = service.with_batch_concurrency(4);
+
+        let contents: Vec<_> = (0..8).map(|i| text(&format!("item {i}"))).collect();
+        let mut inputs = contents.clone();
+        inputs.push(text("fail_cid"));
+
+        let stored = service.try_store_batch(&inputs).await;
+        assert_eq!(stored.len(), 9);
+        assert!(stored[..8].iter().all(|r| r.is_ok()));
+        assert!(stored[8].is_err());
+
+        let mut cids: Vec<_> = stored[..8].iter().map(|r| *r.as_ref().unwrap()).collect();
+        cids.insert(3, text("never stored").calculate_cid().unwrap());
+
+        let fetched = service.try_get_batch::<FailingContent>(&cids).await;
+        assert!(matches!(fetched[3], Err(ObjectStoreError::NotFound(_))));
+        assert_eq!(fetched[0].as_ref().unwrap().data, "item 0");
+        assert_eq!(fetched[8].as_ref().unwrap().data, "item 7");
+
+        assert!(service.get_batch::<FailingContent>(&cids).await.is_err());
+        assert_eq!(service.store_batch(&contents).await.unwrap().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_store_atomic() {
+        let (backend, service) = counting_service();
+
+        let mut inputs: Vec<_> = (0..4).map(|i| text(&format!("atomic {i}"))).collect();
+        inputs.push(text("fail_cid"));
+        assert!(service.store_atomic(&inputs).await.is_err());
+        assert!(backend.inner.list_blocks("cim-documents").await.unwrap().is_empty());
+        assert_eq!(service.cache_stats().await.entries, 0);
+
+        inputs.pop();
+        let commit = service.store_atomic(&inputs).await.unwrap();
+        assert_eq!(commit.written, 4);
+        assert_eq!(service.cache_stats().await.entries, 4);
+
+        let manifest = service.get_manifest(&commit.manifest_cid).await.unwrap();
+        let cids: Vec<_> = manifest.entries.iter().map(|e| e.cid).collect();
+        let fetched = service.get_batch::<FailingContent>(&cids).await.unwrap();
+        assert_eq!(fetched[2].data, "atomic 2");
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore() {
+        let (backend, service) = counting_service();
+        let codec = FailingContent::CONTENT_TYPE.codec();
+        let cid = service.store(&text("soft deleted")).await.unwrap();
+
+        service.soft_delete(&cid, codec, "obsolete", "alice").await.unwrap();
+        assert!(service.get::<FailingContent>(&cid).await.unwrap_err().is_not_found());
+        assert!(!service.exists(&cid, codec).await.unwrap());
+        assert!(service.list(ContentBucket::Documents).await.unwrap().is_empty());
+        assert!(backend.inner.has_block("cim-documents", &cid).await.unwrap());
+
+        service.restore(&cid, codec).await.unwrap();
+        assert_eq!(service.get::<FailingContent>(&cid).await.unwrap().data, "soft deleted");
+        assert_eq!(service.list(ContentBucket::Documents).await.unwrap().len(), 1);
+
+        let service = service.with_purge_window(Duration::ZERO);
+        service.soft_delete(&cid, codec, "obsolete", "alice").await.unwrap();
+        assert_eq!(service.purge_expired().await.unwrap().len(), 1);
+        assert!(!backend.inner.has_block("cim-documents", &cid).await.unwrap());
+        assert!(service.tombstones().purge_record("cim-documents", &cid).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_storing_again_clears_tombstone() {
+        let (_, service) = counting_service();
+        let service = service.with_purge_window(Duration::ZERO);
+        let codec = FailingContent::CONTENT_TYPE.codec();
+        let content = text("stored again");
+        let cid = service.store(&content).await.unwrap();
+
+        service.soft_delete(&cid, codec, "obsolete", "alice").await.unwrap();
+        assert!(service.list(ContentBucket::Documents).await.unwrap().is_empty());
+
+        service.store(&content).await.unwrap();
+        assert_eq!(service.get::<FailingContent>(&cid).await.unwrap().data, "stored again");
+        assert_eq!(service.list(ContentBucket::Documents).await.unwrap().len(), 1);
+        assert!(service.purge_expired().await.unwrap().is_empty());
+        assert!(service.exists(&cid, codec).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_retention_blocks_deletes() {
+        use crate::object_store::{LegalHold, MemoryBackend, NamedRefs, RetentionTarget};
+
+        let retention = Arc::new(Retention::new(Arc::new(MemoryBackend::new()), Arc::new(NamedRefs::in_memory())));
+        let (backend, service) = counting_service();
+        let service = service.with_retention(retention.clone());
+        let codec = FailingContent::CONTENT_TYPE.codec();
+        let cid = service.store(&text("under hold")).await.unwrap();
+
+        let target = RetentionTarget::Content(cid);
+        retention.place_hold(target, LegalHold::new("case-1", "discovery", "legal")).await.unwrap();
+        assert!(matches!(service.delete(&cid, codec).await, Err(ObjectStoreError::Retained(_))));
+        assert!(matches!(
+            service.soft_delete(&cid, codec, "cleanup", "bob").await,
+            Err(ObjectStoreError::Retained(_))
+        ));
+        assert!(backend.inner.has_block("cim-documents", &cid).await.unwrap());
+
+        retention.release_hold(target, "case-1", "legal").await.unwrap();
+        service.delete(&cid, codec).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_store_with_ttl() {
+        let (backend, service) = counting_service();
+        let preview = service.store_with_ttl(&text("preview"), Duration::ZERO).await.unwrap();
+        let kept = service.store_with_ttl(&text("kept"), Duration::ZERO).await.unwrap();
+        service.clear_ttl(&kept).await.unwrap();
+
+        // Content already stored without a TTL does not pick one up
+        let permanent = service.store(&text("permanent")).await.unwrap();
+        service.store_with_ttl(&text("permanent"), Duration::ZERO).await.unwrap();
+        assert!(service.expiry().get(&permanent).await.unwrap().is_none());
+
+        let expired = service.expire_due().await.unwrap();
+        assert_eq!(expired.iter().map(|r| r.cid).collect::<Vec<_>>(), vec![preview]);
+        assert!(service.get::<FailingContent>(&preview).await.unwrap_err().is_not_found());
+        assert!(backend.inner.has_block("cim-documents", &kept).await.unwrap());
+        assert!(backend.inner.has_block("cim-documents", &permanent).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_tier() {
+        let dir = tempfile::tempdir().unwrap();
+        let open_disk = || async {
+            Arc::new(DiskCache::open(dir.path(), 1024 * 1024, Duration::from_secs(60)).await.unwrap())
+        };
+
+        let (backend, service) = counting_service();
+        let service = service.with_disk_cache(open_disk().await);
+        let cid = service.store(&text("cached on disk")).await.unwrap();
+        drop(service);
+
+        // A fresh service over the same disk cache, as after a restart
+        let restarted = ContentStorageService::with_backend(
+            backend.clone(),
+            100,
+            Duration::from_secs(60),
+            1024 * 1024,
+        )
+        .with_disk_cache(open_disk().await);
+
+        assert_eq!(restarted.get::<FailingContent>(&cid).await.unwrap().data, "cached on disk");
+        assert_eq!(restarted.get::<FailingContent>(&cid).await.unwrap().data, "cached on disk");
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 0);
+
+        let stats = restarted.cache_stats().await;
+        assert_eq!((stats.memory.hits, stats.memory.misses), (1, 1));
+        let disk = stats.disk.unwrap();
+        assert_eq!((disk.hits, disk.misses, disk.entries), (1, 0, 1));
+        assert_eq!(stats.memory.hit_rate(), 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_entry_failing_cid_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let disk = Arc::new(DiskCache::open(dir.path(), 1024, Duration::from_secs(60)).await.unwrap());
+        let (backend, service) = counting_service();
+        let service = service.with_disk_cache(disk.clone());
+
+        let cid = service.store(&text("genuine")).await.unwrap();
+        service.clear_cache().await;
+
+        // Valid checksum, but the bytes belong to different content
+        disk.put(&cid, FailingContent::CODEC, b"forged").await.unwrap();
+
+        assert_eq!(service.get::<FailingContent>(&cid).await.unwrap().data, "genuine");
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 1);
+        assert_eq!(disk.get(&cid).await.unwrap().unwrap().0, b"genuine");
+    }
+
+    #[tokio::test]
+    async fn test_cache_metrics_are_exported() {
+        let (_, service) = counting_service();
+        let cid = service.store(&text("measured")).await.unwrap();
+        service.get::<FailingContent>(&cid).await.unwrap();
+
+        let rendered = metrics::global().render();
+        assert!(rendered.contains("cim_ipld_cache_requests_total{tier=\"memory\",result=\"hit\"}"));
+        assert!(rendered.contains("# TYPE cim_ipld_cache_size_bytes gauge"));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_eviction_keeps_size_accurate() {
+        let backend = Arc::new(crate::object_store::MemoryBackend::new());
+        let service = ContentStorageService::with_backend(backend, 2, Duration::from_secs(60), 1024);
+
+        for data in ["one", "two", "three"] {
+            service.store(&text(data)).await.unwrap();
+        }
+
+        let stats = service.cache_stats().await;
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.size, "two".len() + "three".len());
+    }
 }
@@ -0,0 +1,607 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Persistent on-disk cache tier for [`ContentStorageService`](super::ContentStorageService)
+//!
+//! Blocks are stored as `<root>/blocks/<cid>` next to an `index.json` that
+//! records content type, size, checksum and recency. Both are written through
+//! a temporary file and a rename. On open the index is reconciled with the
+//! files on disk, so a crash at any point leaves at worst a few dropped
+//! entries, never a corrupt one.
+//!
+//! The index is rewritten after [`INDEX_WRITE_BATCH`] changes or
+//! [`INDEX_WRITE_INTERVAL`], whichever comes first, on [`DiskCache::flush`],
+//! and when the cache is dropped. Changes since the last write, including
+//! recency from reads, are lost only on a crash.
+//!
+//! The index lock only guards in-memory bookkeeping; block files are read,
+//! written and removed after it is released. A block removed by one task
+//! while another re-adds it can leave an index entry without its file,
+//! which the next lookup treats as a miss.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+
+use cid::Cid;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::content_storage::{record_cache_eviction, record_cache_lookup, record_cache_occupancy, CacheTier};
+use super::{ObjectStoreError, Result};
+
+const INDEX_FILE: &str = "index.json";
+const INDEX_VERSION: u32 = 1;
+
+/// Index changes after which the index is rewritten
+pub const INDEX_WRITE_BATCH: u64 = 64;
+
+/// Longest time index changes wait before the index is rewritten
+pub const INDEX_WRITE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Hit, miss and occupancy counters for one cache tier
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TierStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub size: usize,
+}
+
+impl TierStats {
+    /// Fraction of lookups served by this tier
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Index record for one cached block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DiskEntry {
+    cid: String,
+    content_type: u64,
+    size: u64,
+    checksum: String,
+    last_access: SystemTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    /// Entries from least to most recently used
+    entries: Vec<DiskEntry>,
+}
+
+struct DiskIndex {
+    entries: LruCache<Cid, DiskEntry>,
+    total_size: u64,
+    /// Number of changes made since the cache was opened
+    changes: u64,
+}
+
+impl DiskIndex {
+    fn to_file(&self) -> IndexFile {
+        IndexFile {
+            version: INDEX_VERSION,
+            entries: self.entries.iter().rev().map(|(_, e)| e.clone()).collect(),
+        }
+    }
+}
+
+/// What the index file on disk reflects
+struct IndexWriter {
+    /// Index changes included in the last write
+    written: u64,
+    written_at: Instant,
+}
+
+/// Index writes started by dropped caches, by cache root
+///
+/// Opening a cache waits for the write of a dropped cache at the same root.
+fn pending_drop_writes() -> &'static std::sync::Mutex<HashMap<PathBuf, JoinHandle<()>>> {
+    static PENDING: OnceLock<std::sync::Mutex<HashMap<PathBuf, JoinHandle<()>>>> = OnceLock::new();
+    PENDING.get_or_init(Default::default)
+}
+
+/// Write an index file through a temporary file and a rename, blocking
+fn write_index_file(root: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp_path = root.join(format!(".{INDEX_FILE}.tmp"));
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, root.join(INDEX_FILE))
+}
+
+/// Size-bounded, crash-safe block cache on local disk
+pub struct DiskCache {
+    root: PathBuf,
+    max_size: u64,
+    ttl: Duration,
+    index: Mutex<DiskIndex>,
+    /// Serializes index file writes; taken after releasing `index`
+    writer: Mutex<IndexWriter>,
+    /// Index changes included in the last write, readable without `writer`
+    written: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl DiskCache {
+    /// Open or create a disk cache, recovering any existing index
+    pub async fn open(root: impl Into<PathBuf>, max_size: u64, ttl: Duration) -> Result<Self> {
+        let root = root.into();
+        let pending = pending_drop_writes().lock().unwrap().remove(&root);
+        if let Some(write) = pending {
+            let _ = write.await;
+        }
+        tokio::fs::create_dir_all(root.join("blocks"))
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+
+        let index = Self::recover(&root).await?;
+        let cache = Self {
+            root,
+            max_size,
+            ttl,
+            index: Mutex::new(index),
+            writer: Mutex::new(IndexWriter {
+                written: 0,
+                written_at: Instant::now(),
+            }),
+            written: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+
+        // The limit may have shrunk since the cache was last used
+        let evicted = {
+            let mut index = cache.index.lock().await;
+            cache.evict_to_fit(&mut index, 0)
+        };
+        cache.remove_files(&evicted).await;
+        cache.flush().await?;
+
+        Ok(cache)
+    }
+
+    /// Root directory of this cache
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn block_path(&self, cid: &Cid) -> PathBuf {
+        self.root.join("blocks").join(cid.to_string())
+    }
+
+    /// Remove the block files of entries already dropped from the index
+    async fn remove_files(&self, cids: &[Cid]) {
+        for cid in cids {
+            let _ = tokio::fs::remove_file(self.block_path(cid)).await;
+        }
+    }
+
+    /// Rebuild the index from `index.json` and the block directory
+    async fn recover(root: &Path) -> Result<DiskIndex> {
+        let mut index = DiskIndex {
+            entries: LruCache::unbounded(),
+            total_size: 0,
+            changes: 1,
+        };
+
+        let recorded = match tokio::fs::read(root.join(INDEX_FILE)).await {
+            Ok(bytes) => match serde_json::from_slice::<IndexFile>(&bytes) {
+                Ok(file) if file.version == INDEX_VERSION => file.entries,
+                Ok(file) => {
+                    warn!("Discarding disk cache index with version {}", file.version);
+                    Vec::new()
+                }
+                Err(e) => {
+                    warn!("Discarding unreadable disk cache index: {}", e);
+                    Vec::new()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(ObjectStoreError::Storage(e.to_string())),
+        };
+
+        // Keep index entries whose block file is present and complete
+        let blocks = root.join("blocks");
+        for entry in recorded {
+            let Ok(cid) = Cid::try_from(entry.cid.as_str()) else {
+                continue;
+            };
+            match tokio::fs::metadata(blocks.join(&entry.cid)).await {
+                Ok(metadata) if metadata.len() == entry.size => {
+                    index.total_size += entry.size;
+                    index.entries.put(cid, entry);
+                }
+                _ => debug!("Dropping disk cache entry without a valid block: {}", entry.cid),
+            }
+        }
+
+        // Remove blocks the index does not know about, including partial writes
+        let known: HashSet<String> = index.entries.iter().map(|(_, e)| e.cid.clone()).collect();
+        let mut dir = tokio::fs::read_dir(&blocks)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        while let Some(file) = dir
+            .next_entry()
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?
+        {
+            let name = file.file_name().to_string_lossy().to_string();
+            if !known.contains(&name) {
+                debug!("Removing orphaned disk cache file: {}", name);
+                let _ = tokio::fs::remove_file(file.path()).await;
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn record_lookup(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        record_cache_lookup(CacheTier::Disk, hit);
+    }
+
+    /// Record a change to the index and persist it if enough have piled up
+    ///
+    /// Every change to the index passes through here, so it also publishes
+    /// the occupancy gauges. The index lock is released before any I/O.
+    async fn index_changed(&self, mut index: MutexGuard<'_, DiskIndex>) -> Result<()> {
+        index.changes += 1;
+        record_cache_occupancy(CacheTier::Disk, index.entries.len(), index.total_size as usize);
+
+        let pending = index.changes - self.written.load(Ordering::Acquire);
+        let due = pending >= INDEX_WRITE_BATCH
+            || self.writer.try_lock().is_ok_and(|w| w.written_at.elapsed() >= INDEX_WRITE_INTERVAL);
+        if !due {
+            return Ok(());
+        }
+        let (changes, file) = (index.changes, index.to_file());
+        drop(index);
+        self.write_index(changes, &file).await
+    }
+
+    /// Persist an index snapshot atomically, unless a newer one was written
+    async fn write_index(&self, changes: u64, file: &IndexFile) -> Result<()> {
+        let mut writer = self.writer.lock().await;
+        if writer.written >= changes {
+            return Ok(());
+        }
+
+        let bytes = serde_json::to_vec(file)
+            .map_err(ObjectStoreError::serialization)?;
+        let root = self.root.clone();
+        tokio::task::spawn_blocking(move || write_index_file(&root, &bytes))
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+
+        writer.written = changes;
+        writer.written_at = Instant::now();
+        self.written.store(changes, Ordering::Release);
+        Ok(())
+    }
+
+    /// Evict least recently used entries until `incoming` more bytes fit
+    ///
+    /// Returns the evicted CIDs, whose files the caller removes.
+    fn evict_to_fit(&self, index: &mut DiskIndex, incoming: u64) -> Vec<Cid> {
+        let mut evicted = Vec::new();
+        while index.total_size + incoming > self.max_size {
+            let Some((cid, entry)) = index.entries.pop_lru() else {
+                break;
+            };
+            index.total_size -= entry.size;
+            record_cache_eviction(CacheTier::Disk);
+            debug!("Evicted {} from disk cache", cid);
+            evicted.push(cid);
+        }
+        evicted
+    }
+
+    fn is_expired(&self, entry: &DiskEntry) -> bool {
+        entry.last_access.elapsed().is_ok_and(|age| age >= self.ttl)
+    }
+
+    /// Look up a block, returning its bytes and content type
+    ///
+    /// Entries that are expired or fail their checksum are removed.
+    pub async fn get(&self, cid: &Cid) -> Result<Option<(Vec<u8>, u64)>> {
+        let mut index = self.index.lock().await;
+
+        let Some(entry) = index.entries.get(cid).cloned() else {
+            self.record_lookup(false);
+            return Ok(None);
+        };
+
+        if self.is_expired(&entry) {
+            Self::remove_entry(&mut index, cid);
+            self.record_lookup(false);
+            self.index_changed(index).await?;
+            self.remove_files(&[*cid]).await;
+            return Ok(None);
+        }
+        drop(index);
+
+        let data = tokio::fs::read(self.block_path(cid))
+            .await
+            .ok()
+            .filter(|data| blake3::hash(data).to_hex().as_str() == entry.checksum);
+
+        let mut index = self.index.lock().await;
+        // The entry may have been removed or replaced while the file was read
+        let current = index.entries.get_mut(cid).filter(|e| e.checksum == entry.checksum);
+        let Some(data) = data else {
+            self.record_lookup(false);
+            if current.is_none() {
+                return Ok(None);
+            }
+            warn!("Disk cache entry for {} is missing or corrupt", cid);
+            Self::remove_entry(&mut index, cid);
+            self.index_changed(index).await?;
+            self.remove_files(&[*cid]).await;
+            return Ok(None);
+        };
+
+        if let Some(current) = current {
+            current.last_access = SystemTime::now();
+        }
+        self.record_lookup(true);
+        self.index_changed(index).await?;
+        Ok(Some((data, entry.content_type)))
+    }
+
+    /// Add a block to the cache, evicting older entries as needed
+    pub async fn put(&self, cid: &Cid, content_type: u64, data: &[u8]) -> Result<()> {
+        let size = data.len() as u64;
+        if size > self.max_size {
+            return Ok(());
+        }
+
+        // Each write has its own temporary file, so concurrent puts of one
+        // block do not interleave; the rename replaces the block atomically
+        let path = self.block_path(cid);
+        let tmp_path = self.root.join("blocks").join(format!(".{cid}.{:016x}.tmp", rand::random::<u64>()));
+        tokio::fs::write(&tmp_path, data)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+
+        let mut index = self.index.lock().await;
+        Self::remove_entry(&mut index, cid);
+        let evicted = self.evict_to_fit(&mut index, size);
+        index.total_size += size;
+        index.entries.put(*cid, DiskEntry {
+            cid: cid.to_string(),
+            content_type,
+            size,
+            checksum: blake3::hash(data).to_hex().to_string(),
+            last_access: SystemTime::now(),
+        });
+        self.index_changed(index).await?;
+        self.remove_files(&evicted).await;
+        Ok(())
+    }
+
+    /// Remove a block from the cache
+    pub async fn remove(&self, cid: &Cid) -> Result<()> {
+        let mut index = self.index.lock().await;
+        if Self::remove_entry(&mut index, cid) {
+            self.index_changed(index).await?;
+            self.remove_files(&[*cid]).await;
+        }
+        Ok(())
+    }
+
+    /// Drop an entry from the index; its file is removed by the caller
+    fn remove_entry(index: &mut DiskIndex, cid: &Cid) -> bool {
+        match index.entries.pop(cid) {
+            Some(entry) => {
+                index.total_size -= entry.size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every cached block
+    pub async fn clear(&self) -> Result<()> {
+        let mut index = self.index.lock().await;
+        let cids: Vec<Cid> = index.entries.iter().map(|(cid, _)| *cid).collect();
+        index.entries.clear();
+        index.total_size = 0;
+        self.index_changed(index).await?;
+        self.remove_files(&cids).await;
+        self.flush().await
+    }
+
+    /// Persist every index change, including recency, made since the last write
+    pub async fn flush(&self) -> Result<()> {
+        let (changes, file) = {
+            let index = self.index.lock().await;
+            if index.changes <= self.written.load(Ordering::Acquire) {
+                return Ok(());
+            }
+            (index.changes, index.to_file())
+        };
+        self.write_index(changes, &file).await
+    }
+
+    /// Current counters for this tier
+    pub async fn stats(&self) -> TierStats {
+        let index = self.index.lock().await;
+        TierStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: index.entries.len(),
+            size: index.total_size as usize,
+        }
+    }
+}
+
+impl Drop for DiskCache {
+    /// Write pending index changes so a clean shutdown loses nothing
+    ///
+    /// Inside a runtime the write runs on the blocking pool, which the
+    /// runtime waits for on shutdown.
+    fn drop(&mut self) {
+        let index = self.index.get_mut();
+        if index.changes <= *self.written.get_mut() {
+            return;
+        }
+        let bytes = match serde_json::to_vec(&index.to_file()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to serialize disk cache index on drop: {}", e);
+                return;
+            }
+        };
+
+        let root = self.root.clone();
+        let write = move || {
+            if let Err(e) = write_index_file(&root, &bytes) {
+                warn!("Failed to write disk cache index on drop: {}", e);
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let handle = runtime.spawn_blocking(write);
+                pending_drop_writes().lock().unwrap().insert(self.root.clone(), handle);
+            }
+            Err(_) => write(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cid(data: &[u8]) -> Cid {
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+        Cid::new_v1(0x55, mh)
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let cid = test_cid(b"persisted");
+
+        {
+            let cache = DiskCache::open(dir.path(), 1024, Duration::from_secs(60)).await.unwrap();
+            cache.put(&cid, 0x55, b"persisted").await.unwrap();
+            assert_eq!(cache.get(&cid).await.unwrap(), Some((b"persisted".to_vec(), 0x55)));
+        }
+
+        let cache = DiskCache::open(dir.path(), 1024, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(cache.get(&cid).await.unwrap(), Some((b"persisted".to_vec(), 0x55)));
+        assert!(cache.get(&test_cid(b"other")).await.unwrap().is_none());
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.hits, stats.misses, stats.entries, stats.size), (1, 1, 1, 9));
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path(), 20, Duration::from_secs(60)).await.unwrap();
+        let (a, b, c) = (test_cid(b"a"), test_cid(b"b"), test_cid(b"c"));
+
+        cache.put(&a, 0x55, &[1; 8]).await.unwrap();
+        cache.put(&b, 0x55, &[2; 8]).await.unwrap();
+        cache.get(&a).await.unwrap();
+        cache.put(&c, 0x55, &[3; 8]).await.unwrap();
+
+        assert!(cache.get(&a).await.unwrap().is_some());
+        assert!(cache.get(&b).await.unwrap().is_none());
+        assert!(cache.get(&c).await.unwrap().is_some());
+        assert!(!dir.path().join("blocks").join(b.to_string()).exists());
+    }
+
+    #[tokio::test]
+    async fn test_index_writes_are_batched_and_keep_recency() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_entries = || {
+            let bytes = std::fs::read(dir.path().join(INDEX_FILE)).unwrap();
+            serde_json::from_slice::<IndexFile>(&bytes).unwrap().entries
+        };
+        let (a, b, c) = (test_cid(b"a"), test_cid(b"b"), test_cid(b"c"));
+
+        {
+            let cache = DiskCache::open(dir.path(), 20, Duration::from_secs(60)).await.unwrap();
+            cache.put(&a, 0x55, &[1; 8]).await.unwrap();
+            cache.put(&b, 0x55, &[2; 8]).await.unwrap();
+            assert!(index_entries().is_empty());
+
+            cache.flush().await.unwrap();
+            assert_eq!(index_entries().len(), 2);
+
+            // Only recency changes; dropping the cache persists it
+            cache.get(&a).await.unwrap();
+        }
+
+        // `b` is now the least recently used entry
+        let cache = DiskCache::open(dir.path(), 20, Duration::from_secs(60)).await.unwrap();
+        cache.put(&c, 0x55, &[3; 8]).await.unwrap();
+        assert!(cache.get(&a).await.unwrap().is_some());
+        assert!(cache.get(&b).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path(), 1024, Duration::from_millis(10)).await.unwrap();
+        let cid = test_cid(b"short lived");
+
+        cache.put(&cid, 0x55, b"short lived").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(cache.get(&cid).await.unwrap().is_none());
+        assert_eq!(cache.stats().await.entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_recovery_discards_corrupt_and_orphaned_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kept, corrupt) = (test_cid(b"kept"), test_cid(b"corrupt"));
+
+        {
+            let cache = DiskCache::open(dir.path(), 1024, Duration::from_secs(60)).await.unwrap();
+            cache.put(&kept, 0x55, b"kept").await.unwrap();
+            cache.put(&corrupt, 0x55, b"corrupt").await.unwrap();
+        }
+
+        let blocks = dir.path().join("blocks");
+        // Truncated block, a leftover temp file and a block missing from the index
+        std::fs::write(blocks.join(corrupt.to_string()), b"cor").unwrap();
+        std::fs::write(blocks.join(".partial.tmp"), b"partial").unwrap();
+        std::fs::write(blocks.join(test_cid(b"orphan").to_string()), b"orphan").unwrap();
+
+        let cache = DiskCache::open(dir.path(), 1024, Duration::from_secs(60)).await.unwrap();
+        assert!(cache.get(&kept).await.unwrap().is_some());
+        assert!(cache.get(&corrupt).await.unwrap().is_none());
+        assert_eq!(std::fs::read_dir(&blocks).unwrap().count(), 1);
+
+        // Same size but different bytes fails the checksum
+        std::fs::write(blocks.join(kept.to_string()), b"KEPT").unwrap();
+        assert!(cache.get(&kept).await.unwrap().is_none());
+
+        // An unreadable index starts an empty cache
+        std::fs::write(dir.path().join(INDEX_FILE), b"{not json").unwrap();
+        let cache = DiskCache::open(dir.path(), 1024, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(cache.stats().await.entries, 0);
+    }
+}
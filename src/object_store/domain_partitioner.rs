@@ -424,6 +424,18 @@ impl PartitionStrategy {
             .unwrap_or("cim-general")
     }
     
+    /// Names of all domain buckets
+    pub fn buckets(&self) -> impl Iterator<Item = &str> {
+        self.domain_mapping.values().map(String::as_str)
+    }
+
+    /// Domain whose content is stored in a bucket
+    pub fn domain_for_bucket(&self, bucket: &str) -> Option<ContentDomain> {
+        self.domain_mapping.iter()
+            .find(|(_, name)| name.as_str() == bucket)
+            .map(|(domain, _)| *domain)
+    }
+
     /// Add custom domain mapping
     pub fn add_domain_mapping(&mut self, domain: ContentDomain, bucket: String) {
         self.domain_mapping.insert(domain, bucket);
@@ -443,7 +455,7 @@ impl PartitionStrategy {
     pub fn add_pattern_matcher(&mut self, matcher: PatternMatcher) {
         self.pattern_matchers.push(matcher);
         // Sort by priority (descending)
-        self.pattern_matchers.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.pattern_matchers.sort_by_key(|b| std::cmp::Reverse(b.priority));
     }
 }
 
@@ -0,0 +1,299 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Per-object time to live
+//!
+//! Buckets only have a bucket-wide `max_age`. [`Expiry`] lets individual
+//! objects such as previews, temporary uploads, or derived thumbnails carry
+//! their own expiry time. An expiry pass ([`Expiry::expire_due`], or
+//! [`Expiry::spawn_expirer`] in the background) deletes objects whose time
+//! has come; until then they stay readable.
+//!
+//! Expiry records are stored in the same [`StorageBackend`] as the content,
+//! keyed by the CID of the expiring object.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::{ObjectStoreError, Result, StorageBackend};
+use crate::util::{cid_serde, unix_secs};
+
+/// Bucket holding expiry records
+pub const EXPIRY_BUCKET: &str = "cim-expiry";
+
+/// When an object expires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiryRecord {
+    /// Expiring object
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Bucket holding the object
+    pub bucket: String,
+    /// Expiry time in seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl ExpiryRecord {
+    /// Whether the object has expired at `now`
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        unix_secs(now) >= self.expires_at
+    }
+}
+
+/// Registry of per-object expiry times
+pub struct Expiry {
+    backend: Arc<dyn StorageBackend>,
+}
+
+impl Expiry {
+    /// Store expiry records in a backend
+    pub fn new(backend: Arc<dyn StorageBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Write a record, replacing the object's previous one
+    ///
+    /// Records are keyed by the object's CID rather than their own, and
+    /// backends keep the first block stored under a CID, so an old record
+    /// is deleted before the new one is written.
+    async fn save(&self, record: &ExpiryRecord) -> Result<()> {
+        let data = serde_json::to_vec(record)
+            .map_err(ObjectStoreError::serialization)?;
+        self.clear(&record.cid).await?;
+        self.backend.put_block(EXPIRY_BUCKET, &record.cid, data).await
+    }
+
+    /// Expire an object in `bucket` after `ttl`, replacing any earlier TTL
+    pub async fn set_ttl(&self, bucket: &str, cid: &Cid, ttl: Duration) -> Result<ExpiryRecord> {
+        let record = ExpiryRecord {
+            cid: *cid,
+            bucket: bucket.to_string(),
+            expires_at: unix_secs(SystemTime::now() + ttl),
+        };
+        self.save(&record).await?;
+        debug!("{} expires at {}", cid, record.expires_at);
+        Ok(record)
+    }
+
+    /// Give an object that is being stored with `ttl` its expiry
+    ///
+    /// An object that is already `stored` without a TTL is kept for good;
+    /// one that has a TTL keeps whichever expiry is later. Returns the
+    /// record in force, or `None` if the object does not expire.
+    pub async fn store_ttl(
+        &self,
+        bucket: &str,
+        cid: &Cid,
+        ttl: Duration,
+        stored: bool,
+    ) -> Result<Option<ExpiryRecord>> {
+        let existing = self.get(cid).await?;
+        match existing {
+            None if stored => Ok(None),
+            Some(record) if record.expires_at >= unix_secs(SystemTime::now() + ttl) => Ok(Some(record)),
+            _ => self.set_ttl(bucket, cid, ttl).await.map(Some),
+        }
+    }
+
+    /// Expiry record of an object, if it has a TTL
+    pub async fn get(&self, cid: &Cid) -> Result<Option<ExpiryRecord>> {
+        match self.backend.get_block(EXPIRY_BUCKET, cid).await {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(ObjectStoreError::deserialization),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Push an object's expiry back by `by`
+    pub async fn extend(&self, cid: &Cid, by: Duration) -> Result<ExpiryRecord> {
+        let mut record = self.get(cid).await?
+            .ok_or_else(|| ObjectStoreError::NotFound(format!("TTL for {cid}")))?;
+        record.expires_at += by.as_secs();
+        self.save(&record).await?;
+        Ok(record)
+    }
+
+    /// Remove an object's TTL so it is kept; returns whether it had one
+    pub async fn clear(&self, cid: &Cid) -> Result<bool> {
+        if !self.backend.has_block(EXPIRY_BUCKET, cid).await? {
+            return Ok(false);
+        }
+        self.backend.delete_block(EXPIRY_BUCKET, cid).await?;
+        Ok(true)
+    }
+
+    /// All expiry records, soonest first
+    pub async fn list(&self) -> Result<Vec<ExpiryRecord>> {
+        let mut records = Vec::new();
+        for info in self.backend.list_blocks(EXPIRY_BUCKET).await? {
+            if let Some(record) = self.get(&info.cid).await? {
+                records.push(record);
+            }
+        }
+        records.sort_by_key(|r| (r.expires_at, r.cid.to_string()));
+        Ok(records)
+    }
+
+    /// Delete every object whose expiry time has passed
+    ///
+    /// Returns the records of deleted objects. Each record is read again
+    /// right before its object is deleted, and skipped if a concurrent
+    /// store cleared or moved its expiry. Objects the backend refuses to
+    /// delete because they are under retention are left in place and
+    /// retried on a later pass.
+    pub async fn expire_due(&self) -> Result<Vec<ExpiryRecord>> {
+        let now = SystemTime::now();
+        let mut expired = Vec::new();
+
+        for record in self.list().await? {
+            if !record.is_expired_at(now) {
+                break;
+            }
+            if self.get(&record.cid).await?.as_ref() != Some(&record) {
+                debug!("Expiry of {} changed during the pass", record.cid);
+                continue;
+            }
+            match self.backend.delete_block(&record.bucket, &record.cid).await {
+                Ok(()) => {}
+                Err(e) if e.is_not_found() => {}
+                Err(e @ ObjectStoreError::Retained(_)) => {
+                    debug!("Not expiring {}: {}", record.cid, e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            self.backend.delete_block(EXPIRY_BUCKET, &record.cid).await?;
+
+            info!("Expired {} from {}", record.cid, record.bucket);
+            expired.push(record);
+        }
+
+        Ok(expired)
+    }
+
+    /// Run expiry passes in the background at the given interval
+    pub fn spawn_expirer(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let expiry = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match expiry.expire_due().await {
+                    Ok(expired) if !expired.is_empty() => debug!("Expired {} objects", expired.len()),
+                    Ok(_) => {}
+                    Err(e) => warn!("Expiry pass failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_cid;
+    use crate::object_store::{ListOptions, ListPage, MemoryBackend, ObjectInfo};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Clears TTLs between the expiry pass listing a record and reading it again
+    struct RacingStore {
+        inner: MemoryBackend,
+        record_reads: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl StorageBackend for RacingStore {
+        fn name(&self) -> &str {
+            "racing"
+        }
+
+        async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+            self.inner.put_block(bucket, cid, data).await
+        }
+
+        async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+            if bucket == EXPIRY_BUCKET && self.record_reads.fetch_add(1, Ordering::SeqCst) == 1 {
+                // The content is stored for good after the pass listed it
+                self.inner.delete_block(EXPIRY_BUCKET, cid).await?;
+            }
+            self.inner.get_block(bucket, cid).await
+        }
+
+        async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+            self.inner.has_block(bucket, cid).await
+        }
+
+        async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+            self.inner.delete_block(bucket, cid).await
+        }
+
+        async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+            self.inner.list_blocks(bucket).await
+        }
+
+        async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+            self.inner.list_blocks_page(bucket, options).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expire_due() {
+        let backend = Arc::new(MemoryBackend::new());
+        let expiry = Expiry::new(backend.clone());
+        let (preview, upload) = (test_cid(b"preview"), test_cid(b"upload"));
+        backend.put_block("cim-images", &preview, b"preview".to_vec()).await.unwrap();
+        backend.put_block("cim-documents", &upload, b"upload".to_vec()).await.unwrap();
+
+        expiry.set_ttl("cim-images", &preview, Duration::ZERO).await.unwrap();
+        expiry.set_ttl("cim-documents", &upload, Duration::from_secs(3600)).await.unwrap();
+
+        let expired = expiry.expire_due().await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].cid, preview);
+        assert!(!backend.has_block("cim-images", &preview).await.unwrap());
+        assert!(backend.has_block("cim-documents", &upload).await.unwrap());
+        assert!(expiry.get(&preview).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_extend_and_clear() {
+        let backend = Arc::new(MemoryBackend::new());
+        let expiry = Expiry::new(backend.clone());
+        let thumb = test_cid(b"thumb");
+
+        let set = expiry.set_ttl("cim-images", &thumb, Duration::from_secs(60)).await.unwrap();
+        let extended = expiry.extend(&thumb, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(extended.expires_at, set.expires_at + 60);
+
+        // A store with a shorter TTL keeps the later expiry
+        let kept = expiry.store_ttl("cim-images", &thumb, Duration::from_secs(1), true).await.unwrap();
+        assert_eq!(kept, Some(extended));
+
+        assert!(expiry.clear(&thumb).await.unwrap());
+        assert!(!expiry.clear(&thumb).await.unwrap());
+        assert!(expiry.store_ttl("cim-images", &thumb, Duration::from_secs(1), true).await.unwrap().is_none());
+        assert!(expiry.extend(&thumb, Duration::from_secs(1)).await.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_expire_due_skips_records_changed_after_listing() {
+        let backend = Arc::new(RacingStore {
+            inner: MemoryBackend::new(),
+            record_reads: AtomicUsize::new(0),
+        });
+        let expiry = Expiry::new(backend.clone());
+        let upload = test_cid(b"upload");
+        backend.put_block("cim-documents", &upload, b"upload".to_vec()).await.unwrap();
+        expiry.set_ttl("cim-documents", &upload, Duration::ZERO).await.unwrap();
+
+        assert!(expiry.expire_due().await.unwrap().is_empty());
+        assert!(backend.has_block("cim-documents", &upload).await.unwrap());
+    }
+}
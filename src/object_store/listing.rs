@@ -0,0 +1,270 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Cursor-based, filtered listing of stored blocks
+//!
+//! Pages are ordered by CID string and the cursor is the last CID of the
+//! previous page, so listing stays stable while content is being added.
+//! Backends check [`ListFilter`]s while reading object metadata and only
+//! keep a page worth of results in memory. Filtering is done by the
+//! client: NATS reads the metadata of the whole bucket for every page.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::time::SystemTime;
+
+use cid::Cid;
+
+use super::ObjectInfo;
+
+/// Filters applied while listing a bucket
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    /// Minimum object size in bytes
+    pub min_size: Option<usize>,
+    /// Maximum object size in bytes
+    pub max_size: Option<usize>,
+    /// Only objects created at or after this time
+    pub created_after: Option<SystemTime>,
+    /// Only objects created before this time
+    pub created_before: Option<SystemTime>,
+    /// Only objects whose CID has this codec
+    pub codec: Option<u64>,
+    /// Only objects whose CID string starts with this prefix
+    pub cid_prefix: Option<String>,
+    /// Only objects stored compressed (NATS compresses objects above its
+    /// compression threshold; other backends store them as is)
+    pub compressed_only: bool,
+}
+
+impl ListFilter {
+    /// Check the filters that only need the CID
+    ///
+    /// Backends call this before fetching per-object metadata.
+    pub fn matches_cid(&self, cid: &Cid) -> bool {
+        if self.codec.is_some_and(|codec| cid.codec() != codec) {
+            return false;
+        }
+        match &self.cid_prefix {
+            Some(prefix) => cid.to_string().starts_with(prefix.as_str()),
+            None => true,
+        }
+    }
+
+    /// Check every filter against an object
+    pub fn matches(&self, info: &ObjectInfo) -> bool {
+        self.matches_cid(&info.cid)
+            && self.min_size.is_none_or(|min| info.size >= min)
+            && self.max_size.is_none_or(|max| info.size <= max)
+            && self.created_after.is_none_or(|after| info.created_at >= after)
+            && self.created_before.is_none_or(|before| info.created_at < before)
+            && (!self.compressed_only || info.compressed)
+    }
+}
+
+/// Options for a paginated listing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    /// Filters applied to every object
+    pub filter: ListFilter,
+    /// Cursor returned by the previous page
+    pub cursor: Option<String>,
+    /// Maximum number of objects per page; `None` returns everything
+    pub limit: Option<usize>,
+}
+
+impl ListOptions {
+    /// List everything matching a filter
+    pub fn filtered(filter: ListFilter) -> Self {
+        Self {
+            filter,
+            ..Default::default()
+        }
+    }
+
+    /// Set the page size
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Continue after a previous page
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Whether a CID key lies after the cursor
+    pub(crate) fn is_after_cursor(&self, key: &str) -> bool {
+        self.cursor.as_deref().is_none_or(|cursor| key > cursor)
+    }
+
+    /// Key range of a CID-ordered map that lies after the cursor
+    pub(crate) fn key_range(&self) -> (Bound<&str>, Bound<&str>) {
+        match self.cursor.as_deref() {
+            Some(cursor) => (Bound::Excluded(cursor), Bound::Unbounded),
+            None => (Bound::Unbounded, Bound::Unbounded),
+        }
+    }
+}
+
+/// One page of a listing
+#[derive(Debug, Clone, Default)]
+pub struct ListPage {
+    /// Objects in CID order
+    pub objects: Vec<ObjectInfo>,
+    /// Cursor for the next page, if there are more objects
+    pub next_cursor: Option<String>,
+}
+
+/// Collects the first page of matching objects from unordered input
+///
+/// Holds at most `limit + 1` objects regardless of bucket size.
+pub(crate) struct PageCollector<'a> {
+    options: &'a ListOptions,
+    objects: BTreeMap<String, ObjectInfo>,
+    more: bool,
+}
+
+impl<'a> PageCollector<'a> {
+    pub(crate) fn new(options: &'a ListOptions) -> Self {
+        Self {
+            options,
+            objects: BTreeMap::new(),
+            more: false,
+        }
+    }
+
+    /// Whether a CID could still make it into the page
+    pub(crate) fn wants(&self, cid: &Cid) -> bool {
+        self.options.filter.matches_cid(cid) && self.options.is_after_cursor(&cid.to_string())
+    }
+
+    /// Offer an object; non-matching objects are ignored
+    pub(crate) fn offer(&mut self, info: ObjectInfo) {
+        let key = info.cid.to_string();
+        if !self.options.is_after_cursor(&key) || !self.options.filter.matches(&info) {
+            return;
+        }
+
+        self.objects.insert(key, info);
+        if let Some(limit) = self.options.limit {
+            if self.objects.len() > limit + 1 {
+                self.objects.pop_last();
+            }
+        }
+    }
+
+    /// Record that a source had more objects than it returned
+    pub(crate) fn mark_more(&mut self) {
+        self.more = true;
+    }
+
+    /// Whether the page is complete when input arrives in CID order
+    pub(crate) fn is_full(&self) -> bool {
+        self.options.limit.is_some_and(|limit| self.objects.len() > limit)
+    }
+
+    pub(crate) fn finish(mut self) -> ListPage {
+        let mut more = self.more;
+        if let Some(limit) = self.options.limit {
+            while self.objects.len() > limit {
+                self.objects.pop_last();
+                more = true;
+            }
+        }
+
+        let next_cursor = if more {
+            self.objects.keys().next_back().cloned()
+        } else {
+            None
+        };
+
+        ListPage {
+            objects: self.objects.into_values().collect(),
+            next_cursor,
+        }
+    }
+}
+
+/// Paginate an in-memory list of objects
+pub(crate) fn paginate(objects: impl IntoIterator<Item = ObjectInfo>, options: &ListOptions) -> ListPage {
+    let mut collector = PageCollector::new(options);
+    for info in objects {
+        collector.offer(info);
+    }
+    collector.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(data: &[u8], codec: u64) -> ObjectInfo {
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+        ObjectInfo {
+            cid: Cid::new_v1(codec, mh),
+            size: data.len(),
+            created_at: SystemTime::now(),
+            compressed: false,
+        }
+    }
+
+    #[test]
+    fn test_pages_cover_every_object_once() {
+        let objects: Vec<_> = (0..25u8).map(|i| object(&[i; 4], 0x55)).collect();
+
+        let mut seen = Vec::new();
+        let mut options = ListOptions::default().with_limit(10);
+        loop {
+            let page = paginate(objects.clone(), &options);
+            assert!(page.objects.len() <= 10);
+            seen.extend(page.objects.iter().map(|o| o.cid.to_string()));
+            match page.next_cursor {
+                Some(cursor) => options = options.after(cursor),
+                None => break,
+            }
+        }
+
+        let mut expected: Vec<_> = objects.iter().map(|o| o.cid.to_string()).collect();
+        expected.sort();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn test_filters() {
+        let small = object(b"ab", 0x55);
+        let large = object(b"abcdefgh", 0x55);
+        let other_codec = object(b"abcdefgh", 0x71);
+
+        let filter = ListFilter {
+            min_size: Some(4),
+            codec: Some(0x55),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&small));
+        assert!(filter.matches(&large));
+        assert!(!filter.matches(&other_codec));
+
+        let prefix = large.cid.to_string()[..12].to_string();
+        let filter = ListFilter {
+            cid_prefix: Some(prefix),
+            ..Default::default()
+        };
+        assert!(filter.matches(&large));
+        assert!(!filter.matches(&small));
+
+        let filter = ListFilter {
+            created_after: Some(SystemTime::now() + std::time::Duration::from_secs(60)),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&large));
+    }
+
+    #[test]
+    fn test_exact_page_has_no_cursor() {
+        let objects: Vec<_> = (0..3u8).map(|i| object(&[i], 0x55)).collect();
+        let page = paginate(objects, &ListOptions::default().with_limit(3));
+        assert_eq!(page.objects.len(), 3);
+        assert!(page.next_cursor.is_none());
+    }
+}
@@ -6,9 +6,26 @@ mod nats_object_store;
 mod content_storage;
 mod pull_utils;
 mod domain_partitioner;
+mod backend;
+mod tiered;
+mod watch;
+mod listing;
+mod disk_cache;
+mod resilience;
+mod batch;
+mod refs;
+mod tombstone;
+mod retention;
+mod expiry;
+mod tenant;
+mod capability;
+mod audit;
+mod chain_store;
+mod chain_sync;
 
 pub use nats_object_store::{
     NatsObjectStore,
+    ErrorSource,
     ObjectStoreError,
     ContentBucket,
     ObjectInfo,
@@ -17,6 +34,11 @@ pub use nats_object_store::{
 pub use content_storage::{
     ContentStorageService,
     CacheStats,
+    WritePolicy,
+};
+pub use disk_cache::{
+    DiskCache,
+    TierStats,
 };
 pub use pull_utils::{
     PullOptions,
@@ -31,6 +53,140 @@ pub use domain_partitioner::{
     DomainContentInfo,
     DetectionMethod,
 };
+pub use backend::{
+    StorageBackend,
+    MemoryBackend,
+    LocalDiskBackend,
+};
+pub use tiered::{
+    TieredStore,
+    StorageTier,
+    PlacementPolicy,
+    Placement,
+    TierMove,
+    PLACEMENT_BUCKET,
+};
+pub use listing::{
+    ListFilter,
+    ListOptions,
+    ListPage,
+};
+pub use watch::{
+    ContentEvent,
+    ContentEventKind,
+    ContentEventStream,
+    WatchFrom,
+};
+pub use batch::{
+    Batch,
+    BatchCommit,
+    BatchManifest,
+    ManifestEntry,
+    load_manifest,
+    MANIFEST_BUCKET,
+    BATCH_MANIFEST_CODEC,
+};
+pub use resilience::{
+    Resilience,
+    Retryable,
+    RetryPolicy,
+    CircuitBreakerConfig,
+    CircuitState,
+};
+pub use refs::{
+    NamedRefs,
+    NamedRef,
+    RefValue,
+    RefSignature,
+    RefEvent,
+    RefEventStream,
+    RefStore,
+    MemoryRefStore,
+    KvRefStore,
+    REFS_BUCKET,
+};
+pub use tombstone::{
+    Tombstones,
+    Tombstone,
+    DeletedSet,
+    PurgeRecord,
+    TOMBSTONE_BUCKET,
+    PURGE_LOG_BUCKET,
+    DEFAULT_PURGE_WINDOW,
+    DEFAULT_LISTING_TTL,
+};
+pub use retention::{
+    Retention,
+    RetentionGuard,
+    RetentionPolicy,
+    RetentionRecord,
+    RetentionReport,
+    RetentionStatus,
+    RetentionTarget,
+    LegalHold,
+    RETENTION_BUCKET,
+};
+pub use expiry::{
+    Expiry,
+    ExpiryRecord,
+    EXPIRY_BUCKET,
+};
+pub use tenant::{
+    TenantId,
+    TenantQuota,
+    TenantUsage,
+    BucketUsage,
+    TenantBackend,
+};
+pub use capability::{
+    Action,
+    Authorizer,
+    Capability,
+    CapabilityGuard,
+    CapabilityToken,
+    Credential,
+    Invocation,
+    Resource,
+    key_id,
+    DEFAULT_INVOCATION_WINDOW,
+    MAX_DELEGATION_DEPTH,
+};
+pub use audit::{
+    AuditAction,
+    AuditEvent,
+    AuditLog,
+    AuditQuery,
+    AuditVerification,
+    AUDIT_BUCKET,
+    AUDIT_HEAD_REF,
+    AUDIT_EVENT_CODEC,
+};
+pub use chain_store::{
+    ChainEntryStream,
+    ChainStore,
+    ChainPages,
+    CHAIN_BUCKET,
+    CHAIN_INDEX_BUCKET,
+    CHAIN_SIGNATURE_BUCKET,
+    DEFAULT_PAGE_SIZE,
+};
+pub use chain_sync::{
+    serve_nats,
+    ChainHead,
+    ChainSync,
+    ChainSyncServer,
+    LocalTransport,
+    LocatedEntry,
+    NatsTransport,
+    SyncOutcome,
+    SyncRequest,
+    SyncResponse,
+    SyncTransport,
+    WireEntry,
+    DEFAULT_SYNC_BATCH,
+    MAX_SYNC_BATCH,
+    MAX_LOCATE_SEQUENCES,
+};
 
 // Re-export Result type
 pub type Result<T> = std::result::Result<T, ObjectStoreError>;
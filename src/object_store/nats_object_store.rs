@@ -3,33 +3,49 @@
 //! NATS Object Store wrapper for CIM-IPLD integration
 
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 
 use async_nats::jetstream::{self, object_store::ObjectStore};
+use async_nats::jetstream::context::{GetStreamError, GetStreamErrorKind, ObjectStoreErrorKind};
+use async_nats::jetstream::object_store::{
+    DeleteErrorKind, GetErrorKind, InfoErrorKind, ListErrorKind, ObjectMetadata, PutErrorKind,
+};
+use async_trait::async_trait;
 use cid::Cid;
+use crate::error::ErrorCode;
+use crate::metrics::{self, Counter, Histogram, MetricsRegistry, LATENCY_BUCKETS, RATIO_BUCKETS};
 use crate::TypedContent;
 use futures::StreamExt;
 use tokio::io::AsyncReadExt;
 use tokio::sync::RwLock;
+use tracing::debug;
 use zstd::stream::{decode_all, encode_all};
 
+use super::audit::{AuditAction, AuditEvent, AuditLog, AUDIT_BUCKET};
+use super::backend::StorageBackend;
+use super::capability::{Action, Authorizer, Credential};
 use super::domain_partitioner::{PartitionStrategy, ContentDomain};
+use super::listing::{ListFilter, ListOptions, ListPage, PageCollector};
+use super::resilience::{Resilience, Retryable};
+use super::retention::{check_record_bucket, Retention};
+use super::tenant::{TenantId, TenantQuota, TenantUsage, UsageTracker};
+use super::watch::{ContentEvent, ContentEventKind, ContentEventStream, WatchFrom};
 
 /// Error types for object store operations
 #[derive(Debug, thiserror::Error)]
 pub enum ObjectStoreError {
     #[error("NATS error: {0}")]
-    Nats(#[from] async_nats::Error),
+    Nats(#[source] ErrorSource),
 
     #[error("Serialization error: {0}")]
-    Serialization(String),
+    Serialization(#[source] ErrorSource),
 
     #[error("Deserialization error: {0}")]
-    Deserialization(String),
+    Deserialization(#[source] ErrorSource),
 
     #[error("Compression error: {0}")]
-    Compression(String),
+    Compression(#[source] ErrorSource),
 
     #[error("Object not found: {0}")]
     NotFound(String),
@@ -45,6 +61,164 @@ pub enum ObjectStoreError {
 
     #[error("CID mismatch: expected {expected}, got {actual}")]
     CidMismatch { expected: String, actual: String },
+
+    #[error("NATS unreachable: {context}")]
+    Unreachable {
+        context: String,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    #[error("Operation timed out: {context}")]
+    Timeout {
+        context: String,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    #[error("Circuit open: {0}")]
+    CircuitOpen(String),
+
+    #[error("Conflicting update: {0}")]
+    Conflict(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Content is under retention: {0}")]
+    Retained(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Change history truncated: {0}")]
+    HistoryTruncated(String),
+
+    #[error("Remote node failed ({code}): {message}")]
+    Remote { code: ErrorCode, message: String },
+}
+
+/// The underlying cause of an [`ObjectStoreError`]
+///
+/// Shared rather than boxed so that readers coalesced onto one fetch can each
+/// receive the error with its source chain intact. It derefs to the cause, so
+/// `source()` yields the original error and `downcast_ref` works on it.
+#[derive(Debug, Clone)]
+pub struct ErrorSource(Arc<dyn std::error::Error + Send + Sync>);
+
+impl std::ops::Deref for ErrorSource {
+    type Target = dyn std::error::Error + Send + Sync;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl std::fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E: Into<Box<dyn std::error::Error + Send + Sync>>> From<E> for ErrorSource {
+    fn from(source: E) -> Self {
+        Self(Arc::from(source.into()))
+    }
+}
+
+impl From<async_nats::Error> for ObjectStoreError {
+    fn from(source: async_nats::Error) -> Self {
+        Self::Nats(source.into())
+    }
+}
+
+impl ObjectStoreError {
+    /// The NATS server could not be reached while doing `context`
+    pub fn unreachable(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Unreachable { context: context.into(), source: Some(source.into().into()) }
+    }
+
+    /// The NATS server did not answer in time while doing `context`
+    pub fn timeout(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Timeout { context: context.into(), source: Some(source.into().into()) }
+    }
+
+    /// Encoding content or metadata failed
+    pub fn serialization(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Serialization(source.into().into())
+    }
+
+    /// Decoding content or metadata failed
+    pub fn deserialization(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Deserialization(source.into().into())
+    }
+
+    /// Compressing or decompressing a block failed
+    pub fn compression(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Compression(source.into().into())
+    }
+
+    /// Machine-readable classification of this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Serialization(_) | Self::Deserialization(_) => ErrorCode::Serialization,
+            Self::Compression(_) => ErrorCode::Compression,
+            Self::NotFound(_) | Self::BucketNotFound(_) => ErrorCode::NotFound,
+            Self::Nats(_) | Self::BucketCreation(_) | Self::Storage(_) => ErrorCode::Storage,
+            Self::CidMismatch { .. } => ErrorCode::Integrity,
+            Self::Unreachable { .. } => ErrorCode::Unavailable,
+            Self::Timeout { .. } => ErrorCode::Timeout,
+            Self::CircuitOpen(_) => ErrorCode::CircuitOpen,
+            Self::Conflict(_) => ErrorCode::Conflict,
+            Self::InvalidSignature(_) => ErrorCode::InvalidSignature,
+            Self::Retained(_) => ErrorCode::Retained,
+            Self::QuotaExceeded(_) => ErrorCode::QuotaExceeded,
+            Self::Unauthorized(_) => ErrorCode::Unauthorized,
+            Self::HistoryTruncated(_) => ErrorCode::HistoryTruncated,
+            Self::Remote { code, .. } => *code,
+        }
+    }
+
+    /// Whether the operation may succeed if tried again later
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+
+    /// Whether the object or bucket does not exist, as opposed to being unreachable
+    pub fn is_not_found(&self) -> bool {
+        self.code() == ErrorCode::NotFound
+    }
+}
+
+impl Retryable for ObjectStoreError {
+    fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Unreachable { .. }
+                | Self::Timeout { .. }
+                | Self::Remote { code: ErrorCode::Unavailable | ErrorCode::Timeout, .. }
+        )
+    }
+
+    fn circuit_open(scope: &str) -> Self {
+        Self::CircuitOpen(format!("too many failures reaching bucket {scope}"))
+    }
+
+    fn deadline_exceeded(operation: &str, deadline: Duration) -> Self {
+        Self::Timeout {
+            context: format!("{operation} did not complete within {deadline:?}"),
+            source: None,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, ObjectStoreError>;
@@ -145,6 +319,12 @@ pub struct NatsObjectStore {
     domain_buckets: Arc<RwLock<HashMap<String, ObjectStore>>>,
     compression_threshold: usize,
     partition_strategy: Arc<RwLock<PartitionStrategy>>,
+    resilience: Arc<Resilience>,
+    retention: Option<Arc<Retention>>,
+    tenant: Option<TenantId>,
+    usage: Option<Arc<UsageTracker>>,
+    audit: Option<(Arc<AuditLog>, String)>,
+    capability: Option<(Arc<Authorizer>, Credential)>,
 }
 
 impl NatsObjectStore {
@@ -152,6 +332,34 @@ impl NatsObjectStore {
     pub async fn new(
         jetstream: jetstream::Context,
         compression_threshold: usize,
+    ) -> Result<Self> {
+        Self::open(jetstream, compression_threshold, None).await
+    }
+
+    /// Create a store confined to a tenant's namespace
+    ///
+    /// Every bucket is prefixed with the tenant id, and writes of new
+    /// objects are checked against `quota`. Usage is seeded from the
+    /// tenant's existing buckets.
+    pub async fn for_tenant(
+        jetstream: jetstream::Context,
+        compression_threshold: usize,
+        tenant: TenantId,
+        quota: TenantQuota,
+    ) -> Result<Self> {
+        let store = Self::open(jetstream, compression_threshold, Some(tenant.clone())).await?;
+        let usage = UsageTracker::new(tenant, quota);
+        store.seed_usage(&usage).await?;
+        Ok(Self {
+            usage: Some(Arc::new(usage)),
+            ..store
+        })
+    }
+
+    async fn open(
+        jetstream: jetstream::Context,
+        compression_threshold: usize,
+        tenant: Option<TenantId>,
     ) -> Result<Self> {
         let store = Self {
             jetstream,
@@ -159,6 +367,12 @@ impl NatsObjectStore {
             domain_buckets: Arc::new(RwLock::new(HashMap::new())),
             compression_threshold,
             partition_strategy: Arc::new(RwLock::new(PartitionStrategy::default())),
+            resilience: Arc::new(Resilience::default()),
+            retention: None,
+            tenant,
+            usage: None,
+            audit: None,
+            capability: None,
         };
 
         // Initialize all buckets
@@ -169,34 +383,182 @@ impl NatsObjectStore {
         Ok(store)
     }
 
+    /// Replace the retry policy and circuit breakers used for NATS calls
+    pub fn with_resilience(mut self, resilience: Resilience) -> Self {
+        self.resilience = Arc::new(resilience);
+        self
+    }
+
+    /// Retry policy and circuit breakers used for NATS calls
+    pub fn resilience(&self) -> &Resilience {
+        &self.resilience
+    }
+
+    /// Refuse to delete content under retention or legal hold
+    ///
+    /// Writes through [`Self::put_with_domain`] start the retention period
+    /// of domains with a policy. Writes and deletes in
+    /// [`RETENTION_BUCKET`](super::RETENTION_BUCKET) are refused, so back
+    /// the registry with a store that does not enforce it.
+    pub fn with_retention(mut self, retention: Arc<Retention>) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Record every write and delete, and reads if the log asks for them,
+    /// in an audit log under `actor`
+    pub fn with_audit(mut self, audit: Arc<AuditLog>, actor: impl Into<String>) -> Self {
+        self.audit = Some((audit, actor.into()));
+        self
+    }
+
+    /// Allow only what the credential's token grants
+    ///
+    /// Every read, write, delete, listing, and watch is signed with the
+    /// credential and checked by `authorizer` before it reaches NATS.
+    pub fn with_capability(mut self, authorizer: Arc<Authorizer>, credential: Credential) -> Self {
+        self.capability = Some((authorizer, credential));
+        self
+    }
+
+    /// Check a request against the capability token, if there is one
+    fn authorize(&self, action: Action, bucket_name: &str, cid: Option<&Cid>) -> Result<()> {
+        match &self.capability {
+            Some((authorizer, credential)) => authorizer.authorize(&credential.invoke(action, bucket_name, cid)),
+            None => Ok(()),
+        }
+    }
+
+    /// Append an event to the audit log, if there is one
+    async fn audit(&self, action: AuditAction, bucket_name: &str, cid: &Cid) -> Result<()> {
+        let Some((audit, actor)) = &self.audit else {
+            return Ok(());
+        };
+        // Entries of a log kept in this store are not audited themselves
+        if bucket_name == AUDIT_BUCKET {
+            return Ok(());
+        }
+        let event = AuditEvent::new(action, *cid, actor.as_str()).with_bucket(bucket_name);
+        let result = match action {
+            AuditAction::Read => audit.record_read(event).await,
+            _ => audit.record(event).await.map(|_| ()),
+        };
+        result.map_err(|e| match e {
+            crate::Error::ObjectStore(e) => e,
+            other => ObjectStoreError::Storage(format!("audit log: {other}")),
+        })
+    }
+
+    /// Tenant this store is confined to, if any
+    pub fn tenant(&self) -> Option<&TenantId> {
+        self.tenant.as_ref()
+    }
+
+    /// Storage used by this store's tenant
+    pub fn tenant_usage(&self) -> Option<TenantUsage> {
+        self.usage.as_ref().map(|usage| usage.usage())
+    }
+
+    /// NATS name of a bucket, inside the tenant namespace if there is one
+    fn physical_bucket(&self, bucket_name: &str) -> String {
+        match &self.tenant {
+            Some(tenant) => tenant.namespace(bucket_name),
+            None => bucket_name.to_string(),
+        }
+    }
+
+    /// Count the objects in every bucket of the tenant's namespace
+    async fn seed_usage(&self, usage: &UsageTracker) -> Result<()> {
+        if self.tenant.is_none() {
+            return Ok(());
+        }
+
+        for bucket in self.bucket_names().await? {
+            let object_store = self
+                .open_object_store(&bucket, format!("CIM bucket: {bucket}"))
+                .await?;
+            let objects = self.list_object_store(&bucket, &object_store).await?;
+            usage.seed(&bucket, &objects);
+        }
+        Ok(())
+    }
+
+    /// Names of the object store buckets, without the tenant namespace
+    ///
+    /// A tenant's store only sees the buckets in its namespace.
+    async fn bucket_names(&self) -> Result<Vec<String>> {
+        let mut names = self.jetstream.stream_names();
+        let mut buckets = Vec::new();
+        while let Some(name) = names.next().await {
+            let name = name.map_err(|e| ObjectStoreError::unreachable("listing streams", e))?;
+            let Some(bucket) = name.strip_prefix("OBJ_") else {
+                continue;
+            };
+            let bucket = match &self.tenant {
+                Some(tenant) => tenant.strip_namespace(bucket),
+                None => Some(bucket),
+            };
+            if let Some(bucket) = bucket {
+                buckets.push(bucket.to_string());
+            }
+        }
+        buckets.sort();
+        Ok(buckets)
+    }
+
     /// Ensure a bucket exists, creating it if necessary
     async fn ensure_bucket(&self, bucket: ContentBucket) -> Result<()> {
         let bucket_name = bucket.as_str();
+        let object_store = self
+            .open_object_store(bucket_name, format!("CIM content bucket for {bucket_name}"))
+            .await?;
 
-        // Try to get existing bucket
-        match self.jetstream.get_object_store(bucket_name).await {
-            Ok(object_store) => {
-                let mut buckets = self.buckets.write().await;
-                buckets.insert(bucket, object_store);
-                Ok(())
-            }
-            Err(_) => {
-                // Create new bucket
-                let config = jetstream::object_store::Config {
-                    bucket: bucket_name.to_string(),
-                    description: Some(format!("CIM content bucket for {bucket_name}")),
-                    max_age: Duration::from_secs(365 * 24 * 60 * 60), // 365 days
-                    ..Default::default()
-                };
-
-                let object_store = self.jetstream.create_object_store(config).await
-                    .map_err(|e| ObjectStoreError::BucketCreation(e.to_string()))?;
-
-                let mut buckets = self.buckets.write().await;
-                buckets.insert(bucket, object_store);
-                Ok(())
-            }
+        let mut buckets = self.buckets.write().await;
+        buckets.insert(bucket, object_store);
+        Ok(())
+    }
+
+    /// Open an object store bucket, creating it only if its stream does not exist
+    ///
+    /// Any other failure is returned, so an unreachable server is never
+    /// mistaken for a missing bucket.
+    async fn open_object_store(&self, bucket_name: &str, description: String) -> Result<ObjectStore> {
+        let physical = self.physical_bucket(bucket_name);
+        let existing = self.resilience
+            .run(bucket_name, "open_bucket", || async {
+                match self.jetstream.get_object_store(&physical).await {
+                    Ok(object_store) => Ok(Some(object_store)),
+                    Err(e) if is_missing_stream(&e) => Ok(None),
+                    Err(e) if e.kind() == ObjectStoreErrorKind::InvalidBucketName => {
+                        Err(ObjectStoreError::BucketNotFound(format!("{bucket_name}: {e}")))
+                    }
+                    Err(e) => Err(ObjectStoreError::unreachable(format!("opening {bucket_name}"), e)),
+                }
+            })
+            .await?;
+        if let Some(object_store) = existing {
+            return Ok(object_store);
         }
+
+        debug!("Creating object store bucket {physical}");
+        let config = jetstream::object_store::Config {
+            bucket: physical.clone(),
+            description: Some(description),
+            max_age: Duration::from_secs(365 * 24 * 60 * 60), // 365 days
+            ..Default::default()
+        };
+
+        self.resilience
+            .run(bucket_name, "create_bucket", || async {
+                self.jetstream.create_object_store(config.clone()).await
+                    .map_err(|e| match e.kind() {
+                        jetstream::context::CreateKeyValueErrorKind::TimedOut => {
+                            ObjectStoreError::timeout(format!("creating {bucket_name}"), e)
+                        }
+                        _ => ObjectStoreError::BucketCreation(e.to_string()),
+                    })
+            })
+            .await
     }
 
     /// Get the object store for a specific bucket
@@ -207,71 +569,266 @@ impl NatsObjectStore {
             .ok_or_else(|| ObjectStoreError::BucketNotFound(bucket.as_str().to_string()))
     }
 
-    /// Store content by its CID
-    pub async fn put<T: TypedContent>(&self, content: &T) -> Result<Cid> {
-        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
-        let object_store = self.get_bucket(bucket).await?;
+    /// Get the object store for a bucket name, creating the bucket if necessary
+    async fn get_bucket_by_name(&self, bucket_name: &str) -> Result<ObjectStore> {
+        {
+            let buckets = self.buckets.read().await;
+            if let Some(object_store) = buckets.iter()
+                .find(|(bucket, _)| bucket.as_str() == bucket_name)
+                .map(|(_, object_store)| object_store.clone())
+            {
+                return Ok(object_store);
+            }
+        }
 
-        // Calculate CID
-        let cid = content.calculate_cid()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+        self.ensure_domain_bucket(bucket_name).await?;
+        self.domain_buckets.read().await
+            .get(bucket_name)
+            .cloned()
+            .ok_or_else(|| ObjectStoreError::BucketNotFound(bucket_name.to_string()))
+    }
 
-        // Serialize content
-        let data = content.to_bytes()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+    /// Write serialized content, compressing it if over the threshold
+    async fn write_object(&self, bucket_name: &str, object_store: &ObjectStore, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        self.authorize(Action::Write, bucket_name, Some(cid))?;
+        if self.retention.is_some() {
+            check_record_bucket(bucket_name)?;
+        }
+        let started = Instant::now();
+        let result = self.write_compressed(bucket_name, object_store, cid, data).await;
+        store_metrics().put.record(started, &result);
+        result?;
+        self.audit(AuditAction::Store, bucket_name, cid).await
+    }
+
+    async fn write_compressed(&self, bucket_name: &str, object_store: &ObjectStore, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        // Concurrent writes of one object must not both count it
+        let _lock = match &self.usage {
+            Some(usage) => Some(usage.lock_object(bucket_name, cid).await),
+            None => None,
+        };
+
+        // Same CID, same content: rewriting it would only emit a spurious change
+        match self.object_metadata(bucket_name, object_store, cid).await {
+            Ok(_) => return Ok(()),
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e),
+        }
 
         // Compress if over threshold
-        let (data, _compressed) = if data.len() > self.compression_threshold {
+        let (data, compressed) = if data.len() > self.compression_threshold {
             let compressed = encode_all(&data[..], 3)
-                .map_err(|e| ObjectStoreError::Compression(e.to_string()))?;
+                .map_err(ObjectStoreError::compression)?;
+            store_metrics().compression_ratio.observe(compressed.len() as f64 / data.len() as f64);
             (compressed, true)
         } else {
             (data, false)
         };
+        store_metrics().bytes_written.inc_by(data.len() as u64);
+
+        // Count new objects against the tenant quota
+        let counted = match &self.usage {
+            Some(usage) => {
+                usage.add(bucket_name, data.len() as u64)?;
+                Some(usage)
+            }
+            None => None,
+        };
 
         // Store in NATS
         let key = cid.to_string();
 
-        // Put the object - use key.as_str() to get &str
-        object_store.put(key.as_str(), &mut data.as_slice()).await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        // Each attempt re-reads the same bytes, so retries are idempotent
+        let result = self.resilience
+            .run(bucket_name, "put", || async {
+                object_store.put(put_metadata(&key, compressed), &mut data.as_slice()).await
+                    .map_err(|e| put_error(&key, e))
+            })
+            .await;
+        if let (Err(_), Some(usage)) = (&result, counted) {
+            usage.remove(bucket_name, data.len() as u64);
+        }
 
-        Ok(cid)
+        result.map(|_| ())
     }
 
-    /// Retrieve content by CID
-    pub async fn get<T: TypedContent>(&self, cid: &Cid) -> Result<T> {
-        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
-        let object_store = self.get_bucket(bucket).await?;
+    /// Read serialized content, decompressing it if needed
+    async fn read_object(&self, bucket_name: &str, object_store: &ObjectStore, cid: &Cid) -> Result<Vec<u8>> {
+        self.authorize(Action::Read, bucket_name, Some(cid))?;
+        let started = Instant::now();
+        let result = self.read_decompressed(bucket_name, object_store, cid).await;
+        store_metrics().get.record(started, &result);
+        let data = result?;
+        self.audit(AuditAction::Read, bucket_name, cid).await?;
+        Ok(data)
+    }
 
+    async fn read_decompressed(&self, bucket_name: &str, object_store: &ObjectStore, cid: &Cid) -> Result<Vec<u8>> {
         let key = cid.to_string();
 
-        // Get the object
-        let mut object = object_store.get(&key).await
-            .map_err(|_| ObjectStoreError::NotFound(key.clone()))?;
-
-        // Read all data from the stream
-        let mut data = Vec::new();
-        object.read_to_end(&mut data).await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        // Get the object and read all data from the stream
+        let data = self.resilience
+            .run(bucket_name, "get", || async {
+                let mut object = object_store.get(&key).await
+                    .map_err(|e| get_error(&key, e))?;
+                let mut data = Vec::new();
+                object.read_to_end(&mut data).await
+                    .map_err(|e| ObjectStoreError::unreachable(format!("reading {key}"), e))?;
+                Ok::<_, ObjectStoreError>(data)
+            })
+            .await?;
+        store_metrics().bytes_read.inc_by(data.len() as u64);
 
         // For now, assume compressed if data looks compressed (starts with zstd magic)
         let compressed = data.len() >= 4 && data[0..4] == [0x28, 0xb5, 0x2f, 0xfd];
 
         // Decompress if needed
-        let data = if compressed {
+        if compressed {
             decode_all(&data[..])
-                .map_err(|e| ObjectStoreError::Compression(e.to_string()))?
+                .map_err(ObjectStoreError::compression)
         } else {
-            data
+            Ok(data)
+        }
+    }
+
+    /// Delete an object, recording its latency
+    async fn delete_object(&self, bucket_name: &str, object_store: &ObjectStore, cid: &Cid) -> Result<()> {
+        self.authorize(Action::Delete, bucket_name, Some(cid))?;
+        if let Some(retention) = &self.retention {
+            check_record_bucket(bucket_name)?;
+            retention.check_delete(Some(bucket_name), cid).await?;
+        }
+        let _lock = match &self.usage {
+            Some(usage) => Some(usage.lock_object(bucket_name, cid).await),
+            None => None,
+        };
+        let size = match &self.usage {
+            Some(_) => Some(self.object_metadata(bucket_name, object_store, cid).await?.size),
+            None => None,
         };
 
+        let started = Instant::now();
+        let key = cid.to_string();
+        let result = self.resilience
+            .run(bucket_name, "delete", || async {
+                object_store.delete(key.as_str()).await
+                    .map_err(|e| delete_error(&key, e))
+            })
+            .await;
+        store_metrics().delete.record(started, &result);
+        if let (Ok(()), Some(usage), Some(size)) = (&result, &self.usage, size) {
+            usage.remove(bucket_name, size as u64);
+        }
+        result?;
+        self.audit(AuditAction::Delete, bucket_name, cid).await
+    }
+
+    /// Fetch NATS metadata for an object
+    async fn object_metadata(
+        &self,
+        bucket_name: &str,
+        object_store: &ObjectStore,
+        cid: &Cid,
+    ) -> Result<jetstream::object_store::ObjectInfo> {
+        let key = cid.to_string();
+        let info = self.resilience
+            .run(bucket_name, "info", || async {
+                object_store.info(key.as_str()).await
+                    .map_err(|e| info_error(&key, e))
+            })
+            .await?;
+        // Deleted objects keep their metadata with a deletion marker
+        if info.deleted {
+            return Err(ObjectStoreError::NotFound(key));
+        }
+        Ok(info)
+    }
+
+    /// Convert NATS object metadata into our object info
+    fn object_info(cid: Cid, info: &jetstream::object_store::ObjectInfo) -> ObjectInfo {
+        ObjectInfo {
+            cid,
+            size: info.size,
+            created_at: info.modified.map(SystemTime::from).unwrap_or_else(SystemTime::now),
+            compressed: info.headers
+                .as_ref()
+                .and_then(|h| h.get(COMPRESSED_HEADER))
+                .and_then(|v| v.as_str().parse::<bool>().ok())
+                .unwrap_or(false),
+        }
+    }
+
+    /// List all objects in a NATS object store
+    async fn list_object_store(&self, bucket_name: &str, object_store: &ObjectStore) -> Result<Vec<ObjectInfo>> {
+        Ok(self.list_object_store_page(bucket_name, object_store, &ListOptions::default()).await?.objects)
+    }
+
+    /// List one filtered page of a NATS object store, keeping at most a page in memory
+    ///
+    /// A listing that fails part way is restarted from the beginning.
+    async fn list_object_store_page(
+        &self,
+        bucket_name: &str,
+        object_store: &ObjectStore,
+        options: &ListOptions,
+    ) -> Result<ListPage> {
+        self.authorize(Action::List, bucket_name, None)?;
+        self.resilience
+            .run(bucket_name, "list", || async {
+                let mut list = object_store.list().await
+                    .map_err(|e| match e.kind() {
+                        ListErrorKind::TimedOut => ObjectStoreError::timeout(format!("listing {bucket_name}"), e),
+                        _ => ObjectStoreError::unreachable(format!("listing {bucket_name}"), e),
+                    })?;
+
+                let mut collector = PageCollector::new(options);
+                while let Some(info) = list.next().await {
+                    let info = info
+                        .map_err(|e| ObjectStoreError::unreachable(format!("listing {bucket_name}"), e))?;
+
+                    if let Ok(cid) = Cid::try_from(info.name.as_str()) {
+                        if collector.wants(&cid) {
+                            collector.offer(Self::object_info(cid, &info));
+                        }
+                    }
+                }
+
+                Ok(collector.finish())
+            })
+            .await
+    }
+
+    /// Store content by its CID
+    pub async fn put<T: TypedContent>(&self, content: &T) -> Result<Cid> {
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        let object_store = self.get_bucket(bucket).await?;
+
+        // Calculate CID
+        let cid = content.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+
+        // Serialize content
+        let data = content.to_bytes()
+            .map_err(ObjectStoreError::serialization)?;
+
+        self.write_object(bucket.as_str(), &object_store, &cid, data).await?;
+
+        Ok(cid)
+    }
+
+    /// Retrieve content by CID
+    pub async fn get<T: TypedContent>(&self, cid: &Cid) -> Result<T> {
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        let object_store = self.get_bucket(bucket).await?;
+
+        let data = self.read_object(bucket.as_str(), &object_store, cid).await?;
+
         // Deserialize and verify CID
         let content = T::from_bytes(&data)
-            .map_err(|e| ObjectStoreError::Deserialization(e.to_string()))?;
+            .map_err(ObjectStoreError::deserialization)?;
 
         let computed_cid = content.calculate_cid()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+            .map_err(ObjectStoreError::serialization)?;
 
         if computed_cid != *cid {
             return Err(ObjectStoreError::CidMismatch {
@@ -288,10 +845,11 @@ impl NatsObjectStore {
         let bucket = ContentBucket::for_content_type(content_type);
         let object_store = self.get_bucket(bucket).await?;
 
-        let key = cid.to_string();
-        match object_store.info(&key).await {
+        self.authorize(Action::Read, bucket.as_str(), Some(cid))?;
+        match self.object_metadata(bucket.as_str(), &object_store, cid).await {
             Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
         }
     }
 
@@ -300,39 +858,110 @@ impl NatsObjectStore {
         let bucket = ContentBucket::for_content_type(content_type);
         let object_store = self.get_bucket(bucket).await?;
 
-        let key = cid.to_string();
-        object_store.delete(&key).await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
-
-        Ok(())
+        self.delete_object(bucket.as_str(), &object_store, cid).await
     }
 
     /// List all objects in a bucket
     pub async fn list(&self, bucket: ContentBucket) -> Result<Vec<ObjectInfo>> {
         let object_store = self.get_bucket(bucket).await?;
+        self.list_object_store(bucket.as_str(), &object_store).await
+    }
 
-        let mut list = object_store.list().await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
-
-        let mut objects = Vec::new();
-        while let Some(info) = list.next().await {
-            let info = info.map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
-
-            if let Ok(cid) = Cid::try_from(info.name.as_str()) {
-                objects.push(ObjectInfo {
-                    cid,
-                    size: info.size,
-                    created_at: SystemTime::now(), // NATS doesn't provide mtime in the API
-                    compressed: info.headers
-                        .as_ref()
-                        .and_then(|h| h.get("Compressed"))
-                        .and_then(|v| v.as_str().parse::<bool>().ok())
-                        .unwrap_or(false),
-                });
-            }
+    /// Watch a bucket for content being added or deleted
+    ///
+    /// Event sequences are JetStream stream sequences, so a consumer can
+    /// resume with [`WatchFrom::Sequence`] after the last event it processed.
+    pub async fn watch(&self, bucket: ContentBucket, from: WatchFrom) -> Result<ContentEventStream> {
+        self.get_bucket(bucket).await?;
+        self.watch_object_store(bucket.as_str(), from).await
+    }
+
+    /// Follow the metadata subjects of an object store bucket
+    async fn watch_object_store(&self, bucket_name: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        self.authorize(Action::List, bucket_name, None)?;
+        let physical = self.physical_bucket(bucket_name);
+        let stream = self.resilience
+            .run(bucket_name, "watch", || async {
+                self.jetstream.get_stream(format!("OBJ_{physical}")).await
+                    .map_err(|e| stream_error(bucket_name, e))
+            })
+            .await?;
+
+        if history_truncated(stream.cached_info(), from, SystemTime::now()) {
+            return Err(ObjectStoreError::HistoryTruncated(format!(
+                "events of bucket {bucket_name} from {from:?} are no longer retained"
+            )));
         }
 
-        Ok(objects)
+        let deliver_policy = match from {
+            WatchFrom::New => jetstream::consumer::DeliverPolicy::New,
+            WatchFrom::Beginning => jetstream::consumer::DeliverPolicy::All,
+            WatchFrom::Sequence(start_sequence) => {
+                jetstream::consumer::DeliverPolicy::ByStartSequence { start_sequence }
+            }
+            WatchFrom::Time(start_time) => jetstream::consumer::DeliverPolicy::ByStartTime {
+                start_time: start_time.into(),
+            },
+        };
+
+        let consumer = stream.create_consumer(jetstream::consumer::push::OrderedConfig {
+            deliver_subject: format!("_INBOX.cim-watch.{:032x}", rand::random::<u128>()),
+            description: Some(format!("CIM content watcher for {bucket_name}")),
+            filter_subject: format!("$O.{physical}.M.>"),
+            deliver_policy,
+            ..Default::default()
+        }).await
+            .map_err(|e| ObjectStoreError::Nats(e.into()))?;
+
+        let messages = consumer.messages().await
+            .map_err(|e| ObjectStoreError::Nats(e.into()))?;
+
+        let bucket = bucket_name.to_string();
+        Ok(messages
+            .filter_map(move |message| {
+                let event = message
+                    .map_err(|e| ObjectStoreError::Nats(e.into()))
+                    .and_then(|message| Self::content_event(&bucket, &message))
+                    .transpose();
+                futures::future::ready(event)
+            })
+            .boxed())
+    }
+
+    /// Convert an object metadata message into a content event
+    ///
+    /// Returns `Ok(None)` for objects whose names are not CIDs.
+    fn content_event(bucket: &str, message: &jetstream::Message) -> Result<Option<ContentEvent>> {
+        let info = message.info()
+            .map_err(ObjectStoreError::from)?;
+        let object: jetstream::object_store::ObjectInfo = serde_json::from_slice(&message.payload)
+            .map_err(ObjectStoreError::deserialization)?;
+
+        let Ok(cid) = Cid::try_from(object.name.as_str()) else {
+            return Ok(None);
+        };
+
+        let (kind, size) = if object.deleted {
+            (ContentEventKind::Deleted, 0)
+        } else {
+            (ContentEventKind::Added, object.size)
+        };
+
+        Ok(Some(ContentEvent {
+            kind,
+            bucket: bucket.to_string(),
+            cid,
+            size,
+            codec: cid.codec(),
+            sequence: info.stream_sequence,
+            timestamp: info.published.into(),
+        }))
+    }
+
+    /// List one page of a bucket, filtered and ordered by CID
+    pub async fn list_page(&self, bucket: ContentBucket, options: &ListOptions) -> Result<ListPage> {
+        let object_store = self.get_bucket(bucket).await?;
+        self.list_object_store_page(bucket.as_str(), &object_store, options).await
     }
 
     /// Get bucket statistics
@@ -357,30 +986,13 @@ impl NatsObjectStore {
         }
         drop(buckets);
 
-        // Try to get existing bucket
-        match self.jetstream.get_object_store(bucket_name).await {
-            Ok(object_store) => {
-                let mut buckets = self.domain_buckets.write().await;
-                buckets.insert(bucket_name.to_string(), object_store);
-                Ok(())
-            }
-            Err(_) => {
-                // Create new bucket
-                let config = jetstream::object_store::Config {
-                    bucket: bucket_name.to_string(),
-                    description: Some(format!("CIM domain bucket: {bucket_name}")),
-                    max_age: Duration::from_secs(365 * 24 * 60 * 60), // 365 days
-                    ..Default::default()
-                };
-
-                let object_store = self.jetstream.create_object_store(config).await
-                    .map_err(|e| ObjectStoreError::BucketCreation(e.to_string()))?;
-
-                let mut buckets = self.domain_buckets.write().await;
-                buckets.insert(bucket_name.to_string(), object_store);
-                Ok(())
-            }
-        }
+        let object_store = self
+            .open_object_store(bucket_name, format!("CIM domain bucket: {bucket_name}"))
+            .await?;
+
+        let mut buckets = self.domain_buckets.write().await;
+        buckets.insert(bucket_name.to_string(), object_store);
+        Ok(())
     }
 
     /// Store content with domain-based partitioning
@@ -402,31 +1014,23 @@ impl NatsObjectStore {
         self.ensure_domain_bucket(&bucket_name).await?;
 
         // Get the bucket
-        let buckets = self.domain_buckets.read().await;
-        let object_store = buckets.get(&bucket_name)
+        let object_store = self.domain_buckets.read().await
+            .get(&bucket_name)
+            .cloned()
             .ok_or_else(|| ObjectStoreError::BucketNotFound(bucket_name.clone()))?;
 
         // Calculate CID
         let cid = content.calculate_cid()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+            .map_err(ObjectStoreError::serialization)?;
 
         // Serialize content
         let data = content.to_bytes()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
-
-        // Compress if over threshold
-        let (data, _compressed) = if data.len() > self.compression_threshold {
-            let compressed = encode_all(&data[..], 3)
-                .map_err(|e| ObjectStoreError::Compression(e.to_string()))?;
-            (compressed, true)
-        } else {
-            (data, false)
-        };
+            .map_err(ObjectStoreError::serialization)?;
 
-        // Store in NATS
-        let key = cid.to_string();
-        object_store.put(key.as_str(), &mut data.as_slice()).await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+        self.write_object(&bucket_name, &object_store, &cid, data).await?;
+        if let Some(retention) = &self.retention {
+            retention.record_write(&bucket_name, &cid, Some(domain)).await?;
+        }
 
         Ok((cid, domain))
     }
@@ -441,38 +1045,19 @@ impl NatsObjectStore {
         let bucket_name = strategy.get_bucket_for_domain(domain).to_string();
         drop(strategy);
 
-        let buckets = self.domain_buckets.read().await;
-        let object_store = buckets.get(&bucket_name)
+        let object_store = self.domain_buckets.read().await
+            .get(&bucket_name)
+            .cloned()
             .ok_or_else(|| ObjectStoreError::BucketNotFound(bucket_name.clone()))?;
 
-        let key = cid.to_string();
-
-        // Get the object
-        let mut object = object_store.get(&key).await
-            .map_err(|_| ObjectStoreError::NotFound(key.clone()))?;
-
-        // Read all data from the stream
-        let mut data = Vec::new();
-        object.read_to_end(&mut data).await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
-
-        // For now, assume compressed if data looks compressed (starts with zstd magic)
-        let compressed = data.len() >= 4 && data[0..4] == [0x28, 0xb5, 0x2f, 0xfd];
-
-        // Decompress if needed
-        let data = if compressed {
-            decode_all(&data[..])
-                .map_err(|e| ObjectStoreError::Compression(e.to_string()))?
-        } else {
-            data
-        };
+        let data = self.read_object(&bucket_name, &object_store, cid).await?;
 
         // Deserialize and verify CID
         let content = T::from_bytes(&data)
-            .map_err(|e| ObjectStoreError::Deserialization(e.to_string()))?;
+            .map_err(ObjectStoreError::deserialization)?;
 
         let computed_cid = content.calculate_cid()
-            .map_err(|e| ObjectStoreError::Serialization(e.to_string()))?;
+            .map_err(ObjectStoreError::serialization)?;
 
         if computed_cid != *cid {
             return Err(ObjectStoreError::CidMismatch {
@@ -490,32 +1075,12 @@ impl NatsObjectStore {
         let bucket_name = strategy.get_bucket_for_domain(domain).to_string();
         drop(strategy);
 
-        let buckets = self.domain_buckets.read().await;
-        let object_store = buckets.get(&bucket_name)
+        let object_store = self.domain_buckets.read().await
+            .get(&bucket_name)
+            .cloned()
             .ok_or_else(|| ObjectStoreError::BucketNotFound(bucket_name.clone()))?;
 
-        let mut list = object_store.list().await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
-
-        let mut objects = Vec::new();
-        while let Some(info) = list.next().await {
-            let info = info.map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
-
-            if let Ok(cid) = Cid::try_from(info.name.as_str()) {
-                objects.push(ObjectInfo {
-                    cid,
-                    size: info.size,
-                    created_at: SystemTime::now(),
-                    compressed: info.headers
-                        .as_ref()
-                        .and_then(|h| h.get("Compressed"))
-                        .and_then(|v| v.as_str().parse::<bool>().ok())
-                        .unwrap_or(false),
-                });
-            }
-        }
-
-        Ok(objects)
+        self.list_object_store(&bucket_name, &object_store).await
     }
 
     /// Update partition strategy
@@ -532,20 +1097,9 @@ impl NatsObjectStore {
         let bucket = ContentBucket::for_content_type(content_type);
         let object_store = self.get_bucket(bucket).await?;
 
-        let key = cid.to_string();
-        let info = object_store.info(&key).await
-            .map_err(|_| ObjectStoreError::NotFound(key.clone()))?;
-
-        Ok(ObjectInfo {
-            cid: *cid,
-            size: info.size,
-            created_at: SystemTime::now(), // NATS doesn't provide mtime
-            compressed: info.headers
-                .as_ref()
-                .and_then(|h| h.get("Compressed"))
-                .and_then(|v| v.as_str().parse::<bool>().ok())
-                .unwrap_or(false),
-        })
+        self.authorize(Action::Read, bucket.as_str(), Some(cid))?;
+        let info = self.object_metadata(bucket.as_str(), &object_store, cid).await?;
+        Ok(Self::object_info(*cid, &info))
     }
 
     /// List objects by content type with optional prefix filter
@@ -555,37 +1109,248 @@ impl NatsObjectStore {
         prefix: Option<&str>,
     ) -> Result<Vec<ObjectInfo>> {
         let bucket = ContentBucket::for_content_type(content_type);
-        let object_store = self.get_bucket(bucket).await?;
+        let filter = ListFilter {
+            cid_prefix: prefix.map(str::to_string),
+            ..Default::default()
+        };
+        Ok(self.list_page(bucket, &ListOptions::filtered(filter)).await?.objects)
+    }
+}
 
-        let mut list = object_store.list().await
-            .map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+/// Metric handles for one object store operation
+struct OperationMetrics {
+    success: Counter,
+    error: Counter,
+    duration: Histogram,
+}
 
-        let mut objects = Vec::new();
-        while let Some(info) = list.next().await {
-            let info = info.map_err(|e| ObjectStoreError::Storage(e.to_string()))?;
+impl OperationMetrics {
+    fn register(registry: &MetricsRegistry, operation: &str) -> Self {
+        let outcome = |outcome| {
+            registry.counter(
+                "cim_ipld_object_store_operations_total",
+                "Object store operations by outcome",
+                &[("operation", operation), ("outcome", outcome)],
+            )
+        };
+        Self {
+            success: outcome("success"),
+            error: outcome("error"),
+            duration: registry.histogram(
+                "cim_ipld_object_store_operation_duration_seconds",
+                "Object store operation latency",
+                &[("operation", operation)],
+                LATENCY_BUCKETS,
+            ),
+        }
+    }
 
-            // Filter by prefix if provided
-            if let Some(prefix) = prefix {
-                if !info.name.starts_with(prefix) {
-                    continue;
-                }
-            }
+    /// Record an operation's outcome and latency
+    fn record<T>(&self, started: Instant, result: &Result<T>) {
+        if result.is_ok() { &self.success } else { &self.error }.inc();
+        self.duration.observe(started.elapsed().as_secs_f64());
+    }
+}
 
-            if let Ok(cid) = Cid::try_from(info.name.as_str()) {
-                objects.push(ObjectInfo {
-                    cid,
-                    size: info.size,
-                    created_at: SystemTime::now(),
-                    compressed: info.headers
-                        .as_ref()
-                        .and_then(|h| h.get("Compressed"))
-                        .and_then(|v| v.as_str().parse::<bool>().ok())
-                        .unwrap_or(false),
-                });
-            }
+/// Metric handles for the object store, registered once per process
+struct StoreMetrics {
+    put: OperationMetrics,
+    get: OperationMetrics,
+    delete: OperationMetrics,
+    bytes_written: Counter,
+    bytes_read: Counter,
+    compression_ratio: Histogram,
+}
+
+fn store_metrics() -> &'static StoreMetrics {
+    static METRICS: OnceLock<StoreMetrics> = OnceLock::new();
+    METRICS.get_or_init(|| {
+        let registry = metrics::global();
+        // Bytes moved to or from NATS, after compression
+        let bytes = |direction| {
+            registry.counter(
+                "cim_ipld_object_store_bytes_total",
+                "Bytes transferred to and from the object store",
+                &[("direction", direction)],
+            )
+        };
+        StoreMetrics {
+            put: OperationMetrics::register(registry, "put"),
+            get: OperationMetrics::register(registry, "get"),
+            delete: OperationMetrics::register(registry, "delete"),
+            bytes_written: bytes("written"),
+            bytes_read: bytes("read"),
+            compression_ratio: registry.histogram(
+                "cim_ipld_object_store_compression_ratio",
+                "Compressed size divided by original size",
+                &[],
+                RATIO_BUCKETS,
+            ),
+        }
+    })
+}
+
+/// Object header marking zstd-compressed objects
+const COMPRESSED_HEADER: &str = "Compressed";
+
+/// Metadata for an object put, marking compressed objects for listing filters
+fn put_metadata(key: &str, compressed: bool) -> ObjectMetadata {
+    let headers = compressed.then(|| {
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(COMPRESSED_HEADER, "true");
+        headers
+    });
+    ObjectMetadata {
+        name: key.to_string(),
+        headers,
+        ..Default::default()
+    }
+}
+
+/// Whether stream limits discarded events a watch starting at `from` needs
+///
+/// Object metadata is rolled up per object and deleted objects drop their
+/// chunks, so the first retained message moves forward without anything
+/// being lost. Only the age limit, once the stream is older than it, and
+/// the message and byte limits, once the stream has filled up, discard
+/// messages.
+fn history_truncated(info: &jetstream::stream::Info, from: WatchFrom, now: SystemTime) -> bool {
+    let (config, state) = (&info.config, &info.state);
+    if state.messages == 0 {
+        return false;
+    }
+
+    let created = SystemTime::from(info.created);
+    let age_cutoff = (config.max_age > Duration::ZERO)
+        .then(|| now.checked_sub(config.max_age))
+        .flatten()
+        .filter(|cutoff| *cutoff > created);
+    let average = state.bytes / state.messages;
+    let full = (config.max_messages > 0 && state.messages >= config.max_messages as u64)
+        || (config.max_bytes > 0 && state.bytes + average >= config.max_bytes as u64);
+
+    match from {
+        WatchFrom::New | WatchFrom::Beginning => false,
+        WatchFrom::Sequence(start) => start < state.first_sequence && (full || age_cutoff.is_some()),
+        WatchFrom::Time(start) => {
+            // Nothing was published before the stream existed
+            let start = start.max(created);
+            start < SystemTime::from(state.first_timestamp)
+                && (full || age_cutoff.is_some_and(|cutoff| start < cutoff))
+        }
+    }
+}
+
+/// Whether opening an object store failed because its stream does not exist
+fn is_missing_stream(error: &jetstream::context::ObjectStoreError) -> bool {
+    use std::error::Error as _;
+
+    error.kind() == ObjectStoreErrorKind::GetStore
+        && error.source()
+            .and_then(|source| source.downcast_ref::<GetStreamError>())
+            .is_some_and(|e| matches!(
+                e.kind(),
+                GetStreamErrorKind::JetStream(e) if e.error_code() == jetstream::ErrorCode::STREAM_NOT_FOUND
+            ))
+}
+
+/// Classify a failure to look up a JetStream stream
+fn stream_error(bucket_name: &str, error: GetStreamError) -> ObjectStoreError {
+    match error.kind() {
+        GetStreamErrorKind::JetStream(e) if e.error_code() == jetstream::ErrorCode::STREAM_NOT_FOUND => {
+            ObjectStoreError::BucketNotFound(bucket_name.to_string())
+        }
+        GetStreamErrorKind::Request => ObjectStoreError::unreachable(format!("stream {bucket_name}"), error),
+        _ => ObjectStoreError::Nats(error.into()),
+    }
+}
+
+fn get_error(key: &str, error: jetstream::object_store::GetError) -> ObjectStoreError {
+    match error.kind() {
+        GetErrorKind::NotFound => ObjectStoreError::NotFound(key.to_string()),
+        GetErrorKind::TimedOut => ObjectStoreError::timeout(format!("get {key}"), error),
+        GetErrorKind::InvalidName | GetErrorKind::BucketLink => ObjectStoreError::Nats(error.into()),
+        _ => ObjectStoreError::unreachable(format!("get {key}"), error),
+    }
+}
+
+fn info_error(key: &str, error: jetstream::object_store::InfoError) -> ObjectStoreError {
+    match error.kind() {
+        InfoErrorKind::NotFound => ObjectStoreError::NotFound(key.to_string()),
+        InfoErrorKind::TimedOut => ObjectStoreError::timeout(format!("info {key}"), error),
+        InfoErrorKind::InvalidName => ObjectStoreError::Nats(error.into()),
+        _ => ObjectStoreError::unreachable(format!("info {key}"), error),
+    }
+}
+
+fn delete_error(key: &str, error: jetstream::object_store::DeleteError) -> ObjectStoreError {
+    match error.kind() {
+        DeleteErrorKind::NotFound => ObjectStoreError::NotFound(key.to_string()),
+        DeleteErrorKind::TimedOut => ObjectStoreError::timeout(format!("delete {key}"), error),
+        DeleteErrorKind::InvalidName => ObjectStoreError::Nats(error.into()),
+        _ => ObjectStoreError::unreachable(format!("delete {key}"), error),
+    }
+}
+
+fn put_error(key: &str, error: jetstream::object_store::PutError) -> ObjectStoreError {
+    match error.kind() {
+        PutErrorKind::TimedOut => ObjectStoreError::timeout(format!("put {key}"), error),
+        PutErrorKind::InvalidName | PutErrorKind::ReadChunks => ObjectStoreError::Nats(error.into()),
+        _ => ObjectStoreError::unreachable(format!("put {key}"), error),
+    }
+}
+
+#[async_trait]
+impl StorageBackend for NatsObjectStore {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        let object_store = self.get_bucket_by_name(bucket).await?;
+        self.write_object(bucket, &object_store, cid, data).await
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        let object_store = self.get_bucket_by_name(bucket).await?;
+        self.read_object(bucket, &object_store, cid).await
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        let object_store = self.get_bucket_by_name(bucket).await?;
+        self.authorize(Action::Read, bucket, Some(cid))?;
+        match self.object_metadata(bucket, &object_store, cid).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
         }
+    }
 
-        Ok(objects)
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        let object_store = self.get_bucket_by_name(bucket).await?;
+        self.delete_object(bucket, &object_store, cid).await
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        let object_store = self.get_bucket_by_name(bucket).await?;
+        self.list_object_store(bucket, &object_store).await
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        let object_store = self.get_bucket_by_name(bucket).await?;
+        self.list_object_store_page(bucket, &object_store, options).await
+    }
+
+    /// Only buckets the capability token may list are returned
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        let mut names = self.bucket_names().await?;
+        names.retain(|bucket| self.authorize(Action::List, bucket, None).is_ok());
+        Ok(names)
+    }
+
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        self.get_bucket_by_name(bucket).await?;
+        self.watch_object_store(bucket, from).await
     }
 }
 
@@ -599,4 +1364,106 @@ mod tests {
         assert_eq!(ContentBucket::Graphs.as_str(), "cim-graphs");
         assert_eq!(ContentBucket::for_content_type(0x300100), ContentBucket::Graphs);
     }
+
+    #[test]
+    fn test_error_classification() {
+        let missing = get_error("k", GetErrorKind::NotFound.into());
+        assert!(missing.is_not_found());
+        assert!(!missing.is_transient());
+
+        let timed_out = info_error("k", InfoErrorKind::TimedOut.into());
+        assert!(matches!(timed_out, ObjectStoreError::Timeout { .. }));
+        assert!(timed_out.is_transient());
+
+        let unreachable = put_error("k", PutErrorKind::PublishChunks.into());
+        assert!(matches!(unreachable, ObjectStoreError::Unreachable { .. }));
+        assert!(!unreachable.is_not_found());
+        let source = std::error::Error::source(&unreachable).expect("NATS error kept as source");
+        assert!(source.downcast_ref::<jetstream::object_store::PutError>().is_some());
+
+        let invalid = delete_error("k", DeleteErrorKind::InvalidName.into());
+        assert!(!invalid.is_transient());
+        assert!(std::error::Error::source(&invalid)
+            .is_some_and(|e| e.is::<jetstream::object_store::DeleteError>()));
+        assert!(!ObjectStoreError::circuit_open("cim-graphs").is_transient());
+    }
+
+    #[test]
+    fn test_compressed_header_roundtrip() {
+        let cid = crate::util::test_cid(b"large");
+        for compressed in [true, false] {
+            let metadata = put_metadata(&cid.to_string(), compressed);
+            let info = jetstream::object_store::ObjectInfo {
+                name: metadata.name,
+                description: None,
+                metadata: HashMap::new(),
+                headers: metadata.headers,
+                options: None,
+                bucket: "cim-graphs".to_string(),
+                nuid: String::new(),
+                size: 10,
+                chunks: 1,
+                modified: None,
+                digest: None,
+                deleted: false,
+            };
+            let listed = NatsObjectStore::object_info(cid, &info);
+            assert_eq!(listed.compressed, compressed);
+            let filter = ListFilter { compressed_only: true, ..Default::default() };
+            assert_eq!(filter.matches(&listed), compressed);
+        }
+    }
+
+    /// Stream info of a bucket created at `created` whose oldest retained
+    /// message is sequence 40, published at `first`
+    fn stream_info(created: &str, first: &str, max_age_days: u64, max_msgs: i64) -> jetstream::stream::Info {
+        let config = jetstream::stream::Config {
+            name: "OBJ_cim-graphs".to_string(),
+            max_age: Duration::from_secs(max_age_days * 24 * 60 * 60),
+            max_messages: max_msgs,
+            ..Default::default()
+        };
+        serde_json::from_value(serde_json::json!({
+            "config": config,
+            "created": created,
+            "state": {
+                "messages": 10,
+                "bytes": 1000,
+                "first_seq": 40,
+                "first_ts": first,
+                "last_seq": 60,
+                "last_ts": first,
+                "consumer_count": 0,
+            },
+            "cluster": null,
+        }))
+        .unwrap()
+    }
+
+    fn at(rfc3339: &str) -> SystemTime {
+        chrono::DateTime::parse_from_rfc3339(rfc3339).unwrap().into()
+    }
+
+    #[test]
+    fn test_history_truncation_follows_stream_limits() {
+        let now = at("2026-06-01T00:00:00Z");
+
+        // Rollups and deletes moved the first sequence; nothing was discarded
+        let compacted = stream_info("2026-01-01T00:00:00Z", "2026-03-01T00:00:00Z", 365, -1);
+        assert!(!history_truncated(&compacted, WatchFrom::Sequence(1), now));
+        assert!(!history_truncated(&compacted, WatchFrom::Time(at("2025-01-01T00:00:00Z")), now));
+        assert!(!history_truncated(&compacted, WatchFrom::Beginning, now));
+
+        // The stream outlived its age limit
+        let aged = stream_info("2024-01-01T00:00:00Z", "2025-07-01T00:00:00Z", 365, -1);
+        assert!(history_truncated(&aged, WatchFrom::Sequence(1), now));
+        assert!(history_truncated(&aged, WatchFrom::Time(at("2025-01-01T00:00:00Z")), now));
+        assert!(!history_truncated(&aged, WatchFrom::Time(at("2025-07-01T00:00:00Z")), now));
+        assert!(!history_truncated(&aged, WatchFrom::Sequence(40), now));
+
+        // The stream is at its message limit
+        let full = stream_info("2026-01-01T00:00:00Z", "2026-03-01T00:00:00Z", 365, 10);
+        assert!(history_truncated(&full, WatchFrom::Sequence(1), now));
+        assert!(history_truncated(&full, WatchFrom::Time(at("2026-02-01T00:00:00Z")), now));
+    }
 }
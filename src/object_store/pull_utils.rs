@@ -2,11 +2,14 @@
 
 //! Utility functions for pulling content from NATS JetStream by CID
 
-use super::{NatsObjectStore, ContentBucket, ObjectInfo, ObjectStoreError, Result};
+use super::{NatsObjectStore, ContentBucket, ListFilter, ListOptions, ObjectInfo, ObjectStoreError, Result};
 use crate::{TypedContent, Cid};
 use futures::stream::{self, StreamExt};
 use std::collections::HashMap;
 
+/// Number of objects fetched concurrently by `stream_objects`
+const DEFAULT_PREFETCH: usize = 8;
+
 /// Options for pulling content from JetStream
 #[derive(Debug, Clone, Default)]
 pub struct PullOptions {
@@ -20,6 +23,22 @@ pub struct PullOptions {
     pub compressed_only: bool,
 }
 
+impl PullOptions {
+    /// Equivalent listing options, so filtering happens while listing
+    pub fn to_list_options(&self) -> ListOptions {
+        ListOptions {
+            filter: ListFilter {
+                min_size: self.min_size,
+                max_size: self.max_size,
+                compressed_only: self.compressed_only,
+                ..Default::default()
+            },
+            cursor: None,
+            limit: self.limit,
+        }
+    }
+}
+
 /// Result of a pull operation
 #[derive(Debug, Clone)]
 pub struct PullResult<T> {
@@ -42,24 +61,8 @@ impl NatsObjectStore {
         bucket: ContentBucket,
         options: PullOptions,
     ) -> Result<Vec<PullResult<T>>> {
-        // List objects in bucket
-        let mut objects = self.list(bucket).await?;
-
-        // Apply filters
-        if let Some(min_size) = options.min_size {
-            objects.retain(|obj| obj.size >= min_size);
-        }
-        if let Some(max_size) = options.max_size {
-            objects.retain(|obj| obj.size <= max_size);
-        }
-        if options.compressed_only {
-            objects.retain(|obj| obj.compressed);
-        }
-
-        // Apply limit
-        if let Some(limit) = options.limit {
-            objects.truncate(limit);
-        }
+        // Filters and limit are applied while listing
+        let objects = self.list_page(bucket, &options.to_list_options()).await?.objects;
 
         // Pull each object
         let mut results = Vec::new();
@@ -94,13 +97,7 @@ impl NatsObjectStore {
                 match self.get::<T>(&cid).await {
                     Ok(content) => {
                         // Get metadata if available
-                        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
-                        let metadata = self.list(bucket).await
-                            .ok()
-                            .and_then(|objects| {
-                                objects.into_iter()
-                                    .find(|obj| obj.cid == cid)
-                            });
+                        let metadata = self.info(&cid, T::CONTENT_TYPE.codec()).await.ok();
 
                         Ok(PullResult {
                             cid,
@@ -158,11 +155,13 @@ impl NatsObjectStore {
         bucket: ContentBucket,
         prefix: &str,
     ) -> Result<Vec<PullResult<T>>> {
-        let objects = self.list(bucket).await?;
-
-        let matching_cids: Vec<_> = objects
+        let filter = ListFilter {
+            cid_prefix: Some(prefix.to_string()),
+            ..Default::default()
+        };
+        let matching_cids: Vec<_> = self.list_page(bucket, &ListOptions::filtered(filter)).await?
+            .objects
             .into_iter()
-            .filter(|obj| obj.cid.to_string().starts_with(prefix))
             .map(|obj| obj.cid)
             .collect();
 
@@ -202,35 +201,34 @@ impl NatsObjectStore {
         &self,
         bucket: ContentBucket,
     ) -> impl futures::Stream<Item = Result<PullResult<T>>> + '_ {
-        stream::unfold(Some(0usize), move |state| async move {
-            let offset = state?;
-
-            // Get next batch of objects
-            let objects = match self.list(bucket).await {
-                Ok(objs) => objs,
-                Err(e) => return Some((Err(e), None)),
-            };
-
-            // Check if we've processed all objects
-            if offset >= objects.len() {
-                return None;
-            }
+        self.stream_objects_with(bucket, ListOptions::default(), DEFAULT_PREFETCH)
+    }
 
-            // Get next object
-            let obj = &objects[offset];
-            
-            match self.get::<T>(&obj.cid).await {
-                Ok(content) => {
-                    let result = Ok(PullResult {
-                        cid: obj.cid,
-                        content,
-                        metadata: obj.clone(),
-                    });
-                    Some((result, Some(offset + 1)))
-                }
-                Err(e) => Some((Err(e), Some(offset + 1))),
-            }
-        })
+    /// Stream objects matching the listing options
+    ///
+    /// The bucket is listed once; content is then fetched in CID order with
+    /// up to `prefetch` requests in flight.
+    pub fn stream_objects_with<T: TypedContent>(
+        &self,
+        bucket: ContentBucket,
+        options: ListOptions,
+        prefetch: usize,
+    ) -> impl futures::Stream<Item = Result<PullResult<T>>> + '_ {
+        stream::once(async move { self.list_page(bucket, &options).await })
+            .flat_map(|page| match page {
+                Ok(page) => stream::iter(page.objects.into_iter().map(Ok)).left_stream(),
+                Err(e) => stream::iter(vec![Err(e)]).right_stream(),
+            })
+            .map(move |obj: Result<ObjectInfo>| async move {
+                let obj = obj?;
+                let content = self.get::<T>(&obj.cid).await?;
+                Ok(PullResult {
+                    cid: obj.cid,
+                    content,
+                    metadata: obj,
+                })
+            })
+            .buffered(prefetch.max(1))
     }
 }
 
@@ -306,4 +304,20 @@ mod tests {
         assert_eq!(options.max_size, Some(1024 * 1024));
         assert!(options.compressed_only);
     }
+
+    #[test]
+    fn test_pull_options_to_list_options() {
+        let options = PullOptions {
+            limit: Some(5),
+            min_size: Some(10),
+            compressed_only: true,
+            ..Default::default()
+        };
+
+        let list_options = options.to_list_options();
+        assert_eq!(list_options.limit, Some(5));
+        assert_eq!(list_options.filter.min_size, Some(10));
+        assert!(list_options.filter.compressed_only);
+        assert!(list_options.cursor.is_none());
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,951 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Mutable named references to immutable content
+//!
+//! A named ref maps a name such as `chains.orders` to a CID, like an IPNS
+//! record. Updates are compare-and-swap on the store revision, previous
+//! values are kept as history, and values can be signed with Ed25519 so
+//! readers only follow refs written by keys they trust.
+//!
+//! A signed value also names the CID it replaced, so trusted values form a
+//! chain: readers that require signatures reject a value that does not
+//! follow the one before it in history or in a watch, and one older than a
+//! value they have already seen, so an old signed value cannot be replayed.
+//!
+//! Refs live in a [`RefStore`]: [`KvRefStore`] keeps them in a NATS KV
+//! bucket, [`MemoryRefStore`] keeps them in process.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_nats::jetstream::{self, kv::{Config as KvConfig, Operation, Store as KvStore}};
+use async_nats::jetstream::context::CreateKeyValueErrorKind;
+use async_nats::jetstream::kv::{
+    CreateErrorKind, EntryErrorKind, UpdateErrorKind, WatchError, WatchErrorKind,
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cid::Cid;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use super::{ObjectStoreError, Resilience, Result, TenantId};
+use crate::chain::ContentChain;
+use crate::TypedContent;
+use crate::util::cid_serde;
+
+/// Default NATS KV bucket for named refs
+pub const REFS_BUCKET: &str = "cim-refs";
+
+/// Previous values kept per ref
+const DEFAULT_HISTORY: usize = 64;
+
+/// Attempts made by [`NamedRefs::update`] before reporting a conflict
+const MAX_UPDATE_ATTEMPTS: usize = 16;
+
+/// Ed25519 signature over a ref value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefSignature {
+    /// Base64 public key of the signer
+    pub public_key: String,
+    /// Base64 signature
+    pub signature: String,
+}
+
+/// Value stored for a named ref
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefValue {
+    /// Content the ref points to
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Update time in milliseconds since the Unix epoch
+    pub updated_at: u64,
+    /// CID this value replaced, or `None` if the ref did not exist
+    #[serde(default, with = "cid_serde::option")]
+    pub previous: Option<Cid>,
+    /// Optional signature binding the name, CID, time and previous CID
+    pub signature: Option<RefSignature>,
+}
+
+impl RefValue {
+    /// An unsigned value pointing at a CID
+    pub fn new(cid: Cid) -> Self {
+        Self {
+            cid,
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            previous: None,
+            signature: None,
+        }
+    }
+
+    /// Record the CID this value replaces
+    pub fn with_previous(mut self, previous: Option<Cid>) -> Self {
+        self.previous = previous;
+        self
+    }
+
+    fn signing_payload(&self, name: &str) -> Vec<u8> {
+        let previous = self.previous.map(|cid| cid.to_string()).unwrap_or_default();
+        format!("cim-ipld/ref/v2\n{name}\n{}\n{}\n{previous}", self.cid, self.updated_at).into_bytes()
+    }
+
+    /// Sign the value for a ref name
+    pub fn sign(mut self, name: &str, key: &SigningKey) -> Self {
+        let signature = key.sign(&self.signing_payload(name));
+        self.signature = Some(RefSignature {
+            public_key: BASE64.encode(key.verifying_key().as_bytes()),
+            signature: BASE64.encode(signature.to_bytes()),
+        });
+        self
+    }
+
+    /// Check the signature and return the key that made it
+    pub fn verify(&self, name: &str) -> Result<VerifyingKey> {
+        let invalid = |reason: &str| ObjectStoreError::InvalidSignature(format!("{name}: {reason}"));
+        let signature = self.signature.as_ref().ok_or_else(|| invalid("ref is not signed"))?;
+
+        let public_key: [u8; 32] = BASE64.decode(&signature.public_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("malformed public key"))?;
+        let key = VerifyingKey::from_bytes(&public_key).map_err(|_| invalid("malformed public key"))?;
+        let bytes: [u8; 64] = BASE64.decode(&signature.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("malformed signature"))?;
+
+        key.verify(&self.signing_payload(name), &Signature::from_bytes(&bytes))
+            .map_err(|_| invalid("signature does not match"))?;
+        Ok(key)
+    }
+}
+
+/// A named ref at one revision
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedRef {
+    /// Ref name
+    pub name: String,
+    /// Store revision of this value; increases with every update
+    pub revision: u64,
+    /// The stored value
+    pub value: RefValue,
+}
+
+impl NamedRef {
+    /// Content the ref points to
+    pub fn cid(&self) -> Cid {
+        self.value.cid
+    }
+}
+
+/// A ref being updated or deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefEvent {
+    /// Ref name
+    pub name: String,
+    /// Store revision of the change
+    pub revision: u64,
+    /// New value, or `None` if the ref was deleted
+    pub value: Option<RefValue>,
+}
+
+/// Stream of ref changes
+pub type RefEventStream = BoxStream<'static, Result<RefEvent>>;
+
+/// Storage for named refs
+#[async_trait]
+pub trait RefStore: Send + Sync {
+    /// Current value of a ref
+    async fn get(&self, name: &str) -> Result<Option<NamedRef>>;
+
+    /// Write a value if the ref is at `expected_revision`
+    ///
+    /// A revision of 0 means the ref must not exist. Returns the new
+    /// revision, or [`ObjectStoreError::Conflict`] if the ref has moved.
+    async fn put(&self, name: &str, value: &RefValue, expected_revision: u64) -> Result<u64>;
+
+    /// Delete a ref, keeping its history
+    async fn delete(&self, name: &str) -> Result<()>;
+
+    /// Values the ref has had, newest first
+    async fn history(&self, name: &str) -> Result<Vec<NamedRef>>;
+
+    /// Follow changes to refs whose names start with `prefix`
+    async fn watch(&self, prefix: &str) -> Result<RefEventStream>;
+}
+
+/// Check that a ref name is usable as a NATS KV key
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && !name.ends_with('.')
+        && !name.contains("..")
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_/=.".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(ObjectStoreError::Storage(format!("Invalid ref name: {name:?}")))
+    }
+}
+
+/// Ref changes in a [`MemoryRefStore`]
+#[derive(Default)]
+struct MemoryRefs {
+    revision: u64,
+    /// Oldest first; `None` marks a deletion
+    entries: HashMap<String, Vec<(u64, Option<RefValue>)>>,
+}
+
+/// In-process ref store
+pub struct MemoryRefStore {
+    refs: Mutex<MemoryRefs>,
+    events: broadcast::Sender<RefEvent>,
+    max_history: usize,
+}
+
+impl Default for MemoryRefStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY)
+    }
+}
+
+impl MemoryRefStore {
+    /// Create a store keeping up to `max_history` values per ref
+    pub fn new(max_history: usize) -> Self {
+        let (events, _) = broadcast::channel(1024);
+        Self {
+            refs: Mutex::new(MemoryRefs::default()),
+            events,
+            max_history: max_history.max(1),
+        }
+    }
+
+    fn record(&self, name: &str, value: Option<RefValue>, expected_revision: Option<u64>) -> Result<u64> {
+        let mut refs = self.refs.lock().unwrap();
+        let current = refs.entries
+            .get(name)
+            .and_then(|entries| entries.last())
+            .and_then(|(revision, value)| value.as_ref().map(|_| *revision))
+            .unwrap_or(0);
+        if let Some(expected) = expected_revision {
+            if current != expected {
+                return Err(ObjectStoreError::Conflict(format!(
+                    "{name} is at revision {current}, expected {expected}"
+                )));
+            }
+        }
+
+        refs.revision += 1;
+        let revision = refs.revision;
+        let entries = refs.entries.entry(name.to_string()).or_default();
+        entries.push((revision, value.clone()));
+        if entries.len() > self.max_history {
+            entries.remove(0);
+        }
+
+        let _ = self.events.send(RefEvent {
+            name: name.to_string(),
+            revision,
+            value,
+        });
+        Ok(revision)
+    }
+}
+
+#[async_trait]
+impl RefStore for MemoryRefStore {
+    async fn get(&self, name: &str) -> Result<Option<NamedRef>> {
+        let refs = self.refs.lock().unwrap();
+        Ok(refs.entries
+            .get(name)
+            .and_then(|entries| entries.last())
+            .and_then(|(revision, value)| {
+                value.clone().map(|value| NamedRef {
+                    name: name.to_string(),
+                    revision: *revision,
+                    value,
+                })
+            }))
+    }
+
+    async fn put(&self, name: &str, value: &RefValue, expected_revision: u64) -> Result<u64> {
+        self.record(name, Some(value.clone()), Some(expected_revision))
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.record(name, None, None).map(|_| ())
+    }
+
+    async fn history(&self, name: &str) -> Result<Vec<NamedRef>> {
+        let refs = self.refs.lock().unwrap();
+        Ok(refs.entries
+            .get(name)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .rev()
+                    .filter_map(|(revision, value)| {
+                        value.clone().map(|value| NamedRef {
+                            name: name.to_string(),
+                            revision: *revision,
+                            value,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn watch(&self, prefix: &str) -> Result<RefEventStream> {
+        let prefix = prefix.to_string();
+        let receiver = self.events.subscribe();
+        Ok(stream::unfold(receiver, move |mut receiver| {
+            let prefix = prefix.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if event.name.starts_with(&prefix) => return Some((Ok(event), receiver)),
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            let error = ObjectStoreError::Storage(format!(
+                                "Ref watcher lagged behind by {missed} events"
+                            ));
+                            return Some((Err(error), receiver));
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+        .boxed())
+    }
+}
+
+/// Ref store backed by a NATS KV bucket
+///
+/// Reads are retried through [`Resilience`]; writes are not, since a
+/// compare-and-swap that timed out may already have been applied.
+pub struct KvRefStore {
+    store: KvStore,
+    resilience: Arc<Resilience>,
+}
+
+impl KvRefStore {
+    /// Open or create a KV bucket keeping `history` values per ref (at most 64)
+    pub async fn open(jetstream: &jetstream::Context, bucket: &str, history: usize) -> Result<Self> {
+        let config = KvConfig {
+            bucket: bucket.to_string(),
+            description: "CIM named references".to_string(),
+            history: history.clamp(1, 64) as i64,
+            ..Default::default()
+        };
+        let store = jetstream.create_key_value(config).await
+            .map_err(|e| match e.kind() {
+                CreateKeyValueErrorKind::TimedOut => ObjectStoreError::timeout(format!("opening {bucket}"), e),
+                CreateKeyValueErrorKind::BucketCreate => {
+                    ObjectStoreError::unreachable(format!("opening {bucket}"), e)
+                }
+                _ => ObjectStoreError::BucketCreation(e.to_string()),
+            })?;
+
+        Ok(Self {
+            store,
+            resilience: Arc::new(Resilience::default()),
+        })
+    }
+
+    /// Replace the retry policy and circuit breakers used for reads
+    pub fn with_resilience(mut self, resilience: Resilience) -> Self {
+        self.resilience = Arc::new(resilience);
+        self
+    }
+
+    fn decode(name: &str, revision: u64, data: &[u8]) -> Result<NamedRef> {
+        let value = serde_json::from_slice(data)
+            .map_err(ObjectStoreError::deserialization)?;
+        Ok(NamedRef {
+            name: name.to_string(),
+            revision,
+            value,
+        })
+    }
+}
+
+#[async_trait]
+impl RefStore for KvRefStore {
+    async fn get(&self, name: &str) -> Result<Option<NamedRef>> {
+        let entry = self.resilience
+            .run(&self.store.name, "ref_get", || async {
+                self.store.entry(name).await.map_err(|e| match e.kind() {
+                    EntryErrorKind::TimedOut => ObjectStoreError::timeout(format!("ref {name}"), e),
+                    EntryErrorKind::InvalidKey => ObjectStoreError::Nats(e.into()),
+                    _ => ObjectStoreError::unreachable(format!("ref {name}"), e),
+                })
+            })
+            .await?;
+
+        match entry {
+            Some(entry) if entry.operation == Operation::Put => {
+                Self::decode(name, entry.revision, &entry.value).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn put(&self, name: &str, value: &RefValue, expected_revision: u64) -> Result<u64> {
+        let data = serde_json::to_vec(value)
+            .map_err(ObjectStoreError::serialization)?;
+
+        if expected_revision == 0 {
+            self.store.create(name, data.into()).await.map_err(|e| match e.kind() {
+                CreateErrorKind::AlreadyExists => {
+                    ObjectStoreError::Conflict(format!("{name} already exists"))
+                }
+                CreateErrorKind::InvalidKey => ObjectStoreError::Nats(e.into()),
+                _ => ObjectStoreError::unreachable(format!("ref {name}"), e),
+            })
+        } else {
+            self.store.update(name, data.into(), expected_revision).await.map_err(|e| match e.kind() {
+                UpdateErrorKind::WrongLastRevision => ObjectStoreError::Conflict(format!(
+                    "{name} is no longer at revision {expected_revision}"
+                )),
+                UpdateErrorKind::TimedOut => ObjectStoreError::timeout(format!("ref {name}"), e),
+                UpdateErrorKind::InvalidKey => ObjectStoreError::Nats(e.into()),
+                _ => ObjectStoreError::unreachable(format!("ref {name}"), e),
+            })
+        }
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.store.delete(name).await
+            .map_err(|e| ObjectStoreError::unreachable(format!("ref {name}"), e))
+    }
+
+    async fn history(&self, name: &str) -> Result<Vec<NamedRef>> {
+        self.resilience
+            .run(&self.store.name, "ref_history", || async {
+                let mut history = self.store.history(name).await
+                    .map_err(|e| watch_error(name, e))?;
+
+                let mut values = Vec::new();
+                while let Some(entry) = history.next().await {
+                    let entry = entry
+                        .map_err(|e| ObjectStoreError::unreachable(format!("ref {name}"), e))?;
+                    if entry.operation == Operation::Put {
+                        values.push(Self::decode(name, entry.revision, &entry.value)?);
+                    }
+                }
+                values.reverse();
+                Ok(values)
+            })
+            .await
+    }
+
+    async fn watch(&self, prefix: &str) -> Result<RefEventStream> {
+        let watch = self.store.watch_all().await
+            .map_err(|e| watch_error(prefix, e))?;
+
+        let prefix = prefix.to_string();
+        Ok(watch
+            .filter_map(move |entry| {
+                let event = match entry {
+                    Ok(entry) if entry.key.starts_with(&prefix) => {
+                        let value = match entry.operation {
+                            Operation::Put => serde_json::from_slice(&entry.value)
+                                .map(Some)
+                                .map_err(ObjectStoreError::deserialization),
+                            Operation::Delete | Operation::Purge => Ok(None),
+                        };
+                        Some(value.map(|value| RefEvent {
+                            name: entry.key,
+                            revision: entry.revision,
+                            value,
+                        }))
+                    }
+                    Ok(_) => None,
+                    Err(e) => Some(Err(ObjectStoreError::unreachable(format!("watching {prefix}"), e))),
+                };
+                futures::future::ready(event)
+            })
+            .boxed())
+    }
+}
+
+fn watch_error(name: &str, error: WatchError) -> ObjectStoreError {
+    match error.kind() {
+        WatchErrorKind::TimedOut => ObjectStoreError::timeout(format!("ref {name}"), error),
+        WatchErrorKind::InvalidKey => ObjectStoreError::Nats(error.into()),
+        _ => ObjectStoreError::unreachable(format!("ref {name}"), error),
+    }
+}
+
+/// Named refs with compare-and-swap updates and optional signing
+pub struct NamedRefs {
+    store: Arc<dyn RefStore>,
+    signer: Option<SigningKey>,
+    trusted_keys: Vec<VerifyingKey>,
+    /// Newest update time read per ref, to reject replayed values
+    seen: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl NamedRefs {
+    /// Use a ref store
+    pub fn new(store: Arc<dyn RefStore>) -> Self {
+        Self {
+            store,
+            signer: None,
+            trusted_keys: Vec::new(),
+            seen: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Keep refs in process
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(MemoryRefStore::default()))
+    }
+
+    /// Keep refs in the default NATS KV bucket
+    pub async fn nats(jetstream: &jetstream::Context) -> Result<Self> {
+        let store = KvRefStore::open(jetstream, REFS_BUCKET, DEFAULT_HISTORY).await?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// Keep refs in a tenant's namespaced copy of the default NATS KV bucket
+    ///
+    /// Tenants sharing a cluster then never see or move each other's refs.
+    pub async fn nats_for_tenant(jetstream: &jetstream::Context, tenant: &TenantId) -> Result<Self> {
+        let store = KvRefStore::open(jetstream, &tenant.namespace(REFS_BUCKET), DEFAULT_HISTORY).await?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// Sign every value written
+    pub fn with_signer(mut self, key: SigningKey) -> Self {
+        self.signer = Some(key);
+        self
+    }
+
+    /// Only accept values signed by one of the trusted keys
+    ///
+    /// Reads fail with [`ObjectStoreError::InvalidSignature`] for values
+    /// that are unsigned, signed by any other key, older than a value
+    /// already read, or not following the previous value.
+    pub fn with_trusted_key(mut self, key: VerifyingKey) -> Self {
+        self.trusted_keys.push(key);
+        self
+    }
+
+    /// Check a value against the trusted keys, if any are configured
+    fn check(&self, name: &str, value: &RefValue) -> Result<()> {
+        check_signature(&self.trusted_keys, name, value)
+    }
+
+    /// Check a value and that it is not older than one read before
+    fn check_current(&self, name: &str, value: &RefValue) -> Result<()> {
+        if self.trusted_keys.is_empty() {
+            return Ok(());
+        }
+        self.check(name, value)?;
+        let mut seen = self.seen.lock().unwrap();
+        let newest = seen.entry(name.to_string()).or_default();
+        if value.updated_at < *newest {
+            return Err(ObjectStoreError::InvalidSignature(format!(
+                "{name}: value from {} is older than one already read",
+                value.updated_at
+            )));
+        }
+        *newest = value.updated_at;
+        Ok(())
+    }
+
+    fn value_for(&self, name: &str, current: Option<&NamedRef>, cid: Cid) -> RefValue {
+        let mut value = RefValue::new(cid).with_previous(current.map(NamedRef::cid));
+        // Keep update times increasing even if clocks disagree
+        if let Some(current) = current {
+            value.updated_at = value.updated_at.max(current.value.updated_at + 1);
+        }
+        match &self.signer {
+            Some(key) => value.sign(name, key),
+            None => value,
+        }
+    }
+
+    /// Current value of a ref
+    pub async fn get(&self, name: &str) -> Result<Option<NamedRef>> {
+        validate_name(name)?;
+        let current = self.store.get(name).await?;
+        if let Some(current) = &current {
+            self.check_current(name, &current.value)?;
+        }
+        Ok(current)
+    }
+
+    /// CID a ref points to
+    pub async fn resolve(&self, name: &str) -> Result<Cid> {
+        self.get(name).await?
+            .map(|r| r.cid())
+            .ok_or_else(|| ObjectStoreError::NotFound(format!("ref {name}")))
+    }
+
+    /// Point a ref at a CID, whatever its current value
+    pub async fn set(&self, name: &str, cid: Cid) -> Result<NamedRef> {
+        self.update(name, |_| Ok(cid)).await
+    }
+
+    /// Point a ref at a CID only if it currently points at `expected`
+    ///
+    /// `None` means the ref must not exist yet.
+    pub async fn compare_and_swap(&self, name: &str, expected: Option<Cid>, cid: Cid) -> Result<NamedRef> {
+        let current = self.get(name).await?;
+        let actual = current.as_ref().map(NamedRef::cid);
+        if actual != expected {
+            return Err(ObjectStoreError::Conflict(format!(
+                "{name} points at {}, expected {}",
+                describe(actual),
+                describe(expected)
+            )));
+        }
+        self.write(name, current.as_ref(), cid).await
+    }
+
+    /// Read-modify-write a ref, retrying when concurrent updates conflict
+    ///
+    /// `next` receives the current value and returns the new CID; returning
+    /// the current CID leaves the ref unchanged.
+    pub async fn update<F>(&self, name: &str, mut next: F) -> Result<NamedRef>
+    where
+        F: FnMut(Option<&NamedRef>) -> Result<Cid> + Send,
+    {
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let current = self.get(name).await?;
+            let cid = next(current.as_ref())?;
+            match self.write(name, current.as_ref(), cid).await {
+                Err(ObjectStoreError::Conflict(reason)) => {
+                    debug!("Retrying update of {}: {}", name, reason);
+                }
+                result => return result,
+            }
+        }
+        Err(ObjectStoreError::Conflict(format!(
+            "{name} kept changing after {MAX_UPDATE_ATTEMPTS} attempts"
+        )))
+    }
+
+    async fn write(&self, name: &str, current: Option<&NamedRef>, cid: Cid) -> Result<NamedRef> {
+        if let Some(current) = current.filter(|c| c.cid() == cid) {
+            return Ok(current.clone());
+        }
+
+        // A ref recreated after a delete continues from its last value
+        let last = match current {
+            Some(_) => None,
+            None => self.store.history(name).await?.into_iter().next(),
+        };
+        let value = self.value_for(name, current.or(last.as_ref()), cid);
+        self.check(name, &value)?;
+        let expected = current.map(|c| c.revision).unwrap_or(0);
+        let revision = self.store.put(name, &value, expected).await?;
+        Ok(NamedRef {
+            name: name.to_string(),
+            revision,
+            value,
+        })
+    }
+
+    /// Delete a ref; its history is kept
+    pub async fn delete(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        self.store.delete(name).await
+    }
+
+    /// Values the ref has had, newest first
+    pub async fn history(&self, name: &str) -> Result<Vec<NamedRef>> {
+        validate_name(name)?;
+        let history = self.store.history(name).await?;
+        for entry in &history {
+            self.check(name, &entry.value)?;
+        }
+        if !self.trusted_keys.is_empty() {
+            for pair in history.windows(2) {
+                let (newer, older) = (&pair[0].value, &pair[1].value);
+                if newer.previous != Some(older.cid) {
+                    return Err(broken_link(name, newer, Some(older.cid)));
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    /// Follow changes to refs whose names start with `prefix`
+    ///
+    /// With trusted keys configured, every value is verified like a read,
+    /// and must follow the value seen before it for the same ref; an event
+    /// that does not is delivered as an error.
+    pub async fn watch(&self, prefix: &str) -> Result<RefEventStream> {
+        let events = self.store.watch(prefix).await?;
+        if self.trusted_keys.is_empty() {
+            return Ok(events);
+        }
+
+        let trusted_keys = self.trusted_keys.clone();
+        let seen = self.seen.clone();
+        // Last CID per ref; unknown until the first event for it
+        let mut last: HashMap<String, Option<Cid>> = HashMap::new();
+        Ok(events
+            .map(move |event| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        // Events may have been missed, so links cannot be checked
+                        last.clear();
+                        return Err(e);
+                    }
+                };
+                let Some(value) = &event.value else {
+                    last.insert(event.name.clone(), None);
+                    return Ok(event);
+                };
+
+                check_signature(&trusted_keys, &event.name, value)?;
+                if let Some(expected) = last.get(&event.name) {
+                    if value.previous != *expected {
+                        return Err(broken_link(&event.name, value, *expected));
+                    }
+                }
+                let mut seen = seen.lock().unwrap();
+                let newest = seen.entry(event.name.clone()).or_default();
+                if value.updated_at < *newest {
+                    return Err(ObjectStoreError::InvalidSignature(format!(
+                        "{}: value from {} is older than one already read",
+                        event.name, value.updated_at
+                    )));
+                }
+                *newest = value.updated_at;
+                last.insert(event.name.clone(), Some(value.cid));
+                Ok(event)
+            })
+            .boxed())
+    }
+
+    /// Publish the head of a content chain under a name
+    ///
+    /// The ref only moves forward: if it already points into the chain the
+    /// new head must descend from it, otherwise the update is rejected as a
+    /// conflict.
+    pub async fn advance_chain_head<T: TypedContent>(&self, name: &str, chain: &ContentChain<T>) -> Result<NamedRef> {
+        let head = chain.head()
+            .ok_or_else(|| ObjectStoreError::Storage(format!("Cannot publish {name}: chain is empty")))?;
+        let head_cid = head.cid;
+
+        self.update(name, |current| {
+            if let Some(current) = current {
+                let published = current.cid();
+                if !chain.items().iter().any(|item| item.cid == published) {
+                    return Err(ObjectStoreError::Conflict(format!(
+                        "{name} points at {published}, which is not part of this chain"
+                    )));
+                }
+            }
+            Ok(head_cid)
+        })
+        .await
+    }
+
+    /// Publish the root CID of a collection under a name
+    pub async fn set_root(&self, name: &str, root: Cid) -> Result<NamedRef> {
+        self.set(name, root).await
+    }
+}
+
+/// Check a value's signature against trusted keys, if any are configured
+fn check_signature(trusted_keys: &[VerifyingKey], name: &str, value: &RefValue) -> Result<()> {
+    if trusted_keys.is_empty() {
+        return Ok(());
+    }
+    let signer = value.verify(name)?;
+    if trusted_keys.contains(&signer) {
+        Ok(())
+    } else {
+        Err(ObjectStoreError::InvalidSignature(format!("{name}: signed by an untrusted key")))
+    }
+}
+
+fn broken_link(name: &str, value: &RefValue, expected: Option<Cid>) -> ObjectStoreError {
+    ObjectStoreError::InvalidSignature(format!(
+        "{name}: value {} replaces {}, but the ref pointed at {}",
+        value.cid,
+        describe(value.previous),
+        describe(expected)
+    ))
+}
+
+fn describe(cid: Option<Cid>) -> String {
+    cid.map(|cid| cid.to_string()).unwrap_or_else(|| "nothing".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{test_cid, TestEntry};
+    use rand::rngs::OsRng;
+
+    #[tokio::test]
+    async fn test_compare_and_swap_and_history() {
+        let refs = NamedRefs::in_memory();
+        let (a, b, c) = (test_cid(b"a"), test_cid(b"b"), test_cid(b"c"));
+
+        assert!(refs.get("docs.latest").await.unwrap().is_none());
+        refs.compare_and_swap("docs.latest", None, a).await.unwrap();
+        assert!(matches!(
+            refs.compare_and_swap("docs.latest", None, b).await,
+            Err(ObjectStoreError::Conflict(_))
+        ));
+        refs.compare_and_swap("docs.latest", Some(a), b).await.unwrap();
+        refs.set("docs.latest", c).await.unwrap();
+        assert_eq!(refs.resolve("docs.latest").await.unwrap(), c);
+
+        let history: Vec<_> = refs.history("docs.latest").await.unwrap().iter().map(NamedRef::cid).collect();
+        assert_eq!(history, vec![c, b, a]);
+
+        refs.delete("docs.latest").await.unwrap();
+        assert!(refs.resolve("docs.latest").await.unwrap_err().is_not_found());
+        assert_eq!(refs.history("docs.latest").await.unwrap().len(), 3);
+
+        assert!(refs.set("bad name", a).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_updates_do_not_lose_writes() {
+        let refs = Arc::new(NamedRefs::in_memory());
+        let tasks: Vec<_> = (0..8u8)
+            .map(|i| {
+                let refs = refs.clone();
+                tokio::spawn(async move {
+                    refs.update("counter", |_| Ok(test_cid(&[i]))).await.unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(refs.history("counter").await.unwrap().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_signed_refs() {
+        let key = SigningKey::generate(&mut OsRng);
+        let store = Arc::new(MemoryRefStore::default());
+
+        let writer = NamedRefs::new(store.clone()).with_signer(key.clone());
+        let current = writer.set("signed", test_cid(b"v1")).await.unwrap();
+        assert_eq!(current.value.verify("signed").unwrap(), key.verifying_key());
+        // A signature is bound to the name
+        assert!(current.value.verify("other").is_err());
+
+        let reader = NamedRefs::new(store.clone()).with_trusted_key(key.verifying_key());
+        assert_eq!(reader.resolve("signed").await.unwrap(), test_cid(b"v1"));
+
+        // Unsigned writes are rejected by readers that require signatures
+        NamedRefs::new(store.clone()).set("signed", test_cid(b"forged")).await.unwrap();
+        assert!(matches!(
+            reader.resolve("signed").await,
+            Err(ObjectStoreError::InvalidSignature(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signed_values_cannot_be_replayed() {
+        let key = SigningKey::generate(&mut OsRng);
+        let store = Arc::new(MemoryRefStore::default());
+        let writer = NamedRefs::new(store.clone()).with_signer(key.clone());
+        let reader = NamedRefs::new(store.clone()).with_trusted_key(key.verifying_key());
+
+        let v1 = writer.set("head", test_cid(b"v1")).await.unwrap();
+        let v2 = writer.set("head", test_cid(b"v2")).await.unwrap();
+        assert_eq!(v2.value.previous, Some(test_cid(b"v1")));
+        assert_eq!(reader.resolve("head").await.unwrap(), test_cid(b"v2"));
+
+        // The previous CID is part of what is signed
+        let mut relinked = v2.value.clone();
+        relinked.previous = None;
+        assert!(relinked.verify("head").is_err());
+
+        // Writing the old signed value back is caught by readers that saw
+        // the newer one, and by anyone reading the history
+        let mut events = reader.watch("head").await.unwrap();
+        store.put("head", &v1.value, v2.revision).await.unwrap();
+        assert!(matches!(reader.resolve("head").await, Err(ObjectStoreError::InvalidSignature(_))));
+        let fresh = NamedRefs::new(store.clone()).with_trusted_key(key.verifying_key());
+        assert!(matches!(fresh.history("head").await, Err(ObjectStoreError::InvalidSignature(_))));
+
+        assert!(matches!(events.next().await.unwrap(), Err(ObjectStoreError::InvalidSignature(_))));
+
+        // Later events must follow the one before them
+        let v3 = writer.set("head", test_cid(b"v3")).await.unwrap();
+        assert_eq!(v3.value.previous, Some(test_cid(b"v1")));
+        assert_eq!(events.next().await.unwrap().unwrap().value.unwrap().cid, test_cid(b"v3"));
+        let mut out_of_order = writer.value_for("head", None, test_cid(b"v4"));
+        out_of_order.updated_at = v3.value.updated_at + 1;
+        let out_of_order = out_of_order.sign("head", &key);
+        store.put("head", &out_of_order, v3.revision).await.unwrap();
+        assert!(matches!(events.next().await.unwrap(), Err(ObjectStoreError::InvalidSignature(_))));
+
+        // Unsigned values never reach a trusted watcher
+        NamedRefs::new(store.clone()).set("head", test_cid(b"forged")).await.unwrap();
+        assert!(events.next().await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recreated_ref_links_to_its_history() {
+        let key = SigningKey::generate(&mut OsRng);
+        let refs = NamedRefs::in_memory().with_signer(key.clone()).with_trusted_key(key.verifying_key());
+
+        refs.set("head", test_cid(b"a")).await.unwrap();
+        refs.delete("head").await.unwrap();
+        let recreated = refs.set("head", test_cid(b"b")).await.unwrap();
+        assert_eq!(recreated.value.previous, Some(test_cid(b"a")));
+        assert_eq!(refs.history("head").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let refs = NamedRefs::in_memory();
+        let mut events = refs.watch("graphs.").await.unwrap();
+
+        refs.set("other", test_cid(b"x")).await.unwrap();
+        refs.set("graphs.main", test_cid(b"g1")).await.unwrap();
+        refs.delete("graphs.main").await.unwrap();
+
+        let updated = events.next().await.unwrap().unwrap();
+        assert_eq!(updated.name, "graphs.main");
+        assert_eq!(updated.value.map(|v| v.cid), Some(test_cid(b"g1")));
+        let deleted = events.next().await.unwrap().unwrap();
+        assert!(deleted.value.is_none());
+        assert!(deleted.revision > updated.revision);
+    }
+
+    #[tokio::test]
+    async fn test_chain_head_only_moves_forward() {
+        let refs = NamedRefs::in_memory();
+        let mut chain = ContentChain::new();
+        chain.append(TestEntry { n: 1 }).unwrap();
+        refs.advance_chain_head("chains.events", &chain).await.unwrap();
+
+        chain.append(TestEntry { n: 2 }).unwrap();
+        let head = refs.advance_chain_head("chains.events", &chain).await.unwrap();
+        assert_eq!(head.cid(), chain.head().unwrap().cid);
+
+        let mut fork = ContentChain::new();
+        fork.append(TestEntry { n: 9 }).unwrap();
+        assert!(matches!(
+            refs.advance_chain_head("chains.events", &fork).await,
+            Err(ObjectStoreError::Conflict(_))
+        ));
+    }
+}
@@ -0,0 +1,433 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Retries, backoff and circuit breaking for NATS-backed operations
+//!
+//! Errors are classified as transient or permanent through [`Retryable`].
+//! Transient failures are retried with jittered exponential backoff until
+//! the attempt budget or the operation deadline runs out. Each scope
+//! (usually a bucket name) has its own circuit breaker: after enough
+//! consecutive transient failures calls fail fast until the reset timeout
+//! has passed, after which a single trial call decides whether to close it.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::metrics;
+
+/// Errors that can be classified for retrying
+pub trait Retryable: Sized {
+    /// Whether retrying the same operation may succeed
+    fn is_transient(&self) -> bool;
+
+    /// Error returned while the circuit for a scope is open
+    fn circuit_open(scope: &str) -> Self;
+
+    /// Error returned when an operation runs past its deadline
+    fn deadline_exceeded(operation: &str, deadline: Duration) -> Self;
+}
+
+/// How transient failures are retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for a single delay
+    pub max_backoff: Duration,
+    /// Factor applied to the delay after every retry
+    pub multiplier: f64,
+    /// Fraction of each delay that is randomised, between 0 and 1
+    pub jitter: f64,
+    /// Deadline for the whole operation, retries included
+    pub deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: 0.2,
+            deadline: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt without a deadline
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            deadline: None,
+            ..Default::default()
+        }
+    }
+
+    /// Set the number of attempts
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the initial and maximum delay
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Set the deadline for the whole operation
+    pub fn with_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Delay before retry number `retry`, starting at 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(63) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+        Duration::from_secs_f64(capped * (1.0 - jitter))
+    }
+}
+
+/// When a circuit opens and how long it stays open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive transient failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial call
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// Calls fail fast
+    Open,
+    /// One trial call decides whether to close the circuit
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Breaker {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { since: Instant },
+}
+
+/// Retry policy plus per-scope circuit breakers
+#[derive(Debug)]
+pub struct Resilience {
+    policy: RetryPolicy,
+    breaker: CircuitBreakerConfig,
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+impl Default for Resilience {
+    fn default() -> Self {
+        Self::new(RetryPolicy::default(), CircuitBreakerConfig::default())
+    }
+}
+
+impl Resilience {
+    /// Create with a retry policy and breaker configuration
+    pub fn new(policy: RetryPolicy, breaker: CircuitBreakerConfig) -> Self {
+        Self {
+            policy,
+            breaker,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The retry policy in use
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Current circuit state for a scope
+    pub fn circuit_state(&self, scope: &str) -> CircuitState {
+        let breakers = self.breakers.lock().unwrap();
+        match breakers.get(scope) {
+            None | Some(Breaker::Closed { .. }) => CircuitState::Closed,
+            Some(Breaker::Open { until }) if Instant::now() >= *until => CircuitState::HalfOpen,
+            Some(Breaker::Open { .. }) => CircuitState::Open,
+            Some(Breaker::HalfOpen { .. }) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Run an operation, retrying transient failures
+    ///
+    /// `attempt` is called once per try. Permanent errors and successes both
+    /// close the circuit, since they show the server is reachable.
+    pub async fn run<T, E, F, Fut>(&self, scope: &str, operation: &str, mut attempt: F) -> Result<T, E>
+    where
+        E: Retryable + std::fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let deadline = self.policy.deadline.map(|d| (Instant::now() + d, d));
+        let mut tries = 0;
+
+        loop {
+            if !self.acquire(scope) {
+                return Err(E::circuit_open(scope));
+            }
+            tries += 1;
+
+            let result = match deadline {
+                Some((at, budget)) => {
+                    match tokio::time::timeout_at(at.into(), attempt()).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            self.record(scope, false);
+                            return Err(E::deadline_exceeded(operation, budget));
+                        }
+                    }
+                }
+                None => attempt().await,
+            };
+
+            let error = match result {
+                Ok(value) => {
+                    self.record(scope, true);
+                    return Ok(value);
+                }
+                Err(e) if !e.is_transient() => {
+                    self.record(scope, true);
+                    return Err(e);
+                }
+                Err(e) => e,
+            };
+
+            self.record(scope, false);
+            if tries >= self.policy.max_attempts {
+                return Err(error);
+            }
+
+            let delay = self.policy.backoff(tries);
+            if let Some((at, budget)) = deadline {
+                if Instant::now() + delay >= at {
+                    debug!("{operation} on {scope} would pass its deadline; giving up");
+                    return Err(E::deadline_exceeded(operation, budget));
+                }
+            }
+
+            warn!("{operation} on {scope} failed (attempt {tries}), retrying in {delay:?}: {error}");
+            metrics::global()
+                .counter(
+                    "cim_ipld_retries_total",
+                    "Retried NATS operations",
+                    &[("operation", operation)],
+                )
+                .inc();
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Check whether a call may go through, moving open circuits to half-open
+    fn acquire(&self, scope: &str) -> bool {
+        let mut breakers = self.breakers.lock().unwrap();
+        let now = Instant::now();
+        match breakers.get(scope).copied() {
+            None | Some(Breaker::Closed { .. }) => true,
+            Some(Breaker::Open { until }) if now >= until => {
+                breakers.insert(scope.to_string(), Breaker::HalfOpen { since: now });
+                true
+            }
+            Some(Breaker::Open { .. }) => false,
+            // A trial call that was dropped must not hold the circuit forever
+            Some(Breaker::HalfOpen { since }) if now.duration_since(since) >= self.breaker.reset_timeout => {
+                breakers.insert(scope.to_string(), Breaker::HalfOpen { since: now });
+                true
+            }
+            Some(Breaker::HalfOpen { .. }) => false,
+        }
+    }
+
+    /// Record the outcome of a call
+    fn record(&self, scope: &str, reachable: bool) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let current = breakers.get(scope).copied().unwrap_or(Breaker::Closed { failures: 0 });
+
+        let next = match (current, reachable) {
+            (_, true) => Breaker::Closed { failures: 0 },
+            (Breaker::Closed { failures }, false) if failures + 1 < self.breaker.failure_threshold => {
+                Breaker::Closed { failures: failures + 1 }
+            }
+            (Breaker::Open { until }, false) => Breaker::Open { until },
+            (_, false) => {
+                warn!("Opening circuit for {scope} for {:?}", self.breaker.reset_timeout);
+                Breaker::Open { until: Instant::now() + self.breaker.reset_timeout }
+            }
+        };
+
+        // Only a change of state touches the registry, keeping it off the hot path
+        let was_open = breakers.get(scope).map(|b| matches!(b, Breaker::Open { .. }));
+        let is_open = matches!(next, Breaker::Open { .. });
+        if was_open != Some(is_open) {
+            metrics::global()
+                .gauge(
+                    "cim_ipld_circuit_open",
+                    "Whether the circuit breaker for a scope is open",
+                    &[("scope", scope)],
+                )
+                .set(if is_open { 1.0 } else { 0.0 });
+        }
+        match breakers.get_mut(scope) {
+            Some(breaker) => *breaker = next,
+            None => {
+                breakers.insert(scope.to_string(), next);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Transient,
+        Permanent,
+        CircuitOpen,
+        Deadline,
+    }
+
+    impl std::fmt::Display for TestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{self:?}")
+        }
+    }
+
+    impl Retryable for TestError {
+        fn is_transient(&self) -> bool {
+            matches!(self, Self::Transient)
+        }
+
+        fn circuit_open(_scope: &str) -> Self {
+            Self::CircuitOpen
+        }
+
+        fn deadline_exceeded(_operation: &str, _deadline: Duration) -> Self {
+            Self::Deadline
+        }
+    }
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::default()
+            .with_max_attempts(max_attempts)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(5))
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            jitter: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(50));
+        assert_eq!(policy.backoff(2), Duration::from_millis(100));
+        assert_eq!(policy.backoff(20), Duration::from_secs(2));
+
+        let jittered = RetryPolicy::default();
+        for retry in 1..6 {
+            let delay = jittered.backoff(retry);
+            let full = policy.backoff(retry);
+            assert!(delay <= full);
+            assert!(delay >= full.mul_f64(0.8) - Duration::from_micros(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_only() {
+        let resilience = Resilience::new(fast_policy(3), CircuitBreakerConfig::default());
+
+        let calls = AtomicU32::new(0);
+        let result = resilience
+            .run("bucket", "get", || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(TestError::Transient)
+                } else {
+                    Ok(7)
+                }
+            })
+            .await;
+        assert_eq!(result, Ok(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = resilience
+            .run("bucket", "get", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(TestError::Permanent)
+            })
+            .await;
+        assert_eq!(result, Err(TestError::Permanent));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_recovers() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            reset_timeout: Duration::from_millis(50),
+        };
+        let resilience = Resilience::new(RetryPolicy::none(), config);
+        let fail = || async { Err::<(), _>(TestError::Transient) };
+
+        assert_eq!(resilience.run("a", "put", fail).await, Err(TestError::Transient));
+        assert_eq!(resilience.circuit_state("a"), CircuitState::Closed);
+        assert_eq!(resilience.run("a", "put", fail).await, Err(TestError::Transient));
+        assert_eq!(resilience.circuit_state("a"), CircuitState::Open);
+
+        // Open circuits fail fast and are scoped
+        assert_eq!(resilience.run("a", "put", fail).await, Err(TestError::CircuitOpen));
+        assert_eq!(resilience.run("b", "put", || async { Ok::<_, TestError>(1) }).await, Ok(1));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(resilience.circuit_state("a"), CircuitState::HalfOpen);
+
+        // A failed trial reopens the circuit, a successful one closes it
+        assert_eq!(resilience.run("a", "put", fail).await, Err(TestError::Transient));
+        assert_eq!(resilience.circuit_state("a"), CircuitState::Open);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(resilience.run("a", "put", || async { Ok::<_, TestError>(2) }).await, Ok(2));
+        assert_eq!(resilience.circuit_state("a"), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_deadline() {
+        let policy = fast_policy(10).with_deadline(Some(Duration::from_millis(30)));
+        let resilience = Resilience::new(policy, CircuitBreakerConfig::default());
+
+        let result: Result<(), _> = resilience
+            .run("bucket", "get", || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await;
+        assert_eq!(result, Err(TestError::Deadline));
+    }
+}
@@ -0,0 +1,693 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Retention policies and legal holds
+//!
+//! Content in compliance domains such as [`ContentDomain::Contracts`] or
+//! [`ContentDomain::HealthRecords`] must stay readable and unchanged until
+//! its retention date. [`Retention`] records three kinds of locks:
+//!
+//! - domain policies that retain everything written to a domain for a fixed
+//!   period,
+//! - explicit retention dates on individual CIDs,
+//! - legal holds on a CID or a whole domain, which last until released.
+//!
+//! Locks only ever extend: a retention date or policy can be lengthened but
+//! not shortened, and a legal hold is only released by the actor who placed
+//! it or a configured hold releaser.
+//!
+//! Locks are enforced by the storage backend, so every path that deletes
+//! blocks is covered: wrap a backend in a [`RetentionGuard`],
+//! or use [`NatsObjectStore::with_retention`](super::NatsObjectStore::with_retention).
+//! [`ContentStorageService::with_retention`](super::ContentStorageService::with_retention)
+//! wraps its backend this way. Both also refuse changes to
+//! [`RETENTION_BUCKET`], so the records cannot be removed through them.
+//! Storing a CID that already exists keeps the stored block, so retained
+//! content cannot be replaced either.
+//!
+//! Each version of a record is stored as a JSON block in [`RETENTION_BUCKET`]
+//! of a storage backend. A [`NamedRefs`] entry per target points at the
+//! current version and is moved with compare-and-swap, so concurrent
+//! changes are retried instead of overwriting each other. Targets are named
+//! by the CID they protect; domains by a CID derived from the domain name.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use super::domain_partitioner::{ContentDomain, PartitionStrategy};
+use super::listing::{ListOptions, ListPage};
+use super::watch::{ContentEventStream, WatchFrom};
+use super::{NamedRef, NamedRefs, ObjectInfo, ObjectStoreError, Result, StorageBackend};
+use crate::util::{cid_serde, unix_secs};
+
+/// Bucket holding retention records
+pub const RETENTION_BUCKET: &str = "cim-retention";
+
+/// Codec of the CIDs keying domain records
+const RAW_CODEC: u64 = 0x55;
+
+/// Codec of stored record versions
+const JSON_CODEC: u64 = 0x0200;
+
+/// Attempts at a record change before giving up on concurrent writers
+const MAX_MODIFY_ATTEMPTS: usize = 16;
+
+/// Refuse changes to retention records made around the registry
+pub(crate) fn check_record_bucket(bucket: &str) -> Result<()> {
+    if bucket == RETENTION_BUCKET {
+        return Err(ObjectStoreError::Retained(format!(
+            "{RETENTION_BUCKET} can only be changed through the retention registry"
+        )));
+    }
+    Ok(())
+}
+
+/// What a retention record protects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionTarget {
+    /// A single piece of content
+    Content(#[serde(with = "cid_serde")] Cid),
+    /// Everything stored in a domain
+    Domain(ContentDomain),
+}
+
+impl RetentionTarget {
+    /// Key of the target's record in [`RETENTION_BUCKET`]
+    fn key(&self) -> Result<Cid> {
+        match self {
+            Self::Content(cid) => Ok(*cid),
+            Self::Domain(domain) => {
+                let name = format!("cim-ipld/retention/domain/{domain:?}");
+                let mh = multihash::Multihash::wrap(0x1e, blake3::hash(name.as_bytes()).as_bytes())
+                    .map_err(ObjectStoreError::serialization)?;
+                Ok(Cid::new_v1(RAW_CODEC, mh))
+            }
+        }
+    }
+
+    /// Name of the ref pointing at the target's current record
+    fn ref_name(&self) -> Result<String> {
+        Ok(format!("retention/{}", self.key()?))
+    }
+}
+
+/// Retain everything written to a domain for a fixed period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Retention period in seconds from when content is written
+    pub retain_for_secs: u64,
+    /// When the policy was first set, in seconds since the Unix epoch
+    ///
+    /// Content written earlier, or whose write time is unknown, is retained
+    /// for the full period counted from this time.
+    pub effective_from: u64,
+}
+
+impl RetentionPolicy {
+    /// Retention period
+    pub fn retain_for(&self) -> Duration {
+        Duration::from_secs(self.retain_for_secs)
+    }
+
+    /// End of retention for content written at `written_at`
+    fn retained_until(&self, written_at: Option<u64>) -> u64 {
+        written_at.unwrap_or(0).max(self.effective_from) + self.retain_for_secs
+    }
+}
+
+/// A legal hold preventing deletion until it is released
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalHold {
+    /// Identifier used to release the hold, e.g. a case number
+    pub id: String,
+    /// Why the hold was placed
+    pub reason: String,
+    /// Who placed the hold
+    pub placed_by: String,
+    /// Placement time in seconds since the Unix epoch
+    pub placed_at: u64,
+}
+
+impl LegalHold {
+    /// Create a hold placed now
+    pub fn new(id: impl Into<String>, reason: impl Into<String>, placed_by: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            reason: reason.into(),
+            placed_by: placed_by.into(),
+            placed_at: unix_secs(SystemTime::now()),
+        }
+    }
+}
+
+/// Retention state stored for a CID or domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionRecord {
+    /// What the record protects
+    pub target: RetentionTarget,
+    /// Domain the content belongs to, for content records
+    pub domain: Option<ContentDomain>,
+    /// Time the content was written, in seconds since the Unix epoch
+    pub written_at: Option<u64>,
+    /// Explicit retention date, in seconds since the Unix epoch
+    pub retain_until: Option<u64>,
+    /// Retention policy, for domain records
+    pub policy: Option<RetentionPolicy>,
+    /// Active legal holds
+    pub holds: Vec<LegalHold>,
+}
+
+impl RetentionRecord {
+    fn new(target: RetentionTarget) -> Self {
+        Self {
+            target,
+            domain: None,
+            written_at: None,
+            retain_until: None,
+            policy: None,
+            holds: Vec::new(),
+        }
+    }
+}
+
+/// Effective retention of one piece of content
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RetentionStatus {
+    /// Content the status applies to
+    pub cid: Option<Cid>,
+    /// Domain the content belongs to, if known
+    pub domain: Option<ContentDomain>,
+    /// Latest retention date from the content and its domain, in seconds since the Unix epoch
+    pub retained_until: Option<u64>,
+    /// Legal holds on the content and its domain
+    pub holds: Vec<LegalHold>,
+}
+
+impl RetentionStatus {
+    /// Whether the content may not be deleted or overwritten at `now`
+    pub fn is_locked_at(&self, now: SystemTime) -> bool {
+        !self.holds.is_empty() || self.retained_until.is_some_and(|until| unix_secs(now) < until)
+    }
+
+    /// When the content can next be deleted; `None` while a legal hold applies
+    pub fn expires_at(&self) -> Option<u64> {
+        if self.holds.is_empty() {
+            self.retained_until
+        } else {
+            None
+        }
+    }
+}
+
+/// Held content and domain locks
+#[derive(Debug, Clone, Default)]
+pub struct RetentionReport {
+    /// Content that cannot be deleted now, earliest expiry first
+    pub content: Vec<RetentionStatus>,
+    /// Domains with a retention policy or legal holds
+    pub domains: Vec<RetentionRecord>,
+}
+
+/// Registry of retention policies and legal holds
+pub struct Retention {
+    backend: Arc<dyn StorageBackend>,
+    refs: Arc<NamedRefs>,
+    strategy: PartitionStrategy,
+    hold_releasers: Vec<String>,
+}
+
+impl Retention {
+    /// Store retention records in a backend, tracking current versions in `refs`
+    ///
+    /// The backend must not itself be guarded by this registry.
+    pub fn new(backend: Arc<dyn StorageBackend>, refs: Arc<NamedRefs>) -> Self {
+        Self {
+            backend,
+            refs,
+            strategy: PartitionStrategy::default(),
+            hold_releasers: Vec::new(),
+        }
+    }
+
+    /// Let `actor` release legal holds placed by anyone
+    pub fn with_hold_releaser(mut self, actor: impl Into<String>) -> Self {
+        self.hold_releasers.push(actor.into());
+        self
+    }
+
+    /// Set the strategy used to find the domain of a bucket
+    pub fn with_partition_strategy(mut self, strategy: PartitionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Domain whose content is stored in a bucket
+    pub fn domain_for_bucket(&self, bucket: &str) -> Option<ContentDomain> {
+        self.strategy.domain_for_bucket(bucket)
+    }
+
+    /// Stored record for a target
+    pub async fn record(&self, target: RetentionTarget) -> Result<Option<RetentionRecord>> {
+        match self.refs.get(&target.ref_name()?).await? {
+            Some(current) => self.load(&current.cid()).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn load(&self, cid: &Cid) -> Result<RetentionRecord> {
+        let data = self.backend.get_block(RETENTION_BUCKET, cid).await?;
+        serde_json::from_slice(&data).map_err(ObjectStoreError::deserialization)
+    }
+
+    /// Store a version of a record, returning its CID
+    async fn save(&self, record: &RetentionRecord) -> Result<Cid> {
+        let data = serde_json::to_vec(record)
+            .map_err(ObjectStoreError::serialization)?;
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(&data).as_bytes())
+            .map_err(ObjectStoreError::serialization)?;
+        let cid = Cid::new_v1(JSON_CODEC, mh);
+        self.backend.put_block(RETENTION_BUCKET, &cid, data).await?;
+        Ok(cid)
+    }
+
+    /// Read-modify-write a record, retrying when another writer got there first
+    async fn modify<F>(&self, target: RetentionTarget, mut change: F) -> Result<RetentionRecord>
+    where
+        F: FnMut(&mut RetentionRecord) -> Result<()>,
+    {
+        let name = target.ref_name()?;
+        for _ in 0..MAX_MODIFY_ATTEMPTS {
+            let current = self.refs.get(&name).await?;
+            let mut record = match &current {
+                Some(current) => self.load(&current.cid()).await?,
+                None => RetentionRecord::new(target),
+            };
+            change(&mut record)?;
+
+            let cid = self.save(&record).await?;
+            match self.refs.compare_and_swap(&name, current.as_ref().map(NamedRef::cid), cid).await {
+                Ok(_) => return Ok(record),
+                Err(ObjectStoreError::Conflict(reason)) => {
+                    debug!("Retrying retention change for {:?}: {}", target, reason);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(ObjectStoreError::Conflict(format!(
+            "retention of {target:?} kept changing after {MAX_MODIFY_ATTEMPTS} attempts"
+        )))
+    }
+
+    /// Retain everything written to a domain for `retain_for`
+    ///
+    /// An existing policy can be lengthened but not shortened.
+    pub async fn set_domain_policy(&self, domain: ContentDomain, retain_for: Duration) -> Result<()> {
+        self.modify(RetentionTarget::Domain(domain), |record| {
+            let retain_for_secs = retain_for.as_secs();
+            match &mut record.policy {
+                Some(policy) if retain_for_secs < policy.retain_for_secs => {
+                    Err(ObjectStoreError::Retained(format!(
+                        "{domain:?} policy of {}s cannot be shortened to {retain_for_secs}s",
+                        policy.retain_for_secs
+                    )))
+                }
+                Some(policy) => {
+                    policy.retain_for_secs = retain_for_secs;
+                    Ok(())
+                }
+                None => {
+                    record.policy = Some(RetentionPolicy {
+                        retain_for_secs,
+                        effective_from: unix_secs(SystemTime::now()),
+                    });
+                    Ok(())
+                }
+            }
+        })
+        .await?;
+
+        info!("Retention policy for {:?} set to {:?}", domain, retain_for);
+        Ok(())
+    }
+
+    /// Retain a CID until `until`; an existing later date is kept
+    pub async fn retain_until(&self, cid: &Cid, until: SystemTime) -> Result<()> {
+        let until = unix_secs(until);
+        self.modify(RetentionTarget::Content(*cid), |record| {
+            record.retain_until = Some(record.retain_until.map_or(until, |current| current.max(until)));
+            Ok(())
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Place a legal hold on a CID or domain
+    pub async fn place_hold(&self, target: RetentionTarget, hold: LegalHold) -> Result<()> {
+        self.modify(target, |record| {
+            if record.holds.iter().any(|h| h.id == hold.id) {
+                return Err(ObjectStoreError::Conflict(format!("Legal hold {} already placed", hold.id)));
+            }
+            record.holds.push(hold.clone());
+            Ok(())
+        })
+        .await?;
+
+        info!("Legal hold {} placed on {:?} by {}", hold.id, target, hold.placed_by);
+        Ok(())
+    }
+
+    /// Release a legal hold by id on behalf of `actor`
+    ///
+    /// Only the actor who placed the hold and the configured hold releasers
+    /// may release it; anyone else gets [`ObjectStoreError::Unauthorized`].
+    pub async fn release_hold(&self, target: RetentionTarget, id: &str, actor: &str) -> Result<LegalHold> {
+        let mut released = None;
+        self.modify(target, |record| {
+            let index = record.holds.iter()
+                .position(|h| h.id == id)
+                .ok_or_else(|| ObjectStoreError::NotFound(format!("legal hold {id}")))?;
+            let hold = &record.holds[index];
+            if hold.placed_by != actor && !self.hold_releasers.iter().any(|r| r == actor) {
+                return Err(ObjectStoreError::Unauthorized(format!(
+                    "{actor} may not release legal hold {id} placed by {}",
+                    hold.placed_by
+                )));
+            }
+            released = Some(record.holds.remove(index));
+            Ok(())
+        })
+        .await?;
+
+        info!("Legal hold {} released from {:?} by {}", id, target, actor);
+        released.ok_or_else(|| ObjectStoreError::NotFound(format!("legal hold {id}")))
+    }
+
+    /// Note that content was written to a bucket
+    ///
+    /// Only content in a domain with a retention policy is recorded.
+    pub async fn record_write(&self, bucket: &str, cid: &Cid, domain: Option<ContentDomain>) -> Result<()> {
+        let Some(domain) = domain.or_else(|| self.domain_for_bucket(bucket)) else {
+            return Ok(());
+        };
+        let has_policy = self.record(RetentionTarget::Domain(domain)).await?
+            .is_some_and(|record| record.policy.is_some());
+        if !has_policy {
+            return Ok(());
+        }
+
+        let now = unix_secs(SystemTime::now());
+        self.modify(RetentionTarget::Content(*cid), |record| {
+            record.domain = Some(domain);
+            record.written_at.get_or_insert(now);
+            Ok(())
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Effective retention of a CID stored in `bucket`
+    pub async fn status(&self, bucket: Option<&str>, cid: &Cid) -> Result<RetentionStatus> {
+        let record = self.record(RetentionTarget::Content(*cid)).await?;
+        let domain = record.as_ref()
+            .and_then(|r| r.domain)
+            .or_else(|| bucket.and_then(|b| self.domain_for_bucket(b)));
+        let domain_record = match domain {
+            Some(domain) => self.record(RetentionTarget::Domain(domain)).await?,
+            None => None,
+        };
+        Ok(Self::combine(*cid, domain, record.as_ref(), domain_record.as_ref()))
+    }
+
+    fn combine(
+        cid: Cid,
+        domain: Option<ContentDomain>,
+        record: Option<&RetentionRecord>,
+        domain_record: Option<&RetentionRecord>,
+    ) -> RetentionStatus {
+        let written_at = record.and_then(|r| r.written_at);
+        let from_domain = domain_record
+            .and_then(|r| r.policy.as_ref())
+            .map(|policy| policy.retained_until(written_at));
+        let retained_until = record.and_then(|r| r.retain_until).max(from_domain);
+
+        let holds = record.into_iter()
+            .chain(domain_record)
+            .flat_map(|r| r.holds.iter().cloned())
+            .collect();
+
+        RetentionStatus {
+            cid: Some(cid),
+            domain,
+            retained_until,
+            holds,
+        }
+    }
+
+    /// Fail with [`ObjectStoreError::Retained`] if a CID in `bucket` is locked
+    pub async fn check_delete(&self, bucket: Option<&str>, cid: &Cid) -> Result<()> {
+        let status = self.status(bucket, cid).await?;
+        if !status.is_locked_at(SystemTime::now()) {
+            return Ok(());
+        }
+
+        let reason = match (status.holds.first(), status.retained_until) {
+            (Some(hold), _) => format!("{cid} is under legal hold {}", hold.id),
+            (None, Some(until)) => format!("{cid} is retained until {until}"),
+            (None, None) => cid.to_string(),
+        };
+        Err(ObjectStoreError::Retained(reason))
+    }
+
+    /// Content that cannot currently be deleted, and domain-wide locks
+    ///
+    /// Covers content with its own record: content written through a
+    /// retention-aware path into a domain with a policy, or content given
+    /// an explicit date or hold.
+    pub async fn report(&self) -> Result<RetentionReport> {
+        // The bucket holds every version; only targets are taken from it
+        let mut targets = HashMap::new();
+        for info in self.backend.list_blocks(RETENTION_BUCKET).await? {
+            let target = self.load(&info.cid).await?.target;
+            targets.insert(target.key()?, target);
+        }
+
+        let mut content = Vec::new();
+        let mut domains = HashMap::new();
+        for target in targets.into_values() {
+            let Some(record) = self.record(target).await? else {
+                continue;
+            };
+            match record.target {
+                RetentionTarget::Domain(domain) => {
+                    domains.insert(domain, record);
+                }
+                RetentionTarget::Content(_) => content.push(record),
+            }
+        }
+
+        let now = SystemTime::now();
+        let mut held: Vec<_> = content.iter()
+            .filter_map(|record| {
+                let RetentionTarget::Content(cid) = record.target else {
+                    return None;
+                };
+                let status = Self::combine(cid, record.domain, Some(record), record.domain.and_then(|d| domains.get(&d)));
+                status.is_locked_at(now).then_some(status)
+            })
+            .collect();
+        held.sort_by_key(|status| (status.expires_at().unwrap_or(u64::MAX), status.cid.map(|c| c.to_string())));
+
+        let mut domains: Vec<_> = domains.into_values()
+            .filter(|record| record.policy.is_some() || !record.holds.is_empty())
+            .collect();
+        domains.sort_by_key(|record| format!("{:?}", record.target));
+
+        Ok(RetentionReport { content: held, domains })
+    }
+}
+
+/// Storage backend wrapper that enforces retention
+///
+/// Deletes of locked blocks, writes over existing locked blocks, and any
+/// change to [`RETENTION_BUCKET`] fail with [`ObjectStoreError::Retained`];
+/// writes into domains with a policy are recorded so their retention period
+/// starts. Wrap the outermost backend, such as a
+/// [`TieredStore`](super::TieredStore), so moves between tiers stay inside it.
+pub struct RetentionGuard {
+    inner: Arc<dyn StorageBackend>,
+    retention: Arc<Retention>,
+}
+
+impl RetentionGuard {
+    /// Enforce `retention` on a backend
+    pub fn new(inner: Arc<dyn StorageBackend>, retention: Arc<Retention>) -> Self {
+        Self { inner, retention }
+    }
+
+    /// The retention registry being enforced
+    pub fn retention(&self) -> &Arc<Retention> {
+        &self.retention
+    }
+}
+
+#[async_trait]
+impl StorageBackend for RetentionGuard {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        check_record_bucket(bucket)?;
+        // Same CID, same content: the stored block is never replaced
+        if self.inner.has_block(bucket, cid).await? {
+            return Ok(());
+        }
+        self.inner.put_block(bucket, cid, data).await?;
+        self.retention.record_write(bucket, cid, None).await
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        self.inner.get_block(bucket, cid).await
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        self.inner.has_block(bucket, cid).await
+    }
+
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        check_record_bucket(bucket)?;
+        self.retention.check_delete(Some(bucket), cid).await?;
+        self.inner.delete_block(bucket, cid).await
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        self.inner.list_blocks(bucket).await
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        self.inner.list_blocks_page(bucket, options).await
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        self.inner.list_buckets().await
+    }
+
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        self.inner.watch_blocks(bucket, from).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_cid;
+    use crate::object_store::MemoryBackend;
+
+    fn guarded() -> (Arc<Retention>, RetentionGuard) {
+        let retention = Retention::new(Arc::new(MemoryBackend::new()), Arc::new(NamedRefs::in_memory()));
+        let retention = Arc::new(retention.with_hold_releaser("general counsel"));
+        let guard = RetentionGuard::new(Arc::new(MemoryBackend::new()), retention.clone());
+        (retention, guard)
+    }
+
+    #[tokio::test]
+    async fn test_domain_policy_blocks_delete() {
+        let (retention, guard) = guarded();
+        retention.set_domain_policy(ContentDomain::Contracts, Duration::from_secs(3600)).await.unwrap();
+        assert!(matches!(
+            retention.set_domain_policy(ContentDomain::Contracts, Duration::from_secs(60)).await,
+            Err(ObjectStoreError::Retained(_))
+        ));
+
+        let contract = test_cid(b"contract");
+        guard.put_block("cim-legal-contracts", &contract, b"v1".to_vec()).await.unwrap();
+        let err = guard.delete_block("cim-legal-contracts", &contract).await.unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::Retained);
+        // Storing the CID again succeeds without replacing the retained block
+        guard.put_block("cim-legal-contracts", &contract, b"v1".to_vec()).await.unwrap();
+        guard.put_block("cim-legal-contracts", &contract, b"v2".to_vec()).await.unwrap();
+        assert_eq!(guard.get_block("cim-legal-contracts", &contract).await.unwrap(), b"v1");
+
+        // Other domains are unaffected
+        let meme = test_cid(b"meme");
+        guard.put_block("cim-social-memes", &meme, b"lol".to_vec()).await.unwrap();
+        guard.delete_block("cim-social-memes", &meme).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_legal_hold_and_report() {
+        let (retention, guard) = guarded();
+        let record = test_cid(b"patient record");
+        guard.put_block("cim-docs-general", &record, b"data".to_vec()).await.unwrap();
+
+        let target = RetentionTarget::Content(record);
+        retention.place_hold(target, LegalHold::new("case-42", "litigation", "legal")).await.unwrap();
+        assert!(guard.delete_block("cim-docs-general", &record).await.is_err());
+
+        let dated = test_cid(b"dated");
+        retention.retain_until(&dated, SystemTime::now() + Duration::from_secs(60)).await.unwrap();
+        retention.retain_until(&dated, SystemTime::now()).await.unwrap();
+        retention.place_hold(
+            RetentionTarget::Domain(ContentDomain::HealthRecords),
+            LegalHold::new("audit-2025", "regulator audit", "compliance"),
+        ).await.unwrap();
+
+        let report = retention.report().await.unwrap();
+        assert_eq!(report.content.len(), 2);
+        // Dated content expires first; held content has no expiry
+        assert_eq!(report.content[0].cid, Some(dated));
+        assert!(report.content[0].expires_at().is_some());
+        assert_eq!(report.content[1].expires_at(), None);
+        assert_eq!(report.domains.len(), 1);
+
+        // Only the placer or a hold releaser may lift the hold
+        assert!(matches!(
+            retention.release_hold(target, "case-42", "intern").await,
+            Err(ObjectStoreError::Unauthorized(_))
+        ));
+        retention.release_hold(target, "case-42", "legal").await.unwrap();
+        guard.delete_block("cim-docs-general", &record).await.unwrap();
+        assert!(retention.release_hold(target, "case-42", "legal").await.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_holds_are_all_kept() {
+        let (retention, _) = guarded();
+        let target = RetentionTarget::Domain(ContentDomain::Contracts);
+
+        let placed = futures::future::join_all((0..8).map(|i| {
+            retention.place_hold(target, LegalHold::new(format!("case-{i}"), "litigation", "legal"))
+        }))
+        .await;
+        assert!(placed.iter().all(|r| r.is_ok()));
+        assert_eq!(retention.record(target).await.unwrap().unwrap().holds.len(), 8);
+
+        retention.release_hold(target, "case-3", "general counsel").await.unwrap();
+        assert_eq!(retention.record(target).await.unwrap().unwrap().holds.len(), 7);
+        assert_eq!(retention.report().await.unwrap().domains.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_guard_protects_retention_records() {
+        let backend = Arc::new(MemoryBackend::new());
+        let retention = Arc::new(Retention::new(backend.clone(), Arc::new(NamedRefs::in_memory())));
+        let guard = RetentionGuard::new(backend.clone(), retention.clone());
+
+        let target = RetentionTarget::Content(test_cid(b"evidence"));
+        retention.place_hold(target, LegalHold::new("case-7", "discovery", "legal")).await.unwrap();
+        let stored = backend.list_blocks(RETENTION_BUCKET).await.unwrap();
+        assert_eq!(stored.len(), 1);
+
+        assert!(matches!(
+            guard.delete_block(RETENTION_BUCKET, &stored[0].cid).await,
+            Err(ObjectStoreError::Retained(_))
+        ));
+        assert!(guard.put_block(RETENTION_BUCKET, &test_cid(b"forged"), b"{}".to_vec()).await.is_err());
+        assert_eq!(retention.record(target).await.unwrap().unwrap().holds.len(), 1);
+    }
+}
@@ -0,0 +1,489 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Tenant namespaces and quotas
+//!
+//! Several teams can share one NATS cluster by giving each a [`TenantId`].
+//! Every bucket a tenant uses — content buckets, domain buckets, and the
+//! crate's internal buckets — is namespaced as `{tenant}_{bucket}`, so two
+//! tenants storing identical content get the same CID in different buckets
+//! and can never read each other's objects.
+//!
+//! Caches are keyed by CID alone, so each tenant needs its own
+//! [`ContentStorageService`](super::ContentStorageService) and disk cache
+//! directory on top of its namespaced store.
+//!
+//! A [`TenantQuota`] limits stored bytes and object counts; it is checked
+//! before each write. Usage is tracked in memory, seeded from every bucket
+//! in the tenant's namespace, and reported through
+//! [`TenantUsage`] and the `cim_ipld_tenant_*` gauges. Writes and deletes of
+//! the same object are serialized, so an object is counted once however
+//! many writers store it. Writers in other processes are only seen on the
+//! next seed, so quotas are exact for a single process and approximate
+//! otherwise.
+//!
+//! [`NatsObjectStore::for_tenant`](super::NatsObjectStore::for_tenant)
+//! namespaces NATS buckets directly; [`TenantBackend`] does the same for any
+//! [`StorageBackend`]. Named refs live in a KV bucket of their own; use
+//! [`NamedRefs::nats_for_tenant`](super::NamedRefs::nats_for_tenant) to
+//! namespace it as well.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use cid::Cid;
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex as AsyncMutex, OnceCell, OwnedMutexGuard};
+
+use super::listing::{ListOptions, ListPage};
+use super::watch::{ContentEventStream, WatchFrom};
+use super::{ObjectInfo, ObjectStoreError, Result, StorageBackend};
+use crate::metrics::{self, Gauge};
+
+/// Longest accepted tenant id
+const MAX_TENANT_ID_LEN: usize = 32;
+
+/// Identifier of a tenant, used as a bucket name prefix
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TenantId(String);
+
+impl TenantId {
+    /// Validate a tenant id
+    ///
+    /// Ids are 1 to 32 lowercase ASCII letters, digits, or `-`, and may not
+    /// start or end with `-`.
+    pub fn new(id: impl Into<String>) -> Result<Self> {
+        let id = id.into();
+        let valid = !id.is_empty()
+            && id.len() <= MAX_TENANT_ID_LEN
+            && !id.starts_with('-')
+            && !id.ends_with('-')
+            && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if valid {
+            Ok(Self(id))
+        } else {
+            Err(ObjectStoreError::Storage(format!("Invalid tenant id: {id:?}")))
+        }
+    }
+
+    /// The id as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Name of a bucket inside this tenant's namespace
+    pub fn namespace(&self, bucket: &str) -> String {
+        format!("{}_{}", self.0, bucket)
+    }
+
+    /// Bucket name without this tenant's namespace, if it is in it
+    pub fn strip_namespace<'a>(&self, bucket: &'a str) -> Option<&'a str> {
+        bucket.strip_prefix(self.0.as_str())?.strip_prefix('_')
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Storage limits of a tenant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantQuota {
+    /// Maximum stored bytes across all buckets
+    pub max_bytes: Option<u64>,
+    /// Maximum number of objects across all buckets
+    pub max_objects: Option<u64>,
+}
+
+impl TenantQuota {
+    /// No limits
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limit stored bytes
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Limit the number of objects
+    pub fn with_max_objects(mut self, max_objects: u64) -> Self {
+        self.max_objects = Some(max_objects);
+        self
+    }
+}
+
+/// Stored bytes and objects in one bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketUsage {
+    /// Stored bytes
+    pub bytes: u64,
+    /// Number of objects
+    pub objects: u64,
+}
+
+/// Storage used by a tenant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantUsage {
+    /// Tenant id
+    pub tenant: TenantId,
+    /// Stored bytes across all buckets
+    pub bytes: u64,
+    /// Objects across all buckets
+    pub objects: u64,
+    /// Usage per bucket, by name without the tenant namespace
+    pub buckets: BTreeMap<String, BucketUsage>,
+    /// The tenant's limits
+    pub quota: TenantQuota,
+}
+
+/// Held while an object is written or deleted, see [`UsageTracker::lock_object`]
+pub(crate) struct ObjectLock<'a> {
+    locks: &'a Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    key: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for ObjectLock<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.lock().unwrap();
+        let guard = self.guard.take().expect("guard is held until drop");
+        // Only the map and this guard refer to the lock: nobody is waiting
+        if Arc::strong_count(OwnedMutexGuard::mutex(&guard)) == 2 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+/// In-memory usage of one tenant, checked against its quota
+pub(crate) struct UsageTracker {
+    tenant: TenantId,
+    quota: TenantQuota,
+    buckets: Mutex<HashMap<String, BucketUsage>>,
+    objects: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    bytes_gauge: Gauge,
+    objects_gauge: Gauge,
+}
+
+impl UsageTracker {
+    pub(crate) fn new(tenant: TenantId, quota: TenantQuota) -> Self {
+        let labels = [("tenant", tenant.as_str())];
+        let registry = metrics::global();
+        Self {
+            bytes_gauge: registry.gauge("cim_ipld_tenant_bytes", "Bytes stored per tenant", &labels),
+            objects_gauge: registry.gauge("cim_ipld_tenant_objects", "Objects stored per tenant", &labels),
+            tenant,
+            quota,
+            buckets: Mutex::new(HashMap::new()),
+            objects: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Serialize writes and deletes of one object
+    ///
+    /// Checking whether the object exists and counting it must happen
+    /// together, or two writers of a new object would both count it.
+    pub(crate) async fn lock_object(&self, bucket: &str, cid: &Cid) -> ObjectLock<'_> {
+        let key = format!("{bucket}/{cid}");
+        let lock = self.objects.lock().unwrap().entry(key.clone()).or_default().clone();
+        ObjectLock {
+            locks: &self.objects,
+            key,
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+
+    /// Replace a bucket's usage with a fresh listing
+    pub(crate) fn seed(&self, bucket: &str, objects: &[ObjectInfo]) {
+        let usage = BucketUsage {
+            bytes: objects.iter().map(|o| o.size as u64).sum(),
+            objects: objects.len() as u64,
+        };
+        self.buckets.lock().unwrap().insert(bucket.to_string(), usage);
+        self.publish();
+    }
+
+    /// Account for a new object, failing if it would exceed the quota
+    pub(crate) fn add(&self, bucket: &str, size: u64) -> Result<()> {
+        {
+            let mut buckets = self.buckets.lock().unwrap();
+            let (bytes, objects) = buckets.values()
+                .fold((0u64, 0u64), |(b, o), u| (b + u.bytes, o + u.objects));
+
+            if let Some(max) = self.quota.max_objects.filter(|max| objects + 1 > *max) {
+                return Err(ObjectStoreError::QuotaExceeded(format!(
+                    "tenant {} is limited to {max} objects",
+                    self.tenant
+                )));
+            }
+            if let Some(max) = self.quota.max_bytes.filter(|max| bytes + size > *max) {
+                return Err(ObjectStoreError::QuotaExceeded(format!(
+                    "tenant {} is limited to {max} bytes; {bytes} used, {size} more requested",
+                    self.tenant
+                )));
+            }
+
+            let usage = buckets.entry(bucket.to_string()).or_default();
+            usage.bytes += size;
+            usage.objects += 1;
+        }
+        self.publish();
+        Ok(())
+    }
+
+    /// Account for a removed object
+    pub(crate) fn remove(&self, bucket: &str, size: u64) {
+        {
+            let mut buckets = self.buckets.lock().unwrap();
+            if let Some(usage) = buckets.get_mut(bucket) {
+                usage.bytes = usage.bytes.saturating_sub(size);
+                usage.objects = usage.objects.saturating_sub(1);
+            }
+        }
+        self.publish();
+    }
+
+    pub(crate) fn usage(&self) -> TenantUsage {
+        let buckets: BTreeMap<_, _> = self.buckets.lock().unwrap()
+            .iter()
+            .filter(|(_, usage)| usage.objects > 0)
+            .map(|(bucket, usage)| (bucket.clone(), *usage))
+            .collect();
+        TenantUsage {
+            tenant: self.tenant.clone(),
+            bytes: buckets.values().map(|u| u.bytes).sum(),
+            objects: buckets.values().map(|u| u.objects).sum(),
+            buckets,
+            quota: self.quota,
+        }
+    }
+
+    fn publish(&self) {
+        let (bytes, objects) = self.buckets.lock().unwrap().values()
+            .fold((0u64, 0u64), |(b, o), u| (b + u.bytes, o + u.objects));
+        self.bytes_gauge.set(bytes as f64);
+        self.objects_gauge.set(objects as f64);
+    }
+}
+
+/// Storage backend wrapper that confines a tenant to its own namespace
+///
+/// Bucket names passed in are logical; the wrapped backend sees them with
+/// the tenant prefix. Writes of new objects are checked against the quota.
+pub struct TenantBackend {
+    inner: Arc<dyn StorageBackend>,
+    tenant: TenantId,
+    usage: UsageTracker,
+    seeded: OnceCell<()>,
+}
+
+impl TenantBackend {
+    /// Confine a tenant to its namespace of `inner`, without limits
+    pub fn new(inner: Arc<dyn StorageBackend>, tenant: TenantId) -> Self {
+        Self::with_quota(inner, tenant, TenantQuota::unlimited())
+    }
+
+    /// Confine a tenant to its namespace of `inner` with limits
+    pub fn with_quota(inner: Arc<dyn StorageBackend>, tenant: TenantId, quota: TenantQuota) -> Self {
+        Self {
+            inner,
+            usage: UsageTracker::new(tenant.clone(), quota),
+            tenant,
+            seeded: OnceCell::new(),
+        }
+    }
+
+    /// The tenant this backend is confined to
+    pub fn tenant(&self) -> &TenantId {
+        &self.tenant
+    }
+
+    /// Current usage of the tenant
+    pub async fn usage(&self) -> Result<TenantUsage> {
+        self.seed().await?;
+        Ok(self.usage.usage())
+    }
+
+    /// Count what the tenant already has stored, once
+    async fn seed(&self) -> Result<()> {
+        self.seeded
+            .get_or_try_init(|| async {
+                for physical in self.inner.list_buckets().await? {
+                    let Some(bucket) = self.tenant.strip_namespace(&physical) else {
+                        continue;
+                    };
+                    let objects = self.inner.list_blocks(&physical).await?;
+                    self.usage.seed(bucket, &objects);
+                }
+                Ok::<_, ObjectStoreError>(())
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for TenantBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        self.seed().await?;
+        let _lock = self.usage.lock_object(bucket, cid).await;
+        let physical = self.tenant.namespace(bucket);
+        if self.inner.has_block(&physical, cid).await? {
+            // Same CID, same content: nothing new is stored
+            return self.inner.put_block(&physical, cid, data).await;
+        }
+
+        let size = data.len() as u64;
+        self.usage.add(bucket, size)?;
+        if let Err(e) = self.inner.put_block(&physical, cid, data).await {
+            self.usage.remove(bucket, size);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        self.inner.get_block(&self.tenant.namespace(bucket), cid).await
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        self.inner.has_block(&self.tenant.namespace(bucket), cid).await
+    }
+
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        self.seed().await?;
+        let _lock = self.usage.lock_object(bucket, cid).await;
+        let physical = self.tenant.namespace(bucket);
+        let size = self.inner.get_block(&physical, cid).await?.len() as u64;
+        self.inner.delete_block(&physical, cid).await?;
+        self.usage.remove(bucket, size);
+        Ok(())
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        self.inner.list_blocks(&self.tenant.namespace(bucket)).await
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        self.inner.list_blocks_page(&self.tenant.namespace(bucket), options).await
+    }
+
+    /// Buckets in the tenant's namespace, without the namespace
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        Ok(self.inner.list_buckets().await?
+            .iter()
+            .filter_map(|physical| self.tenant.strip_namespace(physical))
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        let logical = bucket.to_string();
+        let events = self.inner.watch_blocks(&self.tenant.namespace(bucket), from).await?;
+        Ok(events
+            .map(move |event| {
+                event.map(|mut event| {
+                    event.bucket = logical.clone();
+                    event
+                })
+            })
+            .boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_cid;
+    use crate::object_store::MemoryBackend;
+
+    #[test]
+    fn test_tenant_ids() {
+        let tenant = TenantId::new("team-a").unwrap();
+        assert_eq!(tenant.namespace("cim-documents"), "team-a_cim-documents");
+        assert_eq!(tenant.strip_namespace("team-a_cim-documents"), Some("cim-documents"));
+        assert_eq!(tenant.strip_namespace("team-b_cim-documents"), None);
+
+        for invalid in ["", "Team", "a_b", "-a", "a.b", &"x".repeat(33)] {
+            assert!(TenantId::new(invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenants_are_isolated() {
+        let shared: Arc<dyn StorageBackend> = Arc::new(MemoryBackend::new());
+        let a = TenantBackend::new(shared.clone(), TenantId::new("a").unwrap());
+        let b = TenantBackend::new(shared.clone(), TenantId::new("b").unwrap());
+
+        // Identical content has the same CID for both tenants
+        let secret = test_cid(b"same bytes");
+        a.put_block("cim-documents", &secret, b"same bytes".to_vec()).await.unwrap();
+        assert!(!b.has_block("cim-documents", &secret).await.unwrap());
+        assert!(b.get_block("cim-documents", &secret).await.unwrap_err().is_not_found());
+        assert!(b.list_blocks("cim-documents").await.unwrap().is_empty());
+
+        b.put_block("cim-documents", &secret, b"same bytes".to_vec()).await.unwrap();
+        b.delete_block("cim-documents", &secret).await.unwrap();
+        assert!(a.has_block("cim-documents", &secret).await.unwrap());
+        assert!(shared.has_block("a_cim-documents", &secret).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_quotas_and_usage() {
+        let shared: Arc<dyn StorageBackend> = Arc::new(MemoryBackend::new());
+        let tenant = TenantId::new("small").unwrap();
+
+        // Existing content counts towards the quota
+        shared.put_block("small_cim-media", &test_cid(b"old"), vec![0; 40]).await.unwrap();
+        let quota = TenantQuota::unlimited().with_max_bytes(100).with_max_objects(3);
+        let backend = TenantBackend::with_quota(shared.clone(), tenant, quota);
+
+        backend.put_block("cim-documents", &test_cid(b"1"), vec![0; 50]).await.unwrap();
+        let err = backend.put_block("cim-documents", &test_cid(b"2"), vec![0; 20]).await.unwrap_err();
+        assert_eq!(err.code(), crate::ErrorCode::QuotaExceeded);
+        // Rewriting an existing object stores nothing new
+        backend.put_block("cim-documents", &test_cid(b"1"), vec![0; 50]).await.unwrap();
+
+        backend.put_block("cim-documents", &test_cid(b"3"), vec![0; 5]).await.unwrap();
+        assert!(backend.put_block("cim-documents", &test_cid(b"4"), vec![0; 1]).await.is_err());
+
+        let usage = backend.usage().await.unwrap();
+        assert_eq!((usage.bytes, usage.objects), (95, 3));
+        assert_eq!(usage.buckets["cim-media"].bytes, 40);
+
+        backend.delete_block("cim-media", &test_cid(b"old")).await.unwrap();
+        backend.put_block("cim-documents", &test_cid(b"4"), vec![0; 1]).await.unwrap();
+        assert_eq!(backend.usage().await.unwrap().bytes, 56);
+    }
+
+    #[tokio::test]
+    async fn test_usage_counts_each_object_once() {
+        let shared: Arc<dyn StorageBackend> = Arc::new(MemoryBackend::new());
+
+        // Buckets outside the crate's own names are counted too
+        shared.put_block("team_custom-uploads", &test_cid(b"old"), vec![0; 10]).await.unwrap();
+        shared.put_block("other_custom-uploads", &test_cid(b"theirs"), vec![0; 99]).await.unwrap();
+        let backend = TenantBackend::new(shared.clone(), TenantId::new("team").unwrap());
+        assert_eq!(backend.list_buckets().await.unwrap(), vec!["custom-uploads".to_string()]);
+
+        let same = test_cid(b"same");
+        let writes = futures::future::join_all(
+            (0..8).map(|_| backend.put_block("cim-documents", &same, vec![0; 5])),
+        )
+        .await;
+        assert!(writes.iter().all(|r| r.is_ok()));
+
+        let usage = backend.usage().await.unwrap();
+        assert_eq!((usage.bytes, usage.objects), (15, 2));
+        assert_eq!(usage.buckets["custom-uploads"].bytes, 10);
+    }
+}
@@ -0,0 +1,1015 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Tiered storage with hot/cold placement policies
+//!
+//! A [`TieredStore`] composes several [`StorageBackend`]s (for example memory,
+//! local disk, NATS and an archive store) into ordered tiers. New content is
+//! placed according to a [`PlacementPolicy`] that considers size and
+//! [`ContentDomain`]; reads promote frequently accessed content towards the
+//! hot tier, and a background pass demotes idle or old content towards the
+//! archive tier.
+//!
+//! Placements are kept per bucket and CID and persisted as JSON records in
+//! [`PLACEMENT_BUCKET`], so a restarted store still knows how old and how
+//! idle every block is, including blocks that are never read again.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OnceCell, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::backend::StorageBackend;
+use super::listing::{ListOptions, ListPage, PageCollector};
+use super::watch::{ContentEventKind, ContentEventStream, EventLog, WatchFrom};
+use super::{ContentBucket, ContentDomain, ObjectInfo, ObjectStoreError, Result};
+use crate::util::cid_serde;
+use crate::TypedContent;
+
+/// Bucket holding placement records, keyed by a hash of bucket and CID
+pub const PLACEMENT_BUCKET: &str = "cim-tier-placements";
+
+/// Key of the placement record for a block in a bucket
+fn placement_key(bucket: &str, cid: &Cid) -> Cid {
+    let hash = blake3::hash(format!("{bucket}/{cid}").as_bytes());
+    let mh = multihash::Multihash::wrap(0x1e, hash.as_bytes()).expect("BLAKE3 digest fits a multihash");
+    Cid::new_v1(0x55, mh)
+}
+
+/// Storage tiers, ordered from hottest to coldest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StorageTier {
+    /// Fast, small, expensive storage (typically memory)
+    Hot,
+    /// Regular working storage (typically local disk or NATS)
+    Warm,
+    /// Infrequently read content
+    Cold,
+    /// Long-term retention; rarely if ever read
+    Archive,
+}
+
+impl StorageTier {
+    /// Get all tiers from hottest to coldest
+    pub fn all() -> Vec<Self> {
+        vec![Self::Hot, Self::Warm, Self::Cold, Self::Archive]
+    }
+
+    /// The next colder tier, if any
+    pub fn colder(&self) -> Option<Self> {
+        match self {
+            Self::Hot => Some(Self::Warm),
+            Self::Warm => Some(Self::Cold),
+            Self::Cold => Some(Self::Archive),
+            Self::Archive => None,
+        }
+    }
+
+    /// The next hotter tier, if any
+    pub fn hotter(&self) -> Option<Self> {
+        match self {
+            Self::Hot => None,
+            Self::Warm => Some(Self::Hot),
+            Self::Cold => Some(Self::Warm),
+            Self::Archive => Some(Self::Cold),
+        }
+    }
+
+    /// Get tier name as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hot => "hot",
+            Self::Warm => "warm",
+            Self::Cold => "cold",
+            Self::Archive => "archive",
+        }
+    }
+}
+
+/// Rules deciding where content is placed and when it moves between tiers
+#[derive(Debug, Clone)]
+pub struct PlacementPolicy {
+    /// Tier new content is written to
+    pub initial_tier: StorageTier,
+    /// Objects at least this large are written to `large_object_tier` instead
+    pub large_object_threshold: Option<usize>,
+    /// Tier for objects over the size threshold
+    pub large_object_tier: StorageTier,
+    /// Domains pinned to a tier; pinned content is never promoted or demoted
+    pub domain_tiers: HashMap<ContentDomain, StorageTier>,
+    /// How long content may stay unread in a tier before it is demoted
+    pub demote_after_idle: HashMap<StorageTier, Duration>,
+    /// Content older than this is moved straight to the archive tier
+    pub archive_after: Option<Duration>,
+    /// Reads within `promotion_window` needed to promote content one tier
+    pub promote_after_reads: u32,
+    /// Window in which reads are counted towards promotion
+    pub promotion_window: Duration,
+}
+
+impl Default for PlacementPolicy {
+    fn default() -> Self {
+        let mut demote_after_idle = HashMap::new();
+        demote_after_idle.insert(StorageTier::Hot, Duration::from_secs(24 * 60 * 60)); // 1 day
+        demote_after_idle.insert(StorageTier::Warm, Duration::from_secs(7 * 24 * 60 * 60)); // 7 days
+        demote_after_idle.insert(StorageTier::Cold, Duration::from_secs(30 * 24 * 60 * 60)); // 30 days
+
+        Self {
+            initial_tier: StorageTier::Hot,
+            large_object_threshold: None,
+            large_object_tier: StorageTier::Cold,
+            domain_tiers: HashMap::new(),
+            demote_after_idle,
+            archive_after: None,
+            promote_after_reads: 2,
+            promotion_window: Duration::from_secs(60 * 60), // 1 hour
+        }
+    }
+}
+
+impl PlacementPolicy {
+    /// Tier for newly stored content
+    pub fn initial_tier_for(&self, size: usize, domain: Option<ContentDomain>) -> StorageTier {
+        if let Some(tier) = domain.and_then(|d| self.domain_tiers.get(&d)) {
+            return *tier;
+        }
+
+        match self.large_object_threshold {
+            Some(threshold) if size >= threshold => self.large_object_tier,
+            _ => self.initial_tier,
+        }
+    }
+
+    /// Whether content of this domain is pinned to its tier
+    pub fn is_pinned(&self, domain: Option<ContentDomain>) -> bool {
+        domain.is_some_and(|d| self.domain_tiers.contains_key(&d))
+    }
+
+    /// Tier content should be demoted to, if it is due for demotion
+    pub fn demotion_target(&self, placement: &Placement, now: SystemTime) -> Option<StorageTier> {
+        if self.is_pinned(placement.domain) {
+            return None;
+        }
+
+        if let Some(archive_after) = self.archive_after {
+            let age = now.duration_since(placement.stored_at).unwrap_or_default();
+            if age >= archive_after && placement.tier != StorageTier::Archive {
+                return Some(StorageTier::Archive);
+            }
+        }
+
+        let idle_limit = self.demote_after_idle.get(&placement.tier)?;
+        let idle = now.duration_since(placement.last_accessed).unwrap_or_default();
+        if idle >= *idle_limit {
+            placement.tier.colder()
+        } else {
+            None
+        }
+    }
+
+    /// Tier content should be promoted to, if it is read often enough
+    pub fn promotion_target(&self, placement: &Placement) -> Option<StorageTier> {
+        if self.is_pinned(placement.domain) || self.promote_after_reads == 0 {
+            return None;
+        }
+
+        if placement.window_reads >= self.promote_after_reads {
+            placement.tier.hotter()
+        } else {
+            None
+        }
+    }
+}
+
+/// Where a block currently lives and how it has been accessed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Placement {
+    /// Bucket the block is stored under
+    pub bucket: String,
+    /// Current tier
+    pub tier: StorageTier,
+    /// Block size in bytes
+    pub size: usize,
+    /// Content domain, if known
+    pub domain: Option<ContentDomain>,
+    /// When the block was first stored
+    pub stored_at: SystemTime,
+    /// When the block was last read
+    pub last_accessed: SystemTime,
+    /// Start of the current promotion window
+    pub window_start: SystemTime,
+    /// Reads in the current promotion window
+    pub window_reads: u32,
+    /// Reads since the block was stored
+    pub total_reads: u64,
+}
+
+impl Placement {
+    fn new(bucket: &str, tier: StorageTier, size: usize, domain: Option<ContentDomain>) -> Self {
+        let now = SystemTime::now();
+        Self {
+            bucket: bucket.to_string(),
+            tier,
+            size,
+            domain,
+            stored_at: now,
+            last_accessed: now,
+            window_start: now,
+            window_reads: 0,
+            total_reads: 0,
+        }
+    }
+
+    fn record_read(&mut self, window: Duration) {
+        let now = SystemTime::now();
+        if now.duration_since(self.window_start).unwrap_or_default() > window {
+            self.window_start = now;
+            self.window_reads = 0;
+        }
+        self.window_reads += 1;
+        self.total_reads += 1;
+        self.last_accessed = now;
+    }
+}
+
+/// Persisted form of a [`Placement`]
+#[derive(Serialize, Deserialize)]
+struct PlacementRecord {
+    #[serde(with = "cid_serde")]
+    cid: Cid,
+    #[serde(flatten)]
+    placement: Placement,
+}
+
+/// Placements are tracked per bucket, since one CID may be stored in several
+type PlacementKey = (String, Cid);
+
+fn key(bucket: &str, cid: &Cid) -> PlacementKey {
+    (bucket.to_string(), *cid)
+}
+
+/// A block moved between tiers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TierMove {
+    pub bucket: String,
+    pub cid: Cid,
+    pub from: StorageTier,
+    pub to: StorageTier,
+}
+
+/// Storage that composes several backends into hot/cold tiers
+pub struct TieredStore {
+    tiers: BTreeMap<StorageTier, Arc<dyn StorageBackend>>,
+    policy: Arc<RwLock<PlacementPolicy>>,
+    placements: Arc<RwLock<HashMap<PlacementKey, Placement>>>,
+    /// Placements changed by reads and not yet persisted
+    dirty: Arc<RwLock<HashSet<PlacementKey>>>,
+    placement_store: Option<Arc<dyn StorageBackend>>,
+    loaded: OnceCell<()>,
+    events: EventLog,
+}
+
+impl TieredStore {
+    /// Create a tiered store with no tiers; add them with [`TieredStore::with_tier`]
+    pub fn new(policy: PlacementPolicy) -> Self {
+        Self {
+            tiers: BTreeMap::new(),
+            policy: Arc::new(RwLock::new(policy)),
+            placements: Arc::new(RwLock::new(HashMap::new())),
+            dirty: Arc::new(RwLock::new(HashSet::new())),
+            placement_store: None,
+            loaded: OnceCell::new(),
+            events: EventLog::default(),
+        }
+    }
+
+    /// Persist placement records to `backend` instead of the coldest tier
+    pub fn with_placement_store(mut self, backend: Arc<dyn StorageBackend>) -> Self {
+        self.placement_store = Some(backend);
+        self
+    }
+
+    /// Attach a backend to a tier
+    pub fn with_tier(mut self, tier: StorageTier, backend: Arc<dyn StorageBackend>) -> Self {
+        self.tiers.insert(tier, backend);
+        self
+    }
+
+    /// Tiers that have a backend attached, hottest first
+    pub fn configured_tiers(&self) -> Vec<StorageTier> {
+        self.tiers.keys().copied().collect()
+    }
+
+    /// Update the placement policy
+    pub async fn update_policy<F>(&self, updater: F)
+    where
+        F: FnOnce(&mut PlacementPolicy),
+    {
+        let mut policy = self.policy.write().await;
+        updater(&mut policy);
+    }
+
+    /// Current tier of a block, if it is known to this store
+    pub async fn tier_of(&self, bucket: &str, cid: &Cid) -> Option<StorageTier> {
+        self.placement(bucket, cid).await.map(|p| p.tier)
+    }
+
+    /// Current placement record of a block
+    pub async fn placement(&self, bucket: &str, cid: &Cid) -> Option<Placement> {
+        if let Err(e) = self.load_placements().await {
+            warn!("Failed to load tier placements: {}", e);
+        }
+        self.placements.read().await.get(&key(bucket, cid)).cloned()
+    }
+
+    /// Backend placement records are persisted to
+    fn placement_store(&self) -> Option<&Arc<dyn StorageBackend>> {
+        self.placement_store.as_ref().or_else(|| self.tiers.values().next_back())
+    }
+
+    /// Load persisted placements, once
+    async fn load_placements(&self) -> Result<()> {
+        self.loaded
+            .get_or_try_init(|| async {
+                let Some(store) = self.placement_store() else {
+                    return Ok(());
+                };
+                let mut loaded = Vec::new();
+                for info in store.list_blocks(PLACEMENT_BUCKET).await? {
+                    let data = store.get_block(PLACEMENT_BUCKET, &info.cid).await?;
+                    let record: PlacementRecord = serde_json::from_slice(&data)
+                        .map_err(ObjectStoreError::deserialization)?;
+                    loaded.push(record);
+                }
+
+                let mut placements = self.placements.write().await;
+                for record in loaded {
+                    placements
+                        .entry(key(&record.placement.bucket, &record.cid))
+                        .or_insert(record.placement);
+                }
+                debug!("Loaded {} tier placements", placements.len());
+                Ok::<_, ObjectStoreError>(())
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Persist the placement of a block
+    ///
+    /// The record is keyed by the block rather than its own content, and
+    /// backends keep the first block stored under a key, so a previous
+    /// record is deleted first.
+    async fn save_placement(&self, cid: &Cid, placement: &Placement) -> Result<()> {
+        let Some(store) = self.placement_store() else {
+            return Ok(());
+        };
+        let record = PlacementRecord {
+            cid: *cid,
+            placement: placement.clone(),
+        };
+        let data = serde_json::to_vec(&record)
+            .map_err(ObjectStoreError::serialization)?;
+        let key = placement_key(&placement.bucket, cid);
+        if store.has_block(PLACEMENT_BUCKET, &key).await? {
+            store.delete_block(PLACEMENT_BUCKET, &key).await?;
+        }
+        store.put_block(PLACEMENT_BUCKET, &key, data).await
+    }
+
+    /// Persist a placement, leaving it to the next flush if that fails
+    async fn save_or_defer(&self, cid: &Cid, placement: &Placement) {
+        if let Err(e) = self.save_placement(cid, placement).await {
+            warn!("Failed to persist placement of {}: {}", cid, e);
+            self.dirty.write().await.insert(key(&placement.bucket, cid));
+        }
+    }
+
+    /// Persist placements changed by reads since the last flush
+    pub async fn flush_placements(&self) -> Result<usize> {
+        let dirty: Vec<PlacementKey> = self.dirty.write().await.drain().collect();
+        let mut saved = 0;
+        for (i, k) in dirty.iter().enumerate() {
+            let Some(placement) = self.placements.read().await.get(k).cloned() else {
+                continue;
+            };
+            if let Err(e) = self.save_placement(&k.1, &placement).await {
+                self.dirty.write().await.extend(dirty[i..].iter().cloned());
+                return Err(e);
+            }
+            saved += 1;
+        }
+        Ok(saved)
+    }
+
+    /// Map a desired tier onto a configured one, preferring colder tiers
+    fn resolve_tier(&self, desired: StorageTier) -> Result<StorageTier> {
+        self.tiers
+            .range(desired..)
+            .next()
+            .or_else(|| self.tiers.range(..desired).next_back())
+            .map(|(tier, _)| *tier)
+            .ok_or_else(|| ObjectStoreError::Storage("No storage tiers configured".to_string()))
+    }
+
+    fn backend(&self, tier: StorageTier) -> Result<&Arc<dyn StorageBackend>> {
+        self.tiers
+            .get(&tier)
+            .ok_or_else(|| ObjectStoreError::Storage(format!("Tier not configured: {}", tier.as_str())))
+    }
+
+    /// Store a block, placing it according to the policy
+    pub async fn put_block_in_domain(
+        &self,
+        bucket: &str,
+        cid: &Cid,
+        data: Vec<u8>,
+        domain: Option<ContentDomain>,
+    ) -> Result<StorageTier> {
+        self.load_placements().await?;
+        if let Some(existing) = self.placement(bucket, cid).await {
+            return Ok(existing.tier);
+        }
+
+        let size = data.len();
+        let desired = self.policy.read().await.initial_tier_for(size, domain);
+        let tier = self.resolve_tier(desired)?;
+
+        self.backend(tier)?.put_block(bucket, cid, data).await?;
+
+        let placement = Placement::new(bucket, tier, size, domain);
+        self.placements.write().await.insert(key(bucket, cid), placement.clone());
+        self.save_or_defer(cid, &placement).await;
+        self.events.record(ContentEventKind::Added, bucket, cid, size);
+        debug!("Placed {} in {} tier", cid, tier.as_str());
+        Ok(tier)
+    }
+
+    /// Store typed content, returning its CID
+    pub async fn put<T: TypedContent>(&self, content: &T, domain: Option<ContentDomain>) -> Result<Cid> {
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        let cid = content.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+        let data = content.to_bytes()
+            .map_err(ObjectStoreError::serialization)?;
+
+        self.put_block_in_domain(bucket.as_str(), &cid, data, domain).await?;
+        Ok(cid)
+    }
+
+    /// Retrieve typed content, verifying its CID
+    pub async fn get<T: TypedContent>(&self, cid: &Cid) -> Result<T> {
+        let bucket = ContentBucket::for_content_type(T::CONTENT_TYPE.codec());
+        let data = self.get_block(bucket.as_str(), cid).await?;
+
+        let content = T::from_bytes(&data)
+            .map_err(ObjectStoreError::deserialization)?;
+        let computed_cid = content.calculate_cid()
+            .map_err(ObjectStoreError::serialization)?;
+
+        if computed_cid != *cid {
+            return Err(ObjectStoreError::CidMismatch {
+                expected: cid.to_string(),
+                actual: computed_cid.to_string(),
+            });
+        }
+
+        Ok(content)
+    }
+
+    /// Find which tier holds a block that has no placement record
+    async fn locate(&self, bucket: &str, cid: &Cid) -> Result<Option<StorageTier>> {
+        for (tier, backend) in &self.tiers {
+            if backend.has_block(bucket, cid).await? {
+                return Ok(Some(*tier));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Look up the placement of a block, rediscovering it if necessary
+    async fn find_placement(&self, bucket: &str, cid: &Cid) -> Result<Placement> {
+        self.load_placements().await?;
+        if let Some(placement) = self.placement(bucket, cid).await {
+            return Ok(placement);
+        }
+
+        let tier = self.locate(bucket, cid).await?
+            .ok_or_else(|| ObjectStoreError::NotFound(cid.to_string()))?;
+
+        let placement = self.placements.write().await
+            .entry(key(bucket, cid))
+            .or_insert_with(|| Placement::new(bucket, tier, 0, None))
+            .clone();
+        self.save_or_defer(cid, &placement).await;
+        Ok(placement)
+    }
+
+    /// Nearest configured tier at or colder than the desired one
+    fn colder_or_equal(&self, desired: StorageTier) -> Option<StorageTier> {
+        self.tiers.range(desired..).next().map(|(tier, _)| *tier)
+    }
+
+    /// Nearest configured tier at or hotter than the desired one
+    fn hotter_or_equal(&self, desired: StorageTier) -> Option<StorageTier> {
+        self.tiers.range(..=desired).next_back().map(|(tier, _)| *tier)
+    }
+
+    /// Copy a block to another tier and remove it from its current tier
+    async fn move_block(&self, bucket: &str, cid: &Cid, to: StorageTier) -> Result<Option<TierMove>> {
+        let Some(placement) = self.placement(bucket, cid).await else {
+            return Ok(None);
+        };
+        if placement.tier == to {
+            return Ok(None);
+        }
+
+        let from_backend = self.backend(placement.tier)?;
+        let to_backend = self.backend(to)?;
+
+        let data = from_backend.get_block(&placement.bucket, cid).await?;
+        to_backend.put_block(&placement.bucket, cid, data).await?;
+
+        // Record the new tier before removing the old copy; readers that
+        // looked up the old placement retry with this one (see `read_placed`)
+        let moved = self.placements.write().await.get_mut(&key(bucket, cid)).map(|p| {
+            p.tier = to;
+            p.window_reads = 0;
+            p.window_start = SystemTime::now();
+            p.clone()
+        });
+        if let Some(moved) = moved {
+            self.save_or_defer(cid, &moved).await;
+        }
+
+        if let Err(e) = from_backend.delete_block(&placement.bucket, cid).await {
+            warn!("Failed to remove {} from {} tier: {}", cid, placement.tier.as_str(), e);
+        }
+
+        debug!("Moved {} from {} to {} tier", cid, placement.tier.as_str(), to.as_str());
+        Ok(Some(TierMove {
+            bucket: bucket.to_string(),
+            cid: *cid,
+            from: placement.tier,
+            to,
+        }))
+    }
+
+    /// Read a block from the tier of `placement`
+    ///
+    /// A concurrent move may delete the block from that tier after the
+    /// placement was looked up, so a miss is retried on the current tier.
+    async fn read_placed(&self, bucket: &str, cid: &Cid, placement: &Placement) -> Result<Vec<u8>> {
+        match self.backend(placement.tier)?.get_block(bucket, cid).await {
+            Err(e) if e.is_not_found() => match self.placement(bucket, cid).await {
+                Some(current) if current.tier != placement.tier => {
+                    self.backend(current.tier)?.get_block(bucket, cid).await
+                }
+                _ => Err(e),
+            },
+            result => result,
+        }
+    }
+
+    /// Run one demotion pass over all known blocks
+    pub async fn run_demotion(&self) -> Result<Vec<TierMove>> {
+        self.load_placements().await?;
+        if let Err(e) = self.flush_placements().await {
+            warn!("Failed to persist tier placements: {}", e);
+        }
+
+        let now = SystemTime::now();
+        let due: Vec<(PlacementKey, StorageTier)> = {
+            let policy = self.policy.read().await;
+            let placements = self.placements.read().await;
+            placements
+                .iter()
+                .filter_map(|(k, placement)| {
+                    policy.demotion_target(placement, now)
+                        .and_then(|tier| self.colder_or_equal(tier))
+                        .filter(|tier| *tier > placement.tier)
+                        .map(|tier| (k.clone(), tier))
+                })
+                .collect()
+        };
+
+        let mut moves = Vec::new();
+        for ((bucket, cid), tier) in due {
+            if let Some(moved) = self.move_block(&bucket, &cid, tier).await? {
+                moves.push(moved);
+            }
+        }
+
+        Ok(moves)
+    }
+
+    /// Run demotion passes in the background at the given interval
+    pub fn spawn_demotion(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let store = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match store.run_demotion().await {
+                    Ok(moves) if !moves.is_empty() => debug!("Demoted {} blocks", moves.len()),
+                    Ok(_) => {}
+                    Err(e) => warn!("Demotion pass failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl StorageBackend for TieredStore {
+    fn name(&self) -> &str {
+        "tiered"
+    }
+
+    async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+        self.put_block_in_domain(bucket, cid, data, None).await.map(|_| ())
+    }
+
+    async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+        let placement = self.find_placement(bucket, cid).await?;
+        let data = self.read_placed(bucket, cid, &placement).await?;
+
+        let promote_to = {
+            let policy = self.policy.read().await;
+            let mut placements = self.placements.write().await;
+            placements.get_mut(&key(bucket, cid)).and_then(|p| {
+                p.record_read(policy.promotion_window);
+                if p.size == 0 {
+                    p.size = data.len();
+                }
+                policy.promotion_target(p)
+                    .and_then(|tier| self.hotter_or_equal(tier))
+                    .filter(|tier| *tier < p.tier)
+            })
+        };
+
+        match promote_to {
+            Some(tier) => {
+                if let Err(e) = self.move_block(bucket, cid, tier).await {
+                    warn!("Failed to promote {}: {}", cid, e);
+                }
+            }
+            // Access times are persisted by the next flush
+            None => {
+                self.dirty.write().await.insert(key(bucket, cid));
+            }
+        }
+
+        Ok(data)
+    }
+
+    async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        self.load_placements().await?;
+        if self.placements.read().await.contains_key(&key(bucket, cid)) {
+            return Ok(true);
+        }
+        Ok(self.locate(bucket, cid).await?.is_some())
+    }
+
+    async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        let placement = self.find_placement(bucket, cid).await?;
+        self.backend(placement.tier)?.delete_block(bucket, cid).await?;
+        self.placements.write().await.remove(&key(bucket, cid));
+        self.dirty.write().await.remove(&key(bucket, cid));
+        if let Some(store) = self.placement_store() {
+            match store.delete_block(PLACEMENT_BUCKET, &placement_key(bucket, cid)).await {
+                Ok(()) => {}
+                Err(e) if e.is_not_found() => {}
+                Err(e) => warn!("Failed to remove placement of {}: {}", cid, e),
+            }
+        }
+        self.events.record(ContentEventKind::Deleted, bucket, cid, 0);
+        Ok(())
+    }
+
+    async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+        let mut seen = HashSet::new();
+        let mut objects = Vec::new();
+        for backend in self.tiers.values() {
+            for info in backend.list_blocks(bucket).await? {
+                if seen.insert(info.cid) {
+                    objects.push(info);
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+        // Each tier returns its own first page; the merged first page is
+        // always contained in their union
+        let mut collector = PageCollector::new(options);
+        for backend in self.tiers.values() {
+            let page = backend.list_blocks_page(bucket, options).await?;
+            if page.next_cursor.is_some() {
+                collector.mark_more();
+            }
+            for info in page.objects {
+                collector.offer(info);
+            }
+        }
+        Ok(collector.finish())
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for backend in self.tiers.values() {
+            names.extend(backend.list_buckets().await?);
+        }
+        names.retain(|name| name != PLACEMENT_BUCKET);
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    /// Tier moves are internal, so watchers only see additions and deletions
+    async fn watch_blocks(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        self.events.watch(bucket, from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_store::MemoryBackend;
+    use crate::ContentType;
+    use async_trait::async_trait;
+
+    /// Keeps the first block stored under a key, as `NatsObjectStore` does
+    struct FirstWriteWins(MemoryBackend);
+
+    #[async_trait]
+    impl StorageBackend for FirstWriteWins {
+        fn name(&self) -> &str {
+            "first-write-wins"
+        }
+
+        async fn put_block(&self, bucket: &str, cid: &Cid, data: Vec<u8>) -> Result<()> {
+            if self.0.has_block(bucket, cid).await? {
+                return Ok(());
+            }
+            self.0.put_block(bucket, cid, data).await
+        }
+
+        async fn get_block(&self, bucket: &str, cid: &Cid) -> Result<Vec<u8>> {
+            self.0.get_block(bucket, cid).await
+        }
+
+        async fn has_block(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+            self.0.has_block(bucket, cid).await
+        }
+
+        async fn delete_block(&self, bucket: &str, cid: &Cid) -> Result<()> {
+            self.0.delete_block(bucket, cid).await
+        }
+
+        async fn list_blocks(&self, bucket: &str) -> Result<Vec<ObjectInfo>> {
+            self.0.list_blocks(bucket).await
+        }
+
+        async fn list_blocks_page(&self, bucket: &str, options: &ListOptions) -> Result<ListPage> {
+            self.0.list_blocks_page(bucket, options).await
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct TestContent {
+        data: String,
+    }
+
+    impl TypedContent for TestContent {
+        const CODEC: u64 = 0x300100;
+        const CONTENT_TYPE: ContentType = ContentType::Custom(0x300100);
+    }
+
+    fn three_tier_store(policy: PlacementPolicy) -> (TieredStore, MemoryBackend, MemoryBackend, MemoryBackend) {
+        let hot = MemoryBackend::new();
+        let warm = MemoryBackend::new();
+        let archive = MemoryBackend::new();
+        let store = TieredStore::new(policy)
+            .with_tier(StorageTier::Hot, Arc::new(hot.clone()))
+            .with_tier(StorageTier::Warm, Arc::new(warm.clone()))
+            .with_tier(StorageTier::Archive, Arc::new(archive.clone()));
+        (store, hot, warm, archive)
+    }
+
+    #[test]
+    fn test_tier_ordering() {
+        assert!(StorageTier::Hot < StorageTier::Archive);
+        assert_eq!(StorageTier::Hot.colder(), Some(StorageTier::Warm));
+        assert_eq!(StorageTier::Hot.hotter(), None);
+        assert_eq!(StorageTier::Archive.colder(), None);
+    }
+
+    #[test]
+    fn test_initial_placement_rules() {
+        let mut policy = PlacementPolicy {
+            large_object_threshold: Some(1024),
+            ..Default::default()
+        };
+        policy.domain_tiers.insert(ContentDomain::Contracts, StorageTier::Archive);
+
+        assert_eq!(policy.initial_tier_for(10, None), StorageTier::Hot);
+        assert_eq!(policy.initial_tier_for(4096, None), StorageTier::Cold);
+        assert_eq!(
+            policy.initial_tier_for(10, Some(ContentDomain::Contracts)),
+            StorageTier::Archive
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get_roundtrip() {
+        let (store, hot, _, _) = three_tier_store(PlacementPolicy::default());
+        let content = TestContent { data: "hello".to_string() };
+
+        let cid = store.put(&content, None).await.unwrap();
+
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Hot));
+        assert_eq!(hot.list_blocks("cim-graphs").await.unwrap().len(), 1);
+        assert_eq!(store.get::<TestContent>(&cid).await.unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn test_missing_tier_resolves_to_colder() {
+        let policy = PlacementPolicy {
+            large_object_threshold: Some(1),
+            large_object_tier: StorageTier::Cold,
+            ..Default::default()
+        };
+        let (store, _, _, archive) = three_tier_store(policy);
+
+        let cid = store.put(&TestContent { data: "large".to_string() }, None).await.unwrap();
+
+        // No cold tier configured, so the block lands in the archive
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Archive));
+        assert_eq!(archive.list_blocks("cim-graphs").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_demotion_and_promotion() {
+        let mut policy = PlacementPolicy {
+            promote_after_reads: 2,
+            ..Default::default()
+        };
+        policy.demote_after_idle.insert(StorageTier::Hot, Duration::ZERO);
+        let (store, hot, warm, _) = three_tier_store(policy);
+
+        let content = TestContent { data: "cooling".to_string() };
+        let cid = store.put(&content, None).await.unwrap();
+
+        let moves = store.run_demotion().await.unwrap();
+        assert_eq!(moves, vec![TierMove {
+            bucket: "cim-graphs".to_string(),
+            cid,
+            from: StorageTier::Hot,
+            to: StorageTier::Warm,
+        }]);
+        assert!(hot.list_blocks("cim-graphs").await.unwrap().is_empty());
+        assert_eq!(warm.list_blocks("cim-graphs").await.unwrap().len(), 1);
+
+        // Stop demoting so the promotion sticks
+        store.update_policy(|p| { p.demote_after_idle.clear(); }).await;
+
+        store.get::<TestContent>(&cid).await.unwrap();
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Warm));
+        store.get::<TestContent>(&cid).await.unwrap();
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Hot));
+        assert!(warm.list_blocks("cim-graphs").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_after_concurrent_move() {
+        let (store, _, _, _) = three_tier_store(PlacementPolicy::default());
+        let content = TestContent { data: "moving".to_string() };
+        let cid = store.put(&content, None).await.unwrap();
+
+        // A reader looked up the placement just before the block was demoted
+        let stale = store.placement("cim-graphs", &cid).await.unwrap();
+        store.move_block("cim-graphs", &cid, StorageTier::Warm).await.unwrap();
+
+        let data = store.read_placed("cim-graphs", &cid, &stale).await.unwrap();
+        assert_eq!(data, content.to_bytes().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_domain_never_moves() {
+        let mut policy = PlacementPolicy::default();
+        policy.domain_tiers.insert(ContentDomain::Compliance, StorageTier::Warm);
+        policy.demote_after_idle.insert(StorageTier::Warm, Duration::ZERO);
+        let (store, _, _, _) = three_tier_store(policy);
+
+        let cid = store
+            .put(&TestContent { data: "pinned".to_string() }, Some(ContentDomain::Compliance))
+            .await
+            .unwrap();
+
+        assert!(store.run_demotion().await.unwrap().is_empty());
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Warm));
+    }
+
+    #[tokio::test]
+    async fn test_archive_after_age() {
+        let policy = PlacementPolicy {
+            archive_after: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let (store, _, _, archive) = three_tier_store(policy);
+
+        let cid = store.put(&TestContent { data: "old".to_string() }, None).await.unwrap();
+        store.run_demotion().await.unwrap();
+
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Archive));
+        assert_eq!(archive.list_blocks("cim-graphs").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rediscovers_unknown_blocks() {
+        let hot = MemoryBackend::new();
+        let cold = MemoryBackend::new();
+        let content = TestContent { data: "restarted".to_string() };
+        let cid = content.calculate_cid().unwrap();
+        cold.put_block("cim-graphs", &cid, content.to_bytes().unwrap()).await.unwrap();
+
+        let store = TieredStore::new(PlacementPolicy::default())
+            .with_tier(StorageTier::Hot, Arc::new(hot))
+            .with_tier(StorageTier::Cold, Arc::new(cold));
+
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, None);
+        assert_eq!(store.get::<TestContent>(&cid).await.unwrap(), content);
+        assert_eq!(store.tier_of("cim-graphs", &cid).await, Some(StorageTier::Cold));
+    }
+
+    #[tokio::test]
+    async fn test_placement_per_bucket() {
+        let (store, hot, _, archive) = three_tier_store(PlacementPolicy {
+            large_object_threshold: Some(4),
+            large_object_tier: StorageTier::Archive,
+            ..Default::default()
+        });
+        let cid = TestContent { data: "shared".to_string() }.calculate_cid().unwrap();
+
+        store.put_block("cim-small", &cid, b"abc".to_vec()).await.unwrap();
+        store.put_block("cim-large", &cid, b"abcdef".to_vec()).await.unwrap();
+
+        assert_eq!(store.tier_of("cim-small", &cid).await, Some(StorageTier::Hot));
+        assert_eq!(store.tier_of("cim-large", &cid).await, Some(StorageTier::Archive));
+        assert_eq!(hot.get_block("cim-small", &cid).await.unwrap(), b"abc");
+        assert_eq!(archive.get_block("cim-large", &cid).await.unwrap(), b"abcdef");
+
+        store.delete_block("cim-large", &cid).await.unwrap();
+        assert_eq!(store.get_block("cim-small", &cid).await.unwrap(), b"abc");
+        assert!(!store.has_block("cim-large", &cid).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_placements_survive_restart() {
+        let (store, hot, warm, archive) = three_tier_store(PlacementPolicy::default());
+        let cid = store.put(&TestContent { data: "unread".to_string() }, None).await.unwrap();
+        let stored_at = store.placement("cim-graphs", &cid).await.unwrap().stored_at;
+        drop(store);
+
+        // A new store over the same backends knows the block's age without reading it
+        let mut policy = PlacementPolicy::default();
+        policy.demote_after_idle.insert(StorageTier::Hot, Duration::ZERO);
+        let restarted = TieredStore::new(policy)
+            .with_tier(StorageTier::Hot, Arc::new(hot))
+            .with_tier(StorageTier::Warm, Arc::new(warm.clone()))
+            .with_tier(StorageTier::Archive, Arc::new(archive));
+
+        assert_eq!(restarted.placement("cim-graphs", &cid).await.unwrap().stored_at, stored_at);
+        assert_eq!(restarted.list_buckets().await.unwrap(), vec!["cim-graphs".to_string()]);
+        let moves = restarted.run_demotion().await.unwrap();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(warm.list_blocks("cim-graphs").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_moved_placement_is_persisted() {
+        let placements = MemoryBackend::new();
+        let open = |policy| {
+            TieredStore::new(policy)
+                .with_tier(StorageTier::Hot, Arc::new(MemoryBackend::new()))
+                .with_tier(StorageTier::Warm, Arc::new(MemoryBackend::new()))
+                .with_placement_store(Arc::new(FirstWriteWins(placements.clone())))
+        };
+        let mut policy = PlacementPolicy::default();
+        policy.demote_after_idle.insert(StorageTier::Hot, Duration::ZERO);
+        let store = open(policy);
+        let cid = store.put(&TestContent { data: "cooling".to_string() }, None).await.unwrap();
+        assert_eq!(store.run_demotion().await.unwrap().len(), 1);
+        drop(store);
+
+        let restarted = open(PlacementPolicy::default());
+        assert_eq!(restarted.placement("cim-graphs", &cid).await.unwrap().tier, StorageTier::Warm);
+    }
+}
@@ -0,0 +1,468 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Soft delete with tombstones and a restore window
+//!
+//! Soft deleting content writes a [`Tombstone`] recording who deleted it,
+//! why, and when. The bytes stay in their bucket, so the content can be
+//! restored until the purge window ends; services that consult
+//! [`Tombstones`] hide it from reads, listings, and search in the meantime.
+//! Once the window has passed, [`Tombstones::purge_expired`] removes the
+//! bytes and leaves a [`PurgeRecord`] as the audit trail.
+//!
+//! Tombstones and purge records are stored in the same [`StorageBackend`]
+//! as the content, keyed by the bucket and CID of the deleted content, so
+//! deleting a CID from one bucket leaves it visible in the others. Storing
+//! soft-deleted content again clears its tombstone.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::{ObjectStoreError, Result, Retention, StorageBackend};
+use crate::util::{cid_serde, unix_secs};
+
+/// Bucket holding tombstones of soft-deleted content
+pub const TOMBSTONE_BUCKET: &str = "cim-tombstones";
+
+/// Bucket holding records of purged content
+pub const PURGE_LOG_BUCKET: &str = "cim-purge-log";
+
+/// Default time soft-deleted content can be restored
+pub const DEFAULT_PURGE_WINDOW: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Default time a listing of tombstones is reused by [`Tombstones::deleted`]
+pub const DEFAULT_LISTING_TTL: Duration = Duration::from_secs(5);
+
+/// Key of the tombstone and purge record for content in a bucket
+fn tombstone_key(bucket: &str, cid: &Cid) -> Cid {
+    let hash = blake3::hash(format!("{bucket}/{cid}").as_bytes());
+    let mh = multihash::Multihash::wrap(0x1e, hash.as_bytes()).expect("BLAKE3 digest fits a multihash");
+    Cid::new_v1(0x55, mh)
+}
+
+/// Marker left in place of soft-deleted content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// Deleted content
+    #[serde(with = "cid_serde")]
+    pub cid: Cid,
+    /// Bucket holding the content's bytes
+    pub bucket: String,
+    /// Why the content was deleted
+    pub reason: String,
+    /// Who deleted the content
+    pub actor: String,
+    /// Deletion time in seconds since the Unix epoch
+    pub deleted_at: u64,
+    /// Time after which the content is purged, in seconds since the Unix epoch
+    pub purge_after: u64,
+}
+
+impl Tombstone {
+    /// Whether the content can still be restored at `now`
+    pub fn restorable_at(&self, now: SystemTime) -> bool {
+        unix_secs(now) < self.purge_after
+    }
+}
+
+/// Audit record of purged content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeRecord {
+    /// Tombstone that led to the purge
+    pub tombstone: Tombstone,
+    /// Purge time in seconds since the Unix epoch
+    pub purged_at: u64,
+    /// Whether the bytes were still present and have been removed
+    pub removed: bool,
+}
+
+/// Snapshot of soft-deleted content, for filtering listings and search results
+#[derive(Debug, Clone, Default)]
+pub struct DeletedSet {
+    keys: Arc<HashSet<Cid>>,
+}
+
+impl DeletedSet {
+    /// Whether content in `bucket` was soft deleted when the snapshot was taken
+    pub fn contains(&self, bucket: &str, cid: &Cid) -> bool {
+        self.keys.contains(&tombstone_key(bucket, cid))
+    }
+
+    /// Number of soft-deleted objects
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether nothing is soft deleted
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Tombstone keys listed from the backend
+struct Listing {
+    deleted: DeletedSet,
+    fetched_at: Instant,
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(ObjectStoreError::serialization)
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
+    serde_json::from_slice(data).map_err(ObjectStoreError::deserialization)
+}
+
+/// Tombstone registry over a storage backend
+pub struct Tombstones {
+    backend: Arc<dyn StorageBackend>,
+    purge_window: Duration,
+    retention: Option<Arc<Retention>>,
+    listing_ttl: Duration,
+    listing: Mutex<Option<Listing>>,
+}
+
+impl Tombstones {
+    /// Create a registry with the default purge window
+    pub fn new(backend: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            backend,
+            purge_window: DEFAULT_PURGE_WINDOW,
+            retention: None,
+            listing_ttl: DEFAULT_LISTING_TTL,
+            listing: Mutex::new(None),
+        }
+    }
+
+    /// Set how long soft-deleted content can be restored
+    pub fn with_purge_window(mut self, window: Duration) -> Self {
+        self.purge_window = window;
+        self
+    }
+
+    /// Refuse to soft delete content under retention
+    ///
+    /// Purges are checked by the backend, which refuses to delete retained
+    /// content; such content is skipped until its retention ends.
+    pub fn with_retention(mut self, retention: Arc<Retention>) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Set how long [`Self::deleted`] reuses a listing of tombstones
+    ///
+    /// Changes made through this registry show up at once; tombstones
+    /// written by other processes show up once the listing is refreshed.
+    pub fn with_listing_ttl(mut self, ttl: Duration) -> Self {
+        self.listing_ttl = ttl;
+        self
+    }
+
+    /// Time soft-deleted content can be restored
+    pub fn purge_window(&self) -> Duration {
+        self.purge_window
+    }
+
+    /// Soft delete content held in `bucket`
+    ///
+    /// Deleting content that already has a tombstone returns the existing
+    /// tombstone unchanged, so the restore window is not extended.
+    pub async fn delete(&self, bucket: &str, cid: &Cid, reason: &str, actor: &str) -> Result<Tombstone> {
+        if let Some(existing) = self.get(bucket, cid).await? {
+            return Ok(existing);
+        }
+        if !self.backend.has_block(bucket, cid).await? {
+            return Err(ObjectStoreError::NotFound(cid.to_string()));
+        }
+        if let Some(retention) = &self.retention {
+            retention.check_delete(Some(bucket), cid).await?;
+        }
+
+        let now = SystemTime::now();
+        let tombstone = Tombstone {
+            cid: *cid,
+            bucket: bucket.to_string(),
+            reason: reason.to_string(),
+            actor: actor.to_string(),
+            deleted_at: unix_secs(now),
+            purge_after: unix_secs(now + self.purge_window),
+        };
+        let key = tombstone_key(bucket, cid);
+        self.backend.put_block(TOMBSTONE_BUCKET, &key, encode(&tombstone)?).await?;
+        self.update_listing(|keys| {
+            keys.insert(key);
+        }).await;
+
+        info!("Soft deleted {} from {} ({}: {})", cid, bucket, actor, reason);
+        Ok(tombstone)
+    }
+
+    /// Tombstone of content in `bucket`, if it is soft deleted
+    pub async fn get(&self, bucket: &str, cid: &Cid) -> Result<Option<Tombstone>> {
+        self.get_key(&tombstone_key(bucket, cid)).await
+    }
+
+    async fn get_key(&self, key: &Cid) -> Result<Option<Tombstone>> {
+        match self.backend.get_block(TOMBSTONE_BUCKET, key).await {
+            Ok(data) => decode(&data).map(Some),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether content in `bucket` is soft deleted
+    pub async fn is_deleted(&self, bucket: &str, cid: &Cid) -> Result<bool> {
+        self.backend.has_block(TOMBSTONE_BUCKET, &tombstone_key(bucket, cid)).await
+    }
+
+    /// Soft-deleted content, from a listing at most the listing TTL old
+    pub async fn deleted(&self) -> Result<DeletedSet> {
+        let mut listing = self.listing.lock().await;
+        if let Some(cached) = listing.as_ref() {
+            if cached.fetched_at.elapsed() < self.listing_ttl {
+                return Ok(cached.deleted.clone());
+            }
+        }
+
+        let keys: HashSet<Cid> = self.backend
+            .list_blocks(TOMBSTONE_BUCKET)
+            .await?
+            .into_iter()
+            .map(|info| info.cid)
+            .collect();
+        let deleted = DeletedSet { keys: Arc::new(keys) };
+        *listing = Some(Listing {
+            deleted: deleted.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(deleted)
+    }
+
+    /// Apply a change made through this registry to the cached listing
+    async fn update_listing(&self, change: impl FnOnce(&mut HashSet<Cid>)) {
+        if let Some(cached) = self.listing.lock().await.as_mut() {
+            change(Arc::make_mut(&mut cached.deleted.keys));
+        }
+    }
+
+    /// All tombstones
+    pub async fn list(&self) -> Result<Vec<Tombstone>> {
+        let mut tombstones = Vec::new();
+        for info in self.backend.list_blocks(TOMBSTONE_BUCKET).await? {
+            if let Some(tombstone) = self.get_key(&info.cid).await? {
+                tombstones.push(tombstone);
+            }
+        }
+        tombstones.sort_by_key(|t| (t.deleted_at, t.cid.to_string()));
+        Ok(tombstones)
+    }
+
+    /// Undo a soft delete within the purge window
+    ///
+    /// Returns the removed tombstone. Fails with `NotFound` if the CID is not
+    /// soft deleted and with `Conflict` once the purge window has ended.
+    pub async fn restore(&self, bucket: &str, cid: &Cid) -> Result<Tombstone> {
+        let tombstone = self.get(bucket, cid).await?
+            .ok_or_else(|| ObjectStoreError::NotFound(format!("tombstone for {cid} in {bucket}")))?;
+        if !tombstone.restorable_at(SystemTime::now()) {
+            return Err(ObjectStoreError::Conflict(format!(
+                "Restore window for {cid} ended; it is awaiting purge"
+            )));
+        }
+
+        self.remove(bucket, cid).await?;
+        info!("Restored {} in {}", cid, bucket);
+        Ok(tombstone)
+    }
+
+    /// Drop the tombstone of content that has been stored again
+    ///
+    /// Unlike [`Self::restore`] this also works once the purge window has
+    /// ended, so the purge does not remove the newly stored bytes. Returns
+    /// the removed tombstone, if there was one.
+    pub async fn clear(&self, bucket: &str, cid: &Cid) -> Result<Option<Tombstone>> {
+        if !self.is_deleted(bucket, cid).await? {
+            return Ok(None);
+        }
+        let Some(tombstone) = self.get(bucket, cid).await? else {
+            return Ok(None);
+        };
+        self.remove(bucket, cid).await?;
+        info!("Cleared tombstone of {} in {}: stored again", cid, bucket);
+        Ok(Some(tombstone))
+    }
+
+    async fn remove(&self, bucket: &str, cid: &Cid) -> Result<()> {
+        let key = tombstone_key(bucket, cid);
+        match self.backend.delete_block(TOMBSTONE_BUCKET, &key).await {
+            Ok(()) => {}
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e),
+        }
+        self.update_listing(|keys| {
+            keys.remove(&key);
+        }).await;
+        Ok(())
+    }
+
+    /// Remove the bytes of content whose purge window has ended
+    ///
+    /// Each purge leaves a [`PurgeRecord`]; the tombstone is removed last so
+    /// an interrupted purge is retried on the next run. Content that came
+    /// under retention after it was deleted, and that the backend therefore
+    /// refuses to delete, is skipped until its retention ends.
+    pub async fn purge_expired(&self) -> Result<Vec<PurgeRecord>> {
+        let now = SystemTime::now();
+        let mut purged = Vec::new();
+
+        for tombstone in self.list().await? {
+            if tombstone.restorable_at(now) {
+                continue;
+            }
+            let removed = self.backend.has_block(&tombstone.bucket, &tombstone.cid).await?;
+            if removed {
+                match self.backend.delete_block(&tombstone.bucket, &tombstone.cid).await {
+                    Ok(()) => {}
+                    Err(e @ ObjectStoreError::Retained(_)) => {
+                        debug!("Not purging {}: {}", tombstone.cid, e);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let record = PurgeRecord {
+                tombstone: tombstone.clone(),
+                purged_at: unix_secs(SystemTime::now()),
+                removed,
+            };
+            let key = tombstone_key(&tombstone.bucket, &tombstone.cid);
+            self.backend.put_block(PURGE_LOG_BUCKET, &key, encode(&record)?).await?;
+            self.remove(&tombstone.bucket, &tombstone.cid).await?;
+
+            info!("Purged {} from {} (deleted by {})", tombstone.cid, tombstone.bucket, tombstone.actor);
+            purged.push(record);
+        }
+
+        Ok(purged)
+    }
+
+    /// Record left by purging content from `bucket`
+    pub async fn purge_record(&self, bucket: &str, cid: &Cid) -> Result<Option<PurgeRecord>> {
+        match self.backend.get_block(PURGE_LOG_BUCKET, &tombstone_key(bucket, cid)).await {
+            Ok(data) => decode(&data).map(Some),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Run purge passes in the background at the given interval
+    pub fn spawn_purge(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let tombstones = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match tombstones.purge_expired().await {
+                    Ok(purged) if !purged.is_empty() => debug!("Purged {} objects", purged.len()),
+                    Ok(_) => {}
+                    Err(e) => warn!("Purge pass failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_cid;
+    use crate::object_store::MemoryBackend;
+
+    #[tokio::test]
+    async fn test_delete_and_restore() {
+        let backend = Arc::new(MemoryBackend::new());
+        let tombstones = Tombstones::new(backend.clone());
+        let target = test_cid(b"doc");
+        backend.put_block("docs", &target, b"doc".to_vec()).await.unwrap();
+
+        let tombstone = tombstones.delete("docs", &target, "duplicate upload", "alice").await.unwrap();
+        assert_eq!(tombstone.actor, "alice");
+        assert!(tombstones.is_deleted("docs", &target).await.unwrap());
+        // Deleting again keeps the original tombstone
+        assert_eq!(tombstones.delete("docs", &target, "again", "bob").await.unwrap(), tombstone);
+
+        assert!(tombstones.purge_expired().await.unwrap().is_empty());
+        tombstones.restore("docs", &target).await.unwrap();
+        assert!(!tombstones.is_deleted("docs", &target).await.unwrap());
+        assert!(backend.has_block("docs", &target).await.unwrap());
+
+        assert!(tombstones.restore("docs", &target).await.unwrap_err().is_not_found());
+        assert!(tombstones.delete("docs", &test_cid(b"missing"), "", "").await.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_purge_after_window() {
+        let backend = Arc::new(MemoryBackend::new());
+        let tombstones = Tombstones::new(backend.clone()).with_purge_window(Duration::ZERO);
+        let target = test_cid(b"secret");
+        backend.put_block("docs", &target, b"secret".to_vec()).await.unwrap();
+
+        tombstones.delete("docs", &target, "gdpr request", "dpo").await.unwrap();
+        assert!(matches!(
+            tombstones.restore("docs", &target).await,
+            Err(ObjectStoreError::Conflict(_))
+        ));
+
+        let purged = tombstones.purge_expired().await.unwrap();
+        assert_eq!(purged.len(), 1);
+        assert!(purged[0].removed);
+        assert!(!backend.has_block("docs", &target).await.unwrap());
+        assert!(!tombstones.is_deleted("docs", &target).await.unwrap());
+
+        let record = tombstones.purge_record("docs", &target).await.unwrap().unwrap();
+        assert_eq!(record.tombstone.reason, "gdpr request");
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_are_per_bucket() {
+        let backend = Arc::new(MemoryBackend::new());
+        let tombstones = Tombstones::new(backend.clone());
+        let target = test_cid(b"shared");
+        backend.put_block("docs", &target, b"shared".to_vec()).await.unwrap();
+        backend.put_block("media", &target, b"shared".to_vec()).await.unwrap();
+
+        tombstones.delete("docs", &target, "wrong bucket", "alice").await.unwrap();
+        assert!(tombstones.is_deleted("docs", &target).await.unwrap());
+        assert!(!tombstones.is_deleted("media", &target).await.unwrap());
+
+        let deleted = tombstones.deleted().await.unwrap();
+        assert!(deleted.contains("docs", &target));
+        assert!(!deleted.contains("media", &target));
+        assert!(tombstones.restore("media", &target).await.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_listing_follows_local_changes() {
+        let backend = Arc::new(MemoryBackend::new());
+        let tombstones = Tombstones::new(backend.clone()).with_listing_ttl(Duration::from_secs(3600));
+        let target = test_cid(b"cached");
+        backend.put_block("docs", &target, b"cached".to_vec()).await.unwrap();
+        assert!(tombstones.deleted().await.unwrap().is_empty());
+
+        tombstones.delete("docs", &target, "", "alice").await.unwrap();
+        assert!(tombstones.deleted().await.unwrap().contains("docs", &target));
+
+        // Clearing updates the cached listing too
+        assert!(tombstones.clear("docs", &target).await.unwrap().is_some());
+        assert!(tombstones.clear("docs", &target).await.unwrap().is_none());
+        assert!(!tombstones.is_deleted("docs", &target).await.unwrap());
+        assert!(tombstones.deleted().await.unwrap().is_empty());
+    }
+}
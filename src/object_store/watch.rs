@@ -0,0 +1,270 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Change notifications for content buckets
+//!
+//! Watchers receive a [`ContentEvent`] for every block added to or deleted
+//! from a bucket; storing a block that already exists is not a change.
+//! Every event carries a sequence number so a consumer can persist its
+//! position and resume with [`WatchFrom::Sequence`] after a restart without
+//! missing events. Sequences increase within a bucket but are not
+//! contiguous: NATS uses the bucket stream's sequence, and in-process
+//! backends number the events of all their buckets together.
+//!
+//! History is bounded. Resuming from a point that is no longer retained
+//! fails with [`ObjectStoreError::HistoryTruncated`] instead of silently
+//! skipping the lost events.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use cid::Cid;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::{ObjectStoreError, Result};
+
+/// Kind of change observed in a bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContentEventKind {
+    /// A block was stored
+    Added,
+    /// A block was deleted
+    Deleted,
+}
+
+/// A change to a content bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentEvent {
+    /// What happened
+    pub kind: ContentEventKind,
+    /// Bucket the change happened in
+    pub bucket: String,
+    /// CID of the affected block
+    pub cid: Cid,
+    /// Size of the block in bytes (0 for deletions)
+    pub size: usize,
+    /// Codec of the block, taken from its CID
+    pub codec: u64,
+    /// Position of this event in the change log; increasing, with gaps
+    pub sequence: u64,
+    /// When the change was recorded
+    pub timestamp: SystemTime,
+}
+
+/// Where a watch starts in the change log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatchFrom {
+    /// Only changes made after the watch starts
+    #[default]
+    New,
+    /// Every retained change, from the oldest
+    Beginning,
+    /// Changes at or after the given sequence number
+    Sequence(u64),
+    /// Changes recorded at or after the given time
+    Time(SystemTime),
+}
+
+impl WatchFrom {
+    /// Whether a historical event should be replayed for this starting point
+    fn includes(&self, event: &ContentEvent) -> bool {
+        match self {
+            Self::New => false,
+            Self::Beginning => true,
+            Self::Sequence(sequence) => event.sequence >= *sequence,
+            Self::Time(time) => event.timestamp >= *time,
+        }
+    }
+}
+
+/// Stream of change events returned by watch operations
+pub type ContentEventStream = BoxStream<'static, Result<ContentEvent>>;
+
+/// Default number of events retained for resuming watchers
+const DEFAULT_EVENT_HISTORY: usize = 10_000;
+
+#[derive(Debug)]
+struct EventLogInner {
+    next_sequence: u64,
+    history: VecDeque<ContentEvent>,
+    capacity: usize,
+    /// Sequence and time of the newest event dropped from history
+    evicted: Option<(u64, SystemTime)>,
+}
+
+impl EventLogInner {
+    /// Whether events a watch starting at `from` would replay were dropped
+    fn truncates(&self, from: WatchFrom) -> bool {
+        let Some((sequence, timestamp)) = self.evicted else {
+            return false;
+        };
+        match from {
+            WatchFrom::New | WatchFrom::Beginning => false,
+            WatchFrom::Sequence(start) => start <= sequence,
+            WatchFrom::Time(start) => start <= timestamp,
+        }
+    }
+}
+
+/// Bounded, in-process change log shared by backends without native watches
+#[derive(Debug, Clone)]
+pub(crate) struct EventLog {
+    inner: Arc<Mutex<EventLogInner>>,
+    sender: broadcast::Sender<ContentEvent>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_EVENT_HISTORY)
+    }
+}
+
+impl EventLog {
+    /// Create a log that retains up to `capacity` events for replay
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self {
+            inner: Arc::new(Mutex::new(EventLogInner {
+                next_sequence: 1,
+                history: VecDeque::new(),
+                capacity,
+                evicted: None,
+            })),
+            sender,
+        }
+    }
+
+    /// Record a change and notify live watchers
+    pub(crate) fn record(&self, kind: ContentEventKind, bucket: &str, cid: &Cid, size: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        let event = ContentEvent {
+            kind,
+            bucket: bucket.to_string(),
+            cid: *cid,
+            size,
+            codec: cid.codec(),
+            sequence: inner.next_sequence,
+            timestamp: SystemTime::now(),
+        };
+        inner.next_sequence += 1;
+
+        if inner.history.len() == inner.capacity {
+            if let Some(evicted) = inner.history.pop_front() {
+                inner.evicted = Some((evicted.sequence, evicted.timestamp));
+            }
+        }
+        inner.history.push_back(event.clone());
+
+        // Sending while holding the lock keeps replay and live delivery in order
+        let _ = self.sender.send(event);
+    }
+
+    /// Watch a bucket, replaying retained history according to `from`
+    ///
+    /// Fails with [`ObjectStoreError::HistoryTruncated`] if events after
+    /// `from` are no longer retained.
+    pub(crate) fn watch(&self, bucket: &str, from: WatchFrom) -> Result<ContentEventStream> {
+        let (replay, receiver) = {
+            let inner = self.inner.lock().unwrap();
+            if inner.truncates(from) {
+                return Err(ObjectStoreError::HistoryTruncated(format!(
+                    "events of bucket {bucket} from {from:?} are no longer retained"
+                )));
+            }
+            let replay: Vec<_> = inner
+                .history
+                .iter()
+                .filter(|event| event.bucket == bucket && from.includes(event))
+                .cloned()
+                .map(Ok)
+                .collect();
+            (replay, self.sender.subscribe())
+        };
+
+        let bucket = bucket.to_string();
+        let live = stream::unfold(receiver, move |mut receiver| {
+            let bucket = bucket.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if event.bucket == bucket => return Some((Ok(event), receiver)),
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            let error = ObjectStoreError::Storage(format!(
+                                "Watcher lagged behind by {missed} events"
+                            ));
+                            return Some((Err(error), receiver));
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+
+        Ok(stream::iter(replay).chain(live).boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cid(data: &[u8]) -> Cid {
+        let mh = multihash::Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+        Cid::new_v1(0x300100, mh)
+    }
+
+    #[tokio::test]
+    async fn test_live_events_are_filtered_by_bucket() {
+        let log = EventLog::default();
+        let mut watcher = log.watch("cim-graphs", WatchFrom::New).unwrap();
+
+        log.record(ContentEventKind::Added, "cim-media", &test_cid(b"a"), 1);
+        log.record(ContentEventKind::Added, "cim-graphs", &test_cid(b"b"), 2);
+
+        let event = watcher.next().await.unwrap().unwrap();
+        assert_eq!(event.bucket, "cim-graphs");
+        assert_eq!(event.cid, test_cid(b"b"));
+        assert_eq!(event.codec, 0x300100);
+        assert_eq!(event.sequence, 2);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_sequence() {
+        let log = EventLog::default();
+        for data in [b"a", b"b", b"c"] {
+            log.record(ContentEventKind::Added, "cim-graphs", &test_cid(data), 1);
+        }
+        log.record(ContentEventKind::Deleted, "cim-graphs", &test_cid(b"a"), 0);
+
+        let mut watcher = log.watch("cim-graphs", WatchFrom::Sequence(3)).unwrap();
+        let third = watcher.next().await.unwrap().unwrap();
+        let fourth = watcher.next().await.unwrap().unwrap();
+
+        assert_eq!(third.sequence, 3);
+        assert_eq!(fourth.kind, ContentEventKind::Deleted);
+
+        // Live events follow the replayed history
+        log.record(ContentEventKind::Added, "cim-graphs", &test_cid(b"d"), 1);
+        assert_eq!(watcher.next().await.unwrap().unwrap().sequence, 5);
+    }
+
+    #[tokio::test]
+    async fn test_history_is_bounded() {
+        let log = EventLog::with_capacity(2);
+        for data in [b"a", b"b", b"c"] {
+            log.record(ContentEventKind::Added, "cim-graphs", &test_cid(data), 1);
+        }
+
+        let mut watcher = log.watch("cim-graphs", WatchFrom::Beginning).unwrap();
+        assert_eq!(watcher.next().await.unwrap().unwrap().sequence, 2);
+
+        // Resuming before the retained history would miss events
+        let err = log.watch("cim-graphs", WatchFrom::Sequence(1)).err().unwrap();
+        assert_eq!(err.code(), crate::ErrorCode::HistoryTruncated);
+        assert!(log.watch("cim-graphs", WatchFrom::Time(SystemTime::UNIX_EPOCH)).is_err());
+        assert!(log.watch("cim-graphs", WatchFrom::Sequence(2)).is_ok());
+    }
+}
@@ -203,7 +203,7 @@ mod tests {
 
         // Test Clone trait
         let original = ContentType::Graph;
-        let cloned = original.clone();
+        let cloned = original;
         assert_eq!(original, cloned);
 
         // Test Copy trait (implicitly tested by passing by value)
@@ -0,0 +1,76 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Helpers shared across modules
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Serde support for CIDs as strings
+pub(crate) mod cid_serde {
+    use cid::Cid;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(cid: &Cid, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&cid.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<Cid, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+
+    /// Serde support for optional CIDs
+    pub mod option {
+        use cid::Cid;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(cid: &Option<Cid>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match cid {
+                Some(cid) => serializer.serialize_some(&cid.to_string()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<Option<Cid>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<String>::deserialize(deserializer)?
+                .map(|s| s.parse().map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+/// Seconds since the Unix epoch, saturating at zero
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// DAG-CBOR CID of arbitrary bytes, for tests
+#[cfg(test)]
+pub(crate) fn test_cid(data: &[u8]) -> cid::Cid {
+    let mh = multihash::Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+    cid::Cid::new_v1(0x71, mh)
+}
+
+/// Numbered content for chain tests
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct TestEntry {
+    pub(crate) n: u32,
+}
+
+#[cfg(test)]
+impl crate::TypedContent for TestEntry {
+    const CODEC: u64 = 0x300100;
+    const CONTENT_TYPE: crate::ContentType = crate::ContentType::Custom(0x300100);
+}
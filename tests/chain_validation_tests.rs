@@ -49,7 +49,7 @@ fn test_chain_sequence_validation() {
     
     // Verify chain linkage
     assert!(items[0].previous_cid.is_none());
-    assert_eq!(items[1].previous_cid, Some(items[0].cid.clone()));
+    assert_eq!(items[1].previous_cid, Some(items[0].cid));
 }
 
 #[test]
@@ -70,7 +70,7 @@ fn test_chain_cid_linkage() {
     let chained2 = ChainedContent::new(event2, Some(&chained1)).unwrap();
     
     // Verify linkage
-    assert_eq!(chained2.previous_cid, Some(chained1.cid.clone()));
+    assert_eq!(chained2.previous_cid, Some(chained1.cid));
     assert_eq!(chained2.sequence, chained1.sequence + 1);
     
     // Validate the chain
@@ -96,13 +96,14 @@ fn test_chain_validation_detects_tampering() {
     let mut chained2 = ChainedContent::new(event2, Some(&chained1)).unwrap();
     
     // Tamper with the chain by changing previous_cid
-    chained2.previous_cid = Some("tampered-cid".to_string());
+    let tampered = cim_ipld::Cid::new_v1(0x71, multihash::Multihash::wrap(0x1e, blake3::hash(b"tampered").as_bytes()).unwrap());
+    chained2.previous_cid = Some(tampered);
     
     // Validation should fail
     match chained2.validate_chain(Some(&chained1)) {
         Err(Error::ChainValidationError { expected, actual }) => {
-            assert_eq!(expected, chained1.cid);
-            assert_eq!(actual, "tampered-cid");
+            assert_eq!(expected, chained1.cid.to_string());
+            assert_eq!(actual, tampered.to_string());
         }
         _ => panic!("Expected ChainValidationError"),
     }
@@ -296,12 +296,14 @@ fn test_complex_data_structures() {
     assert_eq!(complex_data, cbor_decoded);
 }
 
+type Encoder = Box<dyn Fn(&TestData) -> Vec<u8>>;
+
 #[test]
 fn test_codec_efficiency() {
     let data = TestData::example();
     
     // Compare encoding sizes
-    let codecs: Vec<(&str, Box<dyn Fn(&TestData) -> Vec<u8>>)> = vec![
+    let codecs: Vec<(&str, Encoder)> = vec![
         ("DAG-JSON", Box::new(|d| DagJsonCodec::encode(d).unwrap())),
         ("DAG-CBOR", Box::new(|d| DagCborCodec::encode(d).unwrap())),
     ];
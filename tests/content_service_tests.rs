@@ -0,0 +1,107 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Tests for the content service against a NATS object store
+
+use async_nats::jetstream;
+use cim_ipld::content_types::codec;
+use cim_ipld::content_types::indexing::SearchQuery;
+use cim_ipld::content_types::service::{ContentService, ContentServiceConfig};
+use cim_ipld::object_store::NatsObjectStore;
+use cim_ipld::{ContentType, DocumentMetadata, Error, TextDocument, TypedContent};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+#[ignore] // Requires NATS server running
+async fn test_batch_stored_items_are_searchable() {
+    let client = async_nats::connect("nats://localhost:4222").await.unwrap();
+    let store = Arc::new(NatsObjectStore::new(jetstream::new(client), 1024).await.unwrap());
+    let service = ContentService::new(store, ContentServiceConfig::default());
+
+    let stored = Arc::new(AtomicUsize::new(0));
+    let counter = stored.clone();
+    service.add_post_store_hook(move |_, _| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }).await;
+
+    // A unique word, so earlier runs against the same server do not match
+    let word = format!("batchword{}", uuid::Uuid::new_v4().simple());
+    let docs: Vec<_> = (0..3)
+        .map(|i| {
+            TextDocument::new(
+                format!("document {i} mentions {word}"),
+                DocumentMetadata {
+                    title: Some(format!("Batch document {i}")),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let commit = service.batch_store_atomic(&docs).await.unwrap();
+    assert_eq!(commit.manifest.entries.len(), 3);
+    assert_eq!(stored.load(Ordering::SeqCst), 3);
+
+    let results = service
+        .search(SearchQuery {
+            text: Some(word),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let mut found: Vec<_> = results.iter().map(|r| r.cid).collect();
+    let mut expected: Vec<_> = docs.iter().map(|d| d.calculate_cid().unwrap()).collect();
+    found.sort();
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[tokio::test]
+#[ignore] // Requires NATS server running
+async fn test_batch_respects_allowed_types_and_hooks() {
+    let client = async_nats::connect("nats://localhost:4222").await.unwrap();
+    let store = Arc::new(NatsObjectStore::new(jetstream::new(client), 1024).await.unwrap());
+    let doc = TextDocument::new("never stored".to_string(), DocumentMetadata::default()).unwrap();
+
+    let markdown_only = ContentServiceConfig {
+        allowed_types: vec![ContentType::Custom(codec::MARKDOWN)],
+        ..Default::default()
+    };
+    let service = ContentService::new(store.clone(), markdown_only);
+    assert!(service.batch_store_atomic(std::slice::from_ref(&doc)).await.is_err());
+
+    let service = ContentService::new(store, ContentServiceConfig::default());
+    service
+        .add_pre_store_hook(|_, _| Err(Error::InvalidContent("rejected".to_string())))
+        .await;
+    assert!(service.batch_store_atomic(&[doc]).await.is_err());
+}
+
+#[tokio::test]
+#[ignore] // Requires NATS server running
+async fn test_ttl_follows_permanent_stores() {
+    let client = async_nats::connect("nats://localhost:4222").await.unwrap();
+    let store = Arc::new(NatsObjectStore::new(jetstream::new(client), 1024).await.unwrap());
+    let service = ContentService::new(store, ContentServiceConfig::default());
+    let content_type = ContentType::Custom(codec::TEXT);
+    let unique = uuid::Uuid::new_v4();
+
+    // A TTL cannot be set on content that was never stored
+    let missing = TextDocument::new(format!("missing {unique}"), DocumentMetadata::default()).unwrap();
+    let missing_cid = missing.calculate_cid().unwrap();
+    assert!(service.set_ttl(&missing_cid, content_type, Duration::from_secs(60)).await.is_err());
+
+    // Storing for good drops an earlier TTL
+    let doc = TextDocument::new(format!("temporary {unique}"), DocumentMetadata::default()).unwrap();
+    let stored = service.store_with_ttl(doc.clone(), Duration::from_secs(60)).await.unwrap();
+    assert!(service.expiry().get(&stored.cid).await.unwrap().is_some());
+    service.store_document(doc.content.clone().into_bytes(), doc.metadata.clone(), "text").await.unwrap();
+    assert!(service.expiry().get(&stored.cid).await.unwrap().is_none());
+
+    // ... and a later TTL store does not make it temporary again
+    service.store_with_ttl(doc, Duration::ZERO).await.unwrap();
+    assert!(service.expiry().get(&stored.cid).await.unwrap().is_none());
+    assert!(service.expire_due().await.unwrap().iter().all(|r| r.cid != stored.cid));
+}
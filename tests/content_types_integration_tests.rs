@@ -38,7 +38,6 @@ fn test_document_types() {
             created_at: Some(chrono::Utc::now().timestamp() as u64),
             modified_at: Some(chrono::Utc::now().timestamp() as u64),
             language: Some("en".to_string()),
-            ..Default::default()
         },
     };
     
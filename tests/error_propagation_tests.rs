@@ -39,8 +39,7 @@ impl TypedContent for FailableContent {
             FailMode::SerializationError => {
                 // Force a serialization error by returning invalid JSON
                 Err(Error::SerializationError(
-                    serde_json::Error::io(std::io::Error::new(
-                        std::io::ErrorKind::Other,
+                    serde_json::Error::io(std::io::Error::other(
                         "Forced serialization error"
                     ))
                 ))
@@ -292,8 +291,8 @@ fn test_error_chain_in_service_layer() {
     let storage_errors = vec![
         ObjectStoreError::NotFound("cid123".to_string()),
         ObjectStoreError::Storage("connection failed".to_string()),
-        ObjectStoreError::Serialization("encode failed".to_string()),
-        ObjectStoreError::Deserialization("decode failed".to_string()),
+        ObjectStoreError::serialization("encode failed"),
+        ObjectStoreError::deserialization("decode failed"),
         ObjectStoreError::CidMismatch {
             expected: "cid1".to_string(),
             actual: "cid2".to_string(),
@@ -0,0 +1,61 @@
+// Copyright 2025 Cowboy AI, LLC.
+
+//! Tests for watching NATS object store buckets
+
+use cim_ipld::object_store::{
+    ContentBucket, ContentEvent, ContentEventKind, ContentEventStream, NatsObjectStore, WatchFrom,
+};
+use cim_ipld::{ContentType, TypedContent};
+use async_nats::jetstream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct WatchedGraph {
+    id: String,
+}
+
+impl TypedContent for WatchedGraph {
+    const CODEC: u64 = 0x300100;
+    const CONTENT_TYPE: ContentType = ContentType::Custom(0x300100);
+}
+
+async fn next_event(watcher: &mut ContentEventStream) -> ContentEvent {
+    tokio::time::timeout(Duration::from_secs(5), watcher.next())
+        .await
+        .expect("timed out waiting for event")
+        .expect("watch ended")
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore] // Requires NATS server running
+async fn test_watch_additions_and_deletions() {
+    let client = async_nats::connect("nats://localhost:4222").await.unwrap();
+    let store = NatsObjectStore::new(jetstream::new(client), 1024).await.unwrap();
+
+    let mut watcher = store.watch(ContentBucket::Graphs, WatchFrom::New).await.unwrap();
+
+    let content = WatchedGraph { id: uuid::Uuid::new_v4().to_string() };
+    let cid = store.put(&content).await.unwrap();
+    store.delete(&cid, WatchedGraph::CODEC).await.unwrap();
+
+    let added = next_event(&mut watcher).await;
+    assert_eq!(added.kind, ContentEventKind::Added);
+    assert_eq!(added.cid, cid);
+    assert_eq!(added.codec, WatchedGraph::CODEC);
+
+    let deleted = next_event(&mut watcher).await;
+    assert_eq!(deleted.kind, ContentEventKind::Deleted);
+    assert_eq!(deleted.cid, cid);
+
+    // Resuming after the addition replays the deletion
+    let mut resumed = store
+        .watch(ContentBucket::Graphs, WatchFrom::Sequence(added.sequence + 1))
+        .await
+        .unwrap();
+    let replayed = next_event(&mut resumed).await;
+    assert_eq!(replayed.kind, ContentEventKind::Deleted);
+    assert_eq!(replayed.cid, cid);
+}